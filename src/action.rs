@@ -0,0 +1,147 @@
+use crossterm::event::KeyCode::{self, Char};
+use crossterm::event::{KeyEvent, KeyModifiers};
+
+/// What a key does in Edit Mode, for front ends and tests to drive the editor without key
+/// events through `Editor::apply`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Moves the cursor by a number of bytes, backwards if negative.
+    Move(i64),
+    /// Moves the cursor by a number of lines, backwards if negative.
+    MoveLines(i64),
+    /// Moves the cursor to an offset, scrolling the view to it.
+    MoveTo(usize),
+    /// Types a hexadecimal digit into the byte at the cursor, the high digit first, or into a
+    /// byte inserted before the cursor in insert mode.
+    Digit(u8),
+    /// Switches between overwriting bytes and inserting them.
+    ToggleInsert,
+    /// Restores the byte at the cursor, or the selection, to the values on disk.
+    Revert,
+    /// Copies the selection, or the whole file, into the unnamed register.
+    Yank,
+    /// Writes the unnamed register at the cursor, inserted in insert mode and overwriting the
+    /// bytes at the cursor otherwise.
+    Put,
+    /// Starts a selection at the cursor, or clears it.
+    ToggleSelection,
+    /// Clears the selection and any digit typed into the byte at the cursor.
+    Cancel,
+    Undo,
+    Redo,
+    /// Moves the cursor, or the view if `view`, to the next or previous boundary of the
+    /// alignment.
+    Snap {
+        forward: bool,
+        view: bool,
+    },
+    /// Folds the region at the cursor, or the selection, or unfolds the row at the cursor.
+    ToggleFold,
+    /// Moves the cursor to the next or previous match of the latest search.
+    FindNext {
+        forward: bool,
+    },
+    /// Moves the cursor to the next or previous multiple of the size of the inspected type.
+    Step {
+        forward: bool,
+    },
+    /// Moves the cursor to the start of the next or previous run of printable characters.
+    NextString {
+        forward: bool,
+    },
+    /// Moves the cursor back to where it was before the latest jump in the jump list.
+    JumpBack,
+    NextDocument,
+    PreviousDocument,
+    /// Closes the shown file, quitting if it is the only open one.
+    CloseDocument,
+    /// Moves the focus to the other view of a split.
+    SwitchView,
+    /// Enters Command Mode with the given text already typed.
+    EnterCommand(String),
+    /// Runs a command as if typed in Command Mode.
+    Command(String),
+    /// Quits, discarding unsaved changes.
+    Quit,
+}
+
+impl Action {
+    /// Returns the action of `event` in Edit Mode, with `Ctrl` and the arrow keys moving by
+    /// `max_lines` lines or bytes.
+    pub fn from_key(event: KeyEvent, max_lines: u32) -> Option<Action> {
+        let control = event.modifiers == KeyModifiers::CONTROL;
+        let count = if control { max_lines as i64 } else { 1 };
+        let action = match (event.code, control) {
+            (Char('c'), true) => Action::Quit,
+            (KeyCode::Left, _) => Action::Move(-count),
+            (KeyCode::Right, _) => Action::Move(count),
+            (KeyCode::Up, _) => Action::MoveLines(-count),
+            (KeyCode::Down, _) => Action::MoveLines(count),
+            (Char(c @ ('0'..='9' | 'a'..='f' | 'A'..='F')), _) => {
+                Action::Digit(c.to_digit(16)? as u8)
+            }
+            (Char('U'), _) => Action::Revert,
+            (KeyCode::Insert, _) | (Char('i'), _) => Action::ToggleInsert,
+            (KeyCode::Tab, _) => Action::NextDocument,
+            (KeyCode::BackTab, _) => Action::PreviousDocument,
+            (Char('w'), true) => Action::CloseDocument,
+            (Char('t'), true) => Action::EnterCommand("e ".into()),
+            (Char('%'), _) => Action::EnterCommand("goto ".into()),
+            (Char('/'), _) => Action::EnterCommand("find ".into()),
+            (Char(':'), _) => Action::EnterCommand("".into()),
+            (Char('n'), _) => Action::FindNext { forward: true },
+            (Char('N'), _) => Action::FindNext { forward: false },
+            (Char('o'), true) => Action::SwitchView,
+            (Char('>'), _) => Action::Step { forward: true },
+            (Char('<'), _) => Action::Step { forward: false },
+            (Char('s'), _) => Action::NextString { forward: true },
+            (Char('S'), _) => Action::NextString { forward: false },
+            (Char(c @ ('[' | ']' | '{' | '}')), _) => Action::Snap {
+                forward: matches!(c, ']' | '}'),
+                view: matches!(c, '{' | '}'),
+            },
+            (KeyCode::Backspace, _) => Action::JumpBack,
+            (Char('z'), _) => Action::ToggleFold,
+            (Char('u'), _) => Action::Undo,
+            (Char('r'), true) => Action::Redo,
+            (Char('v'), _) => Action::ToggleSelection,
+            (Char('y'), _) => Action::Yank,
+            (Char('p'), _) => Action::Put,
+            (KeyCode::Esc, _) => Action::Cancel,
+            _ => return None,
+        };
+        Some(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_key() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let control = |code| KeyEvent::new(code, KeyModifiers::CONTROL);
+        assert_eq!(
+            Action::from_key(key(Char('b')), 20),
+            Some(Action::Digit(11))
+        );
+        assert_eq!(Action::from_key(control(Char('c')), 20), Some(Action::Quit));
+        assert_eq!(
+            Action::from_key(control(KeyCode::Up), 20),
+            Some(Action::MoveLines(-20))
+        );
+        assert_eq!(
+            Action::from_key(key(Char('}')), 20),
+            Some(Action::Snap {
+                forward: true,
+                view: true
+            })
+        );
+        assert_eq!(Action::from_key(key(Char('r')), 20), None);
+        let shift = |c| KeyEvent::new(Char(c), KeyModifiers::SHIFT);
+        assert_eq!(Action::from_key(shift('E'), 20), Some(Action::Digit(14)));
+        assert_eq!(Action::from_key(shift('4'), 20), Some(Action::Digit(4)));
+        assert_eq!(Action::from_key(shift('$'), 20), None);
+    }
+}
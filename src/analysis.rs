@@ -0,0 +1,164 @@
+use crossterm::style::Color;
+
+/// Number of bytes each value of the entropy column is computed over.
+pub const ENTROPY_BLOCK_SIZE: usize = 256;
+
+/// Counts the occurrences of each byte value.
+pub fn byte_counts(data: &[u8]) -> [usize; 256] {
+    let mut counts = [0; 256];
+    for byte in data {
+        counts[*byte as usize] += 1;
+    }
+    counts
+}
+
+/// Returns the Shannon entropy of `data` in bits per byte, from 0 to 8.
+pub fn entropy(data: &[u8]) -> f64 {
+    let length = data.len() as f64;
+    byte_counts(data)
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let probability = *count as f64 / length;
+            -probability * probability.log2()
+        })
+        .sum()
+}
+
+/// Minimum number of characters of the strings the cursor moves between.
+pub const MIN_STRING_LENGTH: usize = 4;
+
+fn printable(byte: u8) -> bool {
+    byte == b'\t' || (0x20..0x7f).contains(&byte)
+}
+
+/// Finds runs of at least `min_length` printable ASCII characters, either as single bytes or
+/// as UTF-16LE code units, and returns their offsets, text and whether they are UTF-16.
+pub fn strings(data: &[u8], min_length: usize) -> Vec<(usize, String, bool)> {
+    let mut strings = vec![];
+    for (step, utf16) in [(1, false), (2, true)] {
+        for alignment in 0..step {
+            let mut start = None;
+            let mut text = String::new();
+            let mut position = alignment;
+            loop {
+                let character = data
+                    .get(position..position + step)
+                    .filter(|unit| printable(unit[0]) && unit[1..].iter().all(|b| *b == 0));
+                match character {
+                    Some(unit) => {
+                        start.get_or_insert(position);
+                        text.push(unit[0] as char);
+                    }
+                    None => {
+                        if let Some(start) = start.take()
+                            && text.chars().count() >= min_length
+                        {
+                            strings.push((start, text.clone(), utf16));
+                        }
+                        text.clear();
+                        if position >= data.len() {
+                            break;
+                        }
+                    }
+                }
+                position += step;
+            }
+        }
+    }
+    strings.sort_by_key(|(offset, _, _)| *offset);
+    strings
+}
+
+/// Returns the start of the nearest run of at least `min_length` printable ASCII characters
+/// after `position`, or before it if not `forward`, scanning only as far as needed.
+pub fn next_string(
+    data: &[u8],
+    position: usize,
+    forward: bool,
+    min_length: usize,
+) -> Option<usize> {
+    let is_start = |start: usize| {
+        (start == 0 || !printable(data[start - 1]))
+            && data
+                .get(start..start + min_length)
+                .is_some_and(|run| run.iter().all(|byte| printable(*byte)))
+    };
+    match forward {
+        true => (position + 1..data.len()).find(|start| is_start(*start)),
+        false => (0..position.min(data.len()))
+            .rev()
+            .find(|start| is_start(*start)),
+    }
+}
+
+/// Returns the color used to show `entropy`, from blue for uniform data over green for code
+/// and text to red for compressed or encrypted data.
+pub fn entropy_color(entropy: f64) -> Color {
+    match entropy {
+        e if e < 1.0 => Color::DarkBlue,
+        e if e < 3.0 => Color::Blue,
+        e if e < 5.0 => Color::DarkGreen,
+        e if e < 6.5 => Color::DarkYellow,
+        e if e < 7.5 => Color::DarkMagenta,
+        _ => Color::DarkRed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_counts() {
+        let counts = byte_counts(&[1, 2, 2, 255]);
+        assert_eq!(counts[0], 0);
+        assert_eq!(counts[1], 1);
+        assert_eq!(counts[2], 2);
+        assert_eq!(counts[255], 1);
+    }
+
+    #[test]
+    fn test_entropy() {
+        assert_eq!(entropy(&[]), 0.0);
+        assert_eq!(entropy(&[7; 100]), 0.0);
+        assert_eq!(entropy(&[0, 1, 0, 1]), 1.0);
+        let all: Vec<u8> = (0..=255).collect();
+        assert_eq!(entropy(&all), 8.0);
+    }
+
+    #[test]
+    fn test_strings() {
+        let data = b"\x00abcd\x01\x01\x01h\x00e\x00l\x00l\x00o\x00\x00\x00tail";
+        assert_eq!(
+            strings(data, 4),
+            vec![
+                (1, "abcd".to_string(), false),
+                (8, "hello".to_string(), true),
+                (20, "tail".to_string(), false),
+            ]
+        );
+        assert_eq!(strings(data, 5), vec![(8, "hello".to_string(), true)]);
+        assert!(strings(&[], 1).is_empty());
+    }
+
+    #[test]
+    fn test_next_string() {
+        let data = b"\x00abcd\x01abc\x01\x01text\x00";
+        assert_eq!(next_string(data, 0, true, 4), Some(1));
+        assert_eq!(next_string(data, 1, true, 4), Some(11));
+        assert_eq!(next_string(data, 11, true, 4), None);
+        assert_eq!(next_string(data, 13, false, 4), Some(11));
+        assert_eq!(next_string(data, 11, false, 4), Some(1));
+        assert_eq!(next_string(data, 1, false, 4), None);
+        assert_eq!(next_string(data, 0, true, 3), Some(1));
+        assert_eq!(next_string(data, 1, true, 3), Some(6));
+    }
+
+    #[test]
+    fn test_entropy_color() {
+        assert_eq!(entropy_color(0.0), Color::DarkBlue);
+        assert_eq!(entropy_color(4.5), Color::DarkGreen);
+        assert_eq!(entropy_color(8.0), Color::DarkRed);
+    }
+}
@@ -0,0 +1,215 @@
+use super::notes::Note;
+use super::regions::{self, Region};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Version of the schema written by `export`, and the only one `import` accepts.
+const VERSION: u32 = 1;
+
+/// The annotations of a file as exchanged in JSON, with offsets as numbers and ranges ending
+/// before `end`.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Annotations {
+    version: u32,
+    #[serde(default)]
+    notes: Vec<NoteEntry>,
+    #[serde(default)]
+    regions: Vec<RegionEntry>,
+    #[serde(default)]
+    marks: Vec<MarkEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NoteEntry {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RegionEntry {
+    name: String,
+    start: usize,
+    end: usize,
+    color: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MarkEntry {
+    name: char,
+    offset: u32,
+}
+
+/// The notes, regions and marks of a file.
+pub struct FileAnnotations {
+    pub notes: Vec<Note>,
+    pub regions: Vec<Region>,
+    pub marks: BTreeMap<char, u32>,
+}
+
+impl FileAnnotations {
+    /// Describes how many notes, regions and marks there are, e.g. "1 note, 2 regions and 0 marks".
+    pub fn summary(&self) -> String {
+        format!(
+            "{}, {} and {}",
+            count(self.notes.len(), "note"),
+            count(self.regions.len(), "region"),
+            count(self.marks.len(), "mark")
+        )
+    }
+}
+
+fn count(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", count, noun),
+    }
+}
+
+fn invalid(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// Returns `annotations` as a JSON document.
+pub fn to_json(annotations: &FileAnnotations) -> String {
+    let annotations = Annotations {
+        version: VERSION,
+        notes: annotations
+            .notes
+            .iter()
+            .map(|note| NoteEntry {
+                start: note.range.start,
+                end: note.range.end,
+                text: note.text.clone(),
+            })
+            .collect(),
+        regions: annotations
+            .regions
+            .iter()
+            .map(|region| RegionEntry {
+                name: region.name.clone(),
+                start: region.range.start,
+                end: region.range.end,
+                color: regions::color_name(region.color).into(),
+            })
+            .collect(),
+        marks: annotations
+            .marks
+            .iter()
+            .map(|(&name, &offset)| MarkEntry { name, offset })
+            .collect(),
+    };
+    let mut json = serde_json::to_string_pretty(&annotations).unwrap_or_default();
+    json.push('\n');
+    json
+}
+
+/// Parses the notes, regions and marks of a JSON document, checking its version and that every
+/// range is non-empty, every note has text, every color is known and every mark is named `a`-`z`.
+pub fn from_json(json: &str) -> Result<FileAnnotations, std::io::Error> {
+    let annotations: Annotations =
+        serde_json::from_str(json).map_err(|error| invalid(error.to_string()))?;
+    if annotations.version != VERSION {
+        return Err(invalid(format!(
+            "unsupported version {}, expected {}",
+            annotations.version, VERSION
+        )));
+    }
+    let mut notes = vec![];
+    for (index, entry) in annotations.notes.into_iter().enumerate() {
+        let note = Note {
+            range: entry.start..entry.end,
+            text: entry.text.trim().into(),
+        };
+        if note.range.is_empty() || note.text.is_empty() || note.text.contains('\n') {
+            return Err(invalid(format!("invalid note {}", index + 1)));
+        }
+        notes.push(note);
+    }
+    let mut regions = vec![];
+    for (index, entry) in annotations.regions.into_iter().enumerate() {
+        let region = Region::new(&entry.name, entry.start..entry.end, Some(&entry.color), 0);
+        match region {
+            Some(region) if !entry.name.is_empty() => regions.push(region),
+            _ => return Err(invalid(format!("invalid region {}", index + 1))),
+        }
+    }
+    let mut marks = BTreeMap::new();
+    for (index, entry) in annotations.marks.into_iter().enumerate() {
+        if !entry.name.is_ascii_lowercase() {
+            return Err(invalid(format!("invalid mark {}", index + 1)));
+        }
+        marks.insert(entry.name, entry.offset);
+    }
+    Ok(FileAnnotations {
+        notes,
+        regions,
+        marks,
+    })
+}
+
+/// Writes `annotations` to `path` as JSON.
+pub fn export(path: &Path, annotations: &FileAnnotations) -> Result<(), std::io::Error> {
+    std::fs::write(path, to_json(annotations))
+}
+
+/// Reads the notes, regions and marks from the JSON file at `path`.
+pub fn import(path: &Path) -> Result<FileAnnotations, std::io::Error> {
+    from_json(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let annotations = FileAnnotations {
+            notes: vec![Note::parse("0x40", "\"entry\" point").unwrap()],
+            regions: vec![Region::new("header", 0..0x10, Some("cyan"), 0).unwrap()],
+            marks: BTreeMap::from([('a', 0x40), ('z', 0x1234)]),
+        };
+        let json = to_json(&annotations);
+        assert!(json.starts_with("{\n  \"version\": 1,"));
+        let FileAnnotations {
+            notes,
+            regions,
+            marks,
+        } = from_json(&json).unwrap();
+        assert_eq!(
+            (notes[0].range.clone(), notes[0].text.as_str()),
+            (0x40..0x41, "\"entry\" point")
+        );
+        assert_eq!(
+            (regions[0].name.as_str(), regions[0].range.clone()),
+            ("header", 0..0x10)
+        );
+        assert_eq!(regions::color_name(regions[0].color), "cyan");
+        assert_eq!(marks, annotations.marks);
+    }
+
+    #[test]
+    fn test_validation() {
+        let annotations = from_json("{\"version\": 1}").unwrap();
+        assert_eq!(annotations.summary(), "0 notes, 0 regions and 0 marks");
+        let error = from_json("{\"version\": 2}").err().unwrap();
+        assert_eq!(error.to_string(), "unsupported version 2, expected 1");
+        let json = "{\"version\": 1, \"notes\": [{\"start\": 4, \"end\": 2, \"text\": \"x\"}]}";
+        assert_eq!(from_json(json).err().unwrap().to_string(), "invalid note 1");
+        let json = "{\"version\": 1, \"regions\": [{\"name\": \"a\", \"start\": 0, \"end\": 2, \
+                    \"color\": \"pink\"}]}";
+        assert_eq!(
+            from_json(json).err().unwrap().to_string(),
+            "invalid region 1"
+        );
+        let json = "{\"version\": 1, \"marks\": [{\"name\": \"A\", \"offset\": 2}]}";
+        assert_eq!(from_json(json).err().unwrap().to_string(), "invalid mark 1");
+        assert!(from_json("{\"version\": 1, \"bookmarks\": []}").is_err());
+        assert!(from_json("{\"notes\": []}").is_err());
+    }
+}
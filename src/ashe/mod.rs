@@ -1,4 +0,0 @@
-mod buffer;
-pub mod editor;
-pub mod terminal;
-mod tui;
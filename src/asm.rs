@@ -0,0 +1,51 @@
+use super::disasm::Architecture;
+
+/// Whether the assembler has been built in, as it requires the `assembly` feature.
+pub const AVAILABLE: bool = cfg!(feature = "assembly");
+
+/// Assembles `code`, one or more instructions separated by `;`, to be placed at `address`.
+#[cfg(feature = "assembly")]
+pub fn assemble(architecture: Architecture, code: &str, address: u64) -> Result<Vec<u8>, String> {
+    use keystone_engine::{Arch, Keystone, Mode};
+    let (arch, mode) = match architecture {
+        Architecture::X86 => (Arch::X86, Mode::MODE_32),
+        Architecture::X86_64 => (Arch::X86, Mode::MODE_64),
+        Architecture::Arm => (Arch::ARM, Mode::LITTLE_ENDIAN | Mode::ARM),
+        Architecture::Arm64 => (Arch::ARM64, Mode::LITTLE_ENDIAN),
+    };
+    let keystone = Keystone::new(arch, mode).map_err(|error| error.to_string())?;
+    let output = keystone
+        .asm(code.into(), address)
+        .map_err(|error| error.to_string())?;
+    Ok(output.bytes)
+}
+
+#[cfg(not(feature = "assembly"))]
+pub fn assemble(
+    _architecture: Architecture,
+    _code: &str,
+    _address: u64,
+) -> Result<Vec<u8>, String> {
+    Err("Built without the assembly feature".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "assembly")]
+    #[test]
+    fn test_assemble() {
+        let bytes = assemble(Architecture::X86_64, "mov eax, 1; ret", 0).unwrap();
+        assert_eq!(bytes, b"\xb8\x01\x00\x00\x00\xc3");
+        let bytes = assemble(Architecture::Arm64, "ret", 0).unwrap();
+        assert_eq!(bytes, b"\xc0\x03\x5f\xd6");
+        assert!(assemble(Architecture::X86, "invalid", 0).is_err());
+    }
+
+    #[cfg(not(feature = "assembly"))]
+    #[test]
+    fn test_assemble_unavailable() {
+        assert!(assemble(Architecture::X86, "nop", 0).is_err());
+    }
+}
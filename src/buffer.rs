@@ -0,0 +1,636 @@
+use super::codec::Codec;
+use std::ops;
+use std::ops::Range;
+use std::path::Path;
+
+/// Largest number of inserted and removed bytes `align` looks for before falling back to `diff`.
+const MAX_EDITS: isize = 512;
+/// Largest number of unchanged bytes `align` compares before falling back to `diff`.
+const MAX_STEPS: usize = 1 << 24;
+
+/// A range of bytes which differs between two versions of the data.
+#[derive(Debug, PartialEq)]
+pub struct Change {
+    pub offset: usize,
+    pub old: Vec<u8>,
+    pub new: Vec<u8>,
+}
+
+pub struct Buffer {
+    data: Vec<u8>,
+    original: Vec<u8>,
+    saved: Vec<u8>,
+    dirty: bool,
+    /// Edits which can be undone, the latest last.
+    undo: Vec<Change>,
+    /// Undone edits which can be redone, the latest undone last.
+    redo: Vec<Change>,
+}
+
+impl Buffer {
+    pub fn new(data: Vec<u8>) -> Self {
+        Buffer {
+            original: data.clone(),
+            saved: data.clone(),
+            data,
+            dirty: false,
+            undo: vec![],
+            redo: vec![],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn slice(&self, range: Range<usize>) -> &[u8] {
+        &self.data[range]
+    }
+
+    /// Replaces the bytes in `range` with `data`, which may differ in length.
+    pub fn splice(&mut self, range: Range<usize>, data: &[u8]) {
+        self.record(Change {
+            offset: range.start,
+            old: self.data[range.clone()].to_vec(),
+            new: data.to_vec(),
+        });
+        self.dirty = true;
+        self.data.splice(range, data.iter().copied());
+    }
+
+    pub fn update(&mut self, index: usize, data: u8) {
+        match self.undo.last_mut() {
+            // Both digits typed for a byte, overwritten or inserted, are undone at once.
+            Some(change)
+                if change.offset == index && change.old.len() <= 1 && change.new.len() == 1 =>
+            {
+                change.new[0] = data;
+                self.redo.clear();
+            }
+            _ => self.record(Change {
+                offset: index,
+                old: vec![self.data[index]],
+                new: vec![data],
+            }),
+        }
+        self.dirty = true;
+        self.data[index] = data;
+    }
+
+    fn record(&mut self, change: Change) {
+        if change.old != change.new {
+            self.undo.push(change);
+            self.redo.clear();
+        }
+    }
+
+    /// Reverts the latest edit, returning its offset.
+    pub fn undo(&mut self) -> Option<usize> {
+        let change = self.undo.pop()?;
+        let end = change.offset + change.new.len();
+        self.data
+            .splice(change.offset..end, change.old.iter().copied());
+        self.dirty = self.data != self.saved;
+        let offset = change.offset;
+        self.redo.push(change);
+        Some(offset)
+    }
+
+    /// Applies the latest undone edit again, returning its offset.
+    pub fn redo(&mut self) -> Option<usize> {
+        let change = self.redo.pop()?;
+        let end = change.offset + change.old.len();
+        self.data
+            .splice(change.offset..end, change.new.iter().copied());
+        self.dirty = self.data != self.saved;
+        let offset = change.offset;
+        self.undo.push(change);
+        Some(offset)
+    }
+
+    /// Restores the bytes in `range` to their saved values. Bytes beyond the end of the saved
+    /// data are left unchanged.
+    pub fn revert(&mut self, range: Range<usize>) {
+        let end = range.end.min(self.saved.len()).max(range.start);
+        if let Some(saved) = self.saved.get(range.start..end).map(<[u8]>::to_vec) {
+            self.record(Change {
+                offset: range.start,
+                old: self.data[range.start..end].to_vec(),
+                new: saved.clone(),
+            });
+            self.data[range.start..end].copy_from_slice(&saved);
+        }
+        self.dirty = self.data != self.saved;
+    }
+
+    /// Saves only the given changes, keeping all others pending.
+    pub fn save_changes(
+        &mut self,
+        path: &Path,
+        codec: &Codec,
+        changes: &[&Change],
+    ) -> Result<(), std::io::Error> {
+        let data = self.with_changes(changes);
+        self.save_partial(path, codec, data)
+    }
+
+    /// Returns the saved data with only the given changes applied.
+    pub fn with_changes(&self, changes: &[&Change]) -> Vec<u8> {
+        let mut data = self.saved.clone();
+        for change in changes.iter().rev() {
+            let end = (change.offset + change.old.len()).min(data.len());
+            data.splice(change.offset..end, change.new.iter().copied());
+        }
+        data
+    }
+
+    /// Saves `data`, as built by `with_changes`, keeping the remaining changes pending.
+    pub fn save_partial(
+        &mut self,
+        path: &Path,
+        codec: &Codec,
+        data: Vec<u8>,
+    ) -> Result<(), std::io::Error> {
+        tracing::info!(path = %path.display(), length = data.len(), "writing changes");
+        codec.write(path, &data)?;
+        self.dirty = self.data != data;
+        self.saved = data;
+        Ok(())
+    }
+
+    pub fn save(&mut self, path: &Path, codec: &Codec) -> Result<(), std::io::Error> {
+        tracing::info!(path = %path.display(), length = self.data.len(), "writing");
+        match codec.write(path, &self.data) {
+            Ok(_) => {
+                self.saved = self.data.clone();
+                self.dirty = false;
+                Ok(())
+            }
+            error => error,
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns the data as it was last saved.
+    pub fn saved(&self) -> &[u8] {
+        &self.saved
+    }
+
+    /// Returns the ranges of bytes which differ from the saved data, at their offsets in it.
+    pub fn changes(&self) -> Vec<Change> {
+        align(&self.saved, &self.data)
+    }
+
+    /// Returns the data the buffer has been created with.
+    pub fn original(&self) -> &[u8] {
+        &self.original
+    }
+}
+
+/// Compares `old` and `new` byte by byte and returns the differing ranges. Bytes appended or
+/// removed at the end are part of the last change.
+pub fn diff(old: &[u8], new: &[u8]) -> Vec<Change> {
+    let mut changes: Vec<Change> = vec![];
+    for i in 0..old.len().max(new.len()) {
+        let (old, new) = (old.get(i), new.get(i));
+        if old == new {
+            continue;
+        }
+        match changes.last_mut() {
+            Some(change) if change.offset + change.old.len().max(change.new.len()) == i => {
+                change.old.extend(old);
+                change.new.extend(new);
+            }
+            _ => changes.push(Change {
+                offset: i,
+                old: old.into_iter().copied().collect(),
+                new: new.into_iter().copied().collect(),
+            }),
+        }
+    }
+    changes
+}
+
+/// Compares `old` and `new` like `diff`, but keeps the bytes after an insertion or removal
+/// aligned, so that they are not part of the change. Falls back to `diff` for too many edits.
+pub fn align(old: &[u8], new: &[u8]) -> Vec<Change> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+    let Some(edits) = shortest_edit(old_middle, new_middle) else {
+        return diff(old, new);
+    };
+    let mut changes: Vec<Change> = vec![];
+    let (mut i, mut j) = (0, 0);
+    let mut open = false;
+    for edit in edits {
+        if edit == Edit::Keep {
+            i += 1;
+            j += 1;
+            open = false;
+            continue;
+        }
+        if !open {
+            changes.push(Change {
+                offset: prefix + i,
+                old: vec![],
+                new: vec![],
+            });
+            open = true;
+        }
+        let change = changes.last_mut().unwrap();
+        match edit {
+            Edit::Remove => {
+                change.old.push(old_middle[i]);
+                i += 1;
+            }
+            _ => {
+                change.new.push(new_middle[j]);
+                j += 1;
+            }
+        }
+    }
+    changes
+}
+
+#[derive(PartialEq)]
+enum Edit {
+    Keep,
+    Remove,
+    Insert,
+}
+
+/// Finds the shortest sequence of edits turning `a` into `b` with the algorithm of Myers, or
+/// `None` if it exceeds `MAX_EDITS` edits or `MAX_STEPS` comparisons.
+fn shortest_edit(a: &[u8], b: &[u8]) -> Option<Vec<Edit>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let limit = (n + m).min(MAX_EDITS);
+    let index = |k: isize| (k + limit + 1) as usize;
+    // The furthest position in `a` reached on each diagonal `k = x - y`, for each edit count.
+    let mut furthest = vec![0isize; 2 * limit as usize + 3];
+    let from_above = |furthest: &[isize], d: isize, k: isize| {
+        k == -d || (k != d && furthest[index(k - 1)] < furthest[index(k + 1)])
+    };
+    let mut trace = vec![];
+    let mut steps = 0;
+    let mut found = false;
+    'search: for d in 0..=limit {
+        trace.push(furthest.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = match from_above(&furthest, d, k) {
+                true => furthest[index(k + 1)],
+                false => furthest[index(k - 1)] + 1,
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+                steps += 1;
+            }
+            if steps > MAX_STEPS {
+                return None;
+            }
+            furthest[index(k)] = x;
+            if x >= n && y >= m {
+                found = true;
+                break 'search;
+            }
+        }
+    }
+    if !found {
+        return None;
+    }
+
+    let mut edits = vec![];
+    let (mut x, mut y) = (n, m);
+    for (d, furthest) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let previous = if from_above(furthest, d, k) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let previous_x = furthest[index(previous)];
+        let previous_y = previous_x - previous;
+        while x > previous_x && y > previous_y {
+            edits.push(Edit::Keep);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            edits.push(match x == previous_x {
+                true => Edit::Insert,
+                false => Edit::Remove,
+            });
+            (x, y) = (previous_x, previous_y);
+        }
+    }
+    edits.reverse();
+    Some(edits)
+}
+
+impl ops::Index<usize> for Buffer {
+    type Output = u8;
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.data[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn test_new_buffer() {
+        let data = vec![1, 2, 3];
+        let buffer = Buffer::new(data.clone());
+
+        assert_eq!(buffer.len(), data.len());
+        assert!(!buffer.dirty);
+        assert_eq!(buffer[0], 1);
+        assert_eq!(buffer[1], 2);
+        assert_eq!(buffer[2], 3);
+    }
+
+    #[test]
+    fn test_buffer_len() {
+        let buffer = Buffer::new(vec![1, 2, 3]);
+        assert_eq!(buffer.len(), 3);
+
+        let empty_buffer = Buffer::new(vec![]);
+        assert_eq!(empty_buffer.len(), 0);
+    }
+
+    #[test]
+    fn test_update() {
+        let mut buffer = Buffer::new(vec![1, 2, 3]);
+        buffer.update(1, 5);
+
+        assert!(buffer.dirty);
+        assert_eq!(buffer[1], 5);
+    }
+
+    #[test]
+    fn test_splice() {
+        let mut buffer = Buffer::new(vec![1, 2, 3]);
+        buffer.splice(1..2, &[7, 8, 9]);
+        assert!(buffer.is_dirty());
+        assert_eq!(buffer.slice(0..buffer.len()), &[1, 7, 8, 9, 3]);
+
+        buffer.splice(0..4, &[]);
+        assert_eq!(buffer.slice(0..buffer.len()), &[3]);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut buffer = Buffer::new(vec![1, 2, 3]);
+        assert_eq!(buffer.undo(), None);
+        buffer.update(1, 0x0a);
+        buffer.update(1, 0xab);
+        buffer.splice(2..3, &[7, 8]);
+        assert_eq!(buffer.undo(), Some(2));
+        assert_eq!(buffer.slice(0..buffer.len()), &[1, 0xab, 3]);
+        assert_eq!(buffer.undo(), Some(1));
+        assert_eq!(buffer.slice(0..buffer.len()), &[1, 2, 3]);
+        assert!(!buffer.is_dirty());
+        assert_eq!(buffer.redo(), Some(1));
+        assert_eq!(buffer.slice(0..buffer.len()), &[1, 0xab, 3]);
+        buffer.revert(0..3);
+        assert_eq!(buffer.redo(), None);
+        assert_eq!(buffer.undo(), Some(0));
+        assert_eq!(buffer[1], 0xab);
+
+        buffer.splice(1..1, &[0x0c]);
+        buffer.update(1, 0xcd);
+        assert_eq!(buffer.slice(0..buffer.len()), &[1, 0xcd, 0xab, 3]);
+        assert_eq!(buffer.undo(), Some(1));
+        assert_eq!(buffer.slice(0..buffer.len()), &[1, 0xab, 3]);
+    }
+
+    #[test]
+    fn test_is_dirty() {
+        let mut buffer = Buffer::new(vec![1, 2, 3]);
+        assert!(!buffer.is_dirty());
+
+        buffer.update(1, 5);
+        assert!(buffer.is_dirty());
+    }
+
+    #[test]
+    fn test_slice() {
+        let buffer = Buffer::new(vec![1, 2, 3, 4]);
+        assert_eq!(buffer.slice(1..3), &[2, 3]);
+        assert_eq!(buffer.slice(0..0), &[] as &[u8]);
+    }
+
+    #[test]
+    fn test_save_success() {
+        let mut buffer = Buffer::new(vec![1, 2, 3]);
+        let path = Path::new("test_save_success.bin");
+
+        assert!(buffer.save(path, &Codec::Raw).is_ok());
+        assert!(!buffer.is_dirty());
+
+        let saved_data = fs::read(path).unwrap();
+        assert_eq!(saved_data, vec![1, 2, 3]);
+
+        buffer.update(0, 5);
+        assert_eq!(buffer.saved(), &[1, 2, 3]);
+        assert!(buffer.save(path, &Codec::Raw).is_ok());
+        assert_eq!(buffer.saved(), &[5, 2, 3]);
+        assert_eq!(buffer.original(), &[1, 2, 3]);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_save_changes() {
+        let mut buffer = Buffer::new(vec![1, 2, 3, 4]);
+        let path = Path::new("test_save_changes.bin");
+        buffer.update(0, 5);
+        buffer.update(2, 6);
+        buffer.splice(4..4, &[7]);
+        let changes = buffer.changes();
+        assert_eq!(changes.len(), 3);
+
+        assert!(
+            buffer
+                .save_changes(path, &Codec::Raw, &[&changes[1], &changes[2]])
+                .is_ok()
+        );
+        assert_eq!(fs::read(path).unwrap(), vec![1, 2, 6, 4, 7]);
+        assert!(buffer.is_dirty());
+        assert_eq!(buffer.changes().len(), 1);
+
+        let changes = buffer.changes();
+        assert!(
+            buffer
+                .save_changes(path, &Codec::Raw, &[&changes[0]])
+                .is_ok()
+        );
+        assert_eq!(fs::read(path).unwrap(), vec![5, 2, 6, 4, 7]);
+        assert!(!buffer.is_dirty());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_save_changes_after_insertion() {
+        let mut buffer = Buffer::new(vec![1, 2, 3, 4, 5, 6]);
+        let path = Path::new("test_save_changes_after_insertion.bin");
+        buffer.splice(1..1, &[9]);
+        buffer.update(6, 7);
+        let changes = buffer.changes();
+        assert_eq!(
+            changes,
+            vec![
+                Change {
+                    offset: 1,
+                    old: vec![],
+                    new: vec![9]
+                },
+                Change {
+                    offset: 5,
+                    old: vec![6],
+                    new: vec![7]
+                },
+            ]
+        );
+        assert!(
+            buffer
+                .save_changes(path, &Codec::Raw, &[&changes[1]])
+                .is_ok()
+        );
+        assert_eq!(fs::read(path).unwrap(), vec![1, 2, 3, 4, 5, 7]);
+        assert_eq!(buffer.changes().len(), 1);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_save_error() {
+        let mut buffer = Buffer::new(vec![1, 2, 3]);
+        buffer.update(1, 5);
+
+        let path = Path::new("/invalid/test_save_error.bin");
+        assert!(buffer.is_dirty());
+        assert!(buffer.save(path, &Codec::Raw).is_err());
+        assert!(buffer.is_dirty());
+    }
+
+    #[test]
+    fn test_diff() {
+        assert!(diff(&[1, 2], &[1, 2]).is_empty());
+        assert_eq!(
+            diff(&[], &[1, 2]),
+            vec![Change {
+                offset: 0,
+                old: vec![],
+                new: vec![1, 2]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_align() {
+        assert!(align(&[1, 2], &[1, 2]).is_empty());
+        assert_eq!(
+            align(&[1, 2, 3, 1, 2, 3], &[1, 2, 3, 2, 3]),
+            vec![Change {
+                offset: 3,
+                old: vec![1],
+                new: vec![]
+            }]
+        );
+        // Too many edits to align.
+        let old = vec![0; 2000];
+        let new = vec![1; 1000];
+        assert_eq!(align(&old, &new), diff(&old, &new));
+    }
+
+    #[test]
+    fn test_changes() {
+        let mut buffer = Buffer::new(vec![1, 2, 3, 4, 5]);
+        assert!(buffer.changes().is_empty());
+
+        buffer.update(1, 7);
+        buffer.update(2, 8);
+        buffer.update(4, 9);
+        buffer.splice(5..5, &[10]);
+        assert_eq!(
+            buffer.changes(),
+            vec![
+                Change {
+                    offset: 1,
+                    old: vec![2, 3],
+                    new: vec![7, 8]
+                },
+                Change {
+                    offset: 4,
+                    old: vec![5],
+                    new: vec![9, 10]
+                },
+            ]
+        );
+
+        buffer.splice(2..6, &[]);
+        assert_eq!(
+            buffer.changes(),
+            vec![Change {
+                offset: 1,
+                old: vec![2, 3, 4, 5],
+                new: vec![7]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_revert() {
+        let mut buffer = Buffer::new(vec![1, 2, 3]);
+        buffer.update(0, 5);
+        buffer.update(2, 6);
+        buffer.revert(0..1);
+        assert_eq!(buffer.slice(0..3), &[1, 2, 6]);
+        assert!(buffer.is_dirty());
+        buffer.revert(2..3);
+        assert!(!buffer.is_dirty());
+
+        buffer.splice(3..3, &[7, 8]);
+        buffer.update(1, 9);
+        buffer.revert(0..5);
+        assert_eq!(buffer.slice(0..5), &[1, 2, 3, 7, 8]);
+        assert!(buffer.is_dirty());
+    }
+
+    #[test]
+    fn test_original() {
+        let mut buffer = Buffer::new(vec![1, 2, 3]);
+        buffer.update(1, 5);
+        buffer.splice(0..1, &[]);
+        assert_eq!(buffer.original(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_index_access() {
+        let buffer = Buffer::new(vec![1, 2, 3]);
+
+        assert_eq!(buffer[0], 1);
+        assert_eq!(buffer[1], 2);
+        assert_eq!(buffer[2], 3);
+    }
+}
@@ -0,0 +1,199 @@
+/// Evaluates an integer expression such as `0x1f4 + 12*16`, with the operators of C by their
+/// precedence: `|`, `^`, `&`, `<<` and `>>`, `+` and `-`, `*`, `/` and `%`, and the unary `-`
+/// and `~`. Numbers are decimal, or hexadecimal, binary or octal with `0x`, `0b` or `0o`.
+/// Other words, such as `end` or `'a`, are looked up with `name`.
+pub fn evaluate(expression: &str, name: impl Fn(&str) -> Option<i64>) -> Result<i64, String> {
+    let tokens = tokenize(expression)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+        name: &name,
+    };
+    let value = parser.binary(0)?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(value),
+        Some(token) => Err(format!("Unexpected {}", token.describe())),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Number(i64),
+    Name(String),
+    Operator(&'static str),
+    Open,
+    Close,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(value) => value.to_string(),
+            Token::Name(name) => name.clone(),
+            Token::Operator(operator) => operator.to_string(),
+            Token::Open => "(".into(),
+            Token::Close => ")".into(),
+        }
+    }
+}
+
+/// The binary operators, the loosest binding first.
+const LEVELS: &[&[&str]] = &[
+    &["|"],
+    &["^"],
+    &["&"],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut rest = expression.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let length = if c.is_ascii_alphanumeric() || c == '_' || c == '\'' {
+            let length = rest[1..]
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .map_or(rest.len(), |length| length + 1);
+            let word = &rest[..length];
+            tokens.push(match c.is_ascii_digit() {
+                true => {
+                    Token::Number(parse_integer(word).ok_or(format!("Invalid number {}", word))?)
+                }
+                false => Token::Name(word.into()),
+            });
+            length
+        } else if c == '(' || c == ')' {
+            tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            1
+        } else {
+            let operator = ["<<", ">>", "|", "^", "&", "+", "-", "*", "/", "%", "~"]
+                .into_iter()
+                .find(|operator| rest.starts_with(operator))
+                .ok_or(format!("Unexpected {}", c))?;
+            tokens.push(Token::Operator(operator));
+            operator.len()
+        };
+        rest = rest[length..].trim_start();
+    }
+    Ok(tokens)
+}
+
+fn parse_integer(word: &str) -> Option<i64> {
+    let (digits, radix) = match word.get(..2).map(|prefix| prefix.to_ascii_lowercase()) {
+        Some(prefix) if prefix == "0x" => (&word[2..], 16),
+        Some(prefix) if prefix == "0b" => (&word[2..], 2),
+        Some(prefix) if prefix == "0o" => (&word[2..], 8),
+        _ => (word, 10),
+    };
+    i64::from_str_radix(digits, radix).ok()
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    name: &'a dyn Fn(&str) -> Option<i64>,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.position)?;
+        self.position += 1;
+        Some(token)
+    }
+
+    /// Parses operands joined by the operators of `LEVELS[level]` or binding tighter.
+    fn binary(&mut self, level: usize) -> Result<i64, String> {
+        let Some(operators) = LEVELS.get(level) else {
+            return self.unary();
+        };
+        let mut value = self.binary(level + 1)?;
+        while let Some(&Token::Operator(operator)) = self.tokens.get(self.position) {
+            if !operators.contains(&operator) {
+                break;
+            }
+            self.position += 1;
+            let operand = self.binary(level + 1)?;
+            value = apply(operator, value, operand)?;
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<i64, String> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(*value),
+            Some(Token::Name(name)) => {
+                (self.name)(name).ok_or_else(|| format!("Unknown name {}", name))
+            }
+            Some(Token::Operator("-")) => {
+                let value = self.unary()?;
+                value.checked_neg().ok_or_else(|| "Overflow".into())
+            }
+            Some(Token::Operator("~")) => Ok(!self.unary()?),
+            Some(Token::Open) => {
+                let value = self.binary(0)?;
+                match self.next() {
+                    Some(Token::Close) => Ok(value),
+                    _ => Err("Missing )".into()),
+                }
+            }
+            Some(token) => Err(format!("Unexpected {}", token.describe())),
+            None => Err("Unexpected end of expression".into()),
+        }
+    }
+}
+
+fn apply(operator: &str, left: i64, right: i64) -> Result<i64, String> {
+    let shift = || u32::try_from(right).ok().filter(|&shift| shift < 64);
+    let value = match operator {
+        "|" => Some(left | right),
+        "^" => Some(left ^ right),
+        "&" => Some(left & right),
+        "<<" => shift().and_then(|shift| left.checked_shl(shift)),
+        ">>" => shift().and_then(|shift| left.checked_shr(shift)),
+        "+" => left.checked_add(right),
+        "-" => left.checked_sub(right),
+        "*" => left.checked_mul(right),
+        "/" | "%" if right == 0 => return Err("Division by zero".into()),
+        "/" => left.checked_div(right),
+        _ => left.checked_rem(right),
+    };
+    value.ok_or_else(|| "Overflow".into())
+}
+
+/// Formats `value` in hexadecimal, decimal and binary, e.g. `0x1f4 = 500 = 0b111110100`.
+pub fn format(value: i64) -> String {
+    let sign = if value < 0 { "-" } else { "" };
+    let magnitude = value.unsigned_abs();
+    format!("{sign}0x{magnitude:x} = {value} = {sign}0b{magnitude:b}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calc(expression: &str) -> Result<i64, String> {
+        evaluate(expression, |name| (name == "end").then_some(0x100))
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(calc("0x1f4 + 12*16"), Ok(692));
+        assert_eq!(calc("(1 + 2) * -3"), Ok(-9));
+        assert_eq!(calc("1 << 4 | 0b11 & ~1"), Ok(18));
+        assert_eq!(calc("end - 0o10 % 3"), Ok(254));
+        assert_eq!(calc("1 / 0"), Err("Division by zero".into()));
+        assert_eq!(calc("start"), Err("Unknown name start".into()));
+        assert_eq!(calc("(1 + 2"), Err("Missing )".into()));
+        assert_eq!(calc("1 2"), Err("Unexpected 2".into()));
+        assert_eq!(calc("0x"), Err("Invalid number 0x".into()));
+        assert_eq!(calc("1 + "), Err("Unexpected end of expression".into()));
+        assert_eq!(calc("1 << 64"), Err("Overflow".into()));
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format(500), "0x1f4 = 500 = 0b111110100");
+        assert_eq!(format(-2), "-0x2 = -2 = -0b10");
+    }
+}
@@ -0,0 +1,156 @@
+use super::magic::{self, Signature};
+
+/// A file found embedded in the buffer.
+pub struct Carved {
+    pub offset: usize,
+    pub length: usize,
+    pub signature: &'static Signature,
+}
+
+/// Scans `data` for known file signatures. The length of a file is read from its headers for
+/// formats which allow it and otherwise extends up to the next file found.
+pub fn carve(data: &[u8]) -> Vec<Carved> {
+    let mut found: Vec<(usize, &'static Signature)> = vec![];
+    for position in 0..data.len() {
+        // Two byte signatures match too much random data to be useful.
+        if let Some(signature) = magic::matches_at(data, position).find(|s| s.magic.len() > 2) {
+            found.push((position, signature));
+        }
+    }
+    let mut carved = vec![];
+    for (index, (offset, signature)) in found.iter().enumerate() {
+        let available = data.len() - offset;
+        let next = found.get(index + 1).map_or(data.len(), |(next, _)| *next);
+        let length = estimate_length(signature.extension, &data[*offset..])
+            .filter(|length| *length <= available)
+            .unwrap_or(next - offset);
+        carved.push(Carved {
+            offset: *offset,
+            length,
+            signature,
+        });
+    }
+    carved
+}
+
+/// Reads the length of the file at the start of `data` from its headers.
+fn estimate_length(extension: &str, data: &[u8]) -> Option<usize> {
+    let u16_le = |at: usize| Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?));
+    let u32_le = |at: usize| Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?));
+    let u32_be = |at: usize| Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?));
+    let find = |needle: &[u8], from: usize| {
+        data.get(from..)?
+            .windows(needle.len())
+            .position(|window| window == needle)
+            .map(|position| from + position)
+    };
+    match extension {
+        "png" => {
+            let mut position = 8;
+            loop {
+                let length = u32_be(position)? as usize;
+                let kind = data.get(position + 4..position + 8)?;
+                position = position.checked_add(length)?.checked_add(12)?;
+                if kind == b"IEND" {
+                    return Some(position);
+                }
+            }
+        }
+        "jpg" => find(b"\xff\xd9", 2).map(|end| end + 2),
+        "zip" => {
+            let end = find(b"PK\x05\x06", 4)?;
+            Some(end + 22 + u16_le(end + 20)? as usize)
+        }
+        "pdf" => find(b"%%EOF", 5).map(|end| end + 5),
+        "riff" => Some(u32_le(4)? as usize + 8),
+        "elf" => {
+            let big_endian = *data.get(5)? == 2;
+            let read = |at: usize, size: usize| {
+                let mut bytes = data.get(at..at + size)?.to_vec();
+                if !big_endian {
+                    bytes.reverse();
+                }
+                Some(
+                    bytes
+                        .iter()
+                        .fold(0usize, |value, b| value << 8 | *b as usize),
+                )
+            };
+            let (offset, entry_size, count) = match data.get(4)? {
+                1 => (read(0x20, 4)?, read(0x2e, 2)?, read(0x30, 2)?),
+                2 => (read(0x28, 8)?, read(0x3a, 2)?, read(0x3c, 2)?),
+                _ => return None,
+            };
+            offset.checked_add(entry_size.checked_mul(count)?)
+        }
+        "sqlite" => {
+            let page_size = match u16::from_be_bytes(data.get(16..18)?.try_into().ok()?) {
+                1 => 0x10000,
+                size => size as usize,
+            };
+            page_size.checked_mul(u32_be(28)? as usize)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png() -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend(b"\x00\x00\x00\x01IHDR\x00abcd");
+        png.extend(b"\x00\x00\x00\x00IEND\xae\x42\x60\x82");
+        png
+    }
+
+    #[test]
+    fn test_carve() {
+        let mut data = vec![0; 16];
+        data.extend(png());
+        data.extend([0; 4]);
+        data.extend(b"\x1f\x8b\x08\x00");
+        data.extend([1; 8]);
+        let carved = carve(&data);
+        assert_eq!(carved.len(), 2);
+        assert_eq!(carved[0].offset, 16);
+        assert_eq!(carved[0].length, 33);
+        assert_eq!(carved[0].signature.extension, "png");
+        assert_eq!(carved[1].offset, 53);
+        assert_eq!(carved[1].length, 12);
+    }
+
+    #[test]
+    fn test_carve_ignores_short_signatures() {
+        assert!(carve(b"..MZ..BM..").is_empty());
+    }
+
+    #[test]
+    fn test_estimate_length() {
+        assert_eq!(estimate_length("png", &png()), Some(33));
+        assert_eq!(estimate_length("png", &png()[..20]), None);
+        assert_eq!(
+            estimate_length("jpg", b"\xff\xd8\xff\x00\xff\xd9\x00"),
+            Some(6)
+        );
+        let mut zip = b"PK\x03\x04....PK\x05\x06".to_vec();
+        zip.extend([0; 16]);
+        zip.extend([2, 0, b'h', b'i']);
+        assert_eq!(estimate_length("zip", &zip), Some(32));
+        assert_eq!(
+            estimate_length("riff", b"RIFF\x04\x00\x00\x00WAVE"),
+            Some(12)
+        );
+        let mut elf = vec![0; 0x40];
+        elf[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        elf[0x28] = 0x00;
+        elf[0x29] = 0x10;
+        elf[0x3a] = 0x40;
+        elf[0x3c] = 0x02;
+        assert_eq!(estimate_length("elf", &elf), Some(0x1080));
+        elf[0x28..0x30].fill(0xff);
+        assert_eq!(estimate_length("elf", &elf), None);
+        assert_eq!(estimate_length("gz", b"\x1f\x8b\x08"), None);
+    }
+}
@@ -0,0 +1,86 @@
+use super::hash::HashAlgorithm;
+use super::parse::{parse_number, parse_range};
+use std::ops::Range;
+
+/// Bytes holding a checksum over a range of the file, updated whenever the file is written.
+pub struct ChecksumField {
+    algorithm: HashAlgorithm,
+    range: Range<usize>,
+    offset: usize,
+    little_endian: bool,
+}
+
+impl ChecksumField {
+    /// Parses the arguments of the `:checksum` command, `<algorithm> <start>..<end> <offset>`
+    /// optionally followed by `le` to store the checksum in little-endian byte order.
+    pub fn parse<'a>(mut args: impl Iterator<Item = &'a str>) -> Option<Self> {
+        let algorithm = args.next()?.parse().ok()?;
+        let range = parse_range(args.next()?)?;
+        let offset = parse_number(args.next()?)?;
+        let little_endian = match args.next() {
+            None | Some("be") => false,
+            Some("le") => true,
+            Some(_) => return None,
+        };
+        if range.start > range.end || args.next().is_some() {
+            return None;
+        }
+        Some(ChecksumField {
+            algorithm,
+            range,
+            offset,
+            little_endian,
+        })
+    }
+
+    /// Returns the range and the bytes to write, or `None` if the field lies outside `data`.
+    pub fn compute(&self, data: &[u8]) -> Option<(Range<usize>, Vec<u8>)> {
+        let mut digest = self.algorithm.digest_bytes(data.get(self.range.clone())?);
+        if self.little_endian {
+            digest.reverse();
+        }
+        let field = self.offset..self.offset.checked_add(digest.len())?;
+        (field.end <= data.len()).then_some((field, digest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let field = ChecksumField::parse("crc32 0x4..16 0".split_whitespace()).unwrap();
+        assert_eq!(field.range, 4..16);
+        assert_eq!(field.offset, 0);
+        assert!(!field.little_endian);
+        assert!(ChecksumField::parse("crc32 4..16 0 le".split_whitespace()).is_some());
+        assert!(ChecksumField::parse("crc32 4..16".split_whitespace()).is_none());
+        assert!(ChecksumField::parse("crc32 16..4 0".split_whitespace()).is_none());
+        assert!(ChecksumField::parse("crc64 4..16 0".split_whitespace()).is_none());
+        assert!(ChecksumField::parse("crc32 4..16 0 xe".split_whitespace()).is_none());
+    }
+
+    #[test]
+    fn test_compute() {
+        let mut data = vec![0; 4];
+        data.extend(b"abc");
+        let field = ChecksumField::parse("crc32 4..7 0".split_whitespace()).unwrap();
+        assert_eq!(
+            field.compute(&data),
+            Some((0..4, vec![0x35, 0x24, 0x41, 0xc2]))
+        );
+        let field = ChecksumField::parse("crc32 4..7 0 le".split_whitespace()).unwrap();
+        assert_eq!(
+            field.compute(&data),
+            Some((0..4, vec![0xc2, 0x41, 0x24, 0x35]))
+        );
+        let field = ChecksumField::parse("crc32 4..7 5".split_whitespace()).unwrap();
+        assert_eq!(field.compute(&data), None);
+        let field = ChecksumField::parse("crc32 4..8 0".split_whitespace()).unwrap();
+        assert_eq!(field.compute(&data), None);
+        let field =
+            ChecksumField::parse("crc32 4..7 0xffffffffffffffff".split_whitespace()).unwrap();
+        assert_eq!(field.compute(&data), None);
+    }
+}
@@ -0,0 +1,169 @@
+use flate2::read::MultiGzDecoder;
+use flate2::{Compression, GzBuilder};
+use std::io::{Read, Write};
+
+/// The parameters needed to recompress a file like the original.
+pub enum Layout {
+    /// The header fields of the first gzip member and the compression level implied by its
+    /// extra flags.
+    Gzip {
+        filename: Option<Vec<u8>>,
+        comment: Option<Vec<u8>>,
+        extra: Option<Vec<u8>>,
+        mtime: u32,
+        operating_system: u8,
+        level: Compression,
+    },
+    /// Whether frames carry a checksum of their content.
+    #[cfg(feature = "compression")]
+    Zstd { checksum: bool },
+    /// The integrity check of the stream.
+    #[cfg(feature = "compression")]
+    Xz { check: xz2::stream::Check },
+}
+
+/// Decompresses a gzip file. Multiple members are joined and written back as one.
+pub fn decode_gzip(contents: &[u8]) -> Result<(Layout, Vec<u8>), std::io::Error> {
+    let mut decoder = MultiGzDecoder::new(contents);
+    let mut data = vec![];
+    decoder.read_to_end(&mut data)?;
+    let header = decoder
+        .header()
+        .ok_or_else(|| invalid_data("missing gzip header"))?;
+    let level = match contents.get(8) {
+        Some(2) => Compression::best(),
+        Some(4) => Compression::fast(),
+        _ => Compression::default(),
+    };
+    let layout = Layout::Gzip {
+        filename: header.filename().map(<[u8]>::to_vec),
+        comment: header.comment().map(<[u8]>::to_vec),
+        extra: header.extra().map(<[u8]>::to_vec),
+        mtime: header.mtime(),
+        operating_system: header.operating_system(),
+        level,
+    };
+    Ok((layout, data))
+}
+
+/// Decompresses a Zstandard file.
+#[cfg(feature = "compression")]
+pub fn decode_zstd(contents: &[u8]) -> Result<(Layout, Vec<u8>), std::io::Error> {
+    let data = zstd::decode_all(contents)?;
+    // The frame header descriptor follows the magic number.
+    let checksum = contents
+        .get(4)
+        .is_some_and(|descriptor| descriptor & 0x04 != 0);
+    Ok((Layout::Zstd { checksum }, data))
+}
+
+/// Decompresses an xz file.
+#[cfg(feature = "compression")]
+pub fn decode_xz(contents: &[u8]) -> Result<(Layout, Vec<u8>), std::io::Error> {
+    use xz2::stream::Check;
+    let mut data = vec![];
+    xz2::read::XzDecoder::new_multi_decoder(contents).read_to_end(&mut data)?;
+    // The stream flags follow the magic bytes.
+    let check = match contents.get(7).map(|flags| flags & 0x0f) {
+        Some(0) => Check::None,
+        Some(1) => Check::Crc32,
+        Some(10) => Check::Sha256,
+        _ => Check::Crc64,
+    };
+    Ok((Layout::Xz { check }, data))
+}
+
+pub fn encode(layout: &Layout, data: &[u8]) -> Vec<u8> {
+    // Compressing into memory cannot fail.
+    let failed = "compressing into memory failed";
+    match layout {
+        Layout::Gzip {
+            filename,
+            comment,
+            extra,
+            mtime,
+            operating_system,
+            level,
+        } => {
+            let mut builder = GzBuilder::new()
+                .mtime(*mtime)
+                .operating_system(*operating_system);
+            if let Some(filename) = filename {
+                builder = builder.filename(filename.clone());
+            }
+            if let Some(comment) = comment {
+                builder = builder.comment(comment.clone());
+            }
+            if let Some(extra) = extra {
+                builder = builder.extra(extra.clone());
+            }
+            let mut encoder = builder.write(vec![], *level);
+            encoder.write_all(data).expect(failed);
+            encoder.finish().expect(failed)
+        }
+        #[cfg(feature = "compression")]
+        Layout::Zstd { checksum } => {
+            let mut encoder =
+                zstd::Encoder::new(vec![], zstd::DEFAULT_COMPRESSION_LEVEL).expect(failed);
+            encoder.include_checksum(*checksum).expect(failed);
+            encoder.write_all(data).expect(failed);
+            encoder.finish().expect(failed)
+        }
+        #[cfg(feature = "compression")]
+        Layout::Xz { check } => {
+            let stream = xz2::stream::Stream::new_easy_encoder(6, *check).expect(failed);
+            let mut encoder = xz2::write::XzEncoder::new_stream(vec![], stream);
+            encoder.write_all(data).expect(failed);
+            encoder.finish().expect(failed)
+        }
+    }
+}
+
+fn invalid_data(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gzip() {
+        let mut encoder = GzBuilder::new()
+            .filename("data.bin")
+            .mtime(1234)
+            .write(vec![], Compression::best());
+        encoder.write_all(b"compressed data").unwrap();
+        let contents = encoder.finish().unwrap();
+        let (layout, data) = decode_gzip(&contents).unwrap();
+        assert_eq!(data, b"compressed data");
+        // The header fields and the compression level are kept.
+        assert_eq!(encode(&layout, &data), contents);
+        let (_, data) = decode_gzip(&encode(&layout, b"edited")).unwrap();
+        assert_eq!(data, b"edited");
+        assert!(decode_gzip(b"\x1f\x8b").is_err());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_zstd_and_xz() {
+        let contents = zstd::encode_all(&b"zstd data"[..], 3).unwrap();
+        let (layout, data) = decode_zstd(&contents).unwrap();
+        assert_eq!(data, b"zstd data");
+        assert_eq!(
+            decode_zstd(&encode(&layout, b"edited")).unwrap().1,
+            b"edited"
+        );
+        let mut encoder = xz2::write::XzEncoder::new(vec![], 6);
+        encoder.write_all(b"xz data").unwrap();
+        let (layout, data) = decode_xz(&encoder.finish().unwrap()).unwrap();
+        assert_eq!(data, b"xz data");
+        assert!(matches!(
+            layout,
+            Layout::Xz {
+                check: xz2::stream::Check::Crc64
+            }
+        ));
+        assert_eq!(decode_xz(&encode(&layout, b"edited")).unwrap().1, b"edited");
+    }
+}
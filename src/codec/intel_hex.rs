@@ -0,0 +1,169 @@
+use super::super::parse::{invalid_data, parse_hex};
+use super::{RecordLayout, format_hex_bytes};
+
+const DATA: u8 = 0x00;
+const END_OF_FILE: u8 = 0x01;
+const EXTENDED_SEGMENT_ADDRESS: u8 = 0x02;
+const START_SEGMENT_ADDRESS: u8 = 0x03;
+const EXTENDED_LINEAR_ADDRESS: u8 = 0x04;
+const START_LINEAR_ADDRESS: u8 = 0x05;
+
+/// Placement of the data records and the metadata needed to re-encode the file.
+///
+/// Addresses above 64 KiB are always written using extended linear address records.
+pub struct Layout {
+    records: RecordLayout,
+    start: Option<(u8, Vec<u8>)>,
+    line_ending: &'static str,
+}
+
+/// Decodes Intel HEX records into a contiguous image starting at the lowest address.
+pub fn decode(contents: &[u8]) -> Result<(Layout, Vec<u8>), std::io::Error> {
+    let text = std::str::from_utf8(contents).map_err(|_| invalid_data(0, "not a text file"))?;
+    let mut upper = 0;
+    let mut records = vec![];
+    let mut start = None;
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let record = line
+            .strip_prefix(':')
+            .and_then(parse_hex)
+            .ok_or_else(|| invalid_data(number, "malformed record"))?;
+        if record.len() < 5 || record.len() != record[0] as usize + 5 {
+            return Err(invalid_data(number, "invalid record length"));
+        }
+        if record.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0 {
+            return Err(invalid_data(number, "checksum mismatch"));
+        }
+        let address = u16::from_be_bytes([record[1], record[2]]) as u32;
+        let payload = &record[4..record.len() - 1];
+        match (record[3], payload.len()) {
+            (DATA, _) => records.push((upper + address, payload.to_vec())),
+            (END_OF_FILE, _) => break,
+            (EXTENDED_SEGMENT_ADDRESS, 2) => {
+                upper = (u16::from_be_bytes([payload[0], payload[1]]) as u32) << 4;
+            }
+            (EXTENDED_LINEAR_ADDRESS, 2) => {
+                upper = (u16::from_be_bytes([payload[0], payload[1]]) as u32) << 16;
+            }
+            (START_SEGMENT_ADDRESS | START_LINEAR_ADDRESS, 4) => {
+                start = Some((record[3], payload.to_vec()));
+            }
+            _ => return Err(invalid_data(number, "unsupported record")),
+        }
+    }
+
+    let (records, data) = RecordLayout::decode(records)?;
+    let line_ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    Ok((
+        Layout {
+            records,
+            start,
+            line_ending,
+        },
+        data,
+    ))
+}
+
+/// Encodes `data` following the original record layout.
+pub fn encode(layout: &Layout, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    let mut output = String::new();
+    let mut upper = 0;
+    for (address, payload) in layout.records.split(data)? {
+        if address >> 16 != upper {
+            upper = address >> 16;
+            let payload = (upper as u16).to_be_bytes();
+            output += &format_record(EXTENDED_LINEAR_ADDRESS, 0, &payload, layout.line_ending);
+        }
+        output += &format_record(DATA, address as u16, payload, layout.line_ending);
+    }
+    if let Some((kind, payload)) = &layout.start {
+        output += &format_record(*kind, 0, payload, layout.line_ending);
+    }
+    output += &format_record(END_OF_FILE, 0, &[], layout.line_ending);
+    Ok(output.into_bytes())
+}
+
+fn format_record(kind: u8, address: u16, payload: &[u8], line_ending: &str) -> String {
+    let mut record = vec![payload.len() as u8];
+    record.extend(address.to_be_bytes());
+    record.push(kind);
+    record.extend(payload);
+    let checksum = record
+        .iter()
+        .fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+        .wrapping_neg();
+    record.push(checksum);
+    format!(":{}{}", format_hex_bytes(&record), line_ending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+:020000040800F2
+:0400000001020304F2
+:02000800AABB91
+:0400000508000000EF
+:00000001FF
+";
+
+    #[test]
+    fn test_decode() {
+        let (layout, data) = decode(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(layout.records.base, 0x0800_0000);
+        assert_eq!(
+            layout.records.records,
+            vec![(0x0800_0000, 4), (0x0800_0008, 2)]
+        );
+        assert_eq!(
+            data,
+            vec![0x01, 0x02, 0x03, 0x04, 0xff, 0xff, 0xff, 0xff, 0xaa, 0xbb]
+        );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(decode(b":0400000001020304F3\n").is_err());
+        assert!(decode(b":04000000010203F2\n").is_err());
+        assert!(decode(b"0400000001020304F2\n").is_err());
+        assert!(decode(b":00000006FA\n").is_err());
+    }
+
+    #[test]
+    fn test_encode_roundtrip() {
+        let (layout, data) = decode(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(encode(&layout, &data).unwrap(), EXAMPLE.as_bytes());
+    }
+
+    #[test]
+    fn test_encode_modified() {
+        let (layout, mut data) = decode(EXAMPLE.as_bytes()).unwrap();
+        data[0] = 0x00;
+        data.push(0xcc);
+        let encoded = String::from_utf8(encode(&layout, &data).unwrap()).unwrap();
+        assert_eq!(
+            encoded,
+            "\
+:020000040800F2
+:0400000000020304F3
+:02000800AABB91
+:01000A00CC29
+:0400000508000000EF
+:00000001FF
+"
+        );
+        let (_, decoded) = decode(encoded.as_bytes()).unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_encode_empty() {
+        let (layout, data) = decode(b"").unwrap();
+        assert_eq!(encode(&layout, &data).unwrap(), b":00000001FF\n");
+    }
+}
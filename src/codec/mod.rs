@@ -0,0 +1,251 @@
+mod compressed;
+mod intel_hex;
+mod srecord;
+
+use std::borrow::Cow;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// The on-disk representation of a file, translating between it and the edited byte image.
+pub enum Codec {
+    Raw,
+    IntelHex(intel_hex::Layout),
+    SRecord(srecord::Layout),
+    Compressed(compressed::Layout),
+    /// A range of a larger file, written back in place.
+    Window {
+        start: u64,
+        length: usize,
+    },
+}
+
+impl Codec {
+    /// Picks a codec based on the file extension and decodes `contents` into a byte image.
+    pub fn decode(path: &Path, contents: Vec<u8>) -> Result<(Codec, Vec<u8>), std::io::Error> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("hex" | "ihex" | "ihx") => {
+                let (layout, data) = intel_hex::decode(&contents)?;
+                Ok((Codec::IntelHex(layout), data))
+            }
+            Some("s19" | "s28" | "s37" | "srec" | "mot") => {
+                let (layout, data) = srecord::decode(&contents)?;
+                Ok((Codec::SRecord(layout), data))
+            }
+            Some("gz") => {
+                let (layout, data) = compressed::decode_gzip(&contents)?;
+                Ok((Codec::Compressed(layout), data))
+            }
+            #[cfg(feature = "compression")]
+            Some("zst") => {
+                let (layout, data) = compressed::decode_zstd(&contents)?;
+                Ok((Codec::Compressed(layout), data))
+            }
+            #[cfg(feature = "compression")]
+            Some("xz") => {
+                let (layout, data) = compressed::decode_xz(&contents)?;
+                Ok((Codec::Compressed(layout), data))
+            }
+            _ => Ok((Codec::Raw, contents)),
+        }
+    }
+
+    /// Reads up to `length` bytes at `start` of the file at `path` as a window.
+    pub fn read_window(
+        path: &Path,
+        start: u64,
+        length: usize,
+    ) -> Result<(Codec, Vec<u8>), std::io::Error> {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(start))?;
+        let mut data = vec![];
+        file.take(length as u64).read_to_end(&mut data)?;
+        let length = data.len();
+        Ok((Codec::Window { start, length }, data))
+    }
+
+    /// Writes `data` to the file at `path`, replacing it unless the codec is a window.
+    pub fn write(&self, path: &Path, data: &[u8]) -> Result<(), std::io::Error> {
+        let Codec::Window { start, length } = self else {
+            return std::fs::write(path, self.encode(data)?);
+        };
+        if data.len() != *length {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("the window must keep its length of {} bytes", length),
+            ));
+        }
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        file.seek(SeekFrom::Start(*start))?;
+        file.write_all(data)
+    }
+
+    /// Encodes `data`, failing if it no longer fits the addresses of a record based format.
+    pub fn encode<'a>(&self, data: &'a [u8]) -> Result<Cow<'a, [u8]>, std::io::Error> {
+        Ok(match self {
+            Codec::Raw | Codec::Window { .. } => Cow::Borrowed(data),
+            Codec::IntelHex(layout) => Cow::Owned(intel_hex::encode(layout, data)?),
+            Codec::SRecord(layout) => Cow::Owned(srecord::encode(layout, data)?),
+            Codec::Compressed(layout) => Cow::Owned(compressed::encode(layout, data)),
+        })
+    }
+}
+
+const RECORD_LENGTH: usize = 16;
+/// Largest image the records may span, as the gaps between them are filled in memory.
+const MAX_SPAN: usize = 1 << 26;
+
+/// Placement of the data records of a record based format like Intel HEX or S-records.
+struct RecordLayout {
+    base: u32,
+    records: Vec<(u32, usize)>,
+}
+
+impl RecordLayout {
+    /// Builds a contiguous image starting at the lowest address from `(address, payload)`
+    /// records. Gaps between records are filled with `0xff`.
+    fn decode(records: Vec<(u32, Vec<u8>)>) -> Result<(RecordLayout, Vec<u8>), std::io::Error> {
+        let base = records
+            .iter()
+            .map(|(address, _)| *address)
+            .min()
+            .unwrap_or(0);
+        let end = records
+            .iter()
+            .map(|(address, payload)| (address - base) as usize + payload.len())
+            .max()
+            .unwrap_or(0);
+        if end > MAX_SPAN {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("the records span more than {} bytes", MAX_SPAN),
+            ));
+        }
+        let mut data = vec![0xff; end];
+        for (address, payload) in &records {
+            let position = (address - base) as usize;
+            data[position..position + payload.len()].copy_from_slice(payload);
+        }
+        let records = records
+            .into_iter()
+            .map(|(address, payload)| (address, payload.len()))
+            .collect();
+        Ok((RecordLayout { base, records }, data))
+    }
+
+    /// Splits `data` along the original records. Bytes beyond the original records are
+    /// appended in new records, which never cross a 64 KiB boundary. Fails if they would
+    /// extend beyond the 32-bit address space.
+    fn split<'a>(&self, data: &'a [u8]) -> Result<Vec<(u32, &'a [u8])>, std::io::Error> {
+        let covered = self
+            .records
+            .iter()
+            .map(|(address, length)| (address - self.base) as usize + length)
+            .max()
+            .unwrap_or(0);
+        let mut appended = vec![];
+        let mut position = covered;
+        while position < data.len() {
+            let address = u32::try_from(position)
+                .ok()
+                .and_then(|position| self.base.checked_add(position))
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "the data extends beyond the 32-bit address space",
+                    )
+                })?;
+            let length = RECORD_LENGTH
+                .min(data.len() - position)
+                .min(0x10000 - (address & 0xffff) as usize);
+            appended.push((address, length));
+            position += length;
+        }
+        Ok(self
+            .records
+            .iter()
+            .copied()
+            .chain(appended)
+            .filter_map(|(address, length)| {
+                let position = (address - self.base) as usize;
+                let end = (position + length).min(data.len());
+                data.get(position..end).map(|payload| (address, payload))
+            })
+            .filter(|(_, payload)| !payload.is_empty())
+            .collect())
+    }
+}
+
+fn format_hex_bytes(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:0>2X}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_raw() {
+        let (codec, data) = Codec::decode(Path::new("test.bin"), vec![1, 2, 3]).unwrap();
+        assert!(matches!(codec, Codec::Raw));
+        assert_eq!(data, vec![1, 2, 3]);
+        assert_eq!(codec.encode(&data).unwrap().as_ref(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_decode_by_extension() {
+        let contents = b":0100000041BE\n:00000001FF\n".to_vec();
+        let (codec, data) = Codec::decode(Path::new("firmware.HEX"), contents).unwrap();
+        assert!(matches!(codec, Codec::IntelHex(_)));
+        assert_eq!(data, vec![0x41]);
+    }
+
+    #[test]
+    fn test_window() {
+        let path = std::env::temp_dir().join("ashe_test_window.bin");
+        std::fs::write(&path, [0, 1, 2, 3, 4, 5]).unwrap();
+        let (codec, data) = Codec::read_window(&path, 2, 3).unwrap();
+        assert_eq!(data, vec![2, 3, 4]);
+        codec.write(&path, &[7, 8, 9]).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), vec![0, 1, 7, 8, 9, 5]);
+        assert!(codec.write(&path, &[7, 8]).is_err());
+        let (codec, data) = Codec::read_window(&path, 4, 10).unwrap();
+        assert_eq!(data, vec![9, 5]);
+        assert!(matches!(
+            codec,
+            Codec::Window {
+                start: 4,
+                length: 2
+            }
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_record_layout() {
+        let (layout, data) =
+            RecordLayout::decode(vec![(0x12, vec![3, 4]), (0x10, vec![1])]).unwrap();
+        assert_eq!(layout.base, 0x10);
+        assert_eq!(data, vec![1, 0xff, 3, 4]);
+        assert_eq!(
+            layout.split(&[5, 6, 7, 8, 9]).unwrap(),
+            vec![(0x12, &[7, 8][..]), (0x10, &[5][..]), (0x14, &[9][..])]
+        );
+        assert_eq!(layout.split(&[5, 6]).unwrap(), vec![(0x10, &[5][..])]);
+
+        // Appended bytes beyond the 32-bit address space.
+        let (layout, _) = RecordLayout::decode(vec![(0xffff_ffff, vec![0xaa])]).unwrap();
+        let error = layout.split(&[0xaa, 1, 2]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(RecordLayout::decode(vec![(0, vec![1]), (0xffff_fff0, vec![2])]).is_err());
+    }
+
+    #[test]
+    fn test_format_hex_bytes() {
+        assert_eq!(format_hex_bytes(&[0x0a, 0xff]), "0AFF");
+    }
+}
@@ -0,0 +1,183 @@
+use super::super::parse::{invalid_data, parse_hex};
+use super::{RecordLayout, format_hex_bytes};
+
+const HEADER: u8 = 0;
+const COUNT_16: u8 = 5;
+const COUNT_24: u8 = 6;
+
+/// Placement of the data records and the metadata needed to re-encode the file.
+///
+/// Data records keep their original address width (S19/S28/S37) unless an address no
+/// longer fits, and count records are recomputed.
+pub struct Layout {
+    records: RecordLayout,
+    header: Option<Vec<u8>>,
+    data_kind: u8,
+    count_kind: Option<u8>,
+    termination: Option<(u8, u32)>,
+    line_ending: &'static str,
+}
+
+fn address_length(kind: u8) -> Option<usize> {
+    match kind {
+        0 | 1 | 5 | 9 => Some(2),
+        2 | 6 | 8 => Some(3),
+        3 | 7 => Some(4),
+        _ => None,
+    }
+}
+
+/// Decodes Motorola S-records into a contiguous image starting at the lowest address.
+pub fn decode(contents: &[u8]) -> Result<(Layout, Vec<u8>), std::io::Error> {
+    let text = std::str::from_utf8(contents).map_err(|_| invalid_data(0, "not a text file"))?;
+    let mut records = vec![];
+    let mut header = None;
+    let mut data_kind = None;
+    let mut count_kind = None;
+    let mut termination = None;
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let kind = line
+            .strip_prefix('S')
+            .and_then(|line| line.chars().next())
+            .and_then(|kind| kind.to_digit(10))
+            .ok_or_else(|| invalid_data(number, "malformed record"))? as u8;
+        let record =
+            parse_hex(&line[2..]).ok_or_else(|| invalid_data(number, "malformed record"))?;
+        let address_length =
+            address_length(kind).ok_or_else(|| invalid_data(number, "unsupported record"))?;
+        if record.len() < address_length + 2 || record.len() != record[0] as usize + 1 {
+            return Err(invalid_data(number, "invalid record length"));
+        }
+        if record.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0xff {
+            return Err(invalid_data(number, "checksum mismatch"));
+        }
+        let address = record[1..=address_length]
+            .iter()
+            .fold(0u32, |address, byte| (address << 8) | *byte as u32);
+        let payload = &record[address_length + 1..record.len() - 1];
+        match kind {
+            HEADER => header = Some(payload.to_vec()),
+            1..=3 => {
+                data_kind = data_kind.max(Some(kind));
+                records.push((address, payload.to_vec()));
+            }
+            COUNT_16 | COUNT_24 => count_kind = Some(kind),
+            _ => termination = Some((kind, address)),
+        }
+    }
+
+    let (records, data) = RecordLayout::decode(records)?;
+    let line_ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    Ok((
+        Layout {
+            records,
+            header,
+            data_kind: data_kind.unwrap_or(1),
+            count_kind,
+            termination,
+            line_ending,
+        },
+        data,
+    ))
+}
+
+/// Encodes `data` following the original record layout.
+pub fn encode(layout: &Layout, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    let mut output = String::new();
+    if let Some(header) = &layout.header {
+        output += &format_record(HEADER, 0, header, layout.line_ending);
+    }
+    let records = layout.records.split(data)?;
+    let data_kind = match records.last() {
+        Some((address, payload)) => {
+            let end = *address as u64 + payload.len() as u64 - 1;
+            match end {
+                0..=0xffff => layout.data_kind,
+                0x10000..=0xff_ffff => layout.data_kind.max(2),
+                _ => 3,
+            }
+        }
+        None => layout.data_kind,
+    };
+    for (address, payload) in &records {
+        output += &format_record(data_kind, *address, payload, layout.line_ending);
+    }
+    if let Some(count_kind) = layout.count_kind {
+        let count_kind = if records.len() > 0xffff {
+            COUNT_24
+        } else {
+            count_kind
+        };
+        output += &format_record(count_kind, records.len() as u32, &[], layout.line_ending);
+    }
+    if let Some((kind, address)) = layout.termination {
+        // The termination record has to match the width of the data records.
+        let kind = 10 - data_kind.max(10 - kind);
+        output += &format_record(kind, address, &[], layout.line_ending);
+    }
+    Ok(output.into_bytes())
+}
+
+fn format_record(kind: u8, address: u32, payload: &[u8], line_ending: &str) -> String {
+    let address_length = address_length(kind).unwrap_or(4);
+    let mut record = vec![(address_length + payload.len() + 1) as u8];
+    record.extend(&address.to_be_bytes()[4 - address_length..]);
+    record.extend(payload);
+    let checksum = !record.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    record.push(checksum);
+    format!("S{}{}{}", kind, format_hex_bytes(&record), line_ending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+S00F000068656C6C6F202020202000003C
+S11F00007C0802A6900100049421FFF07C6C1B787C8C23783C6000003863000026
+S11F001C4BFFFFE5398000007D83637880010014382100107C0803A64E800020E9
+S111003848656C6C6F20776F726C642E0A0042
+S5030003F9
+S9030000FC
+";
+
+    #[test]
+    fn test_decode() {
+        let (layout, data) = decode(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(layout.header.as_deref(), Some(&b"hello     \0\0"[..]));
+        assert_eq!(layout.data_kind, 1);
+        assert_eq!(layout.count_kind, Some(COUNT_16));
+        assert_eq!(layout.termination, Some((9, 0)));
+        assert_eq!(data.len(), 0x46);
+        assert_eq!(&data[0x38..], b"Hello world.\n\0");
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(decode(b"S5030003F8\n").is_err());
+        assert!(decode(b"S50400003F9\n").is_err());
+        assert!(decode(b"S4030003F9\n").is_err());
+        assert!(decode(b"X5030003F9\n").is_err());
+    }
+
+    #[test]
+    fn test_encode_roundtrip() {
+        let (layout, data) = decode(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(encode(&layout, &data).unwrap(), EXAMPLE.as_bytes());
+    }
+
+    #[test]
+    fn test_encode_widens_addresses() {
+        let (layout, _) = decode(b"S1050000AABB95\nS9030000FC\n").unwrap();
+        let data = vec![0u8; 0x10001];
+        let encoded = String::from_utf8(encode(&layout, &data).unwrap()).unwrap();
+        assert!(encoded.starts_with("S2060000000000F9\n"));
+        assert!(encoded.ends_with("S804000000FB\n"));
+        let (_, decoded) = decode(encoded.as_bytes()).unwrap();
+        assert_eq!(decoded, data);
+    }
+}
@@ -0,0 +1,63 @@
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use std::io::Read;
+
+/// Decompresses a gzip member, a zlib stream or raw deflate data, detected from the header.
+/// Returns the name of the format and the decompressed data. Data following the compressed
+/// stream is ignored.
+pub fn inflate(data: &[u8]) -> Result<(&'static str, Vec<u8>), std::io::Error> {
+    let mut output = vec![];
+    let format = if data.starts_with(&[0x1f, 0x8b]) {
+        GzDecoder::new(data).read_to_end(&mut output)?;
+        "gzip"
+    } else if is_zlib(data) {
+        ZlibDecoder::new(data).read_to_end(&mut output)?;
+        "zlib"
+    } else {
+        DeflateDecoder::new(data).read_to_end(&mut output)?;
+        "deflate"
+    };
+    Ok((format, output))
+}
+
+/// Checks for the zlib header of deflate compressed data, whose two bytes are a multiple of 31.
+fn is_zlib(data: &[u8]) -> bool {
+    match data {
+        [method, flags, ..] => {
+            method & 0x0f == 8 && u16::from_be_bytes([*method, *flags]) % 31 == 0
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+    use std::io::Write;
+
+    const TEXT: &[u8] = b"hello hello hello hello";
+
+    #[test]
+    fn test_inflate() {
+        let mut gzip = GzEncoder::new(vec![], Compression::default());
+        gzip.write_all(TEXT).unwrap();
+        let mut zlib = ZlibEncoder::new(vec![], Compression::default());
+        zlib.write_all(TEXT).unwrap();
+        let mut deflate = DeflateEncoder::new(vec![], Compression::default());
+        deflate.write_all(TEXT).unwrap();
+        let mut zlib = zlib.finish().unwrap();
+        zlib.extend(b"trailing");
+        assert_eq!(
+            inflate(&gzip.finish().unwrap()).unwrap(),
+            ("gzip", TEXT.to_vec())
+        );
+        assert_eq!(inflate(&zlib).unwrap(), ("zlib", TEXT.to_vec()));
+        assert_eq!(
+            inflate(&deflate.finish().unwrap()).unwrap(),
+            ("deflate", TEXT.to_vec())
+        );
+        assert!(inflate(b"\x1f\x8b\x08\x00").is_err());
+        assert!(inflate(b"\xff\xff\xff").is_err());
+    }
+}
@@ -0,0 +1,63 @@
+/// Alphabet of the patterns, as used by pwntools.
+const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+/// Length of the subsequences which are unique within a pattern.
+pub const SUBSEQUENCE_LENGTH: usize = 4;
+/// Length of the longest pattern in which no subsequence repeats.
+pub const MAX_LENGTH: usize = 26usize.pow(SUBSEQUENCE_LENGTH as u32) + SUBSEQUENCE_LENGTH - 1;
+
+/// Returns the first `length` bytes of the De Bruijn sequence over lowercase letters, in which
+/// every subsequence of four bytes occurs at most once. The sequence repeats after 26⁴ bytes.
+pub fn pattern(length: usize) -> Vec<u8> {
+    let sequence = de_bruijn();
+    sequence.iter().copied().cycle().take(length).collect()
+}
+
+/// Returns the offset of `subsequence` within the pattern.
+pub fn find(subsequence: &[u8]) -> Option<usize> {
+    let subsequence = subsequence.get(..SUBSEQUENCE_LENGTH)?;
+    pattern(MAX_LENGTH)
+        .windows(SUBSEQUENCE_LENGTH)
+        .position(|window| window == subsequence)
+}
+
+/// Generates the De Bruijn sequence with the recursive algorithm of Fredricksen, Kessler and
+/// Maiorana.
+fn de_bruijn() -> Vec<u8> {
+    fn generate(t: usize, p: usize, a: &mut [usize], sequence: &mut Vec<u8>) {
+        if t > SUBSEQUENCE_LENGTH {
+            if SUBSEQUENCE_LENGTH.is_multiple_of(p) {
+                sequence.extend(a[1..=p].iter().map(|&i| ALPHABET[i]));
+            }
+            return;
+        }
+        a[t] = a[t - p];
+        generate(t + 1, p, a, sequence);
+        for j in a[t - p] + 1..ALPHABET.len() {
+            a[t] = j;
+            generate(t + 1, t, a, sequence);
+        }
+    }
+    let mut sequence = vec![];
+    generate(1, 1, &mut [0; SUBSEQUENCE_LENGTH + 1], &mut sequence);
+    sequence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern() {
+        assert_eq!(pattern(20), b"aaaabaaacaaadaaaeaaa");
+        assert_eq!(de_bruijn().len(), 26usize.pow(4));
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(find(b"gaaa"), Some(24));
+        assert_eq!(find(b"aaaa"), Some(0));
+        assert_eq!(find(b"zzzz"), Some(456_972));
+        assert_eq!(find(b"aaa"), None);
+        assert_eq!(find(b"AAAA"), None);
+    }
+}
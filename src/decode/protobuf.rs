@@ -0,0 +1,174 @@
+/// Nesting limit for embedded messages.
+const MAX_DEPTH: usize = 32;
+/// Number of bytes shown of strings and byte fields.
+const PREVIEW_LENGTH: usize = 32;
+
+/// Decodes the fields of a message without a schema. Length-delimited fields are shown as
+/// embedded messages if they parse as such, and as strings or bytes otherwise. Decoding stops
+/// at the first invalid field.
+pub fn decode(data: &[u8], base: usize) -> Vec<(String, usize)> {
+    let mut entries = vec![];
+    if let Err(offset) = decode_message(data, base, 0, &mut entries) {
+        entries.push((format!("{:0>8x}  invalid field", offset), offset));
+    }
+    entries
+}
+
+/// Appends the fields of the message in `data`, returning the offset of the first invalid
+/// field on failure.
+fn decode_message(
+    data: &[u8],
+    base: usize,
+    depth: usize,
+    entries: &mut Vec<(String, usize)>,
+) -> Result<(), usize> {
+    let mut position = 0;
+    while position < data.len() {
+        let start = position;
+        let invalid = || base + start;
+        let key = read_varint(data, &mut position).ok_or_else(invalid)?;
+        let (number, wire_type) = (key >> 3, key & 7);
+        if number == 0 {
+            return Err(invalid());
+        }
+        let mut nested = vec![];
+        let value = match wire_type {
+            0 => {
+                let value = read_varint(data, &mut position).ok_or_else(invalid)?;
+                let zigzag = (value >> 1) as i64 ^ -((value & 1) as i64);
+                format!("varint {} (sint {})", value, zigzag)
+            }
+            1 => {
+                let bytes = data.get(position..position + 8).ok_or_else(invalid)?;
+                position += 8;
+                let value = u64::from_le_bytes(bytes.try_into().unwrap());
+                format!(
+                    "fixed64 0x{:016x} (double {})",
+                    value,
+                    f64::from_bits(value)
+                )
+            }
+            5 => {
+                let bytes = data.get(position..position + 4).ok_or_else(invalid)?;
+                position += 4;
+                let value = u32::from_le_bytes(bytes.try_into().unwrap());
+                format!("fixed32 0x{:08x} (float {})", value, f32::from_bits(value))
+            }
+            2 => {
+                let length = read_varint(data, &mut position).ok_or_else(invalid)? as usize;
+                let payload_start = position;
+                let end = position.checked_add(length).ok_or_else(invalid)?;
+                let payload = data.get(position..end).ok_or_else(invalid)?;
+                position = end;
+                if let Some(text) = printable(payload) {
+                    text
+                } else if depth < MAX_DEPTH
+                    && !payload.is_empty()
+                    && decode_message(payload, base + payload_start, depth + 1, &mut nested).is_ok()
+                {
+                    format!("message ({} bytes)", length)
+                } else {
+                    nested.clear();
+                    let preview = &payload[..payload.len().min(PREVIEW_LENGTH)];
+                    let hex: Vec<_> = preview.iter().map(|b| format!("{:02x}", b)).collect();
+                    format!(
+                        "bytes ({}) {}{}",
+                        length,
+                        hex.join(" "),
+                        ellipsis(preview.len() < payload.len())
+                    )
+                }
+            }
+            3 => "group start".into(),
+            4 => "group end".into(),
+            _ => return Err(invalid()),
+        };
+        let label = format!(
+            "{:0>8x}  {}{}: {}",
+            base + start,
+            "  ".repeat(depth),
+            number,
+            value
+        );
+        entries.push((label, base + start));
+        entries.append(&mut nested);
+    }
+    Ok(())
+}
+
+/// Reads a base 128 varint at `position`, advancing past it.
+fn read_varint(data: &[u8], position: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*position)?;
+        *position += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Describes a length-delimited field as a string if it is printable UTF-8. Such fields are
+/// rarely valid messages, as keys and lengths are mostly control characters.
+fn printable(payload: &[u8]) -> Option<String> {
+    let text = std::str::from_utf8(payload).ok()?;
+    if payload.is_empty() || text.chars().any(|c| c.is_control() && !c.is_whitespace()) {
+        return None;
+    }
+    let preview: String = text.chars().take(PREVIEW_LENGTH).collect();
+    Some(format!(
+        "string {:?}{}",
+        preview,
+        ellipsis(preview.len() < text.len())
+    ))
+}
+
+fn ellipsis(truncated: bool) -> &'static str {
+    if truncated { "…" } else { "" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        // Field 1: varint 150, field 2: "hi", field 3: a message with field 1: varint 3.
+        let data = b"\x08\x96\x01\x12\x02hi\x1a\x02\x08\x03\x25\x00\x00\x80\x3f";
+        let entries = decode(data, 0x10);
+        let labels: Vec<_> = entries.iter().map(|(label, _)| &label[10..]).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "1: varint 150 (sint 75)",
+                "2: string \"hi\"",
+                "3: message (2 bytes)",
+                "  1: varint 3 (sint -2)",
+                "4: fixed32 0x3f800000 (float 1)"
+            ]
+        );
+        let offsets: Vec<_> = entries.iter().map(|(_, offset)| *offset).collect();
+        assert_eq!(offsets, vec![0x10, 0x13, 0x17, 0x19, 0x1b]);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let entries = decode(b"\x08\x01\x0a\x05ab", 0);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1], ("00000002  invalid field".into(), 2));
+        assert_eq!(
+            decode(b"\x0a\x03\xff\xfe\x00", 0)[0].0,
+            "00000000  1: bytes (3) ff fe 00"
+        );
+    }
+
+    #[test]
+    fn test_read_varint() {
+        let mut position = 1;
+        assert_eq!(read_varint(b"\x00\xac\x02", &mut position), Some(300));
+        assert_eq!(position, 3);
+        assert_eq!(read_varint(b"\x80", &mut 0), None);
+    }
+}
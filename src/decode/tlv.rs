@@ -0,0 +1,200 @@
+use super::super::parse::{invalid_data, parse_number};
+use std::collections::HashMap;
+
+/// Nesting limit for constructed values.
+const MAX_DEPTH: usize = 32;
+/// Upper bound for the number of entries listed.
+const MAX_ENTRIES: usize = 65536;
+/// Number of bytes shown of values.
+const PREVIEW_LENGTH: usize = 16;
+
+/// A name for a tag and whether its value holds nested TLV entries.
+struct Tag {
+    name: String,
+    nested: bool,
+}
+
+/// The layout of tag-length-value entries.
+pub struct TlvFormat {
+    tag_size: usize,
+    length_size: usize,
+    big_endian: bool,
+    nested: bool,
+    tags: HashMap<u64, Tag>,
+}
+
+impl TlvFormat {
+    /// Parses the arguments of `:decode tlv`, `<tag size> <length size>` in bytes, optionally
+    /// followed by `le` or `be`, `nested` to decode every value which parses as TLV entries, and
+    /// the path of a tag table, which is returned along with the format.
+    pub fn parse<'a>(mut args: impl Iterator<Item = &'a str>) -> Option<(Self, Option<&'a str>)> {
+        let tag_size = args.next()?.parse().ok()?;
+        let length_size = args.next()?.parse().ok()?;
+        if ![1, 2, 4].contains(&tag_size) || ![1, 2, 4].contains(&length_size) {
+            return None;
+        }
+        let mut format = TlvFormat {
+            tag_size,
+            length_size,
+            big_endian: true,
+            nested: false,
+            tags: HashMap::new(),
+        };
+        let mut table = None;
+        for arg in args {
+            match arg {
+                "le" => format.big_endian = false,
+                "be" => format.big_endian = true,
+                "nested" => format.nested = true,
+                path if table.is_none() => table = Some(path),
+                _ => return None,
+            }
+        }
+        Some((format, table))
+    }
+
+    /// Loads a tag table with one tag per line as `<tag> <name> [nested]`, where `nested`
+    /// marks tags whose values hold TLV entries. Lines starting with `#` are ignored.
+    pub fn load_tags(&mut self, text: &str) -> Result<(), std::io::Error> {
+        for (number, line) in text.lines().enumerate() {
+            let words: Vec<_> = line.split_whitespace().collect();
+            let (tag, name, nested) = match words[..] {
+                [] => continue,
+                [word, ..] if word.starts_with('#') => continue,
+                [tag, name] => (tag, name, false),
+                [tag, name, "nested"] => (tag, name, true),
+                _ => return Err(invalid_data(number, "expected <tag> <name> [nested]")),
+            };
+            let tag = parse_number(tag).ok_or_else(|| invalid_data(number, "invalid tag"))?;
+            let name = name.into();
+            self.tags.insert(tag as u64, Tag { name, nested });
+        }
+        Ok(())
+    }
+
+    /// Decodes the entries in `data` found at `base` in the buffer. Decoding stops at the first
+    /// entry extending beyond its parent.
+    pub fn decode(&self, data: &[u8], base: usize) -> Vec<(String, usize)> {
+        let mut entries = vec![];
+        if let Err(offset) = self.decode_entries(data, base, 0, &mut entries) {
+            entries.push((format!("{:0>8x}  truncated entry", offset), offset));
+        }
+        entries
+    }
+
+    fn decode_entries(
+        &self,
+        data: &[u8],
+        base: usize,
+        depth: usize,
+        entries: &mut Vec<(String, usize)>,
+    ) -> Result<(), usize> {
+        let header_size = self.tag_size + self.length_size;
+        let mut position = 0;
+        while position < data.len() && entries.len() < MAX_ENTRIES {
+            let invalid = base + position;
+            let header = data.get(position..position + header_size).ok_or(invalid)?;
+            let tag = self.read(&header[..self.tag_size]);
+            let length = self.read(&header[self.tag_size..]) as usize;
+            let start = position + header_size;
+            let value = data.get(start..start + length).ok_or(invalid)?;
+            let name = match self.tags.get(&tag) {
+                Some(tag) => tag.name.clone(),
+                None => format!("0x{:0>1$x}", tag, 2 * self.tag_size),
+            };
+            let mut nested = vec![];
+            let constructed = self.tags.get(&tag).is_some_and(|tag| tag.nested) || self.nested;
+            let label = if constructed
+                && depth < MAX_DEPTH
+                && !value.is_empty()
+                && self
+                    .decode_entries(value, base + start, depth + 1, &mut nested)
+                    .is_ok()
+            {
+                format!("{}  length {}", name, length)
+            } else {
+                nested.clear();
+                let preview = &value[..length.min(PREVIEW_LENGTH)];
+                let hex: Vec<_> = preview.iter().map(|b| format!("{:02x}", b)).collect();
+                let ellipsis = if preview.len() < length { " …" } else { "" };
+                let value = match hex.is_empty() {
+                    true => String::new(),
+                    false => format!("  {}{}", hex.join(" "), ellipsis),
+                };
+                format!("{}  length {}{}", name, length, value)
+            };
+            let label = format!("{:0>8x}  {}{}", base + position, "  ".repeat(depth), label);
+            entries.push((label, base + position));
+            entries.append(&mut nested);
+            position = start + length;
+        }
+        Ok(())
+    }
+
+    /// Reads an unsigned integer in the byte order of the format.
+    fn read(&self, bytes: &[u8]) -> u64 {
+        let fold = |value, b: &u8| value << 8 | *b as u64;
+        match self.big_endian {
+            true => bytes.iter().fold(0, fold),
+            false => bytes.iter().rev().fold(0, fold),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let (format, table) =
+            TlvFormat::parse("2 4 le nested tags.txt".split_whitespace()).unwrap();
+        assert_eq!((format.tag_size, format.length_size), (2, 4));
+        assert!(!format.big_endian && format.nested);
+        assert_eq!(table, Some("tags.txt"));
+        assert!(TlvFormat::parse("3 1".split_whitespace()).is_none());
+        assert!(TlvFormat::parse("1".split_whitespace()).is_none());
+        assert!(TlvFormat::parse("1 1 a b".split_whitespace()).is_none());
+    }
+
+    #[test]
+    fn test_load_tags() {
+        let (mut format, _) = TlvFormat::parse("1 1".split_whitespace()).unwrap();
+        format
+            .load_tags("# tags\n0x30 sequence nested\n2 integer\n")
+            .unwrap();
+        assert!(format.tags[&0x30].nested);
+        assert_eq!(format.tags[&2].name, "integer");
+        let error = format.load_tags("1 a\nx b\n").err().unwrap();
+        assert_eq!(error.to_string(), "line 2: invalid tag");
+    }
+
+    #[test]
+    fn test_decode() {
+        let (mut format, _) = TlvFormat::parse("1 1".split_whitespace()).unwrap();
+        format.load_tags("0x30 sequence nested\n2 integer").unwrap();
+        let data = b"\x30\x06\x02\x01\x05\x04\x01\xff\x07\x00";
+        let labels: Vec<_> = format
+            .decode(data, 0x20)
+            .into_iter()
+            .map(|(label, offset)| format!("{:x} {}", offset, &label[10..]))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                "20 sequence  length 6",
+                "22   integer  length 1  05",
+                "25   0x04  length 1  ff",
+                "28 0x07  length 0"
+            ]
+        );
+        let entries = format.decode(b"\x02\x05\x01", 0);
+        assert_eq!(entries, vec![("00000000  truncated entry".into(), 0)]);
+    }
+
+    #[test]
+    fn test_read() {
+        let (format, _) = TlvFormat::parse("2 2 le".split_whitespace()).unwrap();
+        assert_eq!(format.read(&[0x34, 0x12]), 0x1234);
+    }
+}
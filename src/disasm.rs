@@ -0,0 +1,93 @@
+use std::str::FromStr;
+
+/// Whether the disassembler has been built in, as it requires the `disassembly` feature.
+pub const AVAILABLE: bool = cfg!(feature = "disassembly");
+
+/// An instruction set which can be disassembled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Architecture {
+    X86,
+    X86_64,
+    Arm,
+    Arm64,
+}
+
+impl FromStr for Architecture {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "x86" => Ok(Architecture::X86),
+            "x86_64" | "x64" => Ok(Architecture::X86_64),
+            "arm" => Ok(Architecture::Arm),
+            "aarch64" | "arm64" => Ok(Architecture::Arm64),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A decoded instruction as its address and its text.
+pub type Instruction = (u64, String);
+
+impl Architecture {
+    /// Disassembles up to `count` instructions from the start of `data`, located at `address`.
+    /// Stops at the first invalid instruction.
+    #[cfg(feature = "disassembly")]
+    pub fn disassemble(&self, data: &[u8], address: u64, count: usize) -> Vec<Instruction> {
+        use capstone::prelude::*;
+        let builder = Capstone::new();
+        let capstone = match self {
+            Architecture::X86 => builder.x86().mode(arch::x86::ArchMode::Mode32).build(),
+            Architecture::X86_64 => builder.x86().mode(arch::x86::ArchMode::Mode64).build(),
+            Architecture::Arm => builder.arm().mode(arch::arm::ArchMode::Arm).build(),
+            Architecture::Arm64 => builder.arm64().mode(arch::arm64::ArchMode::Arm).build(),
+        };
+        let Ok(capstone) = capstone else {
+            return vec![];
+        };
+        let Ok(instructions) = capstone.disasm_count(data, address, count) else {
+            return vec![];
+        };
+        instructions
+            .iter()
+            .map(|instruction| {
+                let text = format!(
+                    "{} {}",
+                    instruction.mnemonic().unwrap_or("?"),
+                    instruction.op_str().unwrap_or("")
+                );
+                (instruction.address(), text.trim_end().to_string())
+            })
+            .collect()
+    }
+
+    #[cfg(not(feature = "disassembly"))]
+    pub fn disassemble(&self, _data: &[u8], _address: u64, _count: usize) -> Vec<Instruction> {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("x64".parse(), Ok(Architecture::X86_64));
+        assert_eq!("aarch64".parse(), Ok(Architecture::Arm64));
+        assert!("mips".parse::<Architecture>().is_err());
+    }
+
+    #[cfg(feature = "disassembly")]
+    #[test]
+    fn test_disassemble() {
+        let code = b"\xb8\x01\x00\x00\x00\xc3\xff\xff";
+        let instructions = Architecture::X86_64.disassemble(code, 0x10, 4);
+        assert_eq!(
+            instructions,
+            vec![(0x10, "mov eax, 1".into()), (0x15, "ret".into())]
+        );
+        let instructions = Architecture::Arm64.disassemble(b"\xc0\x03\x5f\xd6", 0, 1);
+        assert_eq!(instructions, vec![(0, "ret".into())]);
+    }
+}
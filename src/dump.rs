@@ -0,0 +1,196 @@
+use std::io::Write;
+use std::path::Path;
+
+/// Output formats of a dump.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum DumpFormat {
+    /// Lines of offset, hex values and text, like `hexdump -C`.
+    Text,
+    /// An object with the metadata of the file and an array of lines.
+    Json,
+}
+
+/// Describes the dumped file.
+pub struct Metadata<'a> {
+    pub path: &'a Path,
+    /// Name of the file type identified by its magic bytes.
+    pub file_type: Option<&'a str>,
+    /// Offset of the first dumped byte in the file.
+    pub offset: usize,
+    /// Number of dumped bytes.
+    pub length: usize,
+}
+
+/// Writes the parts of a dump in one output format.
+trait Formatter {
+    fn header(&self, _metadata: &Metadata, _out: &mut dyn Write) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    /// Writes the line of `bytes` at `offset`, whose text is `text`.
+    fn line(
+        &self,
+        offset: usize,
+        bytes: &[u8],
+        text: &str,
+        first: bool,
+        out: &mut dyn Write,
+    ) -> Result<(), std::io::Error>;
+
+    fn footer(&self, _out: &mut dyn Write) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
+
+struct TextFormatter {
+    bytes_per_line: usize,
+}
+
+impl Formatter for TextFormatter {
+    fn line(
+        &self,
+        offset: usize,
+        bytes: &[u8],
+        text: &str,
+        _first: bool,
+        out: &mut dyn Write,
+    ) -> Result<(), std::io::Error> {
+        let hex: Vec<String> = bytes.iter().map(|byte| format!("{:0>2x}", byte)).collect();
+        writeln!(
+            out,
+            "{:0>8x}  {:<3$}  |{}|",
+            offset,
+            hex.join(" "),
+            text,
+            3 * self.bytes_per_line - 1
+        )
+    }
+}
+
+struct JsonFormatter;
+
+impl Formatter for JsonFormatter {
+    fn header(&self, metadata: &Metadata, out: &mut dyn Write) -> Result<(), std::io::Error> {
+        writeln!(out, "{{")?;
+        let path = metadata.path.to_string_lossy();
+        writeln!(out, "  \"path\": {},", json_string(&path))?;
+        let file_type = metadata.file_type.map_or("null".into(), json_string);
+        writeln!(out, "  \"type\": {},", file_type)?;
+        writeln!(out, "  \"offset\": {},", metadata.offset)?;
+        writeln!(out, "  \"length\": {},", metadata.length)?;
+        write!(out, "  \"lines\": [")
+    }
+
+    fn line(
+        &self,
+        offset: usize,
+        bytes: &[u8],
+        text: &str,
+        first: bool,
+        out: &mut dyn Write,
+    ) -> Result<(), std::io::Error> {
+        let bytes: Vec<String> = bytes.iter().map(u8::to_string).collect();
+        write!(
+            out,
+            "{}\n    {{\"offset\": {}, \"bytes\": [{}], \"text\": {}}}",
+            if first { "" } else { "," },
+            offset,
+            bytes.join(", "),
+            json_string(text)
+        )
+    }
+
+    fn footer(&self, out: &mut dyn Write) -> Result<(), std::io::Error> {
+        writeln!(out, "\n  ]\n}}")
+    }
+}
+
+/// Writes `data`, found at `metadata.offset` in the file, in `format` with `bytes_per_line`
+/// bytes per line.
+pub fn write(
+    format: DumpFormat,
+    metadata: &Metadata,
+    data: &[u8],
+    bytes_per_line: usize,
+    out: &mut dyn Write,
+) -> Result<(), std::io::Error> {
+    let formatter: Box<dyn Formatter> = match format {
+        DumpFormat::Text => Box::new(TextFormatter { bytes_per_line }),
+        DumpFormat::Json => Box::new(JsonFormatter),
+    };
+    formatter.header(metadata, out)?;
+    for (index, line) in data.chunks(bytes_per_line).enumerate() {
+        let offset = metadata.offset + index * bytes_per_line;
+        formatter.line(offset, line, &text(line), index == 0, out)?;
+    }
+    formatter.footer(out)
+}
+
+/// Returns the ASCII text of `bytes`, with `.` for bytes without a printable character.
+fn text(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| match byte {
+            0x20..0x7f => *byte as char,
+            _ => '.',
+        })
+        .collect()
+}
+
+/// Quotes `value` as a JSON string.
+fn json_string(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:0>4x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dump(format: DumpFormat, data: &[u8]) -> String {
+        let metadata = Metadata {
+            path: Path::new("dir/\"test\".bin"),
+            file_type: None,
+            offset: 0x10,
+            length: data.len(),
+        };
+        let mut out = vec![];
+        write(format, &metadata, data, 8, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(
+            dump(DumpFormat::Text, b"ASHE\x00\x01 hex"),
+            "00000010  41 53 48 45 00 01 20 68  |ASHE.. h|\n\
+             00000018  65 78                    |ex|\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            dump(DumpFormat::Json, b"ASHE\x00\x01 hex"),
+            "{\n  \"path\": \"dir/\\\"test\\\".bin\",\n  \"type\": null,\n  \"offset\": 16,\n  \
+             \"length\": 10,\n  \"lines\": [\n    \
+             {\"offset\": 16, \"bytes\": [65, 83, 72, 69, 0, 1, 32, 104], \"text\": \"ASHE.. h\"},\n    \
+             {\"offset\": 24, \"bytes\": [101, 120], \"text\": \"ex\"}\n  ]\n}\n"
+        );
+        assert_eq!(dump(DumpFormat::Json, b"").lines().last(), Some("}"));
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a\"b\\c\n"), "\"a\\\"b\\\\c\\u000a\"");
+    }
+}
@@ -0,0 +1,5008 @@
+use super::action::Action;
+use super::analysis::{self, ENTROPY_BLOCK_SIZE, MIN_STRING_LENGTH};
+use super::annotations::{self, FileAnnotations};
+use super::asm;
+use super::base64;
+use super::buffer::{self, Buffer, Change};
+use super::calc;
+use super::carve::{self, Carved};
+use super::checksum::ChecksumField;
+use super::codec::Codec;
+use super::compression;
+use super::cyclic;
+use super::decode::{protobuf, tlv::TlvFormat};
+use super::disasm::{self, Architecture, Instruction};
+use super::dump::{self, DumpFormat, Metadata};
+use super::encoding::{CharTable, Glyph, TextEncoding};
+use super::export::ExportFormat;
+use super::formats;
+use super::hash::HashAlgorithm;
+use super::history::History;
+use super::layout::{Layout, Row, Span};
+use super::magic;
+use super::messages::{Messages, Severity};
+use super::notes::{self, Note, parse_location};
+use super::overview::Overview;
+use super::panel::Panel;
+use super::parse::{self, parse_number, parse_range};
+use super::patch::PatchFormat;
+use super::regions::{self, Region};
+use super::render::Grid;
+use super::report;
+use super::rom::RomChecksum;
+use super::search::{self, Search};
+use super::settings::Settings;
+use super::template::{Field, Template};
+use super::terminal::{Position, Terminal, draw, drawln};
+use super::transform::Transform;
+use super::tui;
+use super::tui::{BoxPart, Tab, draw_box_part};
+use super::xref::PointerFormat;
+use crossterm::event::Event::{Key, Paste};
+use crossterm::event::KeyCode::Char;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, poll, read};
+use crossterm::style::Color;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+const MAX_COMMAND_LENGTH: usize = 256;
+/// Number of offsets kept in the jump list.
+const MAX_JUMPS: usize = 100;
+/// The register yanked into and put from unless another one is named.
+const UNNAMED_REGISTER: char = '"';
+/// Width of the disassembly column, including its padding.
+const DISASSEMBLY_WIDTH: usize = 40;
+/// Width of the symbol column, including its padding.
+const SYMBOL_WIDTH: usize = 24;
+/// Background of every other block when guides are shown.
+const GUIDE_COLOR: Color = Color::AnsiValue(236);
+
+enum EditorMode {
+    Edit(Option<u8>),
+    Command(String),
+}
+
+/// What confirming a panel with Enter does.
+enum PanelAction {
+    Jump,
+    SaveChanges(Vec<Change>),
+    /// Jumps to a carved file, which can also be exported with `e`.
+    Carve(Vec<Carved>),
+    /// Shows the open file with the selected index.
+    SwitchDocument,
+    /// Opens the selected file, or lists the selected directory.
+    Browse(Vec<PathBuf>),
+}
+
+/// A replacement confirmed match by match, waiting for `y`, `n`, `a` or `q`.
+struct Replacement {
+    pattern: Vec<u8>,
+    bytes: Vec<u8>,
+    /// The range searched for matches, the selection when the replacement started or the whole
+    /// file, moving its end as matches are replaced.
+    range: Range<usize>,
+    /// Whether `range` was the selection, to select it again at the end.
+    selected: bool,
+    /// Offset of the shown match, or from which to find the next one.
+    position: usize,
+    /// Number of matches replaced so far.
+    count: usize,
+}
+
+/// A second view of an open file, shown below or right of the focused view.
+struct Split {
+    vertical: bool,
+    /// Index of the shown file, which may be the active one.
+    document: usize,
+    cursor: u32,
+    offset: u32,
+    /// Whether the view is drawn above or left of the focused view.
+    first: bool,
+    /// Distance from the focused view kept while scrolling, if the views are locked.
+    lock: Option<i64>,
+}
+
+/// The data shown by a hex view and its position.
+struct View<'a> {
+    data: &'a [u8],
+    cursor: u32,
+    offset: u32,
+    /// Whether the view has the focus, showing the selection and template of the active file.
+    focused: bool,
+    folds: &'a [Span],
+}
+
+/// The state of an open file, stored while another file is shown.
+struct Document {
+    path: PathBuf,
+    codec: Codec,
+    buffer: Buffer,
+    cursor: u32,
+    offset: u32,
+    selection: Option<u32>,
+    checksums: Vec<ChecksumField>,
+    template: Option<Template>,
+    symbols: Vec<(usize, String)>,
+    notes: Vec<Note>,
+    regions: Vec<Region>,
+    folds: Vec<Span>,
+    expanded: Vec<usize>,
+    marks: BTreeMap<char, u32>,
+    guards: Vec<Range<usize>>,
+    /// A problem found while loading the file, shown once it is opened.
+    warning: Option<String>,
+}
+
+impl Document {
+    fn new(path: PathBuf, codec: Codec, data: Vec<u8>) -> Self {
+        Document {
+            path,
+            codec,
+            symbols: formats::symbols(&data),
+            buffer: Buffer::new(data),
+            cursor: 0,
+            offset: 0,
+            selection: None,
+            checksums: vec![],
+            template: None,
+            notes: vec![],
+            regions: vec![],
+            folds: vec![],
+            expanded: vec![],
+            marks: BTreeMap::new(),
+            guards: vec![],
+            warning: None,
+        }
+    }
+
+    /// Reads the file at `path`, decoding HEX, S-record and compressed files unless `raw` is set.
+    fn load(path: &Path, raw: bool) -> Result<Self, std::io::Error> {
+        tracing::info!(path = %path.display(), raw, "opening");
+        let contents = std::fs::read(path)?;
+        let (codec, data) = match raw {
+            true => (Codec::Raw, contents),
+            false => Codec::decode(path, contents)?,
+        };
+        // Notes are an aid only, so problems with them must not keep the file from opening.
+        let sidecar = notes::sidecar(path);
+        let (notes, warning) = match notes::load(path) {
+            Ok((notes, skipped)) if skipped.is_empty() => (notes, None),
+            Ok((notes, skipped)) => {
+                let lines: Vec<String> = skipped.iter().map(usize::to_string).collect();
+                let warning = format!(
+                    "{}: skipped invalid notes on lines {}",
+                    sidecar.display(),
+                    lines.join(", ")
+                );
+                (notes, Some(warning))
+            }
+            Err(error) => (vec![], Some(format!("{}: {}", sidecar.display(), error))),
+        };
+        Ok(Document {
+            notes,
+            warning,
+            ..Document::new(path.into(), codec, data)
+        })
+    }
+
+    /// Reads up to `length` bytes at `start` of the file at `path`, to be written back in place.
+    fn load_window(path: &Path, start: u64, length: usize) -> Result<Self, std::io::Error> {
+        tracing::info!(path = %path.display(), start, length, "opening window");
+        let (codec, data) = Codec::read_window(path, start, length)?;
+        Ok(Document::new(path.into(), codec, data))
+    }
+}
+
+pub struct Editor {
+    cursor: u32,
+    bytes_per_line: u32,
+    offset: u32,
+    path: PathBuf,
+    codec: Codec,
+    buffer: Buffer,
+    mode: EditorMode,
+    selection: Option<u32>,
+    /// Whether typed bytes are inserted before the cursor rather than overwriting it.
+    insert: bool,
+    diff: Option<(PathBuf, Vec<u8>)>,
+    split: Option<Split>,
+    settings: Settings,
+    checksums: Vec<ChecksumField>,
+    template: Option<Template>,
+    architecture: Option<Architecture>,
+    /// Named offsets sorted by offset, such as the functions of an executable.
+    symbols: Vec<(usize, String)>,
+    /// Notes on ranges of the file, kept in a file next to it.
+    notes: Vec<Note>,
+    /// Named ranges tinted in the panes, sorted by their start.
+    regions: Vec<Region>,
+    /// Ranges collapsed into a single row of the hex view.
+    folds: Vec<Span>,
+    /// Offsets within runs of identical bytes which are shown although `collapse` is set.
+    expanded: Vec<usize>,
+    /// Offsets set with `mark` and named `'a` to `'z` in expressions.
+    marks: BTreeMap<char, u32>,
+    /// Ranges protected with `protect`, in which edits are blocked unless `set noprotect`.
+    guards: Vec<Range<usize>>,
+    panel: Option<(Panel, PanelAction)>,
+    overview: Option<Overview>,
+    /// The pattern of the latest `find`, stepped through with `n` and `N`.
+    search: Option<Search>,
+    /// The `replace ... all confirm` in progress.
+    replacing: Option<Replacement>,
+    /// Type of the value shown at the cursor, as written in templates, e.g. `u32be`.
+    inspector: Option<String>,
+    /// Bytes yanked into the unnamed register `"` and the registers `a` to `z`.
+    registers: BTreeMap<char, Vec<u8>>,
+    /// Offsets the cursor jumped away from with `follow`, the latest last.
+    jumps: Vec<u32>,
+    /// Commands entered in Command Mode, stepped through with `Up` and `Down`.
+    history: History,
+    /// The text typed before stepping through the history, and the index of the shown entry.
+    browsing: Option<(String, usize)>,
+    /// The files whose commands are being run by `source`, the innermost last.
+    sourcing: Vec<PathBuf>,
+    /// Messages, warnings and errors shown in the status bar.
+    messages: Messages,
+    /// Number of rows of the hex view, by which actions scroll.
+    max_lines: u32,
+    should_exit: bool,
+    /// The open files, with the state of the active one held by the editor itself.
+    documents: Vec<Document>,
+    active: usize,
+}
+
+impl Editor {
+    /// Opens the file at `path`, decoding HEX, S-record and compressed files unless `raw` is set.
+    pub fn init(path: &Path, bytes_per_line: u32, raw: bool) -> Result<Self, std::io::Error> {
+        Ok(Self::with_document(
+            Document::load(path, raw)?,
+            bytes_per_line,
+        ))
+    }
+
+    /// Opens the `length` bytes at `start` of the file at `path`, writing them back in place.
+    pub fn init_window(
+        path: &Path,
+        bytes_per_line: u32,
+        start: u64,
+        length: usize,
+    ) -> Result<Self, std::io::Error> {
+        let document = Document::load_window(path, start, length)?;
+        Ok(Self::with_document(document, bytes_per_line))
+    }
+
+    fn with_document(document: Document, bytes_per_line: u32) -> Self {
+        let Document {
+            path,
+            codec,
+            buffer,
+            symbols,
+            notes,
+            warning,
+            ..
+        } = document;
+        let mut editor = Editor {
+            cursor: 0,
+            bytes_per_line,
+            offset: 0,
+            path,
+            codec,
+            buffer,
+            mode: EditorMode::Edit(None),
+            selection: None,
+            insert: false,
+            diff: None,
+            split: None,
+            settings: Settings::default(),
+            checksums: vec![],
+            template: None,
+            architecture: None,
+            symbols,
+            notes,
+            regions: vec![],
+            folds: vec![],
+            expanded: vec![],
+            marks: BTreeMap::new(),
+            guards: vec![],
+            panel: None,
+            overview: None,
+            search: None,
+            inspector: None,
+            registers: BTreeMap::new(),
+            jumps: Vec::new(),
+            history: History::default(),
+            replacing: None,
+            browsing: None,
+            sourcing: vec![],
+            messages: Messages::default(),
+            max_lines: 16,
+            should_exit: false,
+            documents: vec![Document::new(PathBuf::new(), Codec::Raw, vec![])],
+            active: 0,
+        };
+        if let Some(warning) = warning {
+            editor.warn(warning);
+        }
+        editor
+    }
+
+    /// Moves the cursor to `offset` and scrolls the view to start with its line.
+    pub fn move_to(&mut self, offset: usize) {
+        let offset = offset.min(self.buffer.len().saturating_sub(1));
+        self.cursor = offset as u32;
+        self.offset = (offset - offset % self.bytes_per_line as usize) as u32;
+    }
+
+    /// Writes the active file from `from` to the end as a dump in `format`, with the offsets
+    /// within the file on disk.
+    pub fn dump(
+        &self,
+        format: DumpFormat,
+        from: usize,
+        out: &mut dyn std::io::Write,
+    ) -> Result<(), std::io::Error> {
+        let base = match self.codec {
+            Codec::Window { start, .. } => start as usize,
+            _ => 0,
+        };
+        let data = self.buffer.slice(0..self.buffer.len());
+        let from = from.min(data.len());
+        let metadata = Metadata {
+            path: &self.path,
+            file_type: magic::identify(data).map(|signature| signature.name),
+            offset: base + from,
+            length: data.len() - from,
+        };
+        let bytes_per_line = self.bytes_per_line as usize;
+        dump::write(format, &metadata, &data[from..], bytes_per_line, out)
+    }
+
+    /// Reads the command history from `path`, saving the commands entered from now on there.
+    /// Warns and keeps an empty history which is not saved if it cannot be read.
+    pub fn load_history(&mut self, path: PathBuf) {
+        match History::load(path) {
+            Ok(history) => self.history = history,
+            Err(error) => self.warn(format!("Failed to read history: {}", error)),
+        }
+    }
+
+    /// Opens another file without showing it.
+    pub fn add_file(&mut self, path: &Path, raw: bool) -> Result<(), std::io::Error> {
+        let mut document = Document::load(path, raw)?;
+        self.take_warning(&mut document);
+        self.documents.push(document);
+        Ok(())
+    }
+
+    /// Shows the problem found while loading `document`, if any.
+    fn take_warning(&mut self, document: &mut Document) {
+        if let Some(warning) = document.warning.take() {
+            self.warn(warning);
+        }
+    }
+
+    /// Exchanges the state of the active file with the document at `index`.
+    fn swap_document(&mut self, index: usize) {
+        let document = &mut self.documents[index];
+        std::mem::swap(&mut self.path, &mut document.path);
+        std::mem::swap(&mut self.codec, &mut document.codec);
+        std::mem::swap(&mut self.buffer, &mut document.buffer);
+        std::mem::swap(&mut self.cursor, &mut document.cursor);
+        std::mem::swap(&mut self.offset, &mut document.offset);
+        std::mem::swap(&mut self.selection, &mut document.selection);
+        std::mem::swap(&mut self.checksums, &mut document.checksums);
+        std::mem::swap(&mut self.template, &mut document.template);
+        std::mem::swap(&mut self.symbols, &mut document.symbols);
+        std::mem::swap(&mut self.notes, &mut document.notes);
+        std::mem::swap(&mut self.regions, &mut document.regions);
+        std::mem::swap(&mut self.folds, &mut document.folds);
+        std::mem::swap(&mut self.expanded, &mut document.expanded);
+        std::mem::swap(&mut self.marks, &mut document.marks);
+        std::mem::swap(&mut self.guards, &mut document.guards);
+    }
+
+    fn switch_document(&mut self, index: usize) {
+        self.swap_document(self.active);
+        self.swap_document(index);
+        self.active = index;
+    }
+
+    fn open_document(&mut self, mut document: Document) {
+        self.take_warning(&mut document);
+        self.documents.push(document);
+        self.switch_document(self.documents.len() - 1);
+    }
+
+    /// Closes the active file and shows the next one, returning false if it is the only one.
+    fn close_document(&mut self) -> bool {
+        let closed = self.active;
+        let next = match closed + 1 < self.documents.len() {
+            true => closed + 1,
+            false if closed > 0 => closed - 1,
+            false => return false,
+        };
+        self.swap_document(next);
+        self.documents.swap(closed, next);
+        self.documents.remove(closed);
+        self.active = if next > closed { next - 1 } else { next };
+        if let Some(split) = &mut self.split {
+            match split.document {
+                document if document == closed => self.split = None,
+                document if document > closed => split.document -= 1,
+                _ => {}
+            }
+        }
+        true
+    }
+
+    /// Shows `path` side by side with the buffer, highlighting differing bytes.
+    pub fn open_diff(&mut self, path: &Path) -> Result<(), std::io::Error> {
+        let (_, data) = Codec::decode(path, std::fs::read(path)?)?;
+        self.diff = Some((path.into(), data));
+        self.split = None;
+        Ok(())
+    }
+
+    /// Shows a second view of the active file, or of `file` if given, below or right of the
+    /// current one.
+    fn split(&mut self, vertical: bool, file: Option<&str>) {
+        let document = match file {
+            Some(file) => match Document::load(Path::new(file), false) {
+                Ok(mut document) => {
+                    self.take_warning(&mut document);
+                    self.documents.push(document);
+                    self.documents.len() - 1
+                }
+                Err(error) => {
+                    self.fail(error.to_string());
+                    return;
+                }
+            },
+            None => self.active,
+        };
+        self.split = Some(Split {
+            vertical,
+            document,
+            cursor: self.cursor,
+            offset: self.offset,
+            first: false,
+            lock: None,
+        });
+        self.diff = None;
+    }
+
+    /// Moves the focus to the other view of a split, showing its file.
+    fn switch_view(&mut self) {
+        let Some(split) = self.split.take() else {
+            self.warn("No split");
+            return;
+        };
+        let previous = Split {
+            document: self.active,
+            cursor: self.cursor,
+            offset: self.offset,
+            first: !split.first,
+            lock: split.lock.map(|delta| -delta),
+            ..split
+        };
+        if split.document != self.active {
+            self.switch_document(split.document);
+        }
+        self.cursor = split.cursor.min(self.buffer.len().saturating_sub(1) as u32);
+        self.offset = split.offset;
+        self.split = Some(previous);
+    }
+
+    /// Locks the other view of a split to scroll with the focused one, `delta` bytes ahead of
+    /// it, or as far as its cursor is ahead if no distance is given.
+    fn lock(&mut self, delta: Option<&str>) {
+        let Some(split) = &mut self.split else {
+            self.warn("No split");
+            return;
+        };
+        let delta = match delta {
+            Some(value) => {
+                let (sign, number) = match value.strip_prefix('-') {
+                    Some(number) => (-1, number),
+                    None => (1, value),
+                };
+                match parse_number(number) {
+                    Some(number) => sign * number as i64,
+                    None => {
+                        self.warn("Usage: lock [delta]");
+                        return;
+                    }
+                }
+            }
+            None => split.cursor as i64 - self.cursor as i64,
+        };
+        split.lock = Some(delta);
+        self.inform(match delta < 0 {
+            true => format!("Locked at -0x{:x}", delta.unsigned_abs()),
+            false => format!("Locked at +0x{:x}", delta),
+        });
+        self.sync_split();
+    }
+
+    /// Moves the other view of a locked split along with the focused one.
+    fn sync_split(&mut self) {
+        if let Some(split) = &mut self.split
+            && let Some(delta) = split.lock
+        {
+            let shift = |value: u32| (value as i64 + delta).max(0) as u32;
+            split.cursor = shift(self.cursor);
+            split.offset = shift(self.offset);
+        }
+    }
+
+    /// Returns the number of lines of the focused view when `lines` are available for a single
+    /// view.
+    fn view_lines(&self, lines: u32) -> u32 {
+        match &self.split {
+            Some(split) if !split.vertical => {
+                let (first, second) = Self::split_lines(lines);
+                if split.first { second } else { first }
+            }
+            _ => lines,
+        }
+    }
+
+    /// Divides `lines` between two views stacked on top of each other, with a box around each.
+    fn split_lines(lines: u32) -> (u32, u32) {
+        let available = lines.saturating_sub(2);
+        (available / 2, available - available / 2)
+    }
+
+    fn document_data(&self, index: usize) -> &[u8] {
+        let buffer = match index == self.active {
+            true => &self.buffer,
+            false => &self.documents[index].buffer,
+        };
+        buffer.slice(0..buffer.len())
+    }
+
+    fn inform(&mut self, text: impl AsRef<str>) {
+        self.messages.push(Severity::Info, text);
+    }
+
+    fn warn(&mut self, text: impl AsRef<str>) {
+        self.messages.push(Severity::Warning, text);
+    }
+
+    fn fail(&mut self, text: impl AsRef<str>) {
+        self.messages.push(Severity::Error, text);
+    }
+
+    pub fn run(&mut self) -> Result<(), std::io::Error> {
+        Terminal::initialize()?;
+        let result = self.repl();
+        if let Err(error) = &result {
+            tracing::error!(%error, "terminal failed");
+        }
+        Terminal::terminate()?;
+        println!("\r");
+        result
+    }
+
+    /// Runs `commands` without a terminal, printing their messages and warnings. Stops at the
+    /// first command that fails or quits and returns whether all commands succeeded.
+    pub fn run_commands(&mut self, commands: &[String]) -> bool {
+        for command in commands {
+            self.messages.clear();
+            self.process_command(command);
+            let mut failed = false;
+            for message in self.messages.iter() {
+                match message.severity {
+                    Severity::Info => println!("{}", message.text),
+                    _ => {
+                        eprintln!("{}", message.text);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                return false;
+            }
+            if self.should_exit {
+                break;
+            }
+        }
+        true
+    }
+
+    pub fn repl(&mut self) -> Result<(), std::io::Error> {
+        while !self.should_exit {
+            let max_lines = self.draw_frame()?;
+            let lifetime = self.settings.message_time;
+            if let Some(remaining) = self.messages.remaining(lifetime)
+                && !poll(remaining)?
+            {
+                self.messages.expire(lifetime);
+                continue;
+            }
+            let event = read()?;
+            self.messages.expire(lifetime);
+            match event {
+                Key(event) => self.process_event(event, max_lines),
+                Paste(text) => self.process_paste(&text),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws the editor as it would appear on a terminal of `width` by `height` characters and
+    /// returns the text of its rows, without colors.
+    pub fn render(&mut self, width: u16, height: u16) -> Result<Vec<String>, std::io::Error> {
+        let grid = Terminal::capture(Grid::new(width, height), || self.draw_frame().map(|_| ()))?;
+        Ok(grid.lines())
+    }
+
+    /// Scrolls the view to the cursor and draws the editor to fit the terminal, returning the
+    /// number of rows of the focused view.
+    fn draw_frame(&mut self) -> Result<u32, std::io::Error> {
+        let lines = Terminal::height()?.saturating_sub(5) as u32;
+        let max_lines = self.view_lines(lines);
+        self.max_lines = max_lines;
+        self.scroll_to_cursor(max_lines);
+        self.redraw(self.offset, lines)?;
+        Ok(max_lines)
+    }
+
+    fn process_event(&mut self, event: KeyEvent, max_lines: u32) {
+        tracing::trace!(?event, "key");
+        if let Some(overview) = &mut self.overview {
+            let length = self.buffer.len();
+            match event.code {
+                KeyCode::Esc => self.overview = None,
+                KeyCode::Left => overview.move_selection(-1, length, max_lines),
+                KeyCode::Right => overview.move_selection(1, length, max_lines),
+                KeyCode::Up => overview.move_rows(-1, length, max_lines),
+                KeyCode::Down => overview.move_rows(1, length, max_lines),
+                KeyCode::Enter => {
+                    let position = overview.position as u32;
+                    self.overview = None;
+                    self.jump_to(position, max_lines);
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.replacing.is_some() {
+            self.process_replace_event(event);
+            return;
+        }
+        if let Some((panel, _)) = &mut self.panel {
+            match event.code {
+                KeyCode::Esc => self.panel = None,
+                Char(c) if panel.is_filtered() => panel.push_filter(c),
+                KeyCode::Backspace => panel.pop_filter(),
+                KeyCode::Up => panel.move_selection(-1),
+                KeyCode::Down => panel.move_selection(1),
+                KeyCode::PageUp => panel.move_selection(-(max_lines as i64)),
+                KeyCode::PageDown => panel.move_selection(max_lines as i64),
+                Char(' ') => panel.toggle(),
+                Char('e') => self.export_carved(),
+                KeyCode::Enter => {
+                    if let Some((panel, action)) = self.panel.take() {
+                        self.confirm_panel(panel, action, max_lines);
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+        let EditorMode::Command(command) = &self.mode else {
+            if let Some(action) = Action::from_key(event, max_lines) {
+                self.max_lines = max_lines;
+                self.apply(action);
+            }
+            return;
+        };
+        match (event.code, event.modifiers) {
+            (Char('c'), KeyModifiers::CONTROL) => self.should_exit = true,
+            (KeyCode::Esc, _) => {
+                self.browsing = None;
+                self.mode = EditorMode::Edit(None);
+            }
+            _ => {
+                let command = command.clone();
+                if let Some(mode) = self.process_command_event(&command, event) {
+                    self.mode = mode;
+                }
+            }
+        }
+    }
+
+    /// Applies `action` as if its key had been pressed in Edit Mode, leaving Command Mode.
+    pub fn apply(&mut self, action: Action) {
+        let max_lines = self.max_lines;
+        let pending = match self.mode {
+            EditorMode::Edit(pending) => pending,
+            EditorMode::Command(_) => None,
+        };
+        self.mode = EditorMode::Edit(None);
+        match action {
+            Action::Move(delta) => self.move_cursor(delta),
+            Action::MoveLines(lines) => self.move_cursor(lines * self.bytes_per_line as i64),
+            Action::MoveTo(offset) => self.jump_to(offset as u32, max_lines),
+            Action::Digit(value) => {
+                let cursor = self.cursor as usize;
+                let changed = match pending.is_none() && self.insert {
+                    true => cursor..cursor,
+                    false => cursor..cursor + 1,
+                };
+                if !self.check_protected(changed, pending.is_none() && self.insert) {
+                    return;
+                }
+                match pending {
+                    None if self.insert => self.buffer.splice(cursor..cursor, &[value]),
+                    None => self.buffer.update(cursor, value),
+                    Some(high) => self.buffer.update(cursor, (high << 4) | value),
+                }
+                match pending {
+                    None => self.mode = EditorMode::Edit(Some(value)),
+                    // The next byte is inserted after the one just typed.
+                    Some(_) if self.insert => self.move_cursor(1),
+                    Some(_) => {}
+                }
+            }
+            Action::ToggleInsert => self.insert = !self.insert,
+            Action::Revert => {
+                let range = match self.selection {
+                    Some(_) => self.selected_range(),
+                    None => self.cursor as usize..self.cursor as usize + 1,
+                };
+                if self.check_protected(range.clone(), false) {
+                    self.buffer.revert(range);
+                }
+            }
+            Action::ToggleSelection => {
+                self.selection = match self.selection {
+                    None => Some(self.cursor),
+                    Some(_) => None,
+                };
+            }
+            Action::Cancel => self.selection = None,
+            Action::Yank => self.yank(UNNAMED_REGISTER),
+            Action::Put => self.put(UNNAMED_REGISTER),
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+            Action::Snap { forward, view } => self.snap(forward, view, max_lines),
+            Action::ToggleFold => {
+                let layout = self.layout(&self.focused_view());
+                match layout.span_at(self.cursor as usize) {
+                    Some(_) => self.unfold(),
+                    None => self.fold(None),
+                }
+            }
+            Action::FindNext { forward } => self.find_next(forward, max_lines),
+            Action::Step { forward } => self.step(forward),
+            Action::NextString { forward } => {
+                let data = self.buffer.slice(0..self.buffer.len());
+                let cursor = self.cursor as usize;
+                match analysis::next_string(data, cursor, forward, MIN_STRING_LENGTH) {
+                    Some(start) => self.jump_to(start as u32, max_lines),
+                    None if forward => self.warn("No next string"),
+                    None => self.warn("No previous string"),
+                }
+            }
+            Action::JumpBack => self.jump_back(),
+            Action::NextDocument => {
+                self.switch_document((self.active + 1) % self.documents.len());
+            }
+            Action::PreviousDocument => {
+                let count = self.documents.len();
+                self.switch_document((self.active + count - 1) % count);
+            }
+            Action::CloseDocument => self.close(),
+            Action::SwitchView => self.switch_view(),
+            Action::EnterCommand(text) => self.mode = EditorMode::Command(text),
+            Action::Command(command) => self.process_command(&command),
+            Action::Quit => self.should_exit = true,
+        }
+    }
+
+    /// Moves the cursor to the nearest multiple of the size of the inspected type after or
+    /// before it, so that stepping through an array stays aligned to its elements.
+    fn step(&mut self, forward: bool) {
+        let Some(field) = self.inspected_field() else {
+            self.warn("No inspector type");
+            return;
+        };
+        let size = field.range.len();
+        let cursor = self.cursor as usize;
+        let position = match forward {
+            true => (cursor / size + 1) * size,
+            false => cursor.saturating_sub(1) / size * size,
+        };
+        self.jump_to(position as u32, self.max_lines);
+    }
+
+    /// Returns the value of the inspected type at the cursor.
+    fn inspected_field(&self) -> Option<Field> {
+        let kind = self.inspector.as_ref()?;
+        Field::new(kind, self.cursor as usize, kind)
+    }
+
+    /// Moves the cursor by `delta` bytes, past folded rows, and scrolls the view to it.
+    fn move_cursor(&mut self, delta: i64) {
+        self.update_cursor(delta);
+        self.skip_fold(delta > 0);
+        self.scroll_to_cursor(self.max_lines);
+    }
+
+    fn process_paste(&mut self, text: &str) {
+        if let EditorMode::Command(command) = &mut self.mode {
+            let text: String = text.chars().filter(|c| !c.is_control()).collect();
+            if command.len() + text.len() <= MAX_COMMAND_LENGTH {
+                command.push_str(&text);
+            } else {
+                self.warn("Cmd too long");
+            }
+        }
+    }
+
+    fn update_cursor(&mut self, cursor_update: i64) {
+        if (self.cursor as i64 + cursor_update) < 0 {
+            self.cursor = 0;
+        } else if (self.cursor as i64 + cursor_update) >= self.buffer.len() as i64 {
+            self.cursor = (self.buffer.len() - 1) as u32;
+        } else {
+            self.cursor = (self.cursor as i64 + cursor_update) as u32;
+        }
+    }
+
+    fn confirm_panel(&mut self, panel: Panel, action: PanelAction, max_lines: u32) {
+        match action {
+            PanelAction::Jump | PanelAction::Carve(_) => {
+                if let Some(offset) = panel.selected_offset() {
+                    self.jump_to(offset, max_lines);
+                }
+            }
+            PanelAction::SwitchDocument => {
+                if let Some(index) = panel.selected_offset() {
+                    self.switch_document(index as usize);
+                }
+            }
+            PanelAction::Browse(paths) => {
+                let Some(path) = panel.selected().map(|index| &paths[index]) else {
+                    return;
+                };
+                if path.is_dir() {
+                    self.browse(path.to_str());
+                    return;
+                }
+                match Document::load(path, false) {
+                    Ok(document) => self.open_document(document),
+                    Err(error) => self.fail(error.to_string()),
+                }
+            }
+            PanelAction::SaveChanges(changes) => {
+                let checked = panel.checked().unwrap_or_default();
+                let included: Vec<&Change> = changes
+                    .iter()
+                    .zip(checked)
+                    .filter_map(|(change, checked)| checked.then_some(change))
+                    .collect();
+                let mut data = self.buffer.with_changes(&included);
+                // The checksum fields cover the data written, not the data edited.
+                for index in 0..self.checksums.len() {
+                    let Some((range, digest)) = self.checksums[index].compute(&data) else {
+                        self.warn("Checksum field out of range");
+                        return;
+                    };
+                    if data[range.clone()] != digest[..]
+                        && !self.check_protected(range.clone(), false)
+                    {
+                        return;
+                    }
+                    data[range].copy_from_slice(&digest);
+                }
+                if let Err(error) = self.buffer.save_partial(&self.path, &self.codec, data) {
+                    self.fail(format!("Writing failed: {}", error));
+                }
+            }
+        }
+    }
+
+    /// Scrolls the view so that the row of the cursor is visible, along with up to `scrolloff`
+    /// rows above and below it within the data.
+    fn scroll_to_cursor(&mut self, max_lines: u32) {
+        let layout = self.layout(&self.focused_view());
+        let row = layout.row_start(self.cursor as usize);
+        let margin = self
+            .settings
+            .scroll_off
+            .min((max_lines.saturating_sub(1) / 2) as usize);
+        let (mut first, mut last) = (row, row);
+        for _ in 0..margin {
+            first = layout.previous(first);
+            let next = layout.next(last);
+            if next < self.buffer.len() {
+                last = next;
+            }
+        }
+        if first < self.offset as usize {
+            self.offset = first as u32;
+        } else if !layout
+            .rows(self.offset as usize, max_lines as usize)
+            .iter()
+            .any(|visible| visible.start() == last)
+        {
+            self.offset = layout.scroll_to(last, max_lines as usize) as u32;
+        }
+        self.sync_split();
+    }
+
+    /// Moves the cursor to the nearest boundary of the alignment after or before it, or scrolls
+    /// the view so that it starts at the nearest boundary after or before its offset.
+    fn snap(&mut self, forward: bool, view: bool, max_lines: u32) {
+        let last = self.buffer.len().saturating_sub(1);
+        if view {
+            let offset = self.settings.align(self.offset as usize, forward).min(last);
+            self.offset = offset as u32;
+            self.cursor = offset as u32;
+        } else {
+            let cursor = self.settings.align(self.cursor as usize, forward).min(last);
+            self.cursor = cursor as u32;
+            self.scroll_to_cursor(max_lines);
+        }
+    }
+
+    fn focused_view(&self) -> View<'_> {
+        View {
+            data: self.buffer.slice(0..self.buffer.len()),
+            cursor: self.cursor,
+            offset: self.offset,
+            focused: true,
+            folds: &self.folds,
+        }
+    }
+
+    /// Returns the rows of `view`, collapsing runs of identical bytes if enabled.
+    fn layout(&self, view: &View) -> Layout {
+        let layout = Layout::new(
+            self.bytes_per_line as usize,
+            view.offset as usize,
+            view.folds.to_vec(),
+        );
+        match self.settings.collapse {
+            true if view.focused => layout.collapse_runs(view.data, &self.expanded),
+            true => layout.collapse_runs(view.data, &[]),
+            false => layout,
+        }
+    }
+
+    /// Moves a cursor which has been moved into a folded range to the row of the range, or
+    /// past it when moving forward.
+    fn skip_fold(&mut self, forward: bool) {
+        let layout = self.layout(&self.focused_view());
+        let Some(span) = layout.span_at(self.cursor as usize) else {
+            return;
+        };
+        if self.cursor as usize != span.range.start {
+            self.cursor = match forward && span.range.end < self.buffer.len() {
+                true => span.range.end,
+                false => span.range.start,
+            } as u32;
+        }
+    }
+
+    /// Folds the region named `name`, or else the selection or the region at the cursor.
+    fn fold(&mut self, name: Option<&str>) {
+        let region = match name {
+            Some(name) => self.regions.iter().find(|region| region.name == name),
+            None if self.selection.is_some() => None,
+            None => regions::at(&self.regions, self.cursor as usize),
+        };
+        let span = match region {
+            Some(region) => Span {
+                range: region.range.clone(),
+                label: region.name.clone(),
+                run: false,
+            },
+            None if name.is_none() && self.selection.is_some() => Span {
+                range: self.selected_range(),
+                label: "selection".into(),
+                run: false,
+            },
+            None => {
+                self.warn(match name {
+                    Some(_) => "Unknown region",
+                    None => "Nothing to fold",
+                });
+                return;
+            }
+        };
+        self.cursor = span.range.start.min(self.buffer.len().saturating_sub(1)) as u32;
+        self.selection = None;
+        self.folds.push(span);
+    }
+
+    /// Expands the folded row, or the collapsed run, at the cursor.
+    fn unfold(&mut self) {
+        let layout = self.layout(&self.focused_view());
+        let Some(span) = layout.span_at(self.cursor as usize) else {
+            self.warn("No fold at the cursor");
+            return;
+        };
+        if span.run {
+            self.expanded.push(self.cursor as usize);
+            return;
+        }
+        let row = span.range.clone();
+        self.folds
+            .retain(|fold| fold.range.end <= row.start || fold.range.start >= row.end);
+    }
+
+    fn jump_to(&mut self, offset: u32, max_lines: u32) {
+        self.cursor = offset.min(self.buffer.len().saturating_sub(1) as u32);
+        self.scroll_to_cursor(max_lines);
+    }
+
+    fn process_command_event(&mut self, command: &String, event: KeyEvent) -> Option<EditorMode> {
+        if let KeyCode::Up | KeyCode::Down = event.code {
+            return self.browse_history(command, event.code == KeyCode::Up);
+        }
+        self.browsing = None;
+        if let Char(c) = event.code {
+            if c.is_ascii_graphic() || c == ' ' {
+                let mut new_command = command.to_string();
+                if command.len() < MAX_COMMAND_LENGTH {
+                    new_command += &c.to_string();
+                } else {
+                    self.warn("Cmd too long");
+                }
+                return Some(EditorMode::Command(new_command));
+            }
+            return None;
+        } else if event.code == KeyCode::Backspace {
+            if !command.is_empty() {
+                // Pasted text may end with a character of several bytes.
+                let mut new_command = command.to_string();
+                new_command.pop();
+                return Some(EditorMode::Command(new_command));
+            }
+        } else if event.code == KeyCode::Enter {
+            if let Err(error) = self.history.push(command) {
+                self.fail(format!("Failed to save history: {}", error));
+            }
+            self.process_command(command.as_str());
+            if self.panel.is_some() || self.replacing.is_some() {
+                return Some(EditorMode::Edit(None));
+            }
+            return Some(EditorMode::Command("".into()));
+        }
+
+        None
+    }
+
+    /// Replaces the typed command with the previous or next entry of the history starting with
+    /// the text typed before, e.g. with the earlier searches after `/`.
+    fn browse_history(&mut self, command: &str, backwards: bool) -> Option<EditorMode> {
+        let (prefix, index) = self
+            .browsing
+            .take()
+            .unwrap_or_else(|| (command.into(), self.history.len()));
+        let entry = match backwards {
+            true => self.history.previous(&prefix, index),
+            false => self.history.next(&prefix, index),
+        };
+        match entry {
+            Some((index, entry)) => {
+                let entry = entry.to_string();
+                self.browsing = Some((prefix, index));
+                Some(EditorMode::Command(entry))
+            }
+            None if backwards => {
+                self.browsing = Some((prefix, index));
+                None
+            }
+            None => Some(EditorMode::Command(prefix)),
+        }
+    }
+
+    fn process_command(&mut self, value: &str) {
+        tracing::debug!(command = value, "running command");
+        let mut args = value.split_whitespace();
+        let command = args.next().unwrap_or("");
+        match command {
+            "exit" | "quit" | "q" | "x" => {
+                self.close();
+            }
+            "wq" | "qw" => {
+                if self.save() && !self.close_document() {
+                    self.should_exit = true;
+                }
+            }
+            "write" | "w" => {
+                self.save();
+            }
+            "export" => {
+                self.export(args.next(), args.next());
+            }
+            "base64" => {
+                self.base64(args.next(), args.next());
+            }
+            "patch" => {
+                self.patch(args.next(), args.next());
+            }
+            "diff" => match args.next() {
+                Some(file) => {
+                    if let Err(error) = self.open_diff(Path::new(file)) {
+                        self.fail(error.to_string());
+                    }
+                }
+                None => self.diff = None,
+            },
+            "set" => {
+                while let Some(argument) = args.next() {
+                    // Values may also be given as the next argument, e.g. `set encoding ebcdic`.
+                    let argument = match argument {
+                        "encoding" | "unprintable" | "align" | "messagetime" | "scrolloff" => {
+                            format!("{}={}", argument, args.next().unwrap_or_default())
+                        }
+                        _ => argument.into(),
+                    };
+                    if let Err(message) = self.settings.set(&argument) {
+                        self.warn(message);
+                    }
+                }
+            }
+            "changes" => {
+                let entries = Self::change_entries(&self.buffer.changes());
+                self.panel = Some((Panel::new("Changes", entries), PanelAction::Jump));
+            }
+            "write-select" | "ws" => {
+                let changes = self.buffer.changes();
+                let panel = Panel::new("Select changes to write", Self::change_entries(&changes));
+                self.panel = Some((panel.with_checkboxes(), PanelAction::SaveChanges(changes)));
+            }
+            "diff-export" => {
+                self.diff_export(args.next(), args.next());
+            }
+            "checksum" => match ChecksumField::parse(args) {
+                Some(field) => self.checksums.push(field),
+                None => {
+                    self.warn(
+                        "Usage: checksum <crc32|md5|sha1|sha256> <start>..<end> <offset> [le]",
+                    );
+                }
+            },
+            "fix-checksum" => {
+                self.fix_checksum(args.next());
+            }
+            "source" => {
+                self.source(args.next());
+            }
+            "carve" => {
+                let files = carve::carve(self.buffer.slice(0..self.buffer.len()));
+                let entries = files
+                    .iter()
+                    .map(|file| {
+                        let label = format!(
+                            "{}  {:<20}  {:>10} bytes",
+                            tui::format_offset(file.offset),
+                            file.signature.name,
+                            file.length
+                        );
+                        (label, Some(file.offset as u32))
+                    })
+                    .collect();
+                let panel = Panel::new("Embedded files", entries);
+                self.panel = Some((panel, PanelAction::Carve(files)));
+            }
+            "headers" => match formats::parse(self.buffer.slice(0..self.buffer.len())) {
+                Some(structure) => {
+                    let title = format!("{} headers", structure.format);
+                    self.panel = Some((Panel::new(&title, structure.entries), PanelAction::Jump));
+                    self.template = Some(structure.template);
+                }
+                None => self.warn("Unknown file format"),
+            },
+            "template" => {
+                self.load_template(args.next(), args.next());
+            }
+            "magic" => {
+                self.magic(args.next());
+            }
+            "strings" => {
+                self.strings(args.next());
+            }
+            "tbl" => {
+                self.load_char_table(args.next());
+            }
+            "messages" => {
+                self.show_messages();
+            }
+            "yank" | "put" => {
+                let register = match args.next() {
+                    None => Some(UNNAMED_REGISTER),
+                    Some(name) => match name.chars().collect::<Vec<_>>()[..] {
+                        [c @ ('a'..='z' | '"')] => Some(c),
+                        _ => None,
+                    },
+                };
+                match register {
+                    Some(register) if command == "yank" => self.yank(register),
+                    Some(register) => self.put(register),
+                    None => self.warn(format!("Usage: {} [a-z]", command)),
+                }
+            }
+            "inspect" => match args.next() {
+                None => self.inspector = None,
+                Some(kind) if Field::new(kind, 0, kind).is_some() => {
+                    self.inspector = Some(kind.into());
+                }
+                Some(_) => {
+                    self.warn("Usage: inspect [u8-u64|i8-i64|f32|f64[le|be]|bytes[n]|char[n]]")
+                }
+            },
+            "registers" | "reg" => {
+                self.show_registers();
+            }
+            "find" => {
+                self.find(&args.collect::<String>());
+            }
+            "replace" => {
+                self.replace(&args.collect::<Vec<_>>());
+            }
+            "find-text" => {
+                self.find_text(&args.collect::<Vec<_>>().join(" "));
+            }
+            "write-text" => {
+                self.write_text(&args.collect::<Vec<_>>().join(" "));
+            }
+            "relsearch" => {
+                self.relative_search(&args.collect::<Vec<_>>().join(" "));
+            }
+            "xref" => match PointerFormat::parse(args) {
+                Some(format) => self.xref(format),
+                None => self.warn("Usage: xref [u16|u32|u64] [le|be] [base]"),
+            },
+            "follow" => match PointerFormat::parse(args) {
+                Some(format) => self.follow(format),
+                None => self.warn("Usage: follow [u16|u32|u64] [le|be] [base]"),
+            },
+            "back" => self.jump_back(),
+            "goto" => {
+                self.goto(&args.collect::<Vec<_>>().join(" "));
+            }
+            "mark" => match args.next().and_then(|arg| arg.parse::<char>().ok()) {
+                Some(name @ 'a'..='z') => {
+                    self.marks.insert(name, self.cursor);
+                }
+                _ => self.warn("Usage: mark <a-z>"),
+            },
+            "disasm" => {
+                self.set_architecture(args.next());
+            }
+            "asm" => {
+                self.assemble(&args.collect::<Vec<_>>().join(" "));
+            }
+            "decode" => {
+                self.decode(args.next(), args);
+            }
+            "xor" | "and" | "or" | "not" | "add" | "sub" | "bswap" | "rot13" | "rot" | "fill"
+            | "zero" | "fill-random" => match Transform::parse(command, args) {
+                Some(transform) => self.transform(transform),
+                None => self.warn(Transform::usage(command)),
+            },
+            "cyclic" => {
+                self.cyclic(args.next());
+            }
+            "cyclic-find" => {
+                self.cyclic_find(args.next());
+            }
+            "calc" => match args.collect::<Vec<_>>().join(" ") {
+                expression if expression.is_empty() => self.warn("Usage: calc <expression>"),
+                expression => match calc::evaluate(&expression, |name| self.variable(name)) {
+                    Ok(value) => self.inform(calc::format(value)),
+                    Err(error) => self.warn(error),
+                },
+            },
+            "e" | "edit" => match args.next() {
+                Some(file) => match Document::load(Path::new(file), false) {
+                    Ok(document) => self.open_document(document),
+                    Err(error) => self.fail(error.to_string()),
+                },
+                None => self.warn("Usage: e <file>"),
+            },
+            "split" | "sp" => {
+                self.split(false, args.next());
+            }
+            "vsplit" | "vs" => {
+                self.split(true, args.next());
+            }
+            "only" => {
+                self.split = None;
+            }
+            "lock" => {
+                self.lock(args.next());
+            }
+            "unlock" => {
+                if let Some(split) = &mut self.split {
+                    split.lock = None;
+                }
+            }
+            "bnext" | "bn" => {
+                self.switch_document((self.active + 1) % self.documents.len());
+            }
+            "bprev" | "bp" => {
+                let count = self.documents.len();
+                self.switch_document((self.active + count - 1) % count);
+            }
+            "next" | "n" => match self.active + 1 < self.documents.len() {
+                true => self.switch_document(self.active + 1),
+                false => self.warn("No next file"),
+            },
+            "prev" | "previous" | "N" => match self.active.checked_sub(1) {
+                Some(index) => self.switch_document(index),
+                None => self.warn("No previous file"),
+            },
+            "b" | "buffer" => match args.next().and_then(|n| n.parse::<usize>().ok()) {
+                Some(number) if (1..=self.documents.len()).contains(&number) => {
+                    self.switch_document(number - 1);
+                }
+                _ => self.warn("Usage: b <number of an open file>"),
+            },
+            "protect" => self.protect(args.next()),
+            "unprotect" => self.unprotect(args.next()),
+            "note" => match args.next() {
+                Some(location) => {
+                    let text = args.collect::<Vec<_>>().join(" ");
+                    self.add_note(location, &text);
+                }
+                None => self.warn("Usage: note <offset|start..end> <text>"),
+            },
+            "notes" => {
+                let entries = self
+                    .notes
+                    .iter()
+                    .map(|note| {
+                        let label = format!(
+                            "{}  {:>8} bytes  {}",
+                            tui::format_offset(note.range.start),
+                            note.range.len(),
+                            note.text
+                        );
+                        (label, Some(note.range.start as u32))
+                    })
+                    .collect();
+                self.panel = Some((Panel::new("Notes", entries), PanelAction::Jump));
+            }
+            "unnote" => {
+                let cursor = self.cursor as usize;
+                let count = self.notes.len();
+                self.notes.retain(|note| !note.range.contains(&cursor));
+                match count - self.notes.len() {
+                    0 => self.warn("No note at the cursor"),
+                    _ => self.save_notes(),
+                }
+            }
+            "region" => {
+                self.add_region(args.collect());
+            }
+            "regions" => match args.next() {
+                None => self.list_regions(),
+                Some("from-template") => self.regions_from_template(),
+                Some(_) => self.warn("Usage: regions [from-template]"),
+            },
+            "unregion" => {
+                let cursor = self.cursor as usize;
+                let count = self.regions.len();
+                self.regions
+                    .retain(|region| !region.range.contains(&cursor));
+                if self.regions.len() == count {
+                    self.warn("No region at the cursor");
+                }
+            }
+            "annotations" => match (args.next(), args.next()) {
+                (Some("export"), Some(file)) => self.export_annotations(Path::new(file)),
+                (Some("import"), Some(file)) => self.import_annotations(Path::new(file)),
+                _ => self.warn("Usage: annotations <export|import> <file>"),
+            },
+            "fold" => {
+                self.fold(args.next());
+            }
+            "unfold" => match args.next() {
+                None => self.unfold(),
+                Some("all") => self.folds.clear(),
+                Some(_) => self.warn("Usage: unfold [all]"),
+            },
+            "browse" => {
+                self.browse(args.next());
+            }
+            "ls" | "buffers" => {
+                self.list_documents();
+            }
+            "inflate" => {
+                self.inflate();
+            }
+            "undo" => {
+                self.undo();
+            }
+            "redo" => {
+                self.redo();
+            }
+            "overview" => match args.next().unwrap_or("class").parse() {
+                Ok(coloring) => {
+                    let columns = 4 * self.bytes_per_line as usize + 4;
+                    let position = self.cursor as usize;
+                    self.overview = Some(Overview::new(coloring, position, columns));
+                }
+                Err(_) => self.warn("Usage: overview [class|entropy]"),
+            },
+            "histogram" => {
+                self.histogram();
+            }
+            "stats" => {
+                self.statistics();
+            }
+            "hash" => {
+                self.hash(args.next());
+            }
+            "verify" => {
+                self.verify(args.next(), args.next());
+            }
+            "compare" => {
+                self.compare(args.next(), args.next(), args.next());
+            }
+            _ => {
+                self.warn("Invalid command");
+            }
+        }
+    }
+
+    /// Returns the selected range, or the whole buffer if nothing is selected.
+    fn selected_range(&self) -> Range<usize> {
+        match self.selection {
+            Some(anchor) => {
+                let start = anchor.min(self.cursor) as usize;
+                let end = anchor.max(self.cursor) as usize + 1;
+                start..end.min(self.buffer.len())
+            }
+            None => 0..self.buffer.len(),
+        }
+    }
+
+    /// Describes the first and last byte and the length of the selection in hex and decimal.
+    fn selection_summary(&self) -> Option<String> {
+        self.selection?;
+        let range = self.selected_range();
+        let last = range.end.saturating_sub(1);
+        Some(format!(
+            "0x{:x}-0x{:x} ({}-{}), {} bytes (0x{:x})",
+            range.start,
+            last,
+            range.start,
+            last,
+            range.len(),
+            range.len()
+        ))
+    }
+
+    fn is_selected(&self, position: usize) -> bool {
+        self.selection.is_some() && self.selected_range().contains(&position)
+    }
+
+    fn export(&mut self, format: Option<&str>, file: Option<&str>) {
+        let (Some(format), Some(file)) = (format, file) else {
+            self.warn("Usage: export <c|rust|python> <file>");
+            return;
+        };
+        let Ok(format) = format.parse::<ExportFormat>() else {
+            self.warn("Unknown format");
+            return;
+        };
+        let output = format.export(self.buffer.slice(self.selected_range()));
+        if std::fs::write(file, output).is_err() {
+            self.fail("Export failed");
+        }
+    }
+
+    fn base64(&mut self, action: Option<&str>, data: Option<&str>) {
+        let range = self.selected_range();
+        match (action, data) {
+            (Some("encode"), None) => {
+                let encoded = base64::encode(self.buffer.slice(range.clone()));
+                self.replace_range(range, encoded.as_bytes());
+            }
+            (Some("decode"), None) => match base64::decode(self.buffer.slice(range.clone())) {
+                Some(decoded) => self.replace_range(range, &decoded),
+                None => self.warn("Invalid base64"),
+            },
+            (Some("decode"), Some(data)) => {
+                let range = match self.selection {
+                    Some(_) => range,
+                    None => self.cursor as usize..self.cursor as usize,
+                };
+                match base64::decode(data.as_bytes()) {
+                    Some(decoded) => self.replace_range(range, &decoded),
+                    None => self.warn("Invalid base64"),
+                }
+            }
+            (Some("copy"), None) => {
+                let encoded = base64::encode(self.buffer.slice(range));
+                if Terminal::copy_to_clipboard(&encoded).is_err() {
+                    self.fail("Copy failed");
+                }
+            }
+            _ => {
+                self.warn("Usage: base64 <encode|decode [data]|copy>");
+            }
+        }
+    }
+
+    fn patch(&mut self, action: Option<&str>, file: Option<&str>) {
+        let (Some(action @ ("create" | "apply")), Some(file)) = (action, file) else {
+            self.warn("Usage: patch <create|apply> <file>");
+            return;
+        };
+        let Some(format) = PatchFormat::from_path(Path::new(file)) else {
+            self.warn("Unknown patch format");
+            return;
+        };
+        let data = self.buffer.slice(0..self.buffer.len());
+        if action == "create" {
+            let result = format
+                .create(self.buffer.original(), data)
+                .and_then(|patch| std::fs::write(file, patch));
+            if result.is_err() {
+                self.fail("Creating patch failed");
+            }
+        } else {
+            match std::fs::read(file).and_then(|patch| format.apply(&patch, data)) {
+                Ok(patched) => self.replace_range(0..self.buffer.len(), &patched),
+                Err(error) => self.fail(error.to_string()),
+            }
+        }
+    }
+
+    /// Writes a delta between the file `original` and the buffer, as VCDIFF unless the
+    /// extension of `file` names another patch format.
+    fn diff_export(&mut self, original: Option<&str>, file: Option<&str>) {
+        let (Some(original), Some(file)) = (original, file) else {
+            self.warn("Usage: diff-export <original> <file>");
+            return;
+        };
+        let format = PatchFormat::from_path(Path::new(file)).unwrap_or(PatchFormat::Vcdiff);
+        let data = self.buffer.slice(0..self.buffer.len());
+        let result = std::fs::read(original)
+            .and_then(|original| format.create(&original, data))
+            .and_then(|patch| std::fs::write(file, patch));
+        if let Err(error) = result {
+            self.fail(error.to_string());
+        }
+    }
+
+    /// Writes the carved file selected in the panel next to the edited file.
+    fn export_carved(&mut self) {
+        let Some((panel, PanelAction::Carve(files))) = &self.panel else {
+            return;
+        };
+        let Some(file) = panel.selected().and_then(|index| files.get(index)) else {
+            return;
+        };
+        let name = format!(
+            "{}_{:08x}.{}",
+            self.path.file_stem().unwrap_or_default().to_string_lossy(),
+            file.offset,
+            file.signature.extension
+        );
+        let path = self.path.with_file_name(name);
+        let data = self.buffer.slice(file.offset..file.offset + file.length);
+        match std::fs::write(&path, data) {
+            Ok(_) => self.inform(format!("Exported {}", path.display())),
+            Err(_) => self.fail("Export failed"),
+        }
+    }
+
+    /// Maps the structure defined in `file` onto the buffer at `offset`, or at the cursor.
+    /// Without a file, the current template is removed.
+    fn load_template(&mut self, file: Option<&str>, offset: Option<&str>) {
+        let Some(file) = file else {
+            self.template = None;
+            return;
+        };
+        let Some(offset) = offset.map_or(Some(self.cursor as usize), parse_number) else {
+            self.warn("Usage: template [file [offset]]");
+            return;
+        };
+        match std::fs::read_to_string(file).and_then(|text| Template::parse(&text, offset)) {
+            Ok(template) => self.template = Some(template),
+            Err(error) => self.fail(error.to_string()),
+        }
+    }
+
+    /// Shows the type of the file, or of a file embedded at `offset`.
+    fn magic(&mut self, offset: Option<&str>) {
+        let Some(offset) = offset.map_or(Some(0), parse_number) else {
+            self.warn("Usage: magic [offset]");
+            return;
+        };
+        let data = self.buffer.slice(0..self.buffer.len());
+        let signature = magic::matches_at(data, offset).next();
+        match signature {
+            Some(signature) => self.inform(signature.name),
+            None => self.warn("Unknown file type"),
+        }
+    }
+
+    /// Lists the printable strings in the selection, or the whole buffer.
+    fn strings(&mut self, min_length: Option<&str>) {
+        let Ok(min_length) = min_length.map_or(Ok(4), str::parse::<usize>) else {
+            self.warn("Usage: strings [minlen]");
+            return;
+        };
+        let range = self.selected_range();
+        let entries = analysis::strings(self.buffer.slice(range.clone()), min_length.max(1))
+            .into_iter()
+            .map(|(offset, text, utf16)| {
+                let offset = range.start + offset;
+                let label = format!(
+                    "{}  {}  {}",
+                    tui::format_offset(offset),
+                    if utf16 { "utf16" } else { "ascii" },
+                    text
+                );
+                (label, Some(offset as u32))
+            })
+            .collect();
+        self.panel = Some((Panel::new("Strings", entries), PanelAction::Jump));
+    }
+
+    /// Lists the matches of `text` encoded with any constant added to its characters, showing
+    /// the byte each match implies for `A` and `a`.
+    fn relative_search(&mut self, text: &str) {
+        if text.len() < 2 {
+            self.warn("Usage: relsearch <text of two or more characters>");
+            return;
+        }
+        let data = self.buffer.slice(0..self.buffer.len());
+        let entries = search::relative_search(data, text.as_bytes())
+            .into_iter()
+            .map(|(offset, shift)| {
+                let label = format!(
+                    "{}  shift 0x{:0>2x}  A = 0x{:0>2x}  a = 0x{:0>2x}",
+                    tui::format_offset(offset),
+                    shift,
+                    b'A'.wrapping_add(shift),
+                    b'a'.wrapping_add(shift)
+                );
+                (label, Some(offset as u32))
+            })
+            .collect();
+        let title = format!("Relative matches of \"{}\"", text);
+        self.panel = Some((Panel::new(&title, entries), PanelAction::Jump));
+    }
+
+    /// Loads a character table as the encoding of the text pane, or restores ASCII without a file.
+    fn load_char_table(&mut self, file: Option<&str>) {
+        let Some(file) = file else {
+            self.settings.encoding = TextEncoding::Ascii;
+            return;
+        };
+        match std::fs::read_to_string(file).and_then(|text| CharTable::parse(&text)) {
+            Ok(table) => self.settings.encoding = TextEncoding::Table(table),
+            Err(error) => self.fail(error.to_string()),
+        }
+    }
+
+    /// Encodes `text` in the encoding of the text pane, warning if it cannot be encoded.
+    fn encode_text(&mut self, text: &str, usage: &str) -> Option<Vec<u8>> {
+        if text.is_empty() {
+            self.warn(usage);
+            return None;
+        }
+        let bytes = self.settings.encoding.encode(text);
+        if bytes.is_none() {
+            self.warn("Text cannot be encoded in the current encoding");
+        }
+        bytes
+    }
+
+    /// Lists the messages, warnings and errors of the session, selecting the latest.
+    fn show_messages(&mut self) {
+        let entries: Vec<_> = self
+            .messages
+            .log()
+            .iter()
+            .map(|message| {
+                let label = format!("{:<7}  {}", message.severity.name(), message.text);
+                (label, None)
+            })
+            .collect();
+        let count = entries.len() as i64;
+        let mut panel = Panel::new("Messages", entries).with_filter();
+        panel.move_selection(count);
+        self.panel = Some((panel, PanelAction::Jump));
+    }
+
+    /// Copies the selection, or the whole buffer, into `register` and the unnamed register.
+    fn yank(&mut self, register: char) {
+        let bytes = self.buffer.slice(self.selected_range()).to_vec();
+        self.inform(format!("Yanked {} bytes", bytes.len()));
+        self.registers.insert(UNNAMED_REGISTER, bytes.clone());
+        self.registers.insert(register, bytes);
+        self.selection = None;
+    }
+
+    /// Writes the bytes of `register` at the cursor, inserting them in insert mode and
+    /// overwriting the bytes at the cursor otherwise.
+    fn put(&mut self, register: char) {
+        let Some(bytes) = self.registers.get(&register).cloned() else {
+            self.warn(format!("Register {} is empty", register));
+            return;
+        };
+        let cursor = (self.cursor as usize).min(self.buffer.len());
+        let end = match self.insert {
+            true => cursor,
+            false => (cursor + bytes.len()).min(self.buffer.len()),
+        };
+        if self.splice(cursor..end, &bytes) {
+            self.inform(format!("Put {} bytes", bytes.len()));
+        }
+    }
+
+    /// Lists the registers holding yanked bytes and the pattern of the latest search, with
+    /// their length and first bytes.
+    fn show_registers(&mut self) {
+        let search = self.search.as_ref().map(|search| ('/', &search.pattern));
+        let entries = self
+            .registers
+            .iter()
+            .map(|(name, bytes)| (*name, bytes))
+            .chain(search)
+            .map(|(name, bytes)| {
+                let preview: Vec<_> = bytes
+                    .iter()
+                    .take(16)
+                    .map(|b| format!("{:0>2x}", b))
+                    .collect();
+                let more = if bytes.len() > 16 { " ..." } else { "" };
+                let label = format!(
+                    "{}  {:>8} bytes  {}{}",
+                    name,
+                    bytes.len(),
+                    preview.join(" "),
+                    more
+                );
+                (label, None)
+            })
+            .collect();
+        self.panel = Some((Panel::new("Registers", entries), PanelAction::Jump));
+    }
+
+    /// Searches for the hex bytes of `pattern` and moves the cursor to the first match at or
+    /// after it.
+    fn find(&mut self, pattern: &str) {
+        let Some(pattern) = parse::parse_hex(pattern) else {
+            self.warn("Usage: find <hex bytes>");
+            return;
+        };
+        match self.search_from(pattern, self.cursor as usize) {
+            Some(position) => self.cursor = position as u32,
+            None => self.warn("Pattern not found"),
+        }
+    }
+
+    /// Replaces the hex bytes of `args[0]` with those of `args[1]` at the first match at or after
+    /// the cursor, at every match if followed by `all`, or at the matches confirmed one by one
+    /// with `all confirm`. With a selection, only matches within it are replaced.
+    fn replace(&mut self, args: &[&str]) {
+        let parsed = match args {
+            [pattern, bytes, rest @ ..] => parse::parse_hex(pattern)
+                .zip(parse::parse_hex(bytes))
+                .zip(match rest {
+                    [] => Some((false, false)),
+                    ["all"] => Some((true, false)),
+                    ["all", "confirm"] => Some((true, true)),
+                    _ => None,
+                }),
+            _ => None,
+        };
+        let Some(((pattern, bytes), (all, confirm))) = parsed else {
+            self.warn("Usage: replace <hex pattern> <hex bytes> [all [confirm]]");
+            return;
+        };
+        let range = self.selected_range();
+        let selected = self.selection.is_some();
+        if confirm {
+            self.replacing = Some(Replacement {
+                pattern,
+                bytes,
+                position: range.start,
+                range,
+                selected,
+                count: 0,
+            });
+            self.next_replacement();
+        } else if all {
+            let data = self.buffer.slice(range.clone());
+            match search::replace_all(data, &pattern, &bytes) {
+                (_, 0) => self.warn("Pattern not found"),
+                (data, count) => {
+                    let start = range.start;
+                    if !self.splice(range, &data) {
+                        return;
+                    }
+                    if selected {
+                        self.select(start..start + data.len());
+                    }
+                    self.jump_to(self.cursor, self.max_lines);
+                    self.inform(format!("Replaced {} occurrences", count));
+                }
+            }
+        } else {
+            let data = self.buffer.slice(0..range.end);
+            let position = if selected {
+                range.start
+            } else {
+                self.cursor as usize
+            };
+            match search::find_from(data, &pattern, position) {
+                Some(offset) if selected => {
+                    if self.splice(offset..offset + pattern.len(), &bytes) {
+                        self.select(range.start..range.end - pattern.len() + bytes.len());
+                    }
+                }
+                Some(offset) => {
+                    if self.splice(offset..offset + pattern.len(), &bytes) {
+                        self.jump_to(offset as u32, self.max_lines);
+                    }
+                }
+                None => self.warn("Pattern not found"),
+            }
+        }
+    }
+
+    /// Selects `range`, with the cursor on its last byte, or clears the selection if it is empty.
+    fn select(&mut self, range: Range<usize>) {
+        match range.is_empty() {
+            true => self.selection = None,
+            false => {
+                self.selection = Some(range.start as u32);
+                self.cursor = (range.end - 1) as u32;
+            }
+        }
+    }
+
+    /// Selects the next match of the replacement being confirmed, or ends it after the last,
+    /// selecting the replaced range again if it was the selection.
+    fn next_replacement(&mut self) {
+        let Some(replacement) = &mut self.replacing else {
+            return;
+        };
+        let data = self.buffer.slice(0..replacement.range.end);
+        match search::find_from(data, &replacement.pattern, replacement.position) {
+            Some(offset) => {
+                replacement.position = offset;
+                let end = offset + replacement.pattern.len() - 1;
+                self.selection = Some(end as u32);
+                self.jump_to(offset as u32, self.max_lines);
+            }
+            None => {
+                let Replacement {
+                    range,
+                    selected,
+                    count,
+                    ..
+                } = self.replacing.take().unwrap();
+                match selected {
+                    true => self.select(range),
+                    false => self.selection = None,
+                }
+                self.inform(format!("Replaced {} occurrences", count));
+            }
+        }
+    }
+
+    /// Replaces the shown match with `y`, skips it with `n`, replaces it and all further
+    /// matches with `a`, and stops with `q` or `Esc`.
+    fn process_replace_event(&mut self, event: KeyEvent) {
+        let Some(mut replacement) = self.replacing.take() else {
+            return;
+        };
+        let position = replacement.position;
+        let end = replacement.range.end;
+        match event.code {
+            Char('y') => {
+                let (pattern, bytes) = (&replacement.pattern, &replacement.bytes);
+                if !self.splice(position..position + pattern.len(), bytes) {
+                    self.replacing = Some(replacement);
+                    return;
+                }
+                replacement.range.end = end - pattern.len() + bytes.len();
+                replacement.position = position + bytes.len();
+                replacement.count += 1;
+            }
+            Char('n') => replacement.position = position + replacement.pattern.len(),
+            Char('a') => {
+                let data = self.buffer.slice(position..end);
+                let (data, count) =
+                    search::replace_all(data, &replacement.pattern, &replacement.bytes);
+                if !self.splice(position..end, &data) {
+                    self.replacing = Some(replacement);
+                    return;
+                }
+                replacement.range.end = position + data.len();
+                replacement.position = replacement.range.end;
+                replacement.count += count;
+            }
+            Char('q') | KeyCode::Esc => replacement.position = end,
+            _ => {}
+        }
+        self.replacing = Some(replacement);
+        self.next_replacement();
+    }
+
+    /// Searches for the hex bytes of `pattern`, or for `pattern` as text in the encoding of the
+    /// text pane if `text` is set, and moves the cursor and the view to the first match at or
+    /// after the cursor, returning its offset.
+    pub fn find_first(&mut self, pattern: &str, text: bool) -> Result<usize, &'static str> {
+        let pattern = match text {
+            true => self.settings.encoding.encode(pattern),
+            false => parse::parse_hex(pattern),
+        };
+        let pattern = pattern
+            .filter(|pattern| !pattern.is_empty())
+            .ok_or("Invalid pattern")?;
+        let position = self
+            .search_from(pattern, self.cursor as usize)
+            .ok_or("Pattern not found")?;
+        self.move_to(position);
+        Ok(position)
+    }
+
+    /// Makes `pattern` the latest search and returns its first match at or after `position`,
+    /// wrapping around at the end.
+    fn search_from(&mut self, pattern: Vec<u8>, position: usize) -> Option<usize> {
+        let data = self.buffer.slice(0..self.buffer.len());
+        let search = Search::new(data, pattern);
+        let found = match search.index_at(data, position) {
+            Some(_) => Some(position),
+            None => search.next(position, true),
+        };
+        self.search = Some(search);
+        found
+    }
+
+    /// Moves the cursor to the next match of the latest search, or the previous one if not
+    /// `forward`.
+    fn find_next(&mut self, forward: bool, max_lines: u32) {
+        let data = self.buffer.slice(0..self.buffer.len());
+        let Some(search) = &mut self.search else {
+            self.warn("No search pattern");
+            return;
+        };
+        search.update(data);
+        match search.next(self.cursor as usize, forward) {
+            Some(position) => self.jump_to(position as u32, max_lines),
+            None => self.warn("Pattern not found"),
+        }
+    }
+
+    /// Describes the match of the latest search at the cursor, if any.
+    fn search_summary(&self) -> Option<String> {
+        let data = self.buffer.slice(0..self.buffer.len());
+        let search = self.search.as_ref()?;
+        let index = search.index_at(data, self.cursor as usize)?;
+        Some(format!(
+            "Match {} of {} at 0x{:x}",
+            index + 1,
+            search.count(),
+            self.cursor
+        ))
+    }
+
+    /// Lists the occurrences of `text` in the encoding of the text pane.
+    fn find_text(&mut self, text: &str) {
+        let Some(pattern) = self.encode_text(text, "Usage: find-text <text>") else {
+            return;
+        };
+        let data = self.buffer.slice(0..self.buffer.len());
+        let entries = data
+            .windows(pattern.len())
+            .enumerate()
+            .filter(|(_, window)| *window == pattern)
+            .map(|(offset, _)| {
+                let label = tui::format_offset(offset);
+                (label, Some(offset as u32))
+            })
+            .collect();
+        let title = format!("Matches of \"{}\"", text);
+        self.panel = Some((Panel::new(&title, entries), PanelAction::Jump));
+    }
+
+    /// Writes `text` in the encoding of the text pane at the cursor, overwriting the bytes there.
+    fn write_text(&mut self, text: &str) {
+        let Some(bytes) = self.encode_text(text, "Usage: write-text <text>") else {
+            return;
+        };
+        let cursor = (self.cursor as usize).min(self.buffer.len());
+        let end = (cursor + bytes.len()).min(self.buffer.len());
+        if self.splice(cursor..end, &bytes) {
+            self.inform(format!("Wrote {} bytes", bytes.len()));
+        }
+    }
+
+    /// Lists the values in the buffer which point at the cursor.
+    fn xref(&mut self, format: PointerFormat) {
+        let data = self.buffer.slice(0..self.buffer.len());
+        let entries = format
+            .references(data, self.cursor as usize)
+            .into_iter()
+            .map(|offset| {
+                let label = format!(
+                    "{}  {}",
+                    tui::format_offset(offset),
+                    self.nearest_symbol(offset)
+                        .map(|(name, distance)| format!("{}{}", name, distance))
+                        .unwrap_or_default()
+                );
+                (label, Some(offset as u32))
+            })
+            .collect();
+        let title = format!(
+            "References to 0x{:x}",
+            format.base.wrapping_add(self.cursor as u64)
+        );
+        self.panel = Some((Panel::new(&title, entries), PanelAction::Jump));
+    }
+
+    /// Moves the cursor to the offset pointed at by the value at the cursor, remembering the
+    /// cursor in the jump list.
+    fn follow(&mut self, format: PointerFormat) {
+        let data = self.buffer.slice(0..self.buffer.len());
+        match format.target(data, self.cursor as usize) {
+            Some(offset) if offset < self.buffer.len() => {
+                self.push_jump();
+                self.jump_to(offset as u32, self.max_lines);
+            }
+            Some(offset) => self.warn(format!("Pointer to 0x{:x} is outside the file", offset)),
+            None => self.warn("No pointer at the cursor"),
+        }
+    }
+
+    fn push_jump(&mut self) {
+        if self.jumps.len() == MAX_JUMPS {
+            self.jumps.remove(0);
+        }
+        self.jumps.push(self.cursor);
+    }
+
+    /// Moves the cursor back to the latest offset in the jump list.
+    fn jump_back(&mut self) {
+        match self.jumps.pop() {
+            Some(offset) => self.jump_to(offset, self.max_lines),
+            None => self.warn("Jump list is empty"),
+        }
+    }
+
+    /// Returns the value of a name in expressions: `cursor`, `end` (the length of the file), a
+    /// mark such as `'a`, or a symbol.
+    fn variable(&self, name: &str) -> Option<i64> {
+        let offset = match name {
+            "cursor" => self.cursor as usize,
+            "end" => self.buffer.len(),
+            _ => match name
+                .strip_prefix('\'')
+                .and_then(|mark| mark.parse::<char>().ok())
+            {
+                Some(mark) => *self.marks.get(&mark)? as usize,
+                None => self.symbol(name)?,
+            },
+        };
+        Some(offset as i64)
+    }
+
+    fn symbol(&self, name: &str) -> Option<usize> {
+        self.symbols
+            .iter()
+            .find(|(_, symbol)| symbol == name)
+            .map(|(offset, _)| *offset)
+    }
+
+    /// Moves the cursor to a symbol, or to the offset `target` evaluates to, such as
+    /// `cursor + 0x40` or `'a + 8`.
+    fn goto(&mut self, target: &str) {
+        if target.is_empty() {
+            self.warn("Usage: goto <expression|symbol|percent%>");
+            return;
+        }
+        if let Some(percent) = target.strip_suffix('%') {
+            match percent.parse::<f64>() {
+                Ok(percent) if (0.0..=100.0).contains(&percent) => {
+                    let length = self.buffer.len();
+                    let position = (length as f64 * percent / 100.0) as usize;
+                    let position = position.min(length.saturating_sub(1));
+                    self.cursor = self.layout(&self.focused_view()).row_start(position) as u32;
+                }
+                _ => self.warn("Percentage must be between 0 and 100"),
+            }
+            return;
+        }
+        let offset = match self.symbol(target) {
+            Some(offset) => Ok(offset as i64),
+            None => calc::evaluate(target, |name| self.variable(name)),
+        };
+        match offset {
+            Ok(offset) if offset >= 0 => {
+                self.cursor = (offset as usize).min(self.buffer.len().saturating_sub(1)) as u32;
+            }
+            Ok(_) => self.warn("Offset is negative"),
+            Err(_)
+                if target
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            {
+                self.warn("Unknown symbol")
+            }
+            Err(error) => self.warn(error),
+        }
+    }
+
+    /// Selects the architecture for `:asm` and shows the disassembly pane, or hides it if no
+    /// architecture is given.
+    fn set_architecture(&mut self, architecture: Option<&str>) {
+        let Some(architecture) = architecture else {
+            self.architecture = None;
+            return;
+        };
+        if !disasm::AVAILABLE && !asm::AVAILABLE {
+            self.warn("Built without the disassembly and assembly features");
+            return;
+        }
+        match architecture.parse() {
+            Ok(architecture) => self.architecture = Some(architecture),
+            Err(_) => self.warn("Usage: disasm [x86|x86_64|arm|aarch64]"),
+        }
+    }
+
+    /// Assembles `code` for the selected architecture and writes the encoded bytes at the cursor.
+    fn assemble(&mut self, code: &str) {
+        let Some(architecture) = self.architecture else {
+            self.warn("Select an architecture with :disasm first");
+            return;
+        };
+        if code.is_empty() {
+            self.warn("Usage: asm <instructions>");
+            return;
+        }
+        let cursor = (self.cursor as usize).min(self.buffer.len());
+        match asm::assemble(architecture, code, cursor as u64) {
+            Ok(bytes) => {
+                let end = (cursor + bytes.len()).min(self.buffer.len());
+                if self.splice(cursor..end, &bytes) {
+                    self.inform(format!("Assembled {} bytes", bytes.len()));
+                }
+            }
+            Err(error) => self.fail(error),
+        }
+    }
+
+    /// Decodes the selection, or the whole buffer, as `format` and lists the decoded entries.
+    fn decode<'a>(&mut self, format: Option<&str>, args: impl Iterator<Item = &'a str>) {
+        let range = self.selected_range();
+        let data = self.buffer.slice(range.clone());
+        let entries = match format {
+            Some("protobuf") => protobuf::decode(data, range.start),
+            Some("tlv") => {
+                let Some((mut tlv, table)) = TlvFormat::parse(args) else {
+                    self.warn(
+                        "Usage: decode tlv <tag size> <length size> [le|be] [nested] [table]",
+                    );
+                    return;
+                };
+                if let Some(table) = table
+                    && let Err(error) =
+                        std::fs::read_to_string(table).and_then(|text| tlv.load_tags(&text))
+                {
+                    self.fail(error.to_string());
+                    return;
+                }
+                tlv.decode(data, range.start)
+            }
+            _ => {
+                self.warn("Usage: decode <protobuf|tlv> ...");
+                return;
+            }
+        };
+        let entries = entries
+            .into_iter()
+            .map(|(label, offset)| (label, Some(offset as u32)))
+            .collect();
+        let title = format!("Decoded {}", format.unwrap_or_default());
+        self.panel = Some((Panel::new(&title, entries), PanelAction::Jump));
+    }
+
+    /// Lists how often each byte value occurs in the selection, or the whole buffer, as a bar
+    /// chart. Entries jump to the first occurrence of their value.
+    fn histogram(&mut self) {
+        const BAR_WIDTH: usize = 40;
+        let range = self.selected_range();
+        let data = self.buffer.slice(range.clone());
+        let counts = analysis::byte_counts(data);
+        let max = counts.iter().max().copied().unwrap_or_default().max(1);
+        let entries = (0..=255u8)
+            .zip(counts)
+            .filter(|(_, count)| *count > 0)
+            .map(|(value, count)| {
+                let label = format!(
+                    "{:0>2x}  {:<width$}  {:>8}  {:>5.1}%",
+                    value,
+                    "\u{2588}".repeat((count * BAR_WIDTH).div_ceil(max)),
+                    count,
+                    100.0 * count as f64 / data.len() as f64,
+                    width = BAR_WIDTH
+                );
+                let first = data.iter().position(|byte| *byte == value).unwrap();
+                (label, Some((range.start + first) as u32))
+            })
+            .collect();
+        self.panel = Some((Panel::new("Histogram", entries), PanelAction::Jump));
+    }
+
+    /// Writes a cyclic pattern of `length` bytes at the cursor, or over the selection.
+    fn cyclic(&mut self, length: Option<&str>) {
+        let range = match length.map(parse_number) {
+            Some(Some(length)) => {
+                let cursor = (self.cursor as usize).min(self.buffer.len());
+                cursor..cursor.saturating_add(length)
+            }
+            Some(None) => {
+                self.warn("Usage: cyclic [length]");
+                return;
+            }
+            None => self.selected_range(),
+        };
+        if range.len() > cyclic::MAX_LENGTH {
+            self.warn(format!(
+                "Patterns are at most {} bytes long",
+                cyclic::MAX_LENGTH
+            ));
+            return;
+        }
+        let pattern = cyclic::pattern(range.len());
+        let end = range.end.min(self.buffer.len());
+        if self.splice(range.start..end, &pattern) {
+            self.inform(format!("Wrote {} bytes", pattern.len()));
+        }
+    }
+
+    /// Shows the offset of a subsequence of the cyclic pattern, given as a little-endian number as
+    /// read from a register, or as text.
+    fn cyclic_find(&mut self, value: Option<&str>) {
+        let Some(value) = value else {
+            self.warn("Usage: cyclic-find <0xvalue|text>");
+            return;
+        };
+        let subsequence = match value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+            .map(|hex| u32::from_str_radix(hex, 16))
+        {
+            Some(Ok(value)) => value.to_le_bytes().to_vec(),
+            Some(Err(_)) => {
+                self.warn("Usage: cyclic-find <0xvalue|text>");
+                return;
+            }
+            None => value.as_bytes().to_vec(),
+        };
+        match cyclic::find(&subsequence) {
+            Some(offset) => self.inform(format!("Found at offset {} (0x{:x})", offset, offset)),
+            None => self.warn("Not part of the cyclic pattern"),
+        }
+    }
+
+    fn tabs(&self) -> Vec<Tab> {
+        (0..self.documents.len())
+            .map(|index| {
+                let (path, buffer) = match index == self.active {
+                    true => (&self.path, &self.buffer),
+                    false => (&self.documents[index].path, &self.documents[index].buffer),
+                };
+                Tab {
+                    name: path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into(),
+                    modified: buffer.is_dirty(),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns whether any open file has changes which have not been written.
+    pub fn is_modified(&self) -> bool {
+        self.tabs().iter().any(|tab| tab.modified)
+    }
+
+    /// Closes the active file, or quits if it is the only one, unless it has been modified.
+    fn close(&mut self) {
+        if self.buffer.is_dirty() {
+            self.warn("Modified Buffer");
+        } else if !self.close_document() {
+            self.should_exit = true;
+        }
+    }
+
+    fn add_note(&mut self, location: &str, text: &str) {
+        let Some(note) = Note::parse(location, text) else {
+            self.warn("Usage: note <offset|start..end> <text>");
+            return;
+        };
+        let index = self
+            .notes
+            .partition_point(|other| other.range.start <= note.range.start);
+        self.notes.insert(index, note);
+        self.save_notes();
+    }
+
+    /// Adds a region over a range given as `<start>..<end>`, or over the selection, from the
+    /// arguments of `:region`.
+    fn add_region(&mut self, args: Vec<&str>) {
+        const USAGE: &str = "Usage: region [start..end] <name> [red|green|blue|magenta|cyan|grey]";
+        let (range, rest) = match args.first().and_then(|arg| parse_range(arg)) {
+            Some(range) => (range, &args[1..]),
+            None if self.selection.is_some() => (self.selected_range(), &args[..]),
+            None => {
+                self.warn(USAGE);
+                return;
+            }
+        };
+        let region = match rest {
+            [name] => Region::new(name, range, None, self.regions.len()),
+            [name, color] => Region::new(name, range, Some(color), self.regions.len()),
+            _ => None,
+        };
+        let Some(region) = region else {
+            self.warn(USAGE);
+            return;
+        };
+        let index = self
+            .regions
+            .partition_point(|other| other.range.start <= region.range.start);
+        self.regions.insert(index, region);
+    }
+
+    fn list_regions(&mut self) {
+        let entries = self
+            .regions
+            .iter()
+            .map(|region| {
+                let label = format!(
+                    "{}  {:>8} bytes  {}",
+                    tui::format_offset(region.range.start),
+                    region.range.len(),
+                    region.name
+                );
+                (label, Some(region.range.start as u32))
+            })
+            .collect();
+        self.panel = Some((Panel::new("Regions", entries), PanelAction::Jump));
+    }
+
+    /// Adds a region for each field of the template.
+    fn regions_from_template(&mut self) {
+        let Some(template) = &self.template else {
+            self.warn("No template loaded");
+            return;
+        };
+        let count = self.regions.len();
+        for field in &template.fields {
+            let index = self.regions.len();
+            self.regions
+                .extend(Region::new(&field.name, field.range.clone(), None, index));
+        }
+        self.regions.sort_by_key(|region| region.range.start);
+        self.inform(format!("Added {} regions", self.regions.len() - count));
+    }
+
+    fn export_annotations(&mut self, path: &Path) {
+        let annotations = FileAnnotations {
+            notes: std::mem::take(&mut self.notes),
+            regions: std::mem::take(&mut self.regions),
+            marks: std::mem::take(&mut self.marks),
+        };
+        let result = annotations::export(path, &annotations);
+        let summary = annotations.summary();
+        (self.notes, self.regions, self.marks) =
+            (annotations.notes, annotations.regions, annotations.marks);
+        match result {
+            Ok(()) => self.inform(format!("Exported {}", summary)),
+            Err(error) => self.fail(format!("Export failed: {}", error)),
+        }
+    }
+
+    /// Adds the notes, regions and marks of a JSON file to those of the shown file, replacing
+    /// marks of the same name.
+    fn import_annotations(&mut self, path: &Path) {
+        let annotations = match annotations::import(path) {
+            Ok(annotations) => annotations,
+            Err(error) => {
+                self.fail(format!("Import failed: {}", error));
+                return;
+            }
+        };
+        let message = format!("Imported {}", annotations.summary());
+        self.notes.extend(annotations.notes);
+        self.notes.sort_by_key(|note| note.range.start);
+        self.regions.extend(annotations.regions);
+        self.regions.sort_by_key(|region| region.range.start);
+        self.marks.extend(annotations.marks);
+        self.save_notes();
+        self.inform(message);
+    }
+
+    fn save_notes(&mut self) {
+        if let Err(error) = notes::save(&self.path, &self.notes) {
+            self.fail(format!("Saving notes failed: {}", error));
+        }
+    }
+
+    /// Lists the directories and files in `dir`, or in the directory of the shown file, to open
+    /// one of them, with typed text filtering the list.
+    fn browse(&mut self, dir: Option<&str>) {
+        let dir = match dir {
+            Some(dir) => PathBuf::from(dir),
+            None => match self.path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.into(),
+                _ => PathBuf::from("."),
+            },
+        };
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(error) => {
+                self.fail(error.to_string());
+                return;
+            }
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .collect();
+        paths.sort_by_key(|path| (!path.is_dir(), path.file_name().map(|name| name.to_owned())));
+        paths.insert(0, dir.join(".."));
+        let labels = paths
+            .iter()
+            .map(|path| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let label = match path.is_dir() {
+                    true => format!("{}/", name),
+                    false => name.into(),
+                };
+                (label, None)
+            })
+            .collect();
+        let title = dir.display().to_string();
+        let panel = Panel::new(&title, labels).with_filter();
+        self.panel = Some((panel, PanelAction::Browse(paths)));
+    }
+
+    /// Lists the open files with their numbers, marking the active one and those modified.
+    fn list_documents(&mut self) {
+        let entries = (0..self.documents.len())
+            .map(|index| {
+                let (path, buffer) = match index == self.active {
+                    true => (&self.path, &self.buffer),
+                    false => (&self.documents[index].path, &self.documents[index].buffer),
+                };
+                let label = format!(
+                    "{:>3} {} {}{}",
+                    index + 1,
+                    if index == self.active { "%" } else { " " },
+                    path.display(),
+                    if buffer.is_dirty() { " [+]" } else { "" }
+                );
+                (label, Some(index as u32))
+            })
+            .collect();
+        let mut panel = Panel::new("Open files", entries);
+        panel.move_selection(self.active as i64);
+        self.panel = Some((panel, PanelAction::SwitchDocument));
+    }
+
+    /// Decompresses the selection, or the whole buffer, and opens the result as a new file.
+    fn inflate(&mut self) {
+        let range = self.selected_range();
+        match compression::inflate(self.buffer.slice(range.clone())) {
+            Ok((format, data)) => {
+                let name = format!(
+                    "{}.{:x}.inflated",
+                    self.path.file_name().unwrap_or_default().to_string_lossy(),
+                    range.start
+                );
+                let path = self.path.with_file_name(name);
+                self.inform(format!(
+                    "Inflated {} bytes of {} data, :q returns to {}",
+                    data.len(),
+                    format,
+                    self.path.file_name().unwrap_or_default().to_string_lossy()
+                ));
+                self.open_document(Document::new(path, Codec::Raw, data));
+            }
+            Err(error) => self.fail(format!("Decompression failed: {}", error)),
+        }
+    }
+
+    fn undo(&mut self) {
+        match self.buffer.undo() {
+            Some(offset) => self.cursor = offset.min(self.buffer.len().saturating_sub(1)) as u32,
+            None => self.warn("Nothing to undo"),
+        }
+    }
+
+    fn redo(&mut self) {
+        match self.buffer.redo() {
+            Some(offset) => self.cursor = offset.min(self.buffer.len().saturating_sub(1)) as u32,
+            None => self.warn("Nothing to redo"),
+        }
+    }
+
+    /// Applies `transform` to the selection, or the whole buffer.
+    fn transform(&mut self, transform: Transform) {
+        let range = self.selected_range();
+        let mut data = self.buffer.slice(range.clone()).to_vec();
+        transform.apply(&mut data);
+        if self.splice(range, &data) {
+            self.inform(format!("Transformed {} bytes", data.len()));
+        }
+    }
+
+    /// Shows the length, sum, range, mean and most common value of the selection, or the whole
+    /// buffer.
+    fn statistics(&mut self) {
+        let data = self.buffer.slice(self.selected_range());
+        if data.is_empty() {
+            self.warn("No data");
+            return;
+        }
+        let counts = analysis::byte_counts(data);
+        let sum: u64 = data.iter().map(|byte| *byte as u64).sum();
+        let present = || (0..=255u8).filter(|value| counts[*value as usize] > 0);
+        let (common, count) = (0..=255u8)
+            .map(|value| (value, counts[value as usize]))
+            .max_by_key(|(value, count)| (*count, std::cmp::Reverse(*value)))
+            .unwrap();
+        self.inform(format!(
+            "Length {}, sum {} (0x{:x}), min 0x{:0>2x}, max 0x{:0>2x}, mean {:.2}, {} distinct, most common 0x{:0>2x} ({} times)",
+            data.len(),
+            sum,
+            sum,
+            present().min().unwrap(),
+            present().max().unwrap(),
+            sum as f64 / data.len() as f64,
+            present().count(),
+            common,
+            count
+        ));
+    }
+
+    /// Shows the digest of the selection, or the whole buffer, and copies it to the clipboard.
+    fn hash(&mut self, algorithm: Option<&str>) {
+        let Some(algorithm) = algorithm else {
+            self.warn("Usage: hash <crc32|md5|sha1|sha256>");
+            return;
+        };
+        let Ok(algorithm) = algorithm.parse::<HashAlgorithm>() else {
+            self.warn("Unknown hash algorithm");
+            return;
+        };
+        let digest = algorithm.digest(self.buffer.slice(self.selected_range()));
+        if Terminal::copy_to_clipboard(&digest).is_err() {
+            self.fail("Copy failed");
+        }
+        self.inform(digest);
+    }
+
+    /// Compares the digest of the selection, or the whole buffer, with `expected`.
+    fn verify(&mut self, algorithm: Option<&str>, expected: Option<&str>) {
+        let (Some(algorithm), Some(expected)) = (algorithm, expected) else {
+            self.warn("Usage: verify <crc32|md5|sha1|sha256> <digest>");
+            return;
+        };
+        let Ok(algorithm) = algorithm.parse::<HashAlgorithm>() else {
+            self.warn("Unknown hash algorithm");
+            return;
+        };
+        let digest = algorithm.digest(self.buffer.slice(self.selected_range()));
+        if digest.eq_ignore_ascii_case(expected) {
+            self.inform("Checksum OK");
+        } else {
+            self.fail(format!("CHECKSUM MISMATCH: {}", digest));
+        }
+    }
+
+    /// Lists the ranges differing from the file `reference`, optionally writing them as a
+    /// report to `file`.
+    fn compare(&mut self, reference: Option<&str>, option: Option<&str>, file: Option<&str>) {
+        let (Some(reference), None | Some("--report")) = (reference, option) else {
+            self.warn("Usage: compare <file> [--report <file>]");
+            return;
+        };
+        if option.is_some() && file.is_none() {
+            self.warn("Usage: compare <file> [--report <file>]");
+            return;
+        }
+        let reference_data = match std::fs::read(reference)
+            .and_then(|contents| Codec::decode(Path::new(reference), contents))
+        {
+            Ok((_, data)) => data,
+            Err(error) => {
+                self.fail(error.to_string());
+                return;
+            }
+        };
+        let data = self.buffer.slice(0..self.buffer.len());
+        if let Some(file) = file {
+            let name = self.path.to_string_lossy();
+            let output = report::compare_report(&name, data, reference, &reference_data);
+            if std::fs::write(file, output).is_err() {
+                self.fail("Writing report failed");
+                return;
+            }
+        }
+        let entries = Self::change_entries(&buffer::diff(&reference_data, data));
+        let title = format!("Differences from {}", reference);
+        self.panel = Some((Panel::new(&title, entries), PanelAction::Jump));
+    }
+
+    fn change_entries(changes: &[Change]) -> Vec<(String, Option<u32>)> {
+        let preview = |bytes: &[u8]| {
+            let hex: Vec<String> = bytes
+                .iter()
+                .take(8)
+                .map(|b| format!("{:0>2x}", b))
+                .collect();
+            let ellipsis = if bytes.len() > 8 { " .." } else { "" };
+            format!("{}{}", hex.join(" "), ellipsis)
+        };
+        // Changes are at offsets in the old data, shifted by the bytes inserted or removed before.
+        let mut shift = 0isize;
+        changes
+            .iter()
+            .map(|change| {
+                let offset = change.offset.saturating_add_signed(shift);
+                shift += change.new.len() as isize - change.old.len() as isize;
+                let label = format!(
+                    "{}  {:>6} bytes  {} -> {}",
+                    tui::format_offset(offset),
+                    change.old.len().max(change.new.len()),
+                    preview(&change.old),
+                    preview(&change.new)
+                );
+                (label, Some(offset as u32))
+            })
+            .collect()
+    }
+
+    /// Replaces `range` with `data` unless a changed byte is protected, returning whether the
+    /// buffer was changed.
+    fn splice(&mut self, range: Range<usize>, data: &[u8]) -> bool {
+        let old = self.buffer.slice(range.clone());
+        let prefix = old.iter().zip(data).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(data[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let changed = range.start + prefix..range.end - suffix;
+        if changed.is_empty() && old.len() == data.len() {
+            return true;
+        }
+        if !self.check_protected(changed, old.len() != data.len()) {
+            return false;
+        }
+        self.buffer.splice(range, data);
+        true
+    }
+
+    /// Returns whether the bytes in `range` may be changed, warning about the protected range or
+    /// read-only template field otherwise. If the edit `resizes` the buffer, the bytes after
+    /// `range` move and must not be protected either.
+    fn check_protected(&mut self, range: Range<usize>, resizes: bool) -> bool {
+        if !self.settings.protect {
+            return true;
+        }
+        let overlaps = |guard: &Range<usize>| match resizes {
+            true => range.start < guard.end,
+            false => guard.start < range.end && range.start < guard.end,
+        };
+        let field = self.template.as_ref().and_then(|template| {
+            template
+                .fields
+                .iter()
+                .find(|field| field.read_only && overlaps(&field.range))
+        });
+        let message = match (self.guards.iter().find(|guard| overlaps(guard)), field) {
+            (Some(guard), _) => format!("Protected range 0x{:x}..0x{:x}", guard.start, guard.end),
+            (None, Some(field)) => format!("Read-only field {}", field.name),
+            (None, None) => return true,
+        };
+        self.warn(format!("{}, set noprotect to edit", message));
+        false
+    }
+
+    /// Protects the range at `location`, an offset or `start..end`, or lists the protected
+    /// ranges if no location is given.
+    fn protect(&mut self, location: Option<&str>) {
+        let Some(location) = location else {
+            let ranges: Vec<_> = self
+                .guards
+                .iter()
+                .map(|guard| format!("0x{:x}..0x{:x}", guard.start, guard.end))
+                .collect();
+            match ranges.is_empty() {
+                true => self.inform("No protected ranges"),
+                false => self.inform(format!("Protected {}", ranges.join(", "))),
+            }
+            return;
+        };
+        match parse_location(location) {
+            Some(range) => {
+                self.inform(format!("Protected 0x{:x}..0x{:x}", range.start, range.end));
+                self.guards.push(range);
+                self.guards.sort_by_key(|guard| guard.start);
+            }
+            None => self.warn("Usage: protect [offset|start..end]"),
+        }
+    }
+
+    /// Removes the protection of the ranges overlapping `location`, or the cursor.
+    fn unprotect(&mut self, location: Option<&str>) {
+        let range = match location {
+            Some(location) => parse_location(location),
+            None => Some(self.cursor as usize..self.cursor as usize + 1),
+        };
+        let Some(range) = range else {
+            self.warn("Usage: unprotect [offset|start..end]");
+            return;
+        };
+        let count = self.guards.len();
+        self.guards
+            .retain(|guard| guard.end <= range.start || range.end <= guard.start);
+        match count - self.guards.len() {
+            0 => self.warn("No protected range"),
+            removed => self.inform(format!("Unprotected {} ranges", removed)),
+        }
+    }
+
+    /// Replaces `range` with `data` and moves the cursor to its start.
+    fn replace_range(&mut self, range: Range<usize>, data: &[u8]) {
+        if !self.splice(range.clone(), data) {
+            return;
+        }
+        self.selection = None;
+        self.cursor = range.start.min(self.buffer.len().saturating_sub(1)) as u32;
+        if self.cursor < self.offset {
+            self.offset = self.cursor - self.cursor % self.bytes_per_line;
+        }
+    }
+
+    /// Fixes the header checksum of a console ROM, detecting the console if none is given.
+    /// PNG files get the CRC of the chunk under the cursor fixed instead.
+    fn fix_checksum(&mut self, console: Option<&str>) {
+        let data = self.buffer.slice(0..self.buffer.len());
+        if console.unwrap_or_default() == "png"
+            || console.is_none() && data.starts_with(formats::png::SIGNATURE)
+        {
+            match formats::png::fix_crc(data, self.cursor as usize) {
+                Some((offset, bytes)) => {
+                    if self.write_fix(offset, &bytes) {
+                        self.inform("Fixed PNG chunk CRC");
+                    }
+                }
+                None => self.warn("No complete chunk at the cursor"),
+            }
+            return;
+        }
+        let rom = match console {
+            Some(console) => console.parse().ok(),
+            None => RomChecksum::detect(data),
+        };
+        let Some(rom) = rom else {
+            self.warn("Usage: fix-checksum [snes|genesis|gba|png]");
+            return;
+        };
+        let Some((offset, bytes)) = rom.fix(data) else {
+            self.warn("File too small for the header");
+            return;
+        };
+        if self.write_fix(offset, &bytes) {
+            self.inform(format!("Fixed {} checksum", rom.name()));
+        }
+    }
+
+    /// Writes a fixed checksum, leaving the buffer unmodified if it was already correct.
+    /// Returns false if the checksum is protected.
+    fn write_fix(&mut self, offset: usize, bytes: &[u8]) -> bool {
+        self.buffer.slice(offset..offset + bytes.len()) == bytes
+            || self.splice(offset..offset + bytes.len(), bytes)
+    }
+
+    /// Runs the commands in `file`, one per line, skipping empty lines and `#` comments.
+    fn source(&mut self, file: Option<&str>) {
+        let Some(file) = file else {
+            self.warn("Usage: source <file>");
+            return;
+        };
+        let Ok(contents) = std::fs::read_to_string(file) else {
+            self.fail("Reading file failed");
+            return;
+        };
+        let path = std::fs::canonicalize(file).unwrap_or_else(|_| file.into());
+        if self.sourcing.contains(&path) {
+            self.warn(format!("Already sourcing {}", file));
+            return;
+        }
+        self.sourcing.push(path);
+        for line in contents.lines().map(str::trim) {
+            if !line.is_empty() && !line.starts_with('#') {
+                self.process_command(line);
+            }
+        }
+        self.sourcing.pop();
+    }
+
+    /// Recomputes the declared checksum fields, returning false if one does not fit or is
+    /// protected.
+    fn update_checksums(&mut self) -> bool {
+        for index in 0..self.checksums.len() {
+            let data = self.buffer.slice(0..self.buffer.len());
+            let Some((range, digest)) = self.checksums[index].compute(data) else {
+                self.warn("Checksum field out of range");
+                return false;
+            };
+            if !self.splice(range, &digest) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn save(&mut self) -> bool {
+        if !self.buffer.is_dirty() {
+            return true;
+        }
+        if !self.update_checksums() {
+            return false;
+        }
+        match self.buffer.save(&self.path, &self.codec) {
+            Ok(_) => true,
+            Err(error) => {
+                self.fail(format!("Writing failed: {}", error));
+                false
+            }
+        }
+    }
+
+    fn redraw(&self, offset: u32, lines: u32) -> Result<(), std::io::Error> {
+        Terminal::move_cursor_to(Position { x: 0, y: 0 })?;
+        let detail = match &self.diff {
+            Some((path, _)) => format!("<>  {}", path.file_name().unwrap().to_string_lossy()),
+            None => magic::identify(self.buffer.slice(0..self.buffer.len()))
+                .map(|signature| signature.name.to_string())
+                .unwrap_or_default(),
+        };
+        let width = Terminal::width()? as usize;
+        tui::draw_header(&self.tabs(), self.active, &detail, width)?;
+        let panes = if self.diff.is_some() { 2 } else { 1 };
+        match &self.panel {
+            _ if self.overview.is_some() => {
+                let data = self.buffer.slice(0..self.buffer.len());
+                self.overview.as_ref().unwrap().draw(data, lines)?;
+            }
+            Some((panel, _)) => {
+                let columns: usize = self.columns().iter().map(|width| width + 1).sum();
+                let views = match &self.split {
+                    Some(split) if split.vertical => 2,
+                    _ => 1,
+                };
+                let view = 11 + panes as usize * (4 * self.bytes_per_line as usize + 5);
+                let width = views * view + views - 1 + columns;
+                panel.draw(lines, width)?;
+            }
+            None => self.draw_hex_view(offset, lines, panes)?,
+        }
+        draw!(
+            "\r   {}  {}  ",
+            tui::format_offset(self.cursor as usize),
+            if self.insert { "INS" } else { "OVR" }
+        )?;
+        if let Some(replacement) = &self.replacing {
+            let bytes: String = replacement
+                .bytes
+                .iter()
+                .map(|b| format!("{:0>2x}", b))
+                .collect();
+            let prompt = format!("Replace with {}? (y/n/a/q)", bytes);
+            draw!("{}", prompt)?;
+            draw!(
+                "{}",
+                " ".repeat((self.bytes_per_line as usize * 3 + 1).saturating_sub(prompt.len()))
+            )?;
+        } else if let EditorMode::Command(command) = &self.mode {
+            draw!(":{}", command)?;
+            draw!(
+                "{}",
+                " ".repeat((self.bytes_per_line as usize * 3).saturating_sub(command.len()))
+            )?;
+        } else {
+            draw!("{}", " ".repeat(self.bytes_per_line as usize * 3))?;
+        }
+        for (index, message) in self.messages.iter().enumerate() {
+            if index > 0 {
+                draw!("  ")?;
+            }
+            Terminal::set_foreground_color(message.severity.color())?;
+            draw!("{}", message.text)?;
+        }
+        Terminal::set_foreground_color(Color::Reset)?;
+        let note = notes::at(&self.notes, self.cursor as usize);
+        let summary = self.selection_summary().or_else(|| self.search_summary());
+        if let Some(summary) = summary.filter(|_| self.messages.is_empty()) {
+            draw!("{}", summary)?;
+        } else if let Some(note) = note.filter(|_| self.messages.is_empty()) {
+            Terminal::set_foreground_color(Color::Cyan)?;
+            draw!("{}", note.text)?;
+            Terminal::set_foreground_color(Color::Reset)?;
+        } else if let Some(field) = self.inspected_field().filter(|_| self.messages.is_empty()) {
+            let data = self.buffer.slice(0..self.buffer.len());
+            draw!("{}", field.describe(data).unwrap_or(field.name))?;
+        } else if self.messages.is_empty() {
+            let field = self
+                .template
+                .as_ref()
+                .and_then(|template| template.field_at(self.cursor as usize));
+            if let Some((_, field)) = field {
+                let data = self.buffer.slice(0..self.buffer.len());
+                draw!("{}", field.describe(data).unwrap_or(field.name.clone()))?;
+            } else if let Some(region) = regions::at(&self.regions, self.cursor as usize) {
+                draw!("{}", region.name)?;
+            }
+        }
+        Terminal::clear_until_newline()?;
+        drawln!()?;
+
+        Terminal::execute()?;
+        Ok(())
+    }
+
+    fn draw_hex_view(&self, offset: u32, lines: u32, panes: u32) -> Result<(), std::io::Error> {
+        let focused = View {
+            offset,
+            ..self.focused_view()
+        };
+        let Some(split) = &self.split else {
+            return self.draw_views(&[focused], lines, panes, true);
+        };
+        let other = View {
+            data: self.document_data(split.document),
+            cursor: split.cursor,
+            offset: split.offset,
+            focused: false,
+            folds: match split.document == self.active {
+                true => &self.folds,
+                false => &self.documents[split.document].folds,
+            },
+        };
+        let (first, second) = match split.first {
+            true => (other, focused),
+            false => (focused, other),
+        };
+        if split.vertical {
+            return self.draw_views(&[first, second], lines, panes, true);
+        }
+        let (first_lines, second_lines) = Self::split_lines(lines);
+        self.draw_views(&[first], first_lines, panes, !split.first)?;
+        self.draw_views(&[second], second_lines, panes, split.first)
+    }
+
+    /// Draws `views` side by side in a box, followed by the auxiliary columns of the focused view
+    /// if `columns` is set.
+    fn draw_views(
+        &self,
+        views: &[View],
+        lines: u32,
+        panes: u32,
+        columns: bool,
+    ) -> Result<(), std::io::Error> {
+        let widths = if columns { self.columns() } else { vec![] };
+        let count = views.len() as u32;
+        draw_box_part(BoxPart::Top, self.bytes_per_line, count, panes, &widths)?;
+        let data = self.buffer.slice(0..self.buffer.len());
+        let instructions = self
+            .architecture
+            .filter(|_| disasm::AVAILABLE && columns)
+            .map(|architecture| {
+                let cursor = (self.cursor as usize).min(data.len());
+                architecture.disassemble(&data[cursor..], cursor as u64, lines as usize)
+            });
+        let other = self.diff.as_ref().map(|(_, other)| other.as_slice());
+        let compare = match other {
+            None if self.settings.diff_disk => Some(self.buffer.saved()),
+            other => other,
+        };
+        let layouts: Vec<_> = views.iter().map(|view| self.layout(view)).collect();
+        let rows: Vec<_> = views
+            .iter()
+            .zip(&layouts)
+            .map(|(view, layout)| layout.rows(view.offset as usize, lines as usize))
+            .collect();
+        for line in 0..lines as usize {
+            draw!("\r {}", tui::HORIZONTAL)?;
+            for (view, rows) in views.iter().zip(&rows) {
+                let current_line = rows[line].start();
+                draw!(" {} {} ", tui::format_offset(current_line), tui::HORIZONTAL)?;
+                let compare = if view.focused { compare } else { None };
+                self.draw_row(view, &rows[line], compare)?;
+                if let Some(other) = other {
+                    let view = View {
+                        data: other,
+                        ..*view
+                    };
+                    draw!(" ")?;
+                    self.draw_row(&view, &rows[line], Some(data))?;
+                }
+            }
+            if !columns {
+                drawln!()?;
+                continue;
+            }
+            let current_line =
+                rows[views.iter().position(|view| view.focused).unwrap_or(0)][line].start();
+            if !self.symbols.is_empty() {
+                self.draw_symbol_column(current_line, data)?;
+            }
+            if self.settings.entropy {
+                Self::draw_entropy_column(current_line, data)?;
+            }
+            if let Some(instructions) = &instructions {
+                Self::draw_disassembly_column(instructions, line)?;
+            }
+            drawln!()?;
+        }
+        draw_box_part(BoxPart::Bottom, self.bytes_per_line, count, panes, &widths)
+    }
+
+    /// Draws the hex and text panes of a row, with a folded range shown as its label and size.
+    fn draw_row(&self, view: &View, row: &Row, other: Option<&[u8]>) -> Result<(), std::io::Error> {
+        let span = match row {
+            Row::Bytes(start) => {
+                self.draw_hex_pane(view, *start, other)?;
+                return self.draw_text_pane(view, *start, other);
+            }
+            Row::Span(span) => span,
+        };
+        let summary = format!("\u{25b8} {}  {} bytes", span.label, span.range.len());
+        let width = 3 * self.bytes_per_line as usize;
+        let summary: String = summary.chars().take(width).collect();
+        match span.range.contains(&(view.cursor as usize)) {
+            true if view.focused => Terminal::set_background_color(Color::DarkYellow)?,
+            true => Terminal::set_background_color(Color::DarkGrey)?,
+            false => Terminal::set_foreground_color(Color::DarkGrey)?,
+        }
+        draw!("{:<1$}", summary, width)?;
+        Terminal::set_background_color(Color::Reset)?;
+        Terminal::set_foreground_color(Color::Reset)?;
+        draw!(
+            "{} {} {}",
+            tui::HORIZONTAL,
+            " ".repeat(self.bytes_per_line as usize),
+            tui::HORIZONTAL
+        )?;
+        Ok(())
+    }
+
+    /// Returns the widths of the auxiliary columns shown right of the panes.
+    fn columns(&self) -> Vec<usize> {
+        let mut columns = vec![];
+        if !self.symbols.is_empty() {
+            columns.push(SYMBOL_WIDTH);
+        }
+        if self.settings.entropy {
+            columns.push(7);
+        }
+        if self.architecture.is_some() && disasm::AVAILABLE {
+            columns.push(DISASSEMBLY_WIDTH);
+        }
+        columns
+    }
+
+    /// Draws the nearest symbol at or before the line starting at `line_offset`, with the
+    /// distance to it.
+    fn draw_symbol_column(&self, line_offset: usize, data: &[u8]) -> Result<(), std::io::Error> {
+        let text = match self.nearest_symbol(line_offset) {
+            Some((name, distance)) if line_offset < data.len() => {
+                let length = (SYMBOL_WIDTH - 2).saturating_sub(distance.len());
+                format!(
+                    "{}{}",
+                    name.chars().take(length).collect::<String>(),
+                    distance
+                )
+            }
+            _ => String::new(),
+        };
+        draw!(" {:<1$} {2}", text, SYMBOL_WIDTH - 2, tui::HORIZONTAL)
+    }
+
+    /// Returns the name of the nearest symbol at or before `position` and the distance to it
+    /// formatted as `+0x..`, which is empty at the symbol itself.
+    fn nearest_symbol(&self, position: usize) -> Option<(&str, String)> {
+        let index = self
+            .symbols
+            .partition_point(|(offset, _)| *offset <= position);
+        let (offset, name) = &self.symbols[index.checked_sub(1)?];
+        let distance = match position - offset {
+            0 => String::new(),
+            distance => format!("+0x{:x}", distance),
+        };
+        Some((name, distance))
+    }
+
+    /// Draws the entropy of the block containing the line starting at `line_offset`.
+    fn draw_entropy_column(line_offset: usize, data: &[u8]) -> Result<(), std::io::Error> {
+        if line_offset >= data.len() {
+            draw!("       {}", tui::HORIZONTAL)?;
+            return Ok(());
+        }
+        let start = line_offset - line_offset % ENTROPY_BLOCK_SIZE;
+        let end = (start + ENTROPY_BLOCK_SIZE).min(data.len());
+        let entropy = analysis::entropy(&data[start..end]);
+        draw!(" ")?;
+        Terminal::set_background_color(analysis::entropy_color(entropy))?;
+        draw!(" {:.1} ", entropy)?;
+        Terminal::set_background_color(Color::Reset)?;
+        draw!(" {}", tui::HORIZONTAL)?;
+        Ok(())
+    }
+
+    /// Draws the instruction at `index` of those disassembled from the cursor, marking the end
+    /// of the decodable code.
+    fn draw_disassembly_column(
+        instructions: &[Instruction],
+        index: usize,
+    ) -> Result<(), std::io::Error> {
+        let text = match instructions.get(index) {
+            Some((address, text)) => format!("{:0>6x}  {}", address, text),
+            None if index == instructions.len() => "(invalid)".into(),
+            None => "".into(),
+        };
+        let text: String = text.chars().take(DISASSEMBLY_WIDTH - 2).collect();
+        draw!(" {:<1$} {2}", text, DISASSEMBLY_WIDTH - 2, tui::HORIZONTAL)
+    }
+
+    /// Draws the hex values of a line of `view` starting at `line_offset`. Bytes which differ
+    /// from `other` are highlighted, and guide boundaries within the line are separated.
+    fn draw_hex_pane(
+        &self,
+        view: &View,
+        line_offset: usize,
+        other: Option<&[u8]>,
+    ) -> Result<(), std::io::Error> {
+        let line_end = line_offset + self.bytes_per_line as usize;
+        for position in line_offset..line_end {
+            if let Some(byte) = view.data.get(position) {
+                self.set_byte_colors(view, position, other)?;
+                draw!("{:0>2x}", byte)?;
+                Terminal::set_background_color(Color::Reset)?;
+                Terminal::set_foreground_color(Color::Reset)?;
+                Terminal::set_underlined(false)?;
+                match self.settings.guides {
+                    Some(size)
+                        if (position + 1).is_multiple_of(size) && position + 1 < line_end =>
+                    {
+                        Terminal::set_foreground_color(Color::DarkGrey)?;
+                        draw!("\u{250a}")?;
+                        Terminal::set_foreground_color(Color::Reset)?;
+                    }
+                    _ => draw!(" ")?,
+                }
+            } else {
+                draw!("   ")?;
+            }
+        }
+        draw!("{} ", tui::HORIZONTAL)?;
+        Ok(())
+    }
+
+    fn draw_text_pane(
+        &self,
+        view: &View,
+        line_offset: usize,
+        other: Option<&[u8]>,
+    ) -> Result<(), std::io::Error> {
+        let data = view.data;
+        let end = (line_offset + self.bytes_per_line as usize).min(data.len());
+        let glyphs = self
+            .settings
+            .encoding
+            .render(data, line_offset.min(end), end);
+        for position in line_offset..line_offset + self.bytes_per_line as usize {
+            if let Some(glyph) = glyphs.get(position - line_offset) {
+                self.set_byte_colors(view, position, other)?;
+                match glyph {
+                    Glyph::Char(c) => draw!("{}", c)?,
+                    Glyph::Continuation => draw!(" ")?,
+                    Glyph::Covered => {}
+                    Glyph::Unprintable => {
+                        let (symbol, color) = self.settings.unprintable.symbol(data[position]);
+                        if !Self::differs(position, data, other) {
+                            Terminal::set_foreground_color(color)?;
+                        }
+                        draw!("{}", symbol)?;
+                    }
+                }
+                Terminal::set_background_color(Color::Reset)?;
+                Terminal::set_foreground_color(Color::Reset)?;
+                Terminal::set_underlined(false)?;
+            } else {
+                draw!(" ")?;
+            }
+        }
+        draw!(" {}", tui::HORIZONTAL)?;
+        Ok(())
+    }
+
+    fn set_byte_colors(
+        &self,
+        view: &View,
+        position: usize,
+        other: Option<&[u8]>,
+    ) -> Result<(), std::io::Error> {
+        if view.cursor as usize == position {
+            let color = if view.focused {
+                Color::DarkYellow
+            } else {
+                Color::DarkGrey
+            };
+            Terminal::set_background_color(color)?;
+        } else if view.focused && self.is_selected(position) {
+            Terminal::set_background_color(Color::DarkGrey)?;
+        } else if view.focused
+            && let Some((index, _)) = self
+                .template
+                .as_ref()
+                .and_then(|template| template.field_at(position))
+        {
+            let color = if index % 2 == 0 {
+                Color::DarkBlue
+            } else {
+                Color::DarkCyan
+            };
+            Terminal::set_background_color(color)?;
+        } else if view.focused
+            && let Some(region) = regions::at(&self.regions, position)
+        {
+            Terminal::set_background_color(region.color)?;
+        } else if let Some(size) = self.settings.guides
+            && (position / size) % 2 == 1
+        {
+            Terminal::set_background_color(GUIDE_COLOR)?;
+        }
+        if Self::differs(position, view.data, other) {
+            Terminal::set_foreground_color(Color::Red)?;
+        }
+        if view.focused && notes::at(&self.notes, position).is_some() {
+            Terminal::set_underlined(true)?;
+        }
+        Ok(())
+    }
+
+    fn differs(position: usize, data: &[u8], other: Option<&[u8]>) -> bool {
+        other.is_some_and(|other| data.get(position) != other.get(position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// Returns the latest warning or error shown by `editor`, or an empty string.
+    fn warning(editor: &Editor) -> &str {
+        latest(editor, |severity| severity != Severity::Info)
+    }
+
+    /// Returns the latest informational message shown by `editor`, or an empty string.
+    fn message(editor: &Editor) -> &str {
+        latest(editor, |severity| severity == Severity::Info)
+    }
+
+    fn latest(editor: &Editor, filter: impl Fn(Severity) -> bool) -> &str {
+        editor
+            .messages
+            .iter()
+            .filter(|message| filter(message.severity))
+            .last()
+            .map_or("", |message| message.text.as_str())
+    }
+
+    fn setup_test_editor() -> Editor {
+        // Helper function to initialize an Editor for testing.
+        Editor {
+            cursor: 0,
+            bytes_per_line: 16,
+            offset: 0,
+            path: PathBuf::from("test.txt"),
+            codec: Codec::Raw,
+            buffer: Buffer::new([0xa, 0xb, 0xc].repeat(100)),
+            mode: EditorMode::Edit(None),
+            selection: None,
+            insert: false,
+            diff: None,
+            split: None,
+            settings: Settings::default(),
+            checksums: vec![],
+            template: None,
+            architecture: None,
+            symbols: vec![],
+            notes: vec![],
+            regions: vec![],
+            folds: vec![],
+            expanded: vec![],
+            marks: BTreeMap::new(),
+            guards: vec![],
+            panel: None,
+            overview: None,
+            search: None,
+            inspector: None,
+            registers: BTreeMap::new(),
+            jumps: Vec::new(),
+            history: History::default(),
+            replacing: None,
+            browsing: None,
+            sourcing: vec![],
+            messages: Messages::default(),
+            max_lines: 16,
+            should_exit: false,
+            documents: vec![Document::new(PathBuf::new(), Codec::Raw, vec![])],
+            active: 0,
+        }
+    }
+
+    #[test]
+    fn test_editor_initialization_with_bad_file() {
+        let path = Path::new("invalid_file.txt");
+        let bytes_per_line = 16;
+
+        // File at path does not exist
+        let editor = Editor::init(path, bytes_per_line, false);
+        assert!(editor.is_err());
+    }
+
+    #[test]
+    fn test_editor_update_cursor() {
+        let mut editor = setup_test_editor();
+        editor.update_cursor(5);
+        assert_eq!(editor.cursor, 5);
+        editor.update_cursor(-2);
+        assert_eq!(editor.cursor, 3);
+        editor.update_cursor(340);
+        assert_eq!(editor.cursor, 299);
+        editor.update_cursor(-1000);
+        assert_eq!(editor.cursor, 0);
+    }
+    #[test]
+    fn test_process_edit_event() {
+        let mut editor = setup_test_editor();
+        assert_eq!(editor.buffer[editor.cursor as usize], 0xa);
+        let event = KeyEvent::new(Char('2'), KeyModifiers::NONE);
+        editor.process_event(event, 16);
+        assert!(matches!(editor.mode, EditorMode::Edit(Some(2))));
+        assert_eq!(editor.buffer[editor.cursor as usize], 0x02);
+
+        let event = KeyEvent::new(Char('1'), KeyModifiers::NONE);
+        editor.process_event(event, 16);
+        assert!(matches!(editor.mode, EditorMode::Edit(None)));
+        assert_eq!(editor.buffer[editor.cursor as usize], 0x21);
+    }
+
+    #[test]
+    fn test_render() {
+        let mut editor = setup_test_editor();
+        editor.apply(Action::MoveTo(0x25));
+        editor.apply(Action::Command("goto 0x100".into()));
+        let lines = editor.render(100, 8).unwrap();
+        assert_eq!(
+            lines,
+            [
+                "     Ashe      test.txt",
+                " ╭───────────┬─────────────────────────────────────────────────┬──────────────────╮",
+                " │ 0000 00e0 │ 0c 0a 0b 0c 0a 0b 0c 0a 0b 0c 0a 0b 0c 0a 0b 0c │ ................ │",
+                " │ 0000 00f0 │ 0a 0b 0c 0a 0b 0c 0a 0b 0c 0a 0b 0c 0a 0b 0c 0a │ ................ │",
+                " │ 0000 0100 │ 0b 0c 0a 0b 0c 0a 0b 0c 0a 0b 0c 0a 0b 0c 0a 0b │ ................ │",
+                " ╰───────────┴─────────────────────────────────────────────────┴──────────────────╯",
+                "   0000 0100  OVR",
+                "",
+            ]
+        );
+        assert_eq!(editor.max_lines, 3);
+        assert!(editor.render(80, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_insert_key() {
+        let mut editor = setup_test_editor();
+        editor.cursor = 1;
+        let key = |c| KeyEvent::new(Char(c), KeyModifiers::NONE);
+        editor.process_event(key('i'), 16);
+        for c in "4142".chars() {
+            editor.process_event(key(c), 16);
+        }
+        assert_eq!(editor.buffer.slice(0..4), [0xa, 0x41, 0x42, 0xb]);
+        assert_eq!((editor.cursor, editor.buffer.len()), (3, 302));
+        assert!(editor.render(100, 8).unwrap()[6].contains("INS"));
+
+        editor.process_event(KeyEvent::new(KeyCode::Insert, KeyModifiers::NONE), 16);
+        editor.process_event(key('f'), 16);
+        editor.process_event(key('f'), 16);
+        assert_eq!(editor.buffer.slice(3..4), [0xff]);
+        assert_eq!(editor.buffer.len(), 302);
+        editor.process_event(key('u'), 16);
+        editor.process_event(key('u'), 16);
+        assert_eq!(editor.buffer.slice(0..3), [0xa, 0x41, 0xb]);
+    }
+
+    #[test]
+    fn test_string_keys() {
+        let mut editor = setup_test_editor();
+        editor.buffer.splice(0x40..0x40, b"text");
+        editor.buffer.splice(0x80..0x80, b"more text");
+        let key = |c| KeyEvent::new(Char(c), KeyModifiers::NONE);
+        editor.process_event(key('s'), 16);
+        assert_eq!(editor.cursor, 0x40);
+        editor.process_event(key('s'), 16);
+        assert_eq!(editor.cursor, 0x80);
+        editor.process_event(key('s'), 16);
+        assert_eq!(warning(&editor), "No next string");
+        editor.cursor = 0x84;
+        editor.process_event(key('S'), 16);
+        assert_eq!(editor.cursor, 0x80);
+        editor.process_event(key('S'), 16);
+        editor.process_event(key('S'), 16);
+        assert_eq!(
+            (editor.cursor, warning(&editor)),
+            (0x40, "No previous string")
+        );
+    }
+
+    #[test]
+    fn test_inspect_command() {
+        let mut editor = setup_test_editor();
+        let key = |c| KeyEvent::new(Char(c), KeyModifiers::NONE);
+        editor.process_event(key('>'), 16);
+        assert_eq!(warning(&editor), "No inspector type");
+        editor.process_command("inspect u32be");
+        editor.cursor = 5;
+        editor.process_event(key('>'), 16);
+        assert_eq!(editor.cursor, 8);
+        editor.process_event(key('>'), 16);
+        assert_eq!(editor.cursor, 12);
+        editor.process_event(key('<'), 16);
+        editor.process_event(key('<'), 16);
+        assert_eq!(editor.cursor, 4);
+        editor.messages.clear();
+        assert_eq!(
+            editor.render(100, 8).unwrap()[6],
+            "   0000 0004  OVR                                                  u32be = 185338379 (0x0b0c0a0b)"
+        );
+        editor.process_command("inspect u24");
+        assert!(warning(&editor).starts_with("Usage: inspect"));
+        editor.process_command("inspect");
+        assert!(editor.inspected_field().is_none());
+    }
+
+    #[test]
+    fn test_follow() {
+        let mut editor = setup_test_editor();
+        editor.buffer.update(0x10, 0x01);
+        editor.buffer.update(0x11, 0x00);
+        editor.cursor = 0x10;
+        editor.process_command("follow u16 be");
+        assert_eq!(editor.cursor, 0x100);
+        editor.process_command("follow u32 0x20000000");
+        assert_eq!(warning(&editor), "No pointer at the cursor");
+        editor.process_command("follow");
+        assert!(warning(&editor).ends_with("is outside the file"));
+        editor.process_command("follow u24");
+        assert!(warning(&editor).starts_with("Usage: follow"));
+        editor.process_event(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE), 16);
+        assert_eq!(editor.cursor, 0x10);
+        editor.process_command("back");
+        assert_eq!(warning(&editor), "Jump list is empty");
+    }
+
+    #[test]
+    fn test_history_keys() {
+        let mut editor = setup_test_editor();
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let enter = |editor: &mut Editor, command: &str| {
+            editor.mode = EditorMode::Command(command.into());
+            editor.process_event(key(KeyCode::Enter), 16);
+        };
+        enter(&mut editor, "find 0a0b");
+        enter(&mut editor, "goto 0");
+        enter(&mut editor, "find 0b0c");
+        editor.process_event(key(KeyCode::Esc), 16);
+        editor.process_event(key(Char('/')), 16);
+        editor.process_event(key(KeyCode::Up), 16);
+        assert!(matches!(&editor.mode, EditorMode::Command(text) if text == "find 0b0c"));
+        editor.process_event(key(KeyCode::Up), 16);
+        editor.process_event(key(KeyCode::Up), 16);
+        assert!(matches!(&editor.mode, EditorMode::Command(text) if text == "find 0a0b"));
+        editor.process_event(key(KeyCode::Down), 16);
+        assert!(matches!(&editor.mode, EditorMode::Command(text) if text == "find 0b0c"));
+        editor.process_event(key(KeyCode::Down), 16);
+        assert!(matches!(&editor.mode, EditorMode::Command(text) if text == "find "));
+        editor.process_event(key(KeyCode::Esc), 16);
+        editor.process_event(key(Char(':')), 16);
+        editor.process_event(key(KeyCode::Up), 16);
+        editor.process_event(key(KeyCode::Up), 16);
+        assert!(matches!(&editor.mode, EditorMode::Command(text) if text == "goto 0"));
+
+        let mut editor = setup_test_editor();
+        editor.load_history(std::env::temp_dir());
+        assert!(warning(&editor).starts_with("Failed to read history"));
+        assert_eq!(editor.history.len(), 0);
+    }
+
+    #[test]
+    fn test_replace_command() {
+        let mut editor = setup_test_editor();
+        editor.cursor = 1;
+        editor.process_command("replace 0a0b ff");
+        assert_eq!(editor.buffer.slice(0..5), [0xa, 0xb, 0xc, 0xff, 0xc]);
+        assert_eq!(editor.cursor, 3);
+        editor.process_command("replace 0b0c 01 all");
+        assert_eq!(message(&editor), "Replaced 99 occurrences");
+        assert_eq!(editor.buffer.slice(0..5), [0xa, 0x1, 0xff, 0xc, 0xa]);
+        editor.process_command("replace 0b0c 01 all");
+        assert_eq!(warning(&editor), "Pattern not found");
+        editor.process_command("replace 0b0c");
+        assert!(warning(&editor).starts_with("Usage: replace"));
+        editor.process_command("undo");
+        assert_eq!(editor.buffer.slice(0..5), [0xa, 0xb, 0xc, 0xff, 0xc]);
+    }
+
+    #[test]
+    fn test_replace_confirm() {
+        let mut editor = setup_test_editor();
+        let key = |c| KeyEvent::new(Char(c), KeyModifiers::NONE);
+        editor.mode = EditorMode::Command("replace 0c0a 0000 all confirm".into());
+        editor.process_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), 16);
+        assert!(matches!(editor.mode, EditorMode::Edit(None)));
+        assert_eq!((editor.cursor, editor.selected_range()), (2, 2..4));
+        editor.messages.clear();
+        assert_eq!(
+            editor.render(100, 8).unwrap()[6],
+            "   0000 0002  OVR  Replace with 0000? (y/n/a/q)                     0x2-0x3 (2-3), 2 bytes (0x2)"
+        );
+        editor.process_event(key('y'), 16);
+        assert_eq!(editor.cursor, 5);
+        editor.process_event(key('x'), 16);
+        editor.process_event(key('n'), 16);
+        assert_eq!(editor.cursor, 8);
+        editor.process_event(key('y'), 16);
+        editor.process_event(key('q'), 16);
+        assert_eq!(message(&editor), "Replaced 2 occurrences");
+        assert!(editor.replacing.is_none() && editor.selection.is_none());
+        assert_eq!(
+            editor.buffer.slice(0..10),
+            [0xa, 0xb, 0, 0, 0xb, 0xc, 0xa, 0xb, 0, 0]
+        );
+
+        editor.process_command("replace 0c0a 0000 all confirm");
+        editor.process_event(key('a'), 16);
+        assert_eq!(message(&editor), "Replaced 97 occurrences");
+        assert!(
+            !editor
+                .buffer
+                .slice(0..300)
+                .windows(2)
+                .any(|w| w == [0xc, 0xa])
+        );
+    }
+
+    #[test]
+    fn test_replace_selection() {
+        let mut editor = setup_test_editor();
+        editor.selection = Some(3);
+        editor.cursor = 8;
+        editor.process_command("replace 0a0b 00 all");
+        assert_eq!(message(&editor), "Replaced 2 occurrences");
+        assert_eq!(
+            editor.buffer.slice(0..9),
+            [0xa, 0xb, 0xc, 0, 0xc, 0, 0xc, 0xa, 0xb]
+        );
+        assert_eq!(editor.selected_range(), 3..7);
+        editor.process_command("replace 0c 0d");
+        assert_eq!(editor.buffer.slice(0..7), [0xa, 0xb, 0xc, 0, 0xd, 0, 0xc]);
+        editor.process_command("replace 0c0a 00 all");
+        assert_eq!(warning(&editor), "Pattern not found");
+
+        editor.process_command("replace 0c 0e0e all confirm");
+        assert_eq!(editor.selected_range(), 6..7);
+        editor.process_event(KeyEvent::new(Char('y'), KeyModifiers::NONE), 16);
+        assert_eq!(message(&editor), "Replaced 1 occurrences");
+        assert_eq!(editor.buffer.slice(5..9), [0, 0xe, 0xe, 0xa]);
+        assert_eq!(editor.selected_range(), 3..8);
+    }
+
+    #[test]
+    fn test_protect_command() {
+        let mut editor = setup_test_editor();
+        let key = |c| KeyEvent::new(Char(c), KeyModifiers::NONE);
+        editor.process_command("protect 0x10..0x20");
+        editor.process_command("protect 0x40");
+        editor.process_command("protect");
+        assert_eq!(message(&editor), "Protected 0x10..0x20, 0x40..0x41");
+        editor.cursor = 0x18;
+        editor.process_event(key('f'), 16);
+        assert_eq!(
+            warning(&editor),
+            "Protected range 0x10..0x20, set noprotect to edit"
+        );
+        assert_eq!(editor.buffer[0x18], 0xa);
+        editor.process_command("xor 00");
+        assert_eq!(message(&editor), "Transformed 300 bytes");
+        editor.process_command("zero");
+        assert!(warning(&editor).starts_with("Protected range 0x10..0x20"));
+        assert!(!editor.buffer.is_dirty());
+        editor.process_command("replace 0a0b0c 000000 all");
+        assert!(warning(&editor).starts_with("Protected range"));
+        editor.selection = Some(0x20);
+        editor.cursor = 0x3f;
+        editor.process_command("zero");
+        assert_eq!(editor.buffer.slice(0x1f..0x21), [0xb, 0]);
+        editor.selection = None;
+
+        editor.process_event(key('i'), 16);
+        editor.cursor = 0x10;
+        editor.process_event(key('1'), 16);
+        assert_eq!(editor.buffer.len(), 300);
+        // Inserting before a protected range would move it.
+        editor.cursor = 0x20;
+        editor.process_event(key('1'), 16);
+        assert_eq!(
+            warning(&editor),
+            "Protected range 0x40..0x41, set noprotect to edit"
+        );
+        editor.cursor = 0x41;
+        editor.process_event(key('1'), 16);
+        editor.process_event(key('1'), 16);
+        assert_eq!(editor.buffer.slice(0x40..0x43), [0xb, 0x11, 0xc]);
+        editor.process_command("replace 0a0b0c 0a0b all");
+        assert!(warning(&editor).starts_with("Protected range 0x10..0x20"));
+        assert_eq!(editor.buffer.len(), 301);
+        editor.process_event(key('i'), 16);
+        editor.process_command("set noprotect");
+        editor.cursor = 0x40;
+        editor.process_event(key('f'), 16);
+        assert_eq!(editor.buffer[0x40], 0xf);
+        editor.process_command("set protect");
+        editor.process_command("unprotect 0x18");
+        assert_eq!(message(&editor), "Unprotected 1 ranges");
+        editor.process_command("unprotect");
+        editor.process_command("unprotect");
+        assert_eq!(warning(&editor), "No protected range");
+        editor.process_command("protect 0x20..0x10");
+        assert_eq!(warning(&editor), "Usage: protect [offset|start..end]");
+
+        editor.process_command("protect 0x0..0x4");
+        editor.process_command("checksum crc32 4..8 0x0");
+        assert!(!editor.save());
+        assert_eq!(
+            warning(&editor),
+            "Protected range 0x0..0x4, set noprotect to edit"
+        );
+    }
+
+    #[test]
+    fn test_read_only_fields() {
+        let mut editor = setup_test_editor();
+        editor.template = Some(Template::parse("u16 magic readonly\nu8 size\n", 4).unwrap());
+        editor.cursor = 5;
+        editor.process_event(KeyEvent::new(Char('0'), KeyModifiers::NONE), 16);
+        assert_eq!(
+            warning(&editor),
+            "Read-only field magic, set noprotect to edit"
+        );
+        editor.process_command("write-text ab");
+        assert_eq!(editor.buffer.slice(4..7), [0xb, 0xc, 0xa]);
+        editor.cursor = 6;
+        editor.process_command("write-text A");
+        assert_eq!(editor.buffer[6], b'A');
+        editor.process_command("set noprotect");
+        editor.cursor = 4;
+        editor.process_command("write-text A");
+        assert_eq!(editor.buffer[4], b'A');
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut editor = setup_test_editor();
+        editor.apply(Action::MoveLines(2));
+        editor.apply(Action::Move(1));
+        assert_eq!(editor.cursor, 0x21);
+        editor.apply(Action::Digit(0xf));
+        editor.apply(Action::Digit(0xe));
+        assert_eq!(editor.buffer[0x21], 0xfe);
+        editor.apply(Action::ToggleSelection);
+        editor.apply(Action::MoveTo(0x30));
+        assert_eq!(editor.selected_range(), 0x21..0x31);
+        editor.apply(Action::Command("zero".into()));
+        assert_eq!(editor.buffer[0x30], 0);
+        editor.apply(Action::Undo);
+        assert_eq!(editor.buffer[0x30], 0xa);
+        editor.apply(Action::EnterCommand("goto ".into()));
+        assert!(matches!(&editor.mode, EditorMode::Command(command) if command == "goto "));
+        editor.apply(Action::Cancel);
+        assert!(matches!(editor.mode, EditorMode::Edit(None)));
+        assert_eq!(editor.selection, None);
+        editor.apply(Action::Quit);
+        assert!(editor.should_exit);
+    }
+
+    #[test]
+    fn test_revert_key() {
+        let mut editor = setup_test_editor();
+        editor.buffer.update(0, 0xff);
+        editor.buffer.update(1, 0xff);
+        let event = KeyEvent::new(Char('U'), KeyModifiers::SHIFT);
+        editor.process_event(event, 16);
+        assert_eq!(editor.buffer[0], 0xa);
+        assert_eq!(editor.buffer[1], 0xff);
+
+        editor.buffer.update(2, 0xff);
+        editor.selection = Some(1);
+        editor.cursor = 2;
+        editor.process_event(event, 16);
+        assert_eq!(editor.buffer[1], 0xb);
+        assert_eq!(editor.buffer[2], 0xc);
+        assert!(!editor.buffer.is_dirty());
+    }
+
+    #[test]
+    fn test_process_command_event() {
+        let mut editor = setup_test_editor();
+        editor.path = std::env::temp_dir().join("ashe_test_process_command_event.bin");
+        let command = String::from("abc");
+        let event = KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE);
+        let new_mode = editor.process_command_event(&command, event);
+        assert!(matches!(new_mode, Some(EditorMode::Command(_))));
+        assert_eq!(
+            match new_mode {
+                Some(EditorMode::Command(value)) => value,
+                _ => "".into(),
+            },
+            "ab"
+        );
+
+        editor.buffer.update(0, 0x12);
+        assert!(editor.buffer.is_dirty());
+
+        let command = String::from("w");
+        let event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        editor.process_command_event(&command, event);
+        assert!(!editor.buffer.is_dirty());
+
+        let command = String::from("q");
+        let event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        editor.process_command_event(&command, event);
+        assert!(editor.should_exit);
+
+        editor.buffer.update(0, 0x12);
+        assert!(editor.buffer.is_dirty());
+
+        let command = String::from("wq");
+        let event = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        editor.process_command_event(&command, event);
+        assert!(!editor.buffer.is_dirty());
+        assert!(editor.should_exit);
+        std::fs::remove_file(&editor.path).unwrap();
+    }
+
+    #[test]
+    fn test_process_command() {
+        let mut editor = setup_test_editor();
+        let command = "exit";
+
+        editor.process_command(command);
+        assert!(editor.should_exit);
+
+        let mut editor = setup_test_editor();
+        editor.path = std::env::temp_dir().join("ashe_test_process_command.bin");
+        editor.buffer.update(0, 0x12);
+        assert!(editor.buffer.is_dirty());
+        editor.process_command(command);
+        assert!(!editor.should_exit);
+        assert!(!warning(&editor).is_empty());
+
+        let command = "wq";
+        assert!(editor.buffer.is_dirty());
+        editor.process_command(command);
+        assert!(editor.should_exit);
+        std::fs::remove_file(&editor.path).unwrap();
+    }
+
+    #[test]
+    fn test_selected_range() {
+        let mut editor = setup_test_editor();
+        assert_eq!(editor.selected_range(), 0..300);
+        assert!(!editor.is_selected(0));
+
+        editor.cursor = 10;
+        let event = KeyEvent::new(Char('v'), KeyModifiers::NONE);
+        editor.process_event(event, 16);
+        editor.update_cursor(-4);
+        assert_eq!(editor.selected_range(), 6..11);
+        assert!(editor.is_selected(6));
+        assert!(editor.is_selected(10));
+        assert!(!editor.is_selected(11));
+
+        editor.process_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE), 16);
+        assert!(editor.selection.is_none());
+    }
+
+    #[test]
+    fn test_export_command() {
+        let mut editor = setup_test_editor();
+        editor.selection = Some(2);
+        editor.cursor = 3;
+        editor.process_command("export py test_export_command.py");
+        assert!(warning(&editor).is_empty());
+        let exported = std::fs::read_to_string("test_export_command.py").unwrap();
+        assert_eq!(exported, "data = (\n    b\"\\x0c\\x0a\"\n)\n");
+        std::fs::remove_file("test_export_command.py").unwrap();
+
+        editor.process_command("export java test_export_command.java");
+        assert!(!warning(&editor).is_empty());
+    }
+
+    #[test]
+    fn test_base64_command() {
+        let mut editor = setup_test_editor();
+        editor.selection = Some(0);
+        editor.cursor = 2;
+        editor.process_command("base64 encode");
+        assert_eq!(editor.buffer.slice(0..4), b"CgsM");
+        assert_eq!(editor.buffer.len(), 301);
+        assert!(editor.selection.is_none());
+
+        editor.selection = Some(3);
+        editor.cursor = 0;
+        editor.process_command("base64 decode");
+        assert_eq!(editor.buffer.slice(0..4), &[0xa, 0xb, 0xc, 0xa]);
+        assert_eq!(editor.buffer.len(), 300);
+
+        editor.cursor = 1;
+        editor.process_command("base64 decode //8=");
+        assert_eq!(editor.buffer.slice(0..4), &[0xa, 0xff, 0xff, 0xb]);
+        assert_eq!(editor.buffer.len(), 302);
+
+        editor.process_command("base64 decode !!");
+        assert!(!warning(&editor).is_empty());
+    }
+
+    #[test]
+    fn test_patch_command() {
+        let mut editor = setup_test_editor();
+        editor.buffer.update(1, 0xff);
+        editor.process_command("patch create test_patch_command.ips");
+        assert!(warning(&editor).is_empty());
+
+        let mut editor = setup_test_editor();
+        editor.process_command("patch apply test_patch_command.ips");
+        assert!(warning(&editor).is_empty());
+        assert_eq!(editor.buffer[1], 0xff);
+        assert!(editor.buffer.is_dirty());
+        std::fs::remove_file("test_patch_command.ips").unwrap();
+
+        editor.process_command("patch apply test_patch_command.zip");
+        assert!(!warning(&editor).is_empty());
+    }
+
+    #[test]
+    fn test_diff_export_command() {
+        let mut editor = setup_test_editor();
+        std::fs::write("test_diff_export_command.bin", [0xa, 0xb, 0xc]).unwrap();
+        editor.buffer.update(1, 0xff);
+        editor.process_command("diff-export test_diff_export_command.bin test_diff_export.xd");
+        assert!(warning(&editor).is_empty());
+
+        let patch = std::fs::read("test_diff_export.xd").unwrap();
+        let patched = PatchFormat::Vcdiff.apply(&patch, &[0xa, 0xb, 0xc]).unwrap();
+        assert_eq!(patched, editor.buffer.slice(0..editor.buffer.len()));
+        std::fs::remove_file("test_diff_export_command.bin").unwrap();
+        std::fs::remove_file("test_diff_export.xd").unwrap();
+
+        editor.process_command("diff-export missing.bin test_diff_export.xd");
+        assert!(!warning(&editor).is_empty());
+    }
+
+    #[test]
+    fn test_diff_command() {
+        let mut editor = setup_test_editor();
+        std::fs::write("test_diff_command.bin", [0xa, 0xc, 0xc]).unwrap();
+        editor.process_command("diff test_diff_command.bin");
+        std::fs::remove_file("test_diff_command.bin").unwrap();
+        assert!(warning(&editor).is_empty());
+
+        let data = editor.buffer.slice(0..editor.buffer.len());
+        let other = editor.diff.as_ref().map(|(_, other)| other.as_slice());
+        assert!(!Editor::differs(0, data, other));
+        assert!(Editor::differs(1, data, other));
+        assert!(Editor::differs(3, data, other));
+        assert!(!Editor::differs(1, data, None));
+
+        editor.process_command("diff");
+        assert!(editor.diff.is_none());
+        editor.process_command("diff missing.bin");
+        assert!(!warning(&editor).is_empty());
+    }
+
+    #[test]
+    fn test_set_command() {
+        let mut editor = setup_test_editor();
+        editor.process_command("set diffdisk");
+        assert!(editor.settings.diff_disk);
+        assert!(warning(&editor).is_empty());
+        editor.process_command("set nodiffdisk");
+        assert!(!editor.settings.diff_disk);
+        editor.process_command("set unknown");
+        assert!(!warning(&editor).is_empty());
+    }
+
+    #[test]
+    fn test_changes_command() {
+        let mut editor = setup_test_editor();
+        editor.buffer.update(0x21, 0xff);
+        editor.buffer.update(0x40, 0xff);
+        editor.process_command_event(
+            &"changes".into(),
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+        );
+        assert!(editor.panel.is_some());
+
+        editor.process_event(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE), 2);
+        editor.process_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), 2);
+        assert!(editor.panel.is_none());
+        assert_eq!(editor.cursor, 0x40);
+        assert_eq!(editor.offset, 0x30);
+    }
+
+    #[test]
+    fn test_write_select_command() {
+        let mut editor = setup_test_editor();
+        editor.path = PathBuf::from("test_write_select_command.bin");
+        editor.buffer.update(0, 0xff);
+        editor.buffer.update(2, 0xff);
+        editor.process_command("checksum crc32 0..3 4");
+        editor.process_command("write-select");
+        assert!(editor.panel.is_some());
+
+        editor.process_event(KeyEvent::new(Char(' '), KeyModifiers::NONE), 16);
+        editor.process_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), 16);
+        assert!(editor.panel.is_none());
+        assert!(editor.buffer.is_dirty());
+        let saved = std::fs::read("test_write_select_command.bin").unwrap();
+        assert_eq!(saved[..3], [0xa, 0xb, 0xff]);
+        assert_eq!(
+            saved[4..8],
+            crc32fast::hash(&[0xa, 0xb, 0xff]).to_be_bytes()
+        );
+        std::fs::remove_file("test_write_select_command.bin").unwrap();
+    }
+
+    #[test]
+    fn test_checksum_command() {
+        let mut editor = setup_test_editor();
+        editor.path = PathBuf::from("test_checksum_command.bin");
+        editor.process_command("checksum crc32 4..");
+        assert_eq!(
+            warning(&editor),
+            "Usage: checksum <crc32|md5|sha1|sha256> <start>..<end> <offset> [le]"
+        );
+        editor.process_command("checksum crc32 4..7 0x0");
+        editor.buffer.update(4, 0x61);
+        editor.buffer.update(5, 0x62);
+        editor.buffer.update(6, 0x63);
+        assert!(editor.save());
+        let saved = std::fs::read("test_checksum_command.bin").unwrap();
+        assert_eq!(saved[..7], [0x35, 0x24, 0x41, 0xc2, 0x61, 0x62, 0x63]);
+        assert!(!editor.buffer.is_dirty());
+
+        editor.process_command("checksum crc32 0..1 299");
+        editor.buffer.update(8, 0);
+        assert!(!editor.save());
+        assert_eq!(warning(&editor), "Checksum field out of range");
+        std::fs::remove_file("test_checksum_command.bin").unwrap();
+    }
+
+    #[test]
+    fn test_fix_checksum_command() {
+        let mut editor = setup_test_editor();
+        editor.process_command("fix-checksum");
+        assert_eq!(
+            warning(&editor),
+            "Usage: fix-checksum [snes|genesis|gba|png]"
+        );
+        editor.process_command("fix-checksum snes");
+        assert_eq!(warning(&editor), "File too small for the header");
+
+        editor.buffer = Buffer::new(vec![0; 0xc0]);
+        editor.buffer.update(0xb2, 0x96);
+        editor.process_command("fix-checksum");
+        assert_eq!(message(&editor), "Fixed GBA checksum");
+        assert_eq!(editor.buffer[0xbd], 0x51);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend(b"\x00\x00\x00\x00IEND\x00\x00\x00\x00");
+        editor.buffer = Buffer::new(png);
+        editor.process_command("fix-checksum");
+        assert_eq!(warning(&editor), "No complete chunk at the cursor");
+        editor.cursor = 8;
+        editor.process_command("fix-checksum");
+        assert_eq!(message(&editor), "Fixed PNG chunk CRC");
+        assert_eq!(editor.buffer.slice(16..20), [0xae, 0x42, 0x60, 0x82]);
+    }
+
+    #[test]
+    fn test_source_command() {
+        let mut editor = setup_test_editor();
+        std::fs::write(
+            "test_source_command.txt",
+            "# checksums\n\nchecksum crc32 4..7 0\nset diffdisk\n",
+        )
+        .unwrap();
+        editor.process_command("source test_source_command.txt");
+        assert!(warning(&editor).is_empty());
+        assert_eq!(editor.checksums.len(), 1);
+        assert!(editor.settings.diff_disk);
+
+        std::fs::write(
+            "test_source_command.txt",
+            "checksum crc32 0..4 8\nsource ./test_source_command.txt\n",
+        )
+        .unwrap();
+        editor.process_command("source test_source_command.txt");
+        assert_eq!(
+            warning(&editor),
+            "Already sourcing ./test_source_command.txt"
+        );
+        assert_eq!(editor.checksums.len(), 2);
+        assert!(editor.sourcing.is_empty());
+        std::fs::remove_file("test_source_command.txt").unwrap();
+    }
+
+    #[test]
+    fn test_carve_command() {
+        let mut editor = setup_test_editor();
+        editor.path = PathBuf::from("test_carve_command.bin");
+        editor
+            .buffer
+            .splice(0x10..0x10, b"\xff\xd8\xff\x00\xff\xd9");
+        editor.process_command("carve");
+        assert!(editor.panel.is_some());
+        editor.process_event(KeyEvent::new(Char('e'), KeyModifiers::NONE), 16);
+        assert_eq!(message(&editor), "Exported test_carve_command_00000010.jpg");
+        let exported = std::fs::read("test_carve_command_00000010.jpg").unwrap();
+        assert_eq!(exported, b"\xff\xd8\xff\x00\xff\xd9");
+        std::fs::remove_file("test_carve_command_00000010.jpg").unwrap();
+
+        editor.process_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), 16);
+        assert!(editor.panel.is_none());
+        assert_eq!(editor.cursor, 0x10);
+    }
+
+    #[test]
+    fn test_template_command() {
+        let mut editor = setup_test_editor();
+        std::fs::write("test_template_command.txt", "u16be first\nu8 second\n").unwrap();
+        editor.cursor = 2;
+        editor.process_command("template test_template_command.txt");
+        let template = editor.template.as_ref().unwrap();
+        assert_eq!(template.fields[0].range, 2..4);
+        editor.process_command("template test_template_command.txt 0x10");
+        let template = editor.template.as_ref().unwrap();
+        assert_eq!(template.fields[1].range, 0x12..0x13);
+        editor.process_command("template");
+        assert!(editor.template.is_none());
+
+        editor.process_command("template test_template_command.txt x");
+        assert_eq!(warning(&editor), "Usage: template [file [offset]]");
+        std::fs::remove_file("test_template_command.txt").unwrap();
+        editor.process_command("template test_template_command.txt");
+        assert!(editor.template.is_none());
+    }
+
+    #[test]
+    fn test_headers_command() {
+        let mut editor = setup_test_editor();
+        editor.process_command("headers");
+        assert_eq!(warning(&editor), "Unknown file format");
+
+        let mut elf = b"\x7fELF\x01\x01\x01".to_vec();
+        elf.resize(0x34, 0);
+        editor.buffer = Buffer::new(elf);
+        editor.process_command("headers");
+        assert!(editor.panel.is_some());
+        let template = editor.template.as_ref().unwrap();
+        assert_eq!(template.field_at(0x18).unwrap().1.name, "e_entry");
+    }
+
+    #[test]
+    fn test_magic_command() {
+        let mut editor = setup_test_editor();
+        editor.process_command("magic");
+        assert_eq!(warning(&editor), "Unknown file type");
+        editor.buffer.splice(0x10..0x10, b"\x7fELF");
+        editor.process_command("magic 0x10");
+        assert_eq!(message(&editor), "ELF executable");
+        editor.process_command("magic x");
+        assert_eq!(warning(&editor), "Usage: magic [offset]");
+    }
+
+    #[test]
+    fn test_relsearch_command() {
+        let mut editor = setup_test_editor();
+        editor.buffer.splice(0x40..0x40, b"\x01\x02\x03");
+        editor.process_command("relsearch abc");
+        let (panel, _) = editor.panel.as_mut().unwrap();
+        assert_eq!(panel.selected_offset(), Some(0));
+        panel.move_selection(1);
+        assert_eq!(panel.selected_offset(), Some(3));
+        editor.process_command("relsearch a");
+        assert!(warning(&editor).starts_with("Usage: relsearch"));
+    }
+
+    #[test]
+    fn test_find_first() {
+        let mut editor = setup_test_editor();
+        editor.buffer.splice(0x40..0x42, b"hi");
+        assert_eq!(editor.find_first("hi", true), Ok(0x40));
+        assert_eq!((editor.cursor, editor.offset), (0x40, 0x40));
+        assert_eq!(editor.find_first("0c0a", false), Ok(0x44));
+        assert_eq!(editor.cursor, 0x44);
+        assert_eq!(editor.find_first("ff", false), Err("Pattern not found"));
+        assert_eq!(editor.find_first("f", false), Err("Invalid pattern"));
+        assert_eq!(editor.find_first("", true), Err("Invalid pattern"));
+    }
+
+    #[test]
+    fn test_registers() {
+        let mut editor = setup_test_editor();
+        let key = |c| KeyEvent::new(Char(c), KeyModifiers::NONE);
+        editor.process_event(key('v'), 16);
+        editor.cursor = 2;
+        editor.process_event(key('y'), 16);
+        assert_eq!(message(&editor), "Yanked 3 bytes");
+        assert_eq!(editor.selection, None);
+        editor.cursor = 0x10;
+        editor.process_event(key('p'), 16);
+        assert_eq!(editor.buffer.slice(0x10..0x14), [0xa, 0xb, 0xc, 0xb]);
+        assert_eq!(editor.buffer.len(), 300);
+        editor.process_event(key('i'), 16);
+        editor.process_event(key('p'), 16);
+        assert_eq!(editor.buffer.len(), 303);
+
+        editor.selection = Some(0x20);
+        editor.cursor = 0x11f;
+        editor.process_command("yank a");
+        editor.process_command("find 0a0b");
+        editor.process_command("registers");
+        let lines = editor.render(100, 10).unwrap();
+        assert_eq!(
+            lines[2..5],
+            [
+                " │ \"       256 bytes  0c 0a 0b 0c 0a 0b 0c 0a 0b 0c 0a 0b 0c 0a 0b 0c ...         │",
+                " │ a       256 bytes  0c 0a 0b 0c 0a 0b 0c 0a 0b 0c 0a 0b 0c 0a 0b 0c ...         │",
+                " │ /         2 bytes  0a 0b                                                       │",
+            ]
+        );
+        editor.process_command("put b");
+        assert_eq!(warning(&editor), "Register b is empty");
+        editor.process_command("yank ab");
+        assert_eq!(warning(&editor), "Usage: yank [a-z]");
+    }
+
+    #[test]
+    fn test_messages_command() {
+        let mut editor = setup_test_editor();
+        editor.process_command("foo");
+        editor.process_command("stats");
+        editor.messages.clear();
+        editor.process_command("messages");
+        let (panel, _) = editor.panel.as_mut().unwrap();
+        assert_eq!(panel.selected(), Some(1));
+        panel.push_filter('z');
+        assert_eq!(panel.selected(), None);
+        panel.pop_filter();
+        panel.push_filter('i');
+        panel.push_filter('n');
+        panel.push_filter('v');
+        assert_eq!(panel.selected(), Some(0));
+    }
+
+    #[test]
+    fn test_find_command() {
+        let mut editor = setup_test_editor();
+        editor.process_event(KeyEvent::new(Char('n'), KeyModifiers::NONE), 16);
+        assert_eq!(warning(&editor), "No search pattern");
+        editor.process_command("find 0x0a0b");
+        assert_eq!(editor.cursor, 0);
+        assert_eq!(editor.search_summary().unwrap(), "Match 1 of 100 at 0x0");
+        let key = |c| KeyEvent::new(Char(c), KeyModifiers::NONE);
+        editor.process_event(key('n'), 16);
+        assert_eq!(editor.search_summary().unwrap(), "Match 2 of 100 at 0x3");
+        editor.process_event(key('N'), 16);
+        editor.process_event(key('N'), 16);
+        assert_eq!(editor.cursor, 297);
+        editor.cursor = 4;
+        assert!(editor.search_summary().is_none());
+        editor.process_command("find 0c 0a");
+        assert_eq!(editor.cursor, 5);
+        editor.process_command("find ff");
+        assert_eq!(warning(&editor), "Pattern not found");
+        editor.process_command("find f");
+        assert_eq!(warning(&editor), "Usage: find <hex bytes>");
+    }
+
+    #[test]
+    fn test_text_commands() {
+        let mut editor = setup_test_editor();
+        let path = std::env::temp_dir().join("ashe_test_text_commands.tbl");
+        std::fs::write(&path, "0A=A\n0B=B\n0C=C\n").unwrap();
+        editor.process_command(&format!("tbl {}", path.display()));
+        std::fs::remove_file(&path).unwrap();
+        editor.process_command("find-text CA");
+        let (panel, _) = editor.panel.as_mut().unwrap();
+        assert_eq!(panel.selected_offset(), Some(2));
+        editor.cursor = 1;
+        editor.process_command("write-text AAC");
+        assert_eq!(editor.buffer.slice(0..5), b"\x0a\x0a\x0a\x0c\x0b");
+        editor.process_command("write-text D");
+        assert_eq!(
+            warning(&editor),
+            "Text cannot be encoded in the current encoding"
+        );
+        editor.process_command("tbl");
+        editor.process_command("find-text A");
+        assert!(editor.panel.as_ref().unwrap().0.selected_offset().is_none());
+        editor.process_command("tbl /nonexistent.tbl");
+        assert!(!warning(&editor).is_empty());
+    }
+
+    #[test]
+    fn test_transform_commands() {
+        let mut editor = setup_test_editor();
+        editor.selection = Some(1);
+        editor.cursor = 3;
+        editor.process_command("xor 0xff01");
+        assert_eq!(editor.buffer.slice(0..5), [0x0a, 0xf4, 0x0d, 0xf5, 0x0b]);
+        assert_eq!(message(&editor), "Transformed 3 bytes");
+        editor.process_command("xor 0xf");
+        assert_eq!(warning(&editor), "Usage: xor <hex key>");
+        editor.process_command("not");
+        editor.process_command("and 0f");
+        editor.process_command("or 0x10");
+        assert_eq!(editor.buffer.slice(0..5), [0x0a, 0x1b, 0x12, 0x1a, 0x0b]);
+        editor.process_command("undo");
+        editor.process_command("undo");
+        assert_eq!(editor.buffer.slice(0..5), [0x0a, 0x0b, 0xf2, 0x0a, 0x0b]);
+        editor.process_command("redo");
+        assert_eq!(editor.buffer.slice(0..5), [0x0a, 0x0b, 0x02, 0x0a, 0x0b]);
+        editor.cursor = 3;
+        editor.process_command("sub 3");
+        assert_eq!(editor.buffer.slice(0..5), [0x0a, 0x08, 0xff, 0x07, 0x0b]);
+        editor.process_command("bswap 2");
+        assert_eq!(editor.buffer.slice(0..5), [0x0a, 0xff, 0x08, 0x07, 0x0b]);
+        editor.buffer.splice(1..4, b"aB.");
+        editor.process_command("rot 2");
+        assert_eq!(editor.buffer.slice(1..4), b"cD.");
+        editor.process_command("zero");
+        assert_eq!(editor.buffer.slice(0..5), [0x0a, 0, 0, 0, 0x0b]);
+        editor.process_command("fill-random 7");
+        let random = editor.buffer.slice(1..4).to_vec();
+        editor.process_command("fill-random 7");
+        assert_eq!(editor.buffer.slice(1..4), random);
+    }
+
+    #[test]
+    fn test_undo_keys() {
+        let mut editor = setup_test_editor();
+        editor.cursor = 4;
+        editor.process_event(KeyEvent::new(Char('1'), KeyModifiers::NONE), 16);
+        editor.process_event(KeyEvent::new(Char('2'), KeyModifiers::NONE), 16);
+        editor.cursor = 0;
+        editor.process_event(KeyEvent::new(Char('u'), KeyModifiers::NONE), 16);
+        assert_eq!(editor.buffer[4], 0xb);
+        assert_eq!(editor.cursor, 4);
+        let event = KeyEvent::new(Char('r'), KeyModifiers::CONTROL);
+        editor.process_event(event, 16);
+        assert_eq!(editor.buffer[4], 0x12);
+        editor.process_event(event, 16);
+        assert_eq!(warning(&editor), "Nothing to redo");
+    }
+
+    #[test]
+    fn test_calc_command() {
+        let mut editor = setup_test_editor();
+        editor.process_command("calc 0x1f4 + 12*16");
+        assert_eq!(message(&editor), "0x2b4 = 692 = 0b1010110100");
+        editor.process_command("calc 1 / 0");
+        assert_eq!(warning(&editor), "Division by zero");
+        editor.process_command("calc");
+        assert_eq!(warning(&editor), "Usage: calc <expression>");
+    }
+
+    #[test]
+    fn test_cyclic_commands() {
+        let mut editor = setup_test_editor();
+        editor.cursor = 298;
+        editor.process_command("cyclic 8");
+        assert_eq!(editor.buffer.len(), 306);
+        assert_eq!(editor.buffer.slice(298..306), b"aaaabaaa");
+        editor.selection = Some(0);
+        editor.cursor = 2;
+        editor.process_command("cyclic");
+        assert_eq!(editor.buffer.slice(0..4), b"aaa\x0a");
+        editor.process_command("cyclic-find 0x61616167");
+        assert_eq!(message(&editor), "Found at offset 24 (0x18)");
+        editor.process_command("cyclic-find daaa");
+        assert_eq!(message(&editor), "Found at offset 12 (0xc)");
+        editor.process_command("cyclic-find 0x41414141");
+        assert_eq!(warning(&editor), "Not part of the cyclic pattern");
+        editor.selection = None;
+        editor.process_command("cyclic 99999999999999");
+        assert_eq!(warning(&editor), "Patterns are at most 456979 bytes long");
+        assert_eq!(editor.buffer.len(), 306);
+    }
+
+    #[test]
+    fn test_inflate_command() {
+        let mut editor = setup_test_editor();
+        // A zlib stream of "abcabcabc" after two bytes of other data.
+        editor.buffer.splice(
+            0..0,
+            b"\x00\x00\x78\x9c\x4b\x4c\x4a\x4e\x04\x23\x00\x11\x3d\x03\x73",
+        );
+        editor.cursor = 5;
+        editor.process_command("inflate");
+        assert!(warning(&editor).starts_with("Decompression failed"));
+        editor.selection = Some(2);
+        editor.cursor = 14;
+        editor.process_command("inflate");
+        assert_eq!(editor.buffer.slice(0..editor.buffer.len()), b"abcabcabc");
+        assert_eq!(editor.path, PathBuf::from("test.txt.2.inflated"));
+        assert_eq!((editor.cursor, editor.selection), (0, None));
+        editor.buffer.update(0, 0);
+        editor.process_command("q");
+        assert_eq!(warning(&editor), "Modified Buffer");
+        editor.buffer.undo();
+        editor.process_command("q");
+        assert!(!editor.should_exit);
+        assert_eq!(editor.path, PathBuf::from("test.txt"));
+        assert_eq!((editor.cursor, editor.selection), (14, Some(2)));
+        editor.buffer.undo();
+        editor.process_command("q");
+        assert!(editor.should_exit);
+    }
+
+    #[test]
+    fn test_buffer_commands() {
+        let mut editor = setup_test_editor();
+        let path = std::env::temp_dir().join("ashe_test_buffer_commands.bin");
+        std::fs::write(&path, [1, 2, 3]).unwrap();
+        editor.cursor = 5;
+        editor.process_command(&format!("e {}", path.display()));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(editor.buffer.slice(0..editor.buffer.len()), [1, 2, 3]);
+        assert_eq!((editor.active, editor.cursor), (1, 0));
+        editor.buffer.update(0, 0xff);
+        editor.process_command("bnext");
+        assert_eq!((editor.active, editor.cursor), (0, 5));
+        editor.process_command("bp");
+        assert_eq!(editor.buffer[0], 0xff);
+        editor.process_command("b 1");
+        assert_eq!(editor.path, PathBuf::from("test.txt"));
+        editor.process_command("b 3");
+        assert!(warning(&editor).starts_with("Usage: b"));
+        editor.process_command("ls");
+        let (panel, _) = editor.panel.as_mut().unwrap();
+        assert_eq!(panel.selected_offset(), Some(0));
+        panel.move_selection(1);
+        let (panel, action) = editor.panel.take().unwrap();
+        editor.confirm_panel(panel, action, 16);
+        assert_eq!(editor.active, 1);
+        editor.process_command("e /nonexistent/file");
+        assert!(!warning(&editor).is_empty());
+
+        editor.process_command("n");
+        assert_eq!(warning(&editor), "No next file");
+        editor.process_command("prev");
+        assert_eq!(editor.active, 0);
+        editor.process_command("N");
+        assert_eq!(warning(&editor), "No previous file");
+        editor.process_command("next");
+        assert_eq!(editor.active, 1);
+    }
+
+    #[test]
+    fn test_is_modified() {
+        let mut editor = setup_test_editor();
+        editor.open_document(Document::new("other.bin".into(), Codec::Raw, vec![1]));
+        assert!(!editor.is_modified());
+        editor.process_command("bn");
+        editor.buffer.update(0, 0xff);
+        editor.process_command("bn");
+        assert!(editor.is_modified());
+    }
+
+    #[test]
+    fn test_tab_keys() {
+        let mut editor = setup_test_editor();
+        editor.open_document(Document::new("other.bin".into(), Codec::Raw, vec![1]));
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        editor.process_event(key(KeyCode::Tab), 16);
+        assert_eq!(editor.active, 0);
+        editor.process_event(key(KeyCode::BackTab), 16);
+        assert_eq!(editor.active, 1);
+        let tabs = editor.tabs();
+        assert_eq!(
+            (tabs[0].name.as_str(), tabs[1].name.as_str()),
+            ("test.txt", "other.bin")
+        );
+        let event = KeyEvent::new(Char('t'), KeyModifiers::CONTROL);
+        editor.process_event(event, 16);
+        assert!(matches!(&editor.mode, EditorMode::Command(command) if command == "e "));
+        editor.mode = EditorMode::Edit(None);
+        let event = KeyEvent::new(Char('w'), KeyModifiers::CONTROL);
+        editor.process_event(event, 16);
+        assert_eq!((editor.documents.len(), editor.active), (1, 0));
+        assert_eq!(editor.path, PathBuf::from("test.txt"));
+    }
+
+    #[test]
+    fn test_split_commands() {
+        let mut editor = setup_test_editor();
+        editor.cursor = 0x20;
+        editor.process_command("split");
+        assert_eq!(editor.view_lines(20), 9);
+        let event = KeyEvent::new(Char('o'), KeyModifiers::CONTROL);
+        editor.process_event(event, 9);
+        editor.update_cursor(0x100);
+        assert_eq!(editor.view_lines(20), 9);
+        editor.process_event(event, 9);
+        assert_eq!(editor.cursor, 0x20);
+        assert_eq!(editor.split.as_ref().unwrap().cursor, 0x120);
+
+        let path = std::env::temp_dir().join("ashe_test_split_commands.bin");
+        std::fs::write(&path, [1, 2]).unwrap();
+        editor.process_command(&format!("vsplit {}", path.display()));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(editor.view_lines(20), 20);
+        assert_eq!(editor.document_data(1), [1, 2]);
+        editor.process_event(event, 16);
+        assert_eq!((editor.active, editor.buffer.len()), (1, 2));
+        editor.process_command("q");
+        assert_eq!(editor.active, 0);
+        assert_eq!(editor.split.as_ref().unwrap().document, 0);
+
+        editor.process_command("only");
+        editor.process_command("lock");
+        assert_eq!(warning(&editor), "No split");
+        editor.process_command("split /nonexistent.bin");
+        assert!(editor.split.is_none());
+        editor.process_event(event, 16);
+        assert_eq!(warning(&editor), "No split");
+    }
+
+    #[test]
+    fn test_lock_command() {
+        let mut editor = setup_test_editor();
+        editor.process_command("vsplit");
+        editor.process_command("lock 0x10");
+        assert_eq!(message(&editor), "Locked at +0x10");
+        let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        for _ in 0..3 {
+            editor.process_event(down, 2);
+        }
+        let split = editor.split.as_ref().unwrap();
+        assert_eq!((editor.cursor, editor.offset), (0x30, 0x20));
+        assert_eq!((split.cursor, split.offset), (0x40, 0x30));
+
+        let event = KeyEvent::new(Char('o'), KeyModifiers::CONTROL);
+        editor.process_event(event, 2);
+        editor.process_event(down, 2);
+        let split = editor.split.as_ref().unwrap();
+        assert_eq!((editor.cursor, split.cursor), (0x50, 0x40));
+        editor.process_command("lock -0x1x");
+        assert_eq!(warning(&editor), "Usage: lock [delta]");
+        editor.process_command("unlock");
+        editor.process_event(down, 2);
+        assert_eq!(editor.split.as_ref().unwrap().cursor, 0x40);
+        editor.process_command("lock");
+        assert_eq!(message(&editor), "Locked at -0x20");
+    }
+
+    #[test]
+    fn test_browse_command() {
+        let mut editor = setup_test_editor();
+        let dir = std::env::temp_dir().join("ashe_test_browse_command");
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("first.bin"), [1]).unwrap();
+        std::fs::write(dir.join("nested").join("second.bin"), [2, 3]).unwrap();
+        editor.process_command(&format!("browse {}", dir.display()));
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        for c in "nest".chars() {
+            editor.process_event(key(Char(c)), 16);
+        }
+        editor.process_event(key(KeyCode::Enter), 16);
+        editor.process_event(key(Char('x')), 16);
+        editor.process_event(key(KeyCode::Backspace), 16);
+        editor.process_event(key(KeyCode::Down), 16);
+        editor.process_event(key(KeyCode::Enter), 16);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(editor.panel.is_none());
+        assert_eq!(editor.path, dir.join("nested").join("second.bin"));
+        assert_eq!(editor.documents.len(), 2);
+
+        editor.process_command("browse /nonexistent");
+        assert!(!warning(&editor).is_empty());
+    }
+
+    #[test]
+    fn test_note_commands() {
+        let mut editor = setup_test_editor();
+        editor.path = std::env::temp_dir().join("ashe_test_note_commands.bin");
+        editor.process_command("note 0x10..0x20 \"file header\"");
+        editor.process_command("note 0x18 magic value");
+        let sidecar = notes::sidecar(&editor.path);
+        let text = std::fs::read_to_string(&sidecar).unwrap();
+        assert_eq!(text, "0x10..0x20 file header\n0x18..0x19 magic value\n");
+        editor.cursor = 0x18;
+        assert_eq!(notes::at(&editor.notes, 0x18).unwrap().text, "magic value");
+        editor.process_command("notes");
+        let (panel, _) = editor.panel.as_mut().unwrap();
+        panel.move_selection(1);
+        assert_eq!(panel.selected_offset(), Some(0x18));
+
+        editor.process_command("unnote");
+        assert!(editor.notes.is_empty());
+        assert!(!sidecar.exists());
+        editor.process_command("unnote");
+        assert_eq!(warning(&editor), "No note at the cursor");
+        editor.process_command("note 0x10");
+        assert_eq!(warning(&editor), "Usage: note <offset|start..end> <text>");
+
+        // Invalid notes are skipped instead of keeping the file from opening.
+        std::fs::write(&editor.path, [0; 4]).unwrap();
+        std::fs::write(&sidecar, "0x1 valid\nbroken\n").unwrap();
+        let editor = Editor::init(&editor.path, 16, false).unwrap();
+        assert_eq!(editor.notes.len(), 1);
+        assert!(warning(&editor).ends_with("skipped invalid notes on lines 2"));
+        std::fs::remove_file(&editor.path).unwrap();
+        std::fs::remove_file(&sidecar).unwrap();
+    }
+
+    #[test]
+    fn test_region_commands() {
+        let mut editor = setup_test_editor();
+        editor.process_command("region 0x20..0x30 data green");
+        editor.selection = Some(0);
+        editor.cursor = 0xf;
+        editor.process_command("region header");
+        assert_eq!(editor.regions[0].range, 0..0x10);
+        assert_eq!(regions::at(&editor.regions, 0x20).unwrap().name, "data");
+        editor.process_command("regions");
+        let (panel, _) = editor.panel.as_mut().unwrap();
+        panel.move_selection(1);
+        assert_eq!(panel.selected_offset(), Some(0x20));
+        editor.process_command("region 0..2 name pink");
+        assert!(warning(&editor).starts_with("Usage: region"));
+
+        editor.process_command("regions from-template");
+        assert_eq!(warning(&editor), "No template loaded");
+        editor.template = Some(Template::parse("u16 magic\nu32 size\n", 0x40).unwrap());
+        editor.process_command("regions from-template");
+        assert_eq!(message(&editor), "Added 2 regions");
+        assert_eq!(editor.regions[3].range, 0x42..0x46);
+
+        editor.cursor = 0x44;
+        editor.process_command("unregion");
+        assert_eq!(editor.regions.len(), 3);
+        editor.process_command("unregion");
+        assert_eq!(warning(&editor), "No region at the cursor");
+    }
+
+    #[test]
+    fn test_annotations_command() {
+        let mut editor = setup_test_editor();
+        editor.path = std::env::temp_dir().join("ashe_test_annotations.bin");
+        let file = std::env::temp_dir().join("ashe_test_annotations.json");
+        editor.process_command("note 0x10 entry");
+        editor.process_command("region 0x20..0x30 data green");
+        editor.cursor = 0x40;
+        editor.process_command("mark q");
+        editor.process_command(&format!("annotations export {}", file.display()));
+        assert_eq!(message(&editor), "Exported 1 note, 1 region and 1 mark");
+
+        editor.notes.clear();
+        editor.regions.clear();
+        editor.marks.clear();
+        editor.process_command(&format!("annotations import {}", file.display()));
+        assert_eq!(message(&editor), "Imported 1 note, 1 region and 1 mark");
+        assert_eq!(editor.notes[0].range, 0x10..0x11);
+        assert_eq!(editor.regions[0].name, "data");
+        assert_eq!(editor.marks[&'q'], 0x40);
+        assert!(notes::sidecar(&editor.path).exists());
+
+        std::fs::write(&file, "{\"version\": 3}").unwrap();
+        editor.process_command(&format!("annotations import {}", file.display()));
+        assert_eq!(
+            warning(&editor),
+            "Import failed: unsupported version 3, expected 1"
+        );
+        editor.process_command("annotations export");
+        assert_eq!(
+            warning(&editor),
+            "Usage: annotations <export|import> <file>"
+        );
+        editor.process_command("unnote");
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn test_fold_commands() {
+        let mut editor = setup_test_editor();
+        editor.process_command("region 0x20..0x60 table");
+        editor.cursor = 0x30;
+        let key = |c| KeyEvent::new(Char(c), KeyModifiers::NONE);
+        editor.process_event(key('z'), 4);
+        assert_eq!(editor.cursor, 0x20);
+        let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        editor.process_event(down, 4);
+        assert_eq!(editor.cursor, 0x60);
+        let up = KeyEvent::new(KeyCode::Up, KeyModifiers::NONE);
+        editor.process_event(up, 4);
+        assert_eq!(editor.cursor, 0x20);
+        editor.process_event(up, 4);
+        assert_eq!(editor.cursor, 0x10);
+        editor.cursor = 0xa0;
+        editor.scroll_to_cursor(4);
+        assert_eq!(editor.offset, 0x70);
+        editor.cursor = 0x40;
+        editor.scroll_to_cursor(4);
+        assert_eq!(editor.offset, 0x20);
+
+        editor.selection = Some(0x80);
+        editor.cursor = 0x9f;
+        editor.process_command("fold");
+        assert_eq!(editor.cursor, 0x80);
+        assert_eq!(editor.folds[1].label, "selection");
+        editor.process_event(key('z'), 4);
+        assert_eq!(editor.folds.len(), 1);
+        editor.process_command("unfold");
+        assert_eq!(warning(&editor), "No fold at the cursor");
+        editor.process_command("fold data");
+        assert_eq!(warning(&editor), "Unknown region");
+        editor.process_command("fold table");
+        editor.process_command("unfold all");
+        assert!(editor.folds.is_empty());
+    }
+
+    #[test]
+    fn test_collapse_option() {
+        let mut editor = setup_test_editor();
+        editor.buffer.splice(0x10..0x10, &[0; 0x50]);
+        editor.process_command("set collapse");
+        editor.cursor = 0x10;
+        let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        editor.process_event(down, 4);
+        assert_eq!(editor.cursor, 0x20);
+        editor.process_event(down, 4);
+        assert_eq!(editor.cursor, 0x60);
+        let up = KeyEvent::new(KeyCode::Up, KeyModifiers::NONE);
+        editor.process_event(up, 4);
+        assert_eq!(editor.cursor, 0x20);
+        let event = KeyEvent::new(Char('z'), KeyModifiers::NONE);
+        editor.process_event(event, 4);
+        editor.process_event(down, 4);
+        assert_eq!(editor.cursor, 0x30);
+        assert!(editor.folds.is_empty());
+    }
+
+    #[test]
+    fn test_overview_command() {
+        let mut editor = setup_test_editor();
+        editor.cursor = 0x21;
+        editor.process_command("overview entropy");
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        editor.process_event(key(KeyCode::Down), 2);
+        editor.process_event(key(KeyCode::Right), 2);
+        editor.process_event(key(KeyCode::Enter), 2);
+        assert!(editor.overview.is_none());
+        assert_eq!(editor.cursor, 0x21 + 69 * 3);
+        editor.process_command("overview bytes");
+        assert_eq!(warning(&editor), "Usage: overview [class|entropy]");
+        assert!(editor.overview.is_none());
+    }
+
+    #[test]
+    fn test_stats_command() {
+        let mut editor = setup_test_editor();
+        editor.process_command("stats");
+        assert_eq!(
+            message(&editor),
+            "Length 300, sum 3300 (0xce4), min 0x0a, max 0x0c, mean 11.00, 3 distinct, most common 0x0a (100 times)"
+        );
+        editor.selection = Some(1);
+        editor.cursor = 2;
+        editor.process_command("stats");
+        assert!(message(&editor).starts_with("Length 2, sum 23 (0x17), min 0x0b, max 0x0c"));
+    }
+
+    #[test]
+    fn test_xref_command() {
+        let mut editor = setup_test_editor();
+        editor.symbols = vec![(0x20, "table".into())];
+        editor.buffer.splice(0x30..0x32, b"\x02\x01");
+        editor.cursor = 0x102;
+        editor.process_command("xref u16");
+        let (panel, _) = editor.panel.as_mut().unwrap();
+        assert_eq!(panel.selected_offset(), Some(0x30));
+        editor.process_command("xref u16 be");
+        let (panel, _) = editor.panel.as_mut().unwrap();
+        assert_eq!(panel.selected_offset(), None);
+        editor.process_command("xref u8");
+        assert_eq!(warning(&editor), "Usage: xref [u16|u32|u64] [le|be] [base]");
+    }
+
+    #[test]
+    fn test_scrolloff() {
+        let mut editor = setup_test_editor();
+        editor.process_command("set scrolloff 2");
+        let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        for _ in 0..7 {
+            editor.process_event(down, 10);
+        }
+        assert_eq!((editor.cursor, editor.offset), (0x70, 0));
+        editor.process_event(down, 10);
+        assert_eq!((editor.cursor, editor.offset), (0x80, 0x10));
+        editor.move_to(0x120);
+        editor.scroll_to_cursor(10);
+        assert_eq!(editor.offset, 0x100);
+        editor.process_event(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE), 10);
+        assert_eq!((editor.cursor, editor.offset), (0x110, 0xf0));
+        editor.process_command("set scrolloff x");
+        assert_eq!(warning(&editor), "Invalid scroll margin");
+    }
+
+    #[test]
+    fn test_snap_keys() {
+        let mut editor = setup_test_editor();
+        editor.process_command("set align 0x40");
+        editor.cursor = 0x25;
+        let key = |c| KeyEvent::new(Char(c), KeyModifiers::NONE);
+        editor.process_event(key(']'), 4);
+        assert_eq!((editor.cursor, editor.offset), (0x40, 0x10));
+        editor.process_event(key('['), 4);
+        assert_eq!(editor.cursor, 0);
+        editor.process_event(key('}'), 4);
+        assert_eq!((editor.cursor, editor.offset), (0x40, 0x40));
+        editor.process_event(key('}'), 4);
+        editor.process_event(key('}'), 4);
+        editor.process_event(key('}'), 4);
+        editor.process_event(key('}'), 4);
+        assert_eq!((editor.cursor, editor.offset), (299, 299));
+        editor.process_event(key('{'), 4);
+        assert_eq!((editor.cursor, editor.offset), (0x100, 0x100));
+    }
+
+    #[test]
+    fn test_selection_summary() {
+        let mut editor = setup_test_editor();
+        assert_eq!(editor.selection_summary(), None);
+        editor.selection = Some(0x2f);
+        editor.cursor = 0x10;
+        assert_eq!(
+            editor.selection_summary().unwrap(),
+            "0x10-0x2f (16-47), 32 bytes (0x20)"
+        );
+    }
+
+    #[test]
+    fn test_move_to() {
+        let mut editor = setup_test_editor();
+        editor.move_to(0x4a);
+        assert_eq!((editor.cursor, editor.offset), (0x4a, 0x40));
+        editor.move_to(0x4000);
+        assert_eq!((editor.cursor, editor.offset), (299, 288));
+    }
+
+    #[test]
+    fn test_goto_command() {
+        let mut editor = setup_test_editor();
+        editor.symbols = vec![(0x20, "main".into())];
+        editor.process_command("goto main");
+        assert_eq!(editor.cursor, 0x20);
+        editor.process_command("goto 0x10");
+        assert_eq!(editor.cursor, 0x10);
+        editor.process_command("goto 5000");
+        assert_eq!(editor.cursor, 299);
+        editor.process_command("goto helper");
+        assert_eq!(warning(&editor), "Unknown symbol");
+        editor.process_command("goto 50%");
+        assert_eq!(editor.cursor, 0x90);
+        editor.process_command("goto 100%");
+        assert_eq!(editor.cursor, 0x120);
+        editor.process_command("goto 0%");
+        assert_eq!(editor.cursor, 0);
+        editor.process_command("goto 120%");
+        assert_eq!(warning(&editor), "Percentage must be between 0 and 100");
+    }
+
+    #[test]
+    fn test_goto_expression() {
+        let mut editor = setup_test_editor();
+        editor.symbols = vec![(0x20, "main".into())];
+        editor.process_command("goto main + 2 * 8");
+        assert_eq!(editor.cursor, 0x30);
+        editor.process_command("goto cursor + 0x40");
+        assert_eq!(editor.cursor, 0x70);
+        editor.process_command("mark a");
+        editor.process_command("goto end - 100");
+        assert_eq!(editor.cursor, 200);
+        editor.process_command("goto 'a + 8");
+        assert_eq!(editor.cursor, 0x78);
+        editor.process_command("calc 'a - cursor");
+        assert_eq!(message(&editor), "-0x8 = -8 = -0b1000");
+        editor.process_command("goto 'b");
+        assert_eq!(warning(&editor), "Unknown name 'b");
+        editor.process_command("goto cursor - 0x100");
+        assert_eq!(warning(&editor), "Offset is negative");
+        editor.process_command("mark 1");
+        assert_eq!(warning(&editor), "Usage: mark <a-z>");
+    }
+
+    #[test]
+    fn test_strings_command() {
+        let mut editor = setup_test_editor();
+        editor.process_command("strings x");
+        assert_eq!(warning(&editor), "Usage: strings [minlen]");
+        assert!(editor.panel.is_none());
+
+        editor.buffer.splice(10..10, b"text");
+        editor.process_command("strings");
+        let (panel, _) = editor.panel.as_mut().unwrap();
+        assert_eq!(panel.selected_offset(), Some(10));
+        editor.process_command("strings 5");
+        let (panel, _) = editor.panel.as_mut().unwrap();
+        assert_eq!(panel.selected_offset(), None);
+    }
+
+    #[test]
+    fn test_histogram_command() {
+        let mut editor = setup_test_editor();
+        editor.cursor = 1;
+        editor.selection = Some(3);
+        editor.process_command("histogram");
+        let (panel, _) = editor.panel.as_mut().unwrap();
+        assert_eq!(panel.selected_offset(), Some(3));
+        panel.move_selection(1);
+        assert_eq!(panel.selected_offset(), Some(1));
+        panel.move_selection(1);
+        assert_eq!(panel.selected_offset(), Some(2));
+        panel.move_selection(1);
+        assert_eq!(panel.selected_offset(), Some(2));
+    }
+
+    #[test]
+    fn test_hash_command() {
+        let mut editor = setup_test_editor();
+        editor.process_command("hash");
+        assert_eq!(warning(&editor), "Usage: hash <crc32|md5|sha1|sha256>");
+        editor.process_command("hash sha512");
+        assert_eq!(warning(&editor), "Unknown hash algorithm");
+
+        editor.selection = Some(2);
+        editor.process_command("hash md5");
+        assert_eq!(message(&editor), "94263e4d553bcec128704e354b659526");
+    }
+
+    #[test]
+    fn test_verify_command() {
+        let mut editor = setup_test_editor();
+        editor.selection = Some(2);
+        editor.process_command("verify crc32");
+        assert_eq!(
+            warning(&editor),
+            "Usage: verify <crc32|md5|sha1|sha256> <digest>"
+        );
+        editor.messages.clear();
+
+        editor.process_command("verify md5 94263E4D553BCEC128704E354B659526");
+        assert_eq!(message(&editor), "Checksum OK");
+        assert!(warning(&editor).is_empty());
+        editor.process_command("verify md5 00");
+        assert_eq!(
+            warning(&editor),
+            "CHECKSUM MISMATCH: 94263e4d553bcec128704e354b659526"
+        );
+    }
+
+    #[test]
+    fn test_run_commands() {
+        let mut editor = setup_test_editor();
+        let commands = ["verify crc32 00".to_string(), "q".to_string()];
+        assert!(!editor.run_commands(&commands));
+        assert!(!editor.should_exit);
+
+        let mut editor = setup_test_editor();
+        let commands = ["hash crc32".to_string(), "q".to_string()];
+        assert!(editor.run_commands(&commands));
+        assert!(editor.should_exit);
+    }
+
+    #[test]
+    fn test_compare_command() {
+        let mut editor = setup_test_editor();
+        std::fs::write("test_compare_command.bin", [0xa, 0xb, 0xc, 0xa]).unwrap();
+        editor.process_command("compare test_compare_command.bin --report");
+        assert_eq!(warning(&editor), "Usage: compare <file> [--report <file>]");
+        assert!(editor.panel.is_none());
+
+        editor
+            .process_command("compare test_compare_command.bin --report test_compare_command.txt");
+        assert!(editor.panel.is_some());
+        let report = std::fs::read_to_string("test_compare_command.txt").unwrap();
+        assert!(report.contains("# 1 differing ranges, 296 bytes\n"));
+        assert!(report.contains("0x00000004\t296\t-\t0b0c0a"));
+        std::fs::remove_file("test_compare_command.bin").unwrap();
+        std::fs::remove_file("test_compare_command.txt").unwrap();
+    }
+
+    #[test]
+    fn test_process_paste() {
+        let mut editor = setup_test_editor();
+        editor.process_paste("ignored");
+        assert!(matches!(editor.mode, EditorMode::Edit(None)));
+
+        editor.mode = EditorMode::Command("base64 decode ".into());
+        editor.process_paste("Zm9v\n");
+        assert!(matches!(&editor.mode, EditorMode::Command(c) if c == "base64 decode Zm9v"));
+
+        editor.mode = EditorMode::Command("find-text ".into());
+        editor.process_paste("caf\u{e9}");
+        editor.process_event(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE), 16);
+        assert!(matches!(&editor.mode, EditorMode::Command(c) if c == "find-text caf"));
+    }
+
+    #[test]
+    fn test_cursor_keys() {
+        let mut editor = setup_test_editor();
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+        editor.process_event(key(KeyCode::Down, KeyModifiers::NONE), 10);
+        editor.process_event(key(KeyCode::Right, KeyModifiers::NONE), 10);
+        assert_eq!(editor.cursor, editor.bytes_per_line + 1);
+        editor.process_event(key(KeyCode::Left, KeyModifiers::NONE), 10);
+        editor.process_event(key(KeyCode::Up, KeyModifiers::NONE), 10);
+        assert_eq!(editor.cursor, 0);
+
+        editor.process_event(key(KeyCode::Right, KeyModifiers::CONTROL), 10);
+        assert_eq!(editor.cursor, 10);
+        editor.process_event(key(KeyCode::Down, KeyModifiers::CONTROL), 10);
+        assert_eq!(editor.cursor, 10 + editor.bytes_per_line * 10);
+        editor.process_event(key(KeyCode::Up, KeyModifiers::CONTROL), 10);
+        editor.process_event(key(KeyCode::Left, KeyModifiers::CONTROL), 10);
+        assert_eq!(editor.cursor, 0);
+    }
+}
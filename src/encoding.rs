@@ -0,0 +1,542 @@
+use super::parse::{invalid_data, parse_hex};
+use crossterm::style::Color;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Code page 437 glyphs of the control characters.
+const CP437_LOW: [char; 32] = [
+    ' ', '☺', '☻', '♥', '♦', '♣', '♠', '•', '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼', '►', '◄', '↕',
+    '‼', '¶', '§', '▬', '↨', '↑', '↓', '→', '←', '∟', '↔', '▲', '▼',
+];
+
+/// The upper half of code page 437, as used by DOS.
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', 'É', 'æ', 'Æ',
+    'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', 'á', 'í', 'ó', 'ú', 'ñ', 'Ñ',
+    'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕',
+    '╣', '║', '╗', '╝', '╜', '╛', '┐', '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦',
+    '╠', '═', '╬', '╧', '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐',
+    '▀', 'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', '≡', '±',
+    '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+/// EBCDIC code page 037 from 0x40 on, the bytes before being control characters.
+const EBCDIC: [char; 192] = [
+    ' ', '\u{a0}', 'â', 'ä', 'à', 'á', 'ã', 'å', 'ç', 'ñ', '¢', '.', '<', '(', '+', '|', '&', 'é',
+    'ê', 'ë', 'è', 'í', 'î', 'ï', 'ì', 'ß', '!', '$', '*', ')', ';', '¬', '-', '/', 'Â', 'Ä', 'À',
+    'Á', 'Ã', 'Å', 'Ç', 'Ñ', '¦', ',', '%', '_', '>', '?', 'ø', 'É', 'Ê', 'Ë', 'È', 'Í', 'Î', 'Ï',
+    'Ì', '`', ':', '#', '@', '\'', '=', '"', 'Ø', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', '«',
+    '»', 'ð', 'ý', 'þ', '±', '°', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 'ª', 'º', 'æ', '¸',
+    'Æ', '¤', 'µ', '~', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '¡', '¿', 'Ð', 'Ý', 'Þ', '®', '^',
+    '£', '¥', '·', '©', '§', '¶', '¼', '½', '¾', '[', ']', '¯', '¨', '´', '×', '{', 'A', 'B', 'C',
+    'D', 'E', 'F', 'G', 'H', 'I', '\u{ad}', 'ô', 'ö', 'ò', 'ó', 'õ', '}', 'J', 'K', 'L', 'M', 'N',
+    'O', 'P', 'Q', 'R', '¹', 'û', 'ü', 'ù', 'ú', 'ÿ', '\\', '÷', 'S', 'T', 'U', 'V', 'W', 'X', 'Y',
+    'Z', '²', 'Ô', 'Ö', 'Ò', 'Ó', 'Õ', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '³', 'Û',
+    'Ü', 'Ù', 'Ú', '\u{9f}',
+];
+
+/// How a byte is shown in the text pane.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Glyph {
+    Char(char),
+    /// A byte belonging to the character shown in a previous cell.
+    Continuation,
+    /// A continuation byte whose cell is taken up by the double width character before it.
+    Covered,
+    Unprintable,
+}
+
+/// How bytes without a printable character are shown in the text pane.
+#[derive(Default)]
+pub enum UnprintableStyle {
+    #[default]
+    Dot,
+    MiddleDot,
+    /// The glyphs of code page 437, e.g. `☺` for 0x01.
+    Cp437,
+    /// Colored symbols for null bytes, whitespace, other control characters and other bytes.
+    Category,
+}
+
+impl FromStr for UnprintableStyle {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "dot" => Ok(UnprintableStyle::Dot),
+            "middot" => Ok(UnprintableStyle::MiddleDot),
+            "cp437" => Ok(UnprintableStyle::Cp437),
+            "category" => Ok(UnprintableStyle::Category),
+            _ => Err(()),
+        }
+    }
+}
+
+impl UnprintableStyle {
+    /// Returns the symbol and color for `byte`.
+    pub fn symbol(&self, byte: u8) -> (char, Color) {
+        match self {
+            UnprintableStyle::Dot => ('.', Color::Black),
+            UnprintableStyle::MiddleDot => ('·', Color::Black),
+            UnprintableStyle::Cp437 => match byte {
+                0x00..0x20 => (CP437_LOW[byte as usize], Color::DarkCyan),
+                0x7f => ('⌂', Color::DarkCyan),
+                0x80.. => (CP437_HIGH[byte as usize - 0x80], Color::DarkCyan),
+                _ => (byte as char, Color::DarkCyan),
+            },
+            UnprintableStyle::Category => match byte {
+                0x00 => ('0', Color::DarkGrey),
+                b'\t' | b'\n' | b'\r' | 0x0b | 0x0c | b' ' => ('_', Color::DarkGreen),
+                0x01..0x20 | 0x7f => ('•', Color::DarkGreen),
+                _ => ('×', Color::DarkYellow),
+            },
+        }
+    }
+}
+
+/// The encoding the text pane decodes bytes with, also used to encode text for searching and
+/// writing.
+#[derive(Default)]
+pub enum TextEncoding {
+    #[default]
+    Ascii,
+    Latin1,
+    /// Code page 437 of DOS.
+    Cp437,
+    /// EBCDIC code page 037, as used by mainframes.
+    Ebcdic,
+    Utf8,
+    Utf16 {
+        big_endian: bool,
+    },
+    Table(CharTable),
+}
+
+impl FromStr for TextEncoding {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "ascii" => Ok(TextEncoding::Ascii),
+            "latin1" => Ok(TextEncoding::Latin1),
+            "cp437" => Ok(TextEncoding::Cp437),
+            "ebcdic" => Ok(TextEncoding::Ebcdic),
+            "utf8" | "utf-8" => Ok(TextEncoding::Utf8),
+            "utf16le" | "utf16" => Ok(TextEncoding::Utf16 { big_endian: false }),
+            "utf16be" => Ok(TextEncoding::Utf16 { big_endian: true }),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TextEncoding {
+    /// Decodes the bytes from `start` to `end` into one glyph per byte. Sequences may extend
+    /// beyond `end` to decode the last character.
+    pub fn render(&self, data: &[u8], start: usize, end: usize) -> Vec<Glyph> {
+        match self {
+            TextEncoding::Table(table) => table.render(data, start, end),
+            TextEncoding::Utf8 => render_utf8(data, start, end),
+            TextEncoding::Utf16 { big_endian } => render_utf16(data, start, end, *big_endian),
+            _ => data[start..end]
+                .iter()
+                .map(|&byte| match self.decode_byte(byte) {
+                    Some(c) if !c.is_control() => Glyph::Char(c),
+                    _ => Glyph::Unprintable,
+                })
+                .collect(),
+        }
+    }
+
+    /// Encodes `text`, returning `None` if a character cannot be encoded.
+    pub fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        match self {
+            TextEncoding::Table(table) => table.encode(text),
+            TextEncoding::Utf8 => Some(text.as_bytes().to_vec()),
+            TextEncoding::Utf16 { big_endian } => Some(
+                text.encode_utf16()
+                    .flat_map(|unit| match big_endian {
+                        true => unit.to_be_bytes(),
+                        false => unit.to_le_bytes(),
+                    })
+                    .collect(),
+            ),
+            _ => text
+                .chars()
+                .map(|c| (0..=255).find(|&byte| self.decode_byte(byte) == Some(c)))
+                .collect(),
+        }
+    }
+
+    /// Decodes a byte of a single byte encoding.
+    fn decode_byte(&self, byte: u8) -> Option<char> {
+        match self {
+            TextEncoding::Ascii => byte.is_ascii().then_some(byte as char),
+            TextEncoding::Latin1 => Some(byte as char),
+            TextEncoding::Cp437 if byte >= 0x80 => Some(CP437_HIGH[byte as usize - 0x80]),
+            TextEncoding::Cp437 => Some(byte as char),
+            TextEncoding::Ebcdic => EBCDIC.get(byte.checked_sub(0x40)? as usize).copied(),
+            TextEncoding::Utf8 | TextEncoding::Utf16 { .. } | TextEncoding::Table(_) => None,
+        }
+    }
+}
+
+/// Decodes UTF-8 or UTF-16 from `first`, the start of the sequence covering `start`, showing
+/// each character in the cell of its first byte and invalid code units as replacement
+/// characters.
+fn render_sequences(
+    data: &[u8],
+    first: usize,
+    start: usize,
+    end: usize,
+    unit: usize,
+    decode: impl Fn(&[u8], usize) -> Option<(char, usize)>,
+) -> Vec<Glyph> {
+    let mut glyphs = vec![];
+    let mut position = first;
+    while position < end {
+        let (glyph, length) = match decode(data, position) {
+            Some((c, length)) if c.is_control() => (Glyph::Unprintable, length),
+            Some((c, length)) if !is_wide(c) => (Glyph::Char(c), length),
+            // Double width characters are at least two bytes long, taking up the next cell.
+            Some((c, length)) if position + 1 < end => {
+                glyphs.push(Glyph::Char(c));
+                (Glyph::Covered, length)
+            }
+            Some((_, length)) => (Glyph::Unprintable, length),
+            None => (Glyph::Char(char::REPLACEMENT_CHARACTER), unit),
+        };
+        glyphs.push(glyph);
+        position += length;
+        glyphs.resize(glyphs.len().max(position - first), Glyph::Continuation);
+    }
+    glyphs.drain(..start - first);
+    if glyphs.first() == Some(&Glyph::Covered) {
+        glyphs[0] = Glyph::Continuation;
+    }
+    glyphs.truncate(end - start);
+    glyphs
+}
+
+fn render_utf8(data: &[u8], start: usize, end: usize) -> Vec<Glyph> {
+    let first = (start.saturating_sub(3)..start)
+        .rev()
+        .find(|&first| data[first] & 0xc0 != 0x80)
+        .filter(|&first| decode_utf8(data, first).is_some_and(|(_, length)| first + length > start))
+        .unwrap_or(start);
+    render_sequences(data, first, start, end, 1, decode_utf8)
+}
+
+/// Decodes UTF-16 with code units at even offsets.
+fn render_utf16(data: &[u8], start: usize, end: usize, big_endian: bool) -> Vec<Glyph> {
+    let decode = |data: &[u8], position| decode_utf16(data, position, big_endian);
+    let mut first = start - start % 2;
+    if first >= 2
+        && decode(data, first).is_none()
+        && decode(data, first - 2).is_some_and(|(_, length)| length == 4)
+    {
+        first -= 2;
+    }
+    render_sequences(data, first, start, end, 2, decode)
+}
+
+/// Decodes the UTF-16 code unit, or surrogate pair, at `position`.
+fn decode_utf16(data: &[u8], position: usize, big_endian: bool) -> Option<(char, usize)> {
+    let unit = |position: usize| {
+        let bytes = data.get(position..position + 2)?.try_into().unwrap();
+        Some(match big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    };
+    let high = unit(position)?;
+    if let Some(c) = char::from_u32(high as u32) {
+        return Some((c, 2));
+    }
+    let units = [high, unit(position + 2)?];
+    Some((char::decode_utf16(units).next()?.ok()?, 4))
+}
+
+/// Decodes the UTF-8 sequence at `position`, returning the character and its length.
+fn decode_utf8(data: &[u8], position: usize) -> Option<(char, usize)> {
+    let length = match data.get(position)? {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return None,
+    };
+    let text = std::str::from_utf8(data.get(position..position + length)?).ok()?;
+    Some((text.chars().next()?, length))
+}
+
+/// Approximates whether a character takes up two columns of the terminal, covering East Asian
+/// scripts and emoji.
+fn is_wide(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{115f}'
+        | '\u{2e80}'..='\u{303e}'
+        | '\u{3041}'..='\u{a4cf}'
+        | '\u{ac00}'..='\u{d7a3}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{fe30}'..='\u{fe4f}'
+        | '\u{ff00}'..='\u{ff60}'
+        | '\u{ffe0}'..='\u{ffe6}'
+        | '\u{1f300}'..='\u{1f64f}'
+        | '\u{1f900}'..='\u{1f9ff}'
+        | '\u{20000}'..='\u{3fffd}')
+}
+
+/// A character table (`.tbl` file) mapping bytes and byte sequences to text, as used for the
+/// custom text encodings of games.
+///
+/// Tables contain one entry per line as `<hex bytes>=<text>`, e.g. `0A=A` or `8081=the`. Lines
+/// of the form `*<hex bytes>` define a line break and `/<hex bytes>[=<text>]` the end of a
+/// string. Empty lines and lines starting with `#` are ignored.
+pub struct CharTable {
+    entries: HashMap<Vec<u8>, String>,
+    /// Length of the longest byte sequence.
+    longest: usize,
+}
+
+impl CharTable {
+    pub fn parse(text: &str) -> Result<Self, std::io::Error> {
+        let mut table = CharTable {
+            entries: HashMap::new(),
+            longest: 0,
+        };
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (bytes, text) = if let Some(bytes) = line.strip_prefix('*') {
+                (bytes, "\n")
+            } else if let Some(end) = line.strip_prefix('/') {
+                end.split_once('=').unwrap_or((end, ""))
+            } else {
+                line.split_once('=')
+                    .ok_or_else(|| invalid_data(number, "expected <hex bytes>=<text>"))?
+            };
+            let bytes = parse_hex(bytes).ok_or_else(|| invalid_data(number, "invalid bytes"))?;
+            table.longest = table.longest.max(bytes.len());
+            table.entries.insert(bytes, text.into());
+        }
+        Ok(table)
+    }
+
+    /// Returns the length and the text of the longest sequence at the start of `data`.
+    fn token(&self, data: &[u8]) -> Option<(usize, &str)> {
+        (1..=self.longest.min(data.len()))
+            .rev()
+            .find_map(|length| Some((length, self.entries.get(&data[..length])?.as_str())))
+    }
+
+    fn render(&self, data: &[u8], start: usize, end: usize) -> Vec<Glyph> {
+        let mut glyphs = vec![];
+        let mut position = start;
+        while position < end {
+            let Some((length, text)) = self.token(&data[position..]) else {
+                glyphs.push(Glyph::Unprintable);
+                position += 1;
+                continue;
+            };
+            glyphs.push(match text.chars().next() {
+                Some(c) if !c.is_control() => Glyph::Char(c),
+                _ => Glyph::Unprintable,
+            });
+            glyphs.extend(vec![Glyph::Continuation; length - 1]);
+            position += length;
+        }
+        glyphs.truncate(end - start);
+        glyphs
+    }
+
+    /// Encodes `text`, preferring the entries with the longest text.
+    fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        let mut entries: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|(_, text)| !text.is_empty())
+            .collect();
+        // Prefer the shortest byte sequence, and then the lowest, among equal texts.
+        entries.sort_by(|(a, x), (b, y)| (y.len(), a.len(), a).cmp(&(x.len(), b.len(), b)));
+        let mut bytes = vec![];
+        let mut rest = text;
+        while !rest.is_empty() {
+            let (encoded, text) = entries.iter().find(|(_, text)| rest.starts_with(*text))?;
+            bytes.extend(*encoded);
+            rest = &rest[text.len()..];
+        }
+        Some(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &str = "\
+# letters
+0A=A
+0B=B
+8081=the
+20= 
+*FE
+/FF=<end>
+";
+
+    #[test]
+    fn test_parse() {
+        let table = CharTable::parse(TABLE).unwrap();
+        assert_eq!(table.longest, 2);
+        assert_eq!(table.entries[&vec![0xfe]], "\n");
+        assert_eq!(table.entries[&vec![0xff]], "<end>");
+        let error = CharTable::parse("0A=A\n0=B\n").err().unwrap();
+        assert_eq!(error.to_string(), "line 2: invalid bytes");
+        assert!(CharTable::parse("0A").is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let encoding = TextEncoding::Table(CharTable::parse(TABLE).unwrap());
+        let data = b"\x0a\x80\x81\xfe\x00\x0b\x80";
+        assert_eq!(
+            encoding.render(data, 0, 6),
+            vec![
+                Glyph::Char('A'),
+                Glyph::Char('t'),
+                Glyph::Continuation,
+                Glyph::Unprintable,
+                Glyph::Unprintable,
+                Glyph::Char('B')
+            ]
+        );
+        assert_eq!(encoding.render(data, 2, 3), vec![Glyph::Unprintable]);
+        assert_eq!(
+            TextEncoding::Ascii.render(b"a\x00", 0, 2),
+            vec![Glyph::Char('a'), Glyph::Unprintable]
+        );
+    }
+
+    #[test]
+    fn test_render_utf8() {
+        let encoding = TextEncoding::Utf8;
+        let data = "aé€\u{1f600}".as_bytes();
+        assert_eq!(
+            encoding.render(data, 0, data.len()),
+            vec![
+                Glyph::Char('a'),
+                Glyph::Char('é'),
+                Glyph::Continuation,
+                Glyph::Char('€'),
+                Glyph::Continuation,
+                Glyph::Continuation,
+                Glyph::Char('\u{1f600}'),
+                Glyph::Covered,
+                Glyph::Continuation,
+                Glyph::Continuation
+            ]
+        );
+        // Starting within a sequence, and ending within a double width character.
+        assert_eq!(
+            encoding.render(data, 4, 7),
+            vec![Glyph::Continuation, Glyph::Continuation, Glyph::Unprintable]
+        );
+        assert_eq!(
+            encoding.render(b"\xff\xc3\x00", 0, 3),
+            vec![
+                Glyph::Char('\u{fffd}'),
+                Glyph::Char('\u{fffd}'),
+                Glyph::Unprintable
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_utf16() {
+        let encoding: TextEncoding = "utf16be".parse().unwrap();
+        let data = b"\x00A\xd8\x3d\xde\x00\x65\xe5\xd8\x00";
+        assert_eq!(
+            encoding.render(data, 0, data.len()),
+            vec![
+                Glyph::Char('A'),
+                Glyph::Continuation,
+                Glyph::Char('\u{1f600}'),
+                Glyph::Covered,
+                Glyph::Continuation,
+                Glyph::Continuation,
+                Glyph::Char('日'),
+                Glyph::Covered,
+                Glyph::Char('\u{fffd}'),
+                Glyph::Continuation
+            ]
+        );
+        assert_eq!(
+            encoding.render(data, 5, 7),
+            vec![Glyph::Continuation, Glyph::Unprintable]
+        );
+        assert_eq!(
+            "utf16"
+                .parse::<TextEncoding>()
+                .unwrap()
+                .render(b"A\x00", 1, 2),
+            vec![Glyph::Continuation]
+        );
+    }
+
+    #[test]
+    fn test_unprintable_symbol() {
+        assert_eq!(UnprintableStyle::Dot.symbol(0x01), ('.', Color::Black));
+        let style: UnprintableStyle = "cp437".parse().unwrap();
+        assert_eq!(style.symbol(0x01).0, '☺');
+        assert_eq!(style.symbol(0x7f).0, '⌂');
+        assert_eq!(style.symbol(0xdb).0, '█');
+        let style: UnprintableStyle = "category".parse().unwrap();
+        let symbols: String = [0x00, 0x0a, 0x1b, 0xff]
+            .map(|b| style.symbol(b).0)
+            .iter()
+            .collect();
+        assert_eq!(symbols, "0_•×");
+        assert!("box".parse::<UnprintableStyle>().is_err());
+    }
+
+    #[test]
+    fn test_encode() {
+        let encoding = TextEncoding::Table(CharTable::parse(TABLE).unwrap());
+        assert_eq!(
+            encoding.encode("the BA"),
+            Some(vec![0x80, 0x81, 0x20, 0x0b, 0x0a])
+        );
+        assert_eq!(encoding.encode("C"), None);
+        assert_eq!(TextEncoding::Ascii.encode("ab"), Some(b"ab".to_vec()));
+        assert_eq!(TextEncoding::Ascii.encode("ä"), None);
+        assert_eq!(TextEncoding::Latin1.encode("ä"), Some(vec![0xe4]));
+        let encoding = TextEncoding::Utf16 { big_endian: false };
+        assert_eq!(encoding.encode("Aä"), Some(vec![0x41, 0, 0xe4, 0]));
+        assert_eq!(TextEncoding::Cp437.encode("ä░"), Some(vec![0x84, 0xb0]));
+        assert_eq!(
+            TextEncoding::Ebcdic.encode("Hi 1"),
+            Some(vec![0xc8, 0x89, 0x40, 0xf1])
+        );
+    }
+
+    #[test]
+    fn test_render_code_pages() {
+        let data = b"\xc8\x89\x25\x84\x9d";
+        let chars = |encoding: &str| -> Vec<_> {
+            let encoding: TextEncoding = encoding.parse().unwrap();
+            encoding
+                .render(data, 0, data.len())
+                .into_iter()
+                .map(|glyph| match glyph {
+                    Glyph::Char(c) => c,
+                    _ => '.',
+                })
+                .collect()
+        };
+        assert_eq!(chars("ascii"), vec!['.', '.', '%', '.', '.']);
+        assert_eq!(chars("latin1"), vec!['È', '.', '%', '.', '.']);
+        assert_eq!(chars("cp437"), vec!['╚', 'ë', '%', 'ä', '¥']);
+        assert_eq!(chars("ebcdic"), vec!['H', 'i', '.', 'd', '¸']);
+        assert!("utf-7".parse::<TextEncoding>().is_err());
+    }
+}
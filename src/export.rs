@@ -0,0 +1,113 @@
+use super::base64;
+use std::str::FromStr;
+
+const BYTES_PER_LINE: usize = 12;
+
+pub enum ExportFormat {
+    C,
+    Rust,
+    Python,
+    Base64,
+}
+
+impl FromStr for ExportFormat {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "c" => Ok(ExportFormat::C),
+            "rust" | "rs" => Ok(ExportFormat::Rust),
+            "python" | "py" => Ok(ExportFormat::Python),
+            "base64" => Ok(ExportFormat::Base64),
+            _ => Err(()),
+        }
+    }
+}
+
+impl ExportFormat {
+    pub fn export(&self, data: &[u8]) -> String {
+        match self {
+            ExportFormat::C => format!(
+                "unsigned char data[] = {{\n{}}};\nunsigned int data_len = {};\n",
+                format_lines(data),
+                data.len()
+            ),
+            ExportFormat::Rust => format!("pub const DATA: &[u8] = &[\n{}];\n", format_lines(data)),
+            ExportFormat::Python if data.is_empty() => "data = b\"\"\n".into(),
+            ExportFormat::Python => format!(
+                "data = (\n{})\n",
+                data.chunks(BYTES_PER_LINE)
+                    .map(|chunk| {
+                        let bytes: String = chunk
+                            .iter()
+                            .map(|byte| format!("\\x{:0>2x}", byte))
+                            .collect();
+                        format!("    b\"{}\"\n", bytes)
+                    })
+                    .collect::<String>()
+            ),
+            ExportFormat::Base64 => base64::encode(data) + "\n",
+        }
+    }
+}
+
+fn format_lines(data: &[u8]) -> String {
+    data.chunks(BYTES_PER_LINE)
+        .map(|chunk| {
+            let bytes: Vec<String> = chunk
+                .iter()
+                .map(|byte| format!("0x{:0>2x},", byte))
+                .collect();
+            format!("    {}\n", bytes.join(" "))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_format() {
+        assert!(matches!("c".parse(), Ok(ExportFormat::C)));
+        assert!(matches!("rust".parse(), Ok(ExportFormat::Rust)));
+        assert!(matches!("rs".parse(), Ok(ExportFormat::Rust)));
+        assert!(matches!("python".parse(), Ok(ExportFormat::Python)));
+        assert!(matches!("py".parse(), Ok(ExportFormat::Python)));
+        assert!(matches!("base64".parse(), Ok(ExportFormat::Base64)));
+        assert!("java".parse::<ExportFormat>().is_err());
+    }
+
+    #[test]
+    fn test_export_c() {
+        assert_eq!(
+            ExportFormat::C.export(&[0x01, 0xab]),
+            "unsigned char data[] = {\n    0x01, 0xab,\n};\nunsigned int data_len = 2;\n"
+        );
+    }
+
+    #[test]
+    fn test_export_rust() {
+        let data: Vec<u8> = (0..13).collect();
+        assert_eq!(
+            ExportFormat::Rust.export(&data),
+            "pub const DATA: &[u8] = &[\n    \
+             0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b,\n    \
+             0x0c,\n];\n"
+        );
+    }
+
+    #[test]
+    fn test_export_python() {
+        assert_eq!(
+            ExportFormat::Python.export(&[0x00, 0x41, 0xff]),
+            "data = (\n    b\"\\x00\\x41\\xff\"\n)\n"
+        );
+        assert_eq!(ExportFormat::Python.export(&[]), "data = b\"\"\n");
+    }
+
+    #[test]
+    fn test_export_base64() {
+        assert_eq!(ExportFormat::Base64.export(b"foo"), "Zm9v\n");
+    }
+}
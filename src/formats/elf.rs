@@ -0,0 +1,333 @@
+use super::{Structure, read_string};
+
+/// Upper bound for the number of program and section headers listed.
+const MAX_HEADERS: u64 = 4096;
+const SHT_SYMTAB: u64 = 2;
+const SHT_NOBITS: u64 = 8;
+const SHT_DYNSYM: u64 = 11;
+const ET_REL: u64 = 1;
+/// Upper bound for the number of symbols read from each symbol table.
+const MAX_SYMBOLS: u64 = 1 << 20;
+
+/// A section as `(name, type, address, file offset, size, link)`.
+type Section = (u64, u64, u64, u64, u64, u64);
+
+pub fn parse(data: &[u8]) -> Option<Structure> {
+    let wide = match data.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let big_endian = *data.get(5)? == 2;
+    let address = if wide { "u64" } else { "u32" };
+    let mut elf = Structure::new("ELF");
+    elf.entry("ELF header".into(), Some(0));
+    let ident = [
+        ("magic", "bytes[4]"),
+        ("class", "u8"),
+        ("data", "u8"),
+        ("version", "u8"),
+        ("osabi", "u8"),
+        ("abiversion", "u8"),
+        ("pad", "bytes[7]"),
+    ];
+    let end = elf.add_fields("e_ident", 0, false, &ident);
+    let header = [
+        ("e_type", "u16"),
+        ("e_machine", "u16"),
+        ("e_version", "u32"),
+        ("e_entry", address),
+        ("e_phoff", address),
+        ("e_shoff", address),
+        ("e_flags", "u32"),
+        ("e_ehsize", "u16"),
+        ("e_phentsize", "u16"),
+        ("e_phnum", "u16"),
+        ("e_shentsize", "u16"),
+        ("e_shnum", "u16"),
+        ("e_shstrndx", "u16"),
+    ];
+    elf.add_fields("", end, big_endian, &header);
+
+    let program_header: &[_] = if wide {
+        &[
+            ("p_type", "u32"),
+            ("p_flags", "u32"),
+            ("p_offset", "u64"),
+            ("p_vaddr", "u64"),
+            ("p_paddr", "u64"),
+            ("p_filesz", "u64"),
+            ("p_memsz", "u64"),
+            ("p_align", "u64"),
+        ]
+    } else {
+        &[
+            ("p_type", "u32"),
+            ("p_offset", "u32"),
+            ("p_vaddr", "u32"),
+            ("p_paddr", "u32"),
+            ("p_filesz", "u32"),
+            ("p_memsz", "u32"),
+            ("p_flags", "u32"),
+            ("p_align", "u32"),
+        ]
+    };
+    let offset = elf.value(data, "e_phoff")?;
+    let size = elf.value(data, "e_phentsize")?;
+    for index in 0..elf.value(data, "e_phnum")?.min(MAX_HEADERS) {
+        let start = offset + index * size;
+        if start + size > data.len() as u64 {
+            break;
+        }
+        let prefix = format!("ph[{}]", index);
+        elf.add_fields(&prefix, start as usize, big_endian, program_header);
+        let value = |name: &str| elf.value(data, &format!("{}.{}", prefix, name));
+        let label = format!(
+            "Program header {:<3} {:<14} offset 0x{:<8x} size 0x{:x}",
+            index,
+            segment_type(value("p_type")?),
+            value("p_offset")?,
+            value("p_filesz")?
+        );
+        elf.entry(label, Some(start));
+    }
+
+    let section_header: &[_] = &[
+        ("sh_name", "u32"),
+        ("sh_type", "u32"),
+        ("sh_flags", address),
+        ("sh_addr", address),
+        ("sh_offset", address),
+        ("sh_size", address),
+        ("sh_link", "u32"),
+        ("sh_info", "u32"),
+        ("sh_addralign", address),
+        ("sh_entsize", address),
+    ];
+    let offset = elf.value(data, "e_shoff")?;
+    let size = elf.value(data, "e_shentsize")?;
+    let count = elf.value(data, "e_shnum")?.min(MAX_HEADERS);
+    let names = elf.value(data, "e_shstrndx")?;
+    let mut sections = vec![];
+    for index in 0..count {
+        let start = offset + index * size;
+        if start + size > data.len() as u64 {
+            break;
+        }
+        let prefix = format!("sh[{}]", index);
+        elf.add_fields(&prefix, start as usize, big_endian, section_header);
+        let value = |name: &str| elf.value(data, &format!("{}.{}", prefix, name));
+        sections.push((
+            value("sh_name")?,
+            value("sh_type")?,
+            value("sh_addr")?,
+            value("sh_offset")?,
+            value("sh_size")?,
+            value("sh_link")?,
+        ));
+    }
+    let relocatable = elf.value(data, "e_type")? == ET_REL;
+    elf.symbols = parse_symbols(data, &sections, wide, big_endian, relocatable);
+    let names = sections
+        .get(names as usize)
+        .map(|(_, _, _, offset, _, _)| *offset);
+    for (index, (name, kind, _, offset, size, _)) in sections.into_iter().enumerate() {
+        let name = names
+            .and_then(|names| read_string(data, (names + name) as usize))
+            .unwrap_or_default();
+        let label = format!(
+            "Section {:<3} {:<20} {:<14} offset 0x{:<8x} size 0x{:x}",
+            index,
+            name,
+            section_type(kind),
+            offset,
+            size
+        );
+        let target = (kind != SHT_NOBITS).then_some(offset);
+        elf.entry(label, target);
+    }
+    Some(elf)
+}
+
+/// Reads the named functions and objects from the symbol tables, mapping their values to file
+/// offsets through the sections defining them.
+fn parse_symbols(
+    data: &[u8],
+    sections: &[Section],
+    wide: bool,
+    big_endian: bool,
+    relocatable: bool,
+) -> Vec<(usize, String)> {
+    let read = |offset: u64, size: u64| {
+        let bytes = data.get(offset as usize..(offset + size) as usize)?;
+        let fold = |value, b: &u8| value << 8 | *b as u64;
+        Some(match big_endian {
+            true => bytes.iter().fold(0, fold),
+            false => bytes.iter().rev().fold(0, fold),
+        })
+    };
+    let mut symbols = vec![];
+    for (_, kind, _, offset, size, link) in sections {
+        if *kind != SHT_SYMTAB && *kind != SHT_DYNSYM {
+            continue;
+        }
+        let Some((_, _, _, strings, _, _)) = sections.get(*link as usize) else {
+            continue;
+        };
+        let entry_size = if wide { 24 } else { 16 };
+        for symbol in (*offset..offset + size)
+            .step_by(entry_size)
+            .take(MAX_SYMBOLS as usize)
+        {
+            let (info, index, value) = match wide {
+                true => (
+                    read(symbol + 4, 1),
+                    read(symbol + 6, 2),
+                    read(symbol + 8, 8),
+                ),
+                false => (
+                    read(symbol + 12, 1),
+                    read(symbol + 14, 2),
+                    read(symbol + 4, 4),
+                ),
+            };
+            let (Some(name), Some(info), Some(index), Some(value)) =
+                (read(symbol, 4), info, index, value)
+            else {
+                break;
+            };
+            // Only functions and objects defined in a section are of interest.
+            let Some((_, _, address, section_offset, _, _)) = sections.get(index as usize) else {
+                continue;
+            };
+            if !matches!(info & 0xf, 1 | 2) || index == 0 {
+                continue;
+            }
+            let offset = match relocatable {
+                true => section_offset + value,
+                false => section_offset + value.wrapping_sub(*address),
+            };
+            if let Some(name) = read_string(data, (strings + name) as usize)
+                && !name.is_empty()
+                && offset < data.len() as u64
+            {
+                symbols.push((offset as usize, name));
+            }
+        }
+    }
+    symbols
+}
+
+fn segment_type(kind: u64) -> String {
+    let name = match kind {
+        0 => "NULL",
+        1 => "LOAD",
+        2 => "DYNAMIC",
+        3 => "INTERP",
+        4 => "NOTE",
+        5 => "SHLIB",
+        6 => "PHDR",
+        7 => "TLS",
+        0x6474_e550 => "GNU_EH_FRAME",
+        0x6474_e551 => "GNU_STACK",
+        0x6474_e552 => "GNU_RELRO",
+        0x6474_e553 => "GNU_PROPERTY",
+        _ => return format!("0x{:x}", kind),
+    };
+    name.into()
+}
+
+fn section_type(kind: u64) -> String {
+    let name = match kind {
+        0 => "NULL",
+        1 => "PROGBITS",
+        2 => "SYMTAB",
+        3 => "STRTAB",
+        4 => "RELA",
+        5 => "HASH",
+        6 => "DYNAMIC",
+        7 => "NOTE",
+        SHT_NOBITS => "NOBITS",
+        9 => "REL",
+        11 => "DYNSYM",
+        14 => "INIT_ARRAY",
+        15 => "FINI_ARRAY",
+        0x6fff_fff6 => "GNU_HASH",
+        _ => return format!("0x{:x}", kind),
+    };
+    name.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a 64-bit little-endian ELF file with one segment and a section name table.
+    fn build_elf() -> Vec<u8> {
+        let mut elf = b"\x7fELF\x02\x01\x01".to_vec();
+        elf.resize(0x40, 0);
+        elf[0x10] = 2;
+        elf[0x20] = 0x40; // e_phoff
+        elf[0x28] = 0x78; // e_shoff
+        elf[0x36] = 56; // e_phentsize
+        elf[0x38] = 1; // e_phnum
+        elf[0x3a] = 64; // e_shentsize
+        elf[0x3c] = 2; // e_shnum
+        elf[0x3e] = 1; // e_shstrndx
+        let mut segment = vec![0; 56];
+        segment[0] = 1;
+        segment[0x20] = 0x10;
+        elf.extend(segment);
+        elf.extend(vec![0; 64]);
+        let mut names = vec![0; 64];
+        names[0] = 1;
+        names[4] = 3;
+        names[0x18] = 0xf8;
+        names[0x20] = 11;
+        elf.extend(names);
+        elf.extend(b"\0.shstrtab\0");
+        elf
+    }
+
+    #[test]
+    fn test_parse() {
+        let data = build_elf();
+        let elf = parse(&data).unwrap();
+        let offsets: Vec<_> = elf.entries.iter().map(|(_, offset)| *offset).collect();
+        assert_eq!(offsets, vec![Some(0), Some(0x40), Some(0), Some(0xf8)]);
+        assert!(elf.entries[1].0.contains("LOAD"));
+        assert!(elf.entries[3].0.contains(".shstrtab"));
+        assert!(elf.entries[3].0.contains("STRTAB"));
+        assert_eq!(elf.value(&data, "e_shnum"), Some(2));
+        assert_eq!(elf.value(&data, "ph[0].p_filesz"), Some(0x10));
+        assert_eq!(elf.value(&data, "sh[1].sh_size"), Some(11));
+    }
+
+    #[test]
+    fn test_parse_symbols() {
+        let mut data = vec![0; 0x50];
+        data[0x18] = 1; // st_name
+        data[0x1c] = 0x12; // st_info
+        data[0x1e] = 1; // st_shndx
+        data[0x20..0x28].copy_from_slice(&0x1004u64.to_le_bytes());
+        data[0x30..0x36].copy_from_slice(b"\0main\0");
+        let sections = [
+            (0, 0, 0, 0, 0, 0),
+            (0, 1, 0x1000, 0x40, 0x10, 0),
+            (0, SHT_SYMTAB, 0, 0, 0x30, 3),
+            (0, 3, 0, 0x30, 6, 0),
+        ];
+        let symbols = parse_symbols(&data, &sections, true, false, false);
+        assert_eq!(symbols, vec![(0x44, "main".into())]);
+        let symbols = parse_symbols(&data, &sections, true, false, true);
+        assert!(symbols.is_empty());
+    }
+
+    #[test]
+    fn test_parse_truncated() {
+        let data = build_elf();
+        assert!(parse(&data[..0x20]).is_none());
+        let elf = parse(&data[..0x80]).unwrap();
+        assert_eq!(elf.entries.len(), 2);
+    }
+}
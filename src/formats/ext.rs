@@ -0,0 +1,158 @@
+use super::Structure;
+
+/// Offset of the superblock from the start of the file system.
+const SUPERBLOCK: usize = 1024;
+const MAGIC: u16 = 0xef53;
+const COMPAT_HAS_JOURNAL: u64 = 0x4;
+const INCOMPAT_EXT4: u64 = 0x40 | 0x80 | 0x200;
+const RO_COMPAT_METADATA_CSUM: u64 = 0x400;
+/// Largest `log_block_size`, for blocks of 64 KiB.
+const MAX_LOG_BLOCK_SIZE: u64 = 6;
+
+/// Whether `data` holds an ext2, ext3 or ext4 superblock.
+pub fn detect(data: &[u8]) -> bool {
+    data.get(SUPERBLOCK + 56..SUPERBLOCK + 58) == Some(&MAGIC.to_le_bytes())
+}
+
+pub fn parse(data: &[u8]) -> Option<Structure> {
+    let mut ext = Structure::new("ext2");
+    ext.add_fields(
+        "superblock",
+        SUPERBLOCK,
+        false,
+        &[
+            ("inodes_count", "u32"),
+            ("blocks_count", "u32"),
+            ("reserved_blocks_count", "u32"),
+            ("free_blocks_count", "u32"),
+            ("free_inodes_count", "u32"),
+            ("first_data_block", "u32"),
+            ("log_block_size", "u32"),
+            ("log_cluster_size", "u32"),
+            ("blocks_per_group", "u32"),
+            ("clusters_per_group", "u32"),
+            ("inodes_per_group", "u32"),
+            ("mount_time", "u32"),
+            ("write_time", "u32"),
+            ("mount_count", "u16"),
+            ("max_mount_count", "i16"),
+            ("magic", "u16"),
+            ("state", "u16"),
+            ("errors", "u16"),
+            ("minor_revision", "u16"),
+            ("last_check", "u32"),
+            ("check_interval", "u32"),
+            ("creator_os", "u32"),
+            ("revision", "u32"),
+            ("default_reserved_uid", "u16"),
+            ("default_reserved_gid", "u16"),
+            ("first_inode", "u32"),
+            ("inode_size", "u16"),
+            ("block_group", "u16"),
+            ("feature_compat", "u32"),
+            ("feature_incompat", "u32"),
+            ("feature_ro_compat", "u32"),
+            ("uuid", "bytes[16]"),
+            ("volume_name", "char[16]"),
+            ("last_mounted", "char[64]"),
+        ],
+    );
+    ext.add_fields(
+        "superblock",
+        SUPERBLOCK + 0x3fc,
+        false,
+        &[("checksum", "u32")],
+    );
+    let field = |ext: &Structure, name: &str| ext.value(data, &format!("superblock.{}", name));
+    let incompat = field(&ext, "feature_incompat")?;
+    if incompat & INCOMPAT_EXT4 != 0 {
+        ext.format = "ext4";
+    } else if field(&ext, "feature_compat")? & COMPAT_HAS_JOURNAL != 0 {
+        ext.format = "ext3";
+    }
+    let log_block_size = field(&ext, "log_block_size")?;
+    if log_block_size > MAX_LOG_BLOCK_SIZE {
+        return None;
+    }
+    let block_size = 1024u64 << log_block_size;
+    let mut label = format!(
+        "Superblock  {} blocks of {} bytes",
+        field(&ext, "blocks_count")?,
+        block_size
+    );
+    if field(&ext, "feature_ro_compat")? & RO_COMPAT_METADATA_CSUM != 0 {
+        let checksum = crc32c(data.get(SUPERBLOCK..SUPERBLOCK + 0x3fc)?);
+        if Some(checksum as u64) != field(&ext, "checksum") {
+            label += "  checksum mismatch";
+        }
+    }
+    ext.entry(label, Some(SUPERBLOCK as u64));
+    let descriptors = field(&ext, "first_data_block")?
+        .checked_add(1)?
+        .checked_mul(block_size)?;
+    ext.entry("Block group descriptors".into(), Some(descriptors));
+    Some(ext)
+}
+
+/// Computes the CRC32C of `data` as used for ext4 metadata, starting from `!0` without
+/// inverting the result.
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0x82f63b78 & (crc & 1).wrapping_neg());
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_ext4() -> Vec<u8> {
+        let mut ext = vec![0; 3 * SUPERBLOCK];
+        let superblock = &mut ext[SUPERBLOCK..2 * SUPERBLOCK];
+        superblock[4..8].copy_from_slice(&2048u32.to_le_bytes());
+        superblock[20..24].copy_from_slice(&1u32.to_le_bytes());
+        superblock[56..58].copy_from_slice(&MAGIC.to_le_bytes());
+        superblock[96..100].copy_from_slice(&0x40u32.to_le_bytes());
+        superblock[100..104].copy_from_slice(&0x400u32.to_le_bytes());
+        let checksum = crc32c(&superblock[..0x3fc]);
+        superblock[0x3fc..].copy_from_slice(&checksum.to_le_bytes());
+        ext
+    }
+
+    #[test]
+    fn test_parse() {
+        let mut data = build_ext4();
+        assert!(detect(&data));
+        let ext = parse(&data).unwrap();
+        assert_eq!(ext.format, "ext4");
+        assert_eq!(ext.entries[0].0, "Superblock  2048 blocks of 1024 bytes");
+        assert_eq!(ext.entries[1].1, Some(2048));
+
+        data[SUPERBLOCK + 120] = b'x';
+        let ext = parse(&data).unwrap();
+        assert!(ext.entries[0].0.ends_with("checksum mismatch"));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let data = build_ext4();
+        assert!(parse(&data[..SUPERBLOCK + 0x200]).is_none());
+        let mut data = build_ext4();
+        data[SUPERBLOCK + 24] = 60;
+        assert!(parse(&data).is_none());
+        data[SUPERBLOCK + 24] = 6;
+        data[SUPERBLOCK + 20..SUPERBLOCK + 24].fill(0xff);
+        assert_eq!(parse(&data).unwrap().entries[1].1, None);
+    }
+
+    #[test]
+    fn test_crc32c() {
+        // CRC32C of "123456789" is 0xe3069283 after the final inversion.
+        assert_eq!(!crc32c(b"123456789"), 0xe3069283);
+    }
+}
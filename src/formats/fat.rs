@@ -0,0 +1,181 @@
+use super::Structure;
+
+/// Whether `data` starts with a boot sector holding a plausible FAT BIOS parameter block.
+pub fn detect(data: &[u8]) -> bool {
+    let Some(sector) = data.get(..512) else {
+        return false;
+    };
+    let bytes_per_sector = u16::from_le_bytes([sector[11], sector[12]]);
+    matches!(sector[0], 0xeb | 0xe9)
+        && matches!(bytes_per_sector, 512 | 1024 | 2048 | 4096)
+        && sector[13].is_power_of_two()
+        && sector[16] != 0
+}
+
+pub fn parse(data: &[u8]) -> Option<Structure> {
+    let mut fat = Structure::new("FAT");
+    let end = fat.add_fields(
+        "bpb",
+        0,
+        false,
+        &[
+            ("jump", "bytes[3]"),
+            ("oem", "char[8]"),
+            ("bytes_per_sector", "u16"),
+            ("sectors_per_cluster", "u8"),
+            ("reserved_sectors", "u16"),
+            ("fat_count", "u8"),
+            ("root_entries", "u16"),
+            ("total_sectors_16", "u16"),
+            ("media", "u8"),
+            ("sectors_per_fat_16", "u16"),
+            ("sectors_per_track", "u16"),
+            ("heads", "u16"),
+            ("hidden_sectors", "u32"),
+            ("total_sectors_32", "u32"),
+        ],
+    );
+    let field = |fat: &Structure, name: &str| fat.value(data, &format!("bpb.{}", name));
+    let bytes_per_sector = field(&fat, "bytes_per_sector")?;
+    let fat32 = field(&fat, "sectors_per_fat_16")? == 0;
+    let extended = match fat32 {
+        true => fat.add_fields(
+            "bpb",
+            end,
+            false,
+            &[
+                ("sectors_per_fat_32", "u32"),
+                ("flags", "u16"),
+                ("version", "u16"),
+                ("root_cluster", "u32"),
+                ("fsinfo_sector", "u16"),
+                ("backup_boot_sector", "u16"),
+                ("reserved", "bytes[12]"),
+            ],
+        ),
+        false => end,
+    };
+    fat.add_fields(
+        "bpb",
+        extended,
+        false,
+        &[
+            ("drive", "u8"),
+            ("reserved1", "u8"),
+            ("boot_signature", "u8"),
+            ("volume_id", "u32"),
+            ("volume_label", "char[11]"),
+            ("fs_type", "char[8]"),
+        ],
+    );
+    fat.add_fields("", 510, false, &[("signature", "u16")]);
+
+    let sectors_per_fat = match fat32 {
+        true => field(&fat, "sectors_per_fat_32")?,
+        false => field(&fat, "sectors_per_fat_16")?,
+    };
+    let total_sectors = match field(&fat, "total_sectors_16")? {
+        0 => field(&fat, "total_sectors_32")?,
+        total => total,
+    };
+    let root_sectors = (field(&fat, "root_entries")? * 32).div_ceil(bytes_per_sector);
+    let first_fat = field(&fat, "reserved_sectors")?;
+    let root = first_fat + field(&fat, "fat_count")? * sectors_per_fat;
+    let first_data = root + root_sectors;
+    let clusters =
+        total_sectors.saturating_sub(first_data) / field(&fat, "sectors_per_cluster")?.max(1);
+    // The type is determined by the number of clusters alone.
+    fat.format = match clusters {
+        ..4085 => "FAT12",
+        4085..65525 => "FAT16",
+        _ => "FAT32",
+    };
+
+    let mut label = format!("Boot sector  {} clusters", clusters);
+    if fat.value(data, "signature")? != 0xaa55 {
+        label += "  missing boot signature";
+    }
+    if fat32 != (fat.format == "FAT32") {
+        label += "  cluster count does not match the BPB layout";
+    }
+    fat.entry(label, Some(0));
+    if fat32 {
+        let fsinfo = field(&fat, "fsinfo_sector")? * bytes_per_sector;
+        let mut label = "FSInfo sector".to_string();
+        let sector = data.get(fsinfo as usize..fsinfo as usize + 512);
+        if sector.is_none_or(|s| &s[..4] != b"RRaA" || &s[484..488] != b"rrAa") {
+            label += "  invalid signature";
+        }
+        fat.entry(label, Some(fsinfo));
+        let backup = field(&fat, "backup_boot_sector")?;
+        fat.entry("Backup boot sector".into(), Some(backup * bytes_per_sector));
+    }
+    for index in 0..field(&fat, "fat_count")? {
+        let sector = first_fat + index * sectors_per_fat;
+        fat.entry(
+            format!("FAT {}", index + 1),
+            Some(sector * bytes_per_sector),
+        );
+    }
+    if !fat32 {
+        fat.entry("Root directory".into(), Some(root * bytes_per_sector));
+    }
+    fat.entry("Data region".into(), Some(first_data * bytes_per_sector));
+    Some(fat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_fat16() -> Vec<u8> {
+        let mut fat = vec![0; 512];
+        fat[..3].copy_from_slice(b"\xeb\x3c\x90");
+        fat[3..11].copy_from_slice(b"MSDOS5.0");
+        fat[11..13].copy_from_slice(&512u16.to_le_bytes());
+        fat[13] = 4;
+        fat[14..16].copy_from_slice(&4u16.to_le_bytes());
+        fat[16] = 2;
+        fat[17..19].copy_from_slice(&512u16.to_le_bytes());
+        fat[22..24].copy_from_slice(&64u16.to_le_bytes());
+        fat[32..36].copy_from_slice(&40000u32.to_le_bytes());
+        fat[54..62].copy_from_slice(b"FAT16   ");
+        fat[510..512].copy_from_slice(b"\x55\xaa");
+        fat
+    }
+
+    #[test]
+    fn test_parse() {
+        let data = build_fat16();
+        assert!(detect(&data));
+        let fat = parse(&data).unwrap();
+        assert_eq!(fat.format, "FAT16");
+        let offsets: Vec<_> = fat.entries.iter().map(|(_, offset)| *offset).collect();
+        let sector = |n: u32| Some(n * 512);
+        assert_eq!(
+            offsets,
+            vec![Some(0), sector(4), sector(68), sector(132), sector(164)]
+        );
+        assert_eq!(fat.entries[0].0, "Boot sector  9959 clusters");
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut data = build_fat16();
+        data[32..36].copy_from_slice(&10000u32.to_le_bytes());
+        data[510] = 0;
+        let fat = parse(&data).unwrap();
+        assert_eq!(fat.format, "FAT12");
+        assert!(fat.entries[0].0.ends_with("missing boot signature"));
+
+        data[32..36].copy_from_slice(&400000u32.to_le_bytes());
+        let fat = parse(&data).unwrap();
+        assert_eq!(fat.format, "FAT32");
+        assert!(fat.entries[0].0.ends_with("does not match the BPB layout"));
+
+        data[22] = 0;
+        let fat = parse(&data).unwrap();
+        assert!(fat.entries[1].0.ends_with("invalid signature"));
+        assert!(!detect(&data[..100]));
+    }
+}
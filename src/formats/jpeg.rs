@@ -0,0 +1,133 @@
+use super::{Structure, read_string};
+
+pub const SIGNATURE: &[u8] = b"\xff\xd8\xff";
+/// Upper bound for the number of segments listed.
+const MAX_SEGMENTS: usize = 65536;
+
+pub fn parse(data: &[u8]) -> Option<Structure> {
+    let mut jpeg = Structure::new("JPEG");
+    let mut position = 0;
+    while position + 2 <= data.len() && jpeg.entries.len() < MAX_SEGMENTS {
+        if data[position] != 0xff {
+            jpeg.entry(
+                format!("{:<12} offset 0x{:x}  expected a marker", "?", position),
+                Some(position as u64),
+            );
+            break;
+        }
+        // Markers may be preceded by any number of fill bytes.
+        let fill = data[position + 1..]
+            .iter()
+            .take_while(|b| **b == 0xff)
+            .count();
+        let offset = position + fill;
+        let Some(&marker) = data.get(offset + 1) else {
+            break;
+        };
+        let prefix = format!("segment[{}]", jpeg.entries.len());
+        let mut end = jpeg.add_fields(&prefix, offset, true, &[("marker", "u16")]);
+        let mut label = format!("{:<12} offset 0x{:<8x}", marker_name(marker), offset);
+        if has_length(marker) {
+            let payload = jpeg.add_fields(&prefix, end, true, &[("length", "u16")]);
+            let length = jpeg.value(data, &format!("{}.length", prefix)).unwrap_or(0) as usize;
+            label += &format!(" length 0x{:x}", length);
+            if (0xe0..=0xef).contains(&marker)
+                && let Some(identifier) = read_string(data, payload)
+                && !identifier.is_empty()
+                && identifier.len() < 32
+            {
+                label = format!("{}  {}", label, identifier.escape_debug());
+            }
+            end += length;
+            if length < 2 {
+                label += "  invalid length";
+                jpeg.entry(label, Some(offset as u64));
+                break;
+            } else if end > data.len() {
+                label += "  truncated";
+            } else if end < data.len() && data[end] != 0xff {
+                label += "  overlaps the following data";
+            }
+        }
+        jpeg.entry(label, Some(offset as u64));
+        position = match marker {
+            0xd9 => break,
+            0xda => scan_end(data, end),
+            _ => end,
+        };
+    }
+    Some(jpeg)
+}
+
+/// Whether a segment with `marker` has a length field.
+fn has_length(marker: u8) -> bool {
+    !matches!(marker, 0x01 | 0xd0..=0xd9)
+}
+
+/// Returns the offset of the first marker after the entropy coded data starting at `offset`.
+fn scan_end(data: &[u8], offset: usize) -> usize {
+    (offset..data.len().saturating_sub(1))
+        .find(|i| data[*i] == 0xff && !matches!(data[i + 1], 0x00 | 0xd0..=0xd7 | 0xff))
+        .unwrap_or(data.len())
+}
+
+fn marker_name(marker: u8) -> String {
+    match marker {
+        0xd8 => "SOI".into(),
+        0xd9 => "EOI".into(),
+        0xda => "SOS".into(),
+        0xdb => "DQT".into(),
+        0xdd => "DRI".into(),
+        0xc4 => "DHT".into(),
+        0xcc => "DAC".into(),
+        0xfe => "COM".into(),
+        0xc0..=0xcf if marker != 0xc8 => format!("SOF{}", marker - 0xc0),
+        0xd0..=0xd7 => format!("RST{}", marker - 0xd0),
+        0xe0..=0xef => format!("APP{}", marker - 0xe0),
+        _ => format!("0x{:02x}", marker),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_jpeg() -> Vec<u8> {
+        let mut jpeg = b"\xff\xd8".to_vec();
+        jpeg.extend(b"\xff\xe1\x00\x08Exif\x00\x00");
+        jpeg.extend(b"\xff\xda\x00\x03\x01\x12\xff\x00\x34\xff\xd0\x56");
+        jpeg.extend(b"\xff\xff\xd9");
+        jpeg
+    }
+
+    #[test]
+    fn test_parse() {
+        let data = build_jpeg();
+        assert!(data.starts_with(SIGNATURE));
+        let jpeg = parse(&data).unwrap();
+        let labels: Vec<_> = jpeg
+            .entries
+            .iter()
+            .map(|(label, _)| label.split_whitespace().next().unwrap())
+            .collect();
+        assert_eq!(labels, vec!["SOI", "APP1", "SOS", "EOI"]);
+        let offsets: Vec<_> = jpeg.entries.iter().map(|(_, offset)| *offset).collect();
+        assert_eq!(offsets, vec![Some(0), Some(2), Some(12), Some(25)]);
+        assert!(jpeg.entries[1].0.ends_with("Exif"));
+        assert_eq!(jpeg.value(&data, "segment[1].length"), Some(8));
+    }
+
+    #[test]
+    fn test_invalid_segments() {
+        let mut data = build_jpeg();
+        data[5] = 0x06;
+        let jpeg = parse(&data).unwrap();
+        assert!(jpeg.entries[1].0.ends_with("overlaps the following data"));
+        assert!(jpeg.entries[2].0.ends_with("expected a marker"));
+
+        let mut data = build_jpeg();
+        data.truncate(8);
+        let jpeg = parse(&data).unwrap();
+        assert!(jpeg.entries[1].0.ends_with("truncated"));
+    }
+}
@@ -0,0 +1,328 @@
+use super::{Structure, read_string};
+
+/// Upper bound for the number of architectures, load commands and sections listed.
+const MAX_ENTRIES: u64 = 4096;
+const LC_SEGMENT: u64 = 0x1;
+const LC_SEGMENT_64: u64 = 0x19;
+
+/// Whether `data` starts with a Mach-O or fat header. Fat headers share their magic with Java
+/// class files, which are told apart by the architecture count standing in for the version.
+pub fn detect(data: &[u8]) -> bool {
+    match data.get(..4) {
+        Some(
+            b"\xfe\xed\xfa\xce" | b"\xfe\xed\xfa\xcf" | b"\xce\xfa\xed\xfe" | b"\xcf\xfa\xed\xfe",
+        ) => true,
+        Some(b"\xca\xfe\xba\xbe" | b"\xca\xfe\xba\xbf") => data
+            .get(4..8)
+            .is_some_and(|count| u32::from_be_bytes(count.try_into().unwrap()) < 32),
+        _ => false,
+    }
+}
+
+pub fn parse(data: &[u8]) -> Option<Structure> {
+    let mut macho = Structure::new("Mach-O");
+    if !data.starts_with(b"\xca\xfe\xba") {
+        parse_image(&mut macho, data, 0, "mach")?;
+        return Some(macho);
+    }
+    let wide = data[3] == 0xbf;
+    macho.entry("Fat header".into(), Some(0));
+    let end = macho.add_fields("fat", 0, true, &[("magic", "u32"), ("nfat_arch", "u32")]);
+    let (address, size) = if wide { ("u64", 32) } else { ("u32", 20) };
+    let mut fields = vec![
+        ("cputype", "u32"),
+        ("cpusubtype", "u32"),
+        ("offset", address),
+        ("size", address),
+        ("align", "u32"),
+    ];
+    if wide {
+        fields.push(("reserved", "u32"));
+    }
+    for index in 0..macho.value(data, "fat.nfat_arch")?.min(MAX_ENTRIES) as usize {
+        let start = end + index * size;
+        if start + size > data.len() {
+            break;
+        }
+        let prefix = format!("arch[{}]", index);
+        macho.add_fields(&prefix, start, true, &fields);
+        let value = |name: &str| macho.value(data, &format!("{}.{}", prefix, name));
+        let (cpu, offset, length) = (value("cputype")?, value("offset")?, value("size")?);
+        let label = format!(
+            "Architecture {:<3} {:<8} offset 0x{:<8x} size 0x{:x}",
+            index,
+            cpu_type(cpu),
+            offset,
+            length
+        );
+        macho.entry(label, Some(offset));
+        parse_image(&mut macho, data, offset as usize, &prefix);
+    }
+    Some(macho)
+}
+
+/// Adds the header, load commands and sections of the image starting at `base`, naming its
+/// fields `prefix.name`.
+fn parse_image(macho: &mut Structure, data: &[u8], base: usize, prefix: &str) -> Option<()> {
+    let (wide, big_endian) = match data.get(base..base + 4)? {
+        b"\xfe\xed\xfa\xce" => (false, true),
+        b"\xfe\xed\xfa\xcf" => (true, true),
+        b"\xce\xfa\xed\xfe" => (false, false),
+        b"\xcf\xfa\xed\xfe" => (true, false),
+        _ => return None,
+    };
+    let address = if wide { "u64" } else { "u32" };
+    let mut header = vec![
+        ("magic", "u32"),
+        ("cputype", "u32"),
+        ("cpusubtype", "u32"),
+        ("filetype", "u32"),
+        ("ncmds", "u32"),
+        ("sizeofcmds", "u32"),
+        ("flags", "u32"),
+    ];
+    if wide {
+        header.push(("reserved", "u32"));
+    }
+    let mut command = macho.add_fields(prefix, base, big_endian, &header);
+    let cpu = macho.value(data, &format!("{}.cputype", prefix))?;
+    macho.entry(
+        format!("Mach-O header {}", cpu_type(cpu)),
+        Some(base as u64),
+    );
+    let count = macho.value(data, &format!("{}.ncmds", prefix))?;
+    let segment = [
+        ("segname", "char[16]"),
+        ("vmaddr", address),
+        ("vmsize", address),
+        ("fileoff", address),
+        ("filesize", address),
+        ("maxprot", "u32"),
+        ("initprot", "u32"),
+        ("nsects", "u32"),
+        ("flags", "u32"),
+    ];
+    let mut section = vec![
+        ("sectname", "char[16]"),
+        ("segname", "char[16]"),
+        ("addr", address),
+        ("size", address),
+        ("offset", "u32"),
+        ("align", "u32"),
+        ("reloff", "u32"),
+        ("nreloc", "u32"),
+        ("flags", "u32"),
+        ("reserved1", "u32"),
+        ("reserved2", "u32"),
+    ];
+    if wide {
+        section.push(("reserved3", "u32"));
+    }
+    for index in 0..count.min(MAX_ENTRIES) {
+        let name = format!("{}.command[{}]", prefix, index);
+        macho.add_fields(
+            &name,
+            command,
+            big_endian,
+            &[("cmd", "u32"), ("cmdsize", "u32")],
+        );
+        let (Some(kind), Some(size)) = (
+            macho.value(data, &format!("{}.cmd", name)),
+            macho.value(data, &format!("{}.cmdsize", name)),
+        ) else {
+            break;
+        };
+        let mut label = format!("Load command {:<3} {}", index, command_type(kind));
+        if matches!(kind, LC_SEGMENT | LC_SEGMENT_64) {
+            let mut position = macho.add_fields(&name, command + 8, big_endian, &segment);
+            let segment_name = read_name(data, command + 8);
+            label += &format!(" {}", segment_name);
+            macho.entry(label, Some(command as u64));
+            let sections = macho.value(data, &format!("{}.nsects", name)).unwrap_or(0);
+            for number in 0..sections.min(MAX_ENTRIES) {
+                let prefix = format!("{}.section[{}]", name, number);
+                let start = position;
+                position = macho.add_fields(&prefix, position, big_endian, &section);
+                let value = |field: &str| macho.value(data, &format!("{}.{}", prefix, field));
+                let (Some(offset), Some(size)) = (value("offset"), value("size")) else {
+                    break;
+                };
+                let label = format!(
+                    "  Section {:<24} offset 0x{:<8x} size 0x{:x}",
+                    format!("{},{}", segment_name, read_name(data, start)),
+                    offset,
+                    size
+                );
+                let target = (offset != 0).then_some(base as u64 + offset);
+                macho.entry(label, target);
+            }
+        } else {
+            if let Some(path) = library_path(kind, data, command, big_endian) {
+                label += &format!(" {}", path);
+            }
+            macho.entry(label, Some(command as u64));
+        }
+        if size < 8 {
+            break;
+        }
+        command += size as usize;
+    }
+    Some(())
+}
+
+/// Reads a name of up to 16 characters padded with NUL bytes.
+fn read_name(data: &[u8], offset: usize) -> String {
+    let name = data.get(offset..offset + 16).unwrap_or_default();
+    let end = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+    String::from_utf8_lossy(&name[..end]).into()
+}
+
+/// Returns the path named by dylib, dylinker and rpath commands.
+fn library_path(kind: u64, data: &[u8], command: usize, big_endian: bool) -> Option<String> {
+    if !matches!(
+        kind,
+        0xc | 0xd | 0xe | 0xf | 0x8000_0018 | 0x8000_001c | 0x8000_001f | 0x8000_0023
+    ) {
+        return None;
+    }
+    let offset: [u8; 4] = data.get(command + 8..command + 12)?.try_into().ok()?;
+    let offset = match big_endian {
+        true => u32::from_be_bytes(offset),
+        false => u32::from_le_bytes(offset),
+    };
+    read_string(data, command + offset as usize)
+}
+
+fn cpu_type(kind: u64) -> String {
+    let name = match kind {
+        7 => "x86",
+        0x0100_0007 => "x86_64",
+        12 => "arm",
+        0x0100_000c => "arm64",
+        0x0200_000c => "arm64_32",
+        18 => "ppc",
+        0x0100_0012 => "ppc64",
+        _ => return format!("0x{:x}", kind),
+    };
+    name.into()
+}
+
+fn command_type(kind: u64) -> String {
+    let name = match kind {
+        LC_SEGMENT => "SEGMENT",
+        0x2 => "SYMTAB",
+        0xb => "DYSYMTAB",
+        0xc => "LOAD_DYLIB",
+        0xd => "ID_DYLIB",
+        0xe => "LOAD_DYLINKER",
+        0xf => "ID_DYLINKER",
+        LC_SEGMENT_64 => "SEGMENT_64",
+        0x1b => "UUID",
+        0x1d => "CODE_SIGNATURE",
+        0x24 => "VERSION_MIN_MACOSX",
+        0x26 => "FUNCTION_STARTS",
+        0x29 => "DATA_IN_CODE",
+        0x2a => "SOURCE_VERSION",
+        0x2c => "ENCRYPTION_INFO_64",
+        0x32 => "BUILD_VERSION",
+        0x8000_0018 => "LOAD_WEAK_DYLIB",
+        0x8000_001c => "RPATH",
+        0x8000_001f => "REEXPORT_DYLIB",
+        0x8000_0022 => "DYLD_INFO_ONLY",
+        0x8000_0023 => "LOAD_UPWARD_DYLIB",
+        0x8000_0028 => "MAIN",
+        0x8000_0033 => "DYLD_EXPORTS_TRIE",
+        0x8000_0034 => "DYLD_CHAINED_FIXUPS",
+        _ => return format!("0x{:x}", kind),
+    };
+    name.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn put(data: &mut [u8], offset: usize, value: u32) {
+        data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Builds a 64-bit little-endian image with a `__TEXT` segment holding one section and a
+    /// `LOAD_DYLIB` command.
+    fn build_image() -> Vec<u8> {
+        let mut image = vec![0; 0x200];
+        image[..4].copy_from_slice(b"\xcf\xfa\xed\xfe");
+        put(&mut image, 4, 0x0100_000c);
+        put(&mut image, 16, 2); // ncmds
+        put(&mut image, 32, LC_SEGMENT_64 as u32);
+        put(&mut image, 36, 72 + 80);
+        image[40..46].copy_from_slice(b"__TEXT");
+        put(&mut image, 32 + 64, 1); // nsects
+        image[104..110].copy_from_slice(b"__text");
+        image[120..126].copy_from_slice(b"__TEXT");
+        put(&mut image, 104 + 40, 0x10); // size
+        put(&mut image, 104 + 48, 0x180); // offset
+        let dylib = 32 + 72 + 80;
+        put(&mut image, dylib, 0xc);
+        put(&mut image, dylib + 4, 48);
+        put(&mut image, dylib + 8, 24);
+        image[dylib + 24..dylib + 34].copy_from_slice(b"libSystem\0");
+        image
+    }
+
+    #[test]
+    fn test_detect() {
+        assert!(detect(&build_image()));
+        assert!(detect(b"\xca\xfe\xba\xbe\x00\x00\x00\x02"));
+        assert!(!detect(b"\xca\xfe\xba\xbe\x00\x00\x00\x34"));
+        assert!(!detect(b"\x7fELF"));
+    }
+
+    #[test]
+    fn test_parse() {
+        let data = build_image();
+        let macho = parse(&data).unwrap();
+        let labels: Vec<_> = macho
+            .entries
+            .iter()
+            .map(|(label, _)| label.trim())
+            .collect();
+        assert_eq!(labels[0], "Mach-O header arm64");
+        assert_eq!(labels[1], "Load command 0   SEGMENT_64 __TEXT");
+        assert!(labels[2].starts_with("Section __TEXT,__text"));
+        assert_eq!(labels[3], "Load command 1   LOAD_DYLIB libSystem");
+        let offsets: Vec<_> = macho.entries.iter().map(|(_, offset)| *offset).collect();
+        assert_eq!(offsets, vec![Some(0), Some(32), Some(0x180), Some(184)]);
+        assert_eq!(
+            macho.value(&data, "mach.command[0].section[0].size"),
+            Some(0x10)
+        );
+    }
+
+    #[test]
+    fn test_parse_fat() {
+        let mut data = b"\xca\xfe\xba\xbe\x00\x00\x00\x01".to_vec();
+        data.extend(0x0100_000cu32.to_be_bytes());
+        data.extend([0; 4]);
+        data.extend(0x1000u32.to_be_bytes());
+        data.extend(0x200u32.to_be_bytes());
+        data.extend([0; 4]);
+        data.resize(0x1000, 0);
+        data.extend(build_image());
+        let macho = parse(&data).unwrap();
+        let labels: Vec<_> = macho
+            .entries
+            .iter()
+            .map(|(label, _)| label.trim())
+            .collect();
+        assert_eq!(
+            labels[..3],
+            [
+                "Fat header",
+                "Architecture 0   arm64    offset 0x1000     size 0x200",
+                "Mach-O header arm64"
+            ]
+        );
+        assert_eq!(macho.entries[4].1, Some(0x1180));
+        assert_eq!(macho.value(&data, "arch[0].ncmds"), Some(2));
+    }
+}
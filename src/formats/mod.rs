@@ -0,0 +1,147 @@
+mod elf;
+mod ext;
+mod fat;
+mod jpeg;
+mod macho;
+mod mp4;
+mod partition;
+mod pcap;
+mod pe;
+pub mod png;
+mod riff;
+mod sqlite;
+mod zip;
+
+use super::template::Template;
+
+/// The headers of a file in a known format, as a list to navigate and as fields to decode.
+pub struct Structure {
+    pub format: &'static str,
+    pub entries: Vec<(String, Option<u32>)>,
+    pub template: Template,
+    /// Named offsets, such as the functions of an executable.
+    pub symbols: Vec<(usize, String)>,
+}
+
+/// Parses the headers of executables, media files, archives, file systems and disk images,
+/// returning `None` for unknown or malformed files.
+pub fn parse(data: &[u8]) -> Option<Structure> {
+    if data.starts_with(b"\x7fELF") {
+        elf::parse(data)
+    } else if data.starts_with(b"MZ") {
+        pe::parse(data)
+    } else if macho::detect(data) {
+        macho::parse(data)
+    } else if data.starts_with(jpeg::SIGNATURE) {
+        jpeg::parse(data)
+    } else if data.starts_with(png::SIGNATURE) {
+        png::parse(data)
+    } else if zip::detect(data) {
+        zip::parse(data)
+    } else if data.starts_with(b"RIFF") {
+        riff::parse(data)
+    } else if data.starts_with(sqlite::SIGNATURE) {
+        sqlite::parse(data)
+    } else if pcap::detect(data) {
+        pcap::parse(data)
+    } else if mp4::detect(data) {
+        mp4::parse(data)
+    } else if ext::detect(data) {
+        ext::parse(data)
+    } else if fat::detect(data) {
+        fat::parse(data)
+    } else if partition::detect(data) {
+        partition::parse(data)
+    } else {
+        None
+    }
+}
+
+/// Returns the symbols of an executable sorted by offset.
+pub fn symbols(data: &[u8]) -> Vec<(usize, String)> {
+    let mut symbols = parse(data).map_or(vec![], |structure| structure.symbols);
+    symbols.sort();
+    symbols.dedup_by_key(|(offset, _)| *offset);
+    symbols
+}
+
+impl Structure {
+    fn new(format: &'static str) -> Self {
+        Structure {
+            format,
+            entries: vec![],
+            template: Template::default(),
+            symbols: vec![],
+        }
+    }
+
+    /// Adds `fields` as `(name, type)` one after another from `offset`, naming them
+    /// `prefix.name`, and returns the offset after the last one.
+    fn add_fields(
+        &mut self,
+        prefix: &str,
+        offset: usize,
+        big_endian: bool,
+        fields: &[(&str, &str)],
+    ) -> usize {
+        let mut position = offset;
+        for (name, kind) in fields {
+            let name = match prefix {
+                "" => name.to_string(),
+                prefix => format!("{}.{}", prefix, name),
+            };
+            let kind = match big_endian && !kind.contains('[') {
+                true => format!("{}be", kind),
+                false => kind.to_string(),
+            };
+            position += self
+                .template
+                .add(&name, position, &kind)
+                .expect("valid field type");
+        }
+        position
+    }
+
+    /// Reads the field `name` as an unsigned integer.
+    fn value(&self, data: &[u8], name: &str) -> Option<u64> {
+        self.template
+            .fields
+            .iter()
+            .rev()
+            .find(|field| field.name == name)?
+            .unsigned(data)
+    }
+
+    fn entry(&mut self, label: String, offset: Option<u64>) {
+        let offset = offset.and_then(|offset| u32::try_from(offset).ok());
+        self.entries.push((label, offset));
+    }
+}
+
+/// Reads a NUL terminated string at `offset`.
+fn read_string(data: &[u8], offset: usize) -> Option<String> {
+    let bytes = data.get(offset..)?;
+    let end = bytes.iter().position(|b| *b == 0)?;
+    Some(String::from_utf8_lossy(&bytes[..end]).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_fields() {
+        let mut structure = Structure::new("Test");
+        let end = structure.add_fields("header", 2, true, &[("a", "u16"), ("b", "bytes[3]")]);
+        assert_eq!(end, 7);
+        let data = [0, 0, 1, 2, 3, 4, 5];
+        assert_eq!(structure.value(&data, "header.a"), Some(0x0102));
+        assert_eq!(structure.value(&data, "header.c"), None);
+    }
+
+    #[test]
+    fn test_read_string() {
+        assert_eq!(read_string(b"ab\0cd\0", 3), Some("cd".into()));
+        assert_eq!(read_string(b"ab", 0), None);
+    }
+}
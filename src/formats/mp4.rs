@@ -0,0 +1,120 @@
+use super::Structure;
+
+/// Upper bound for the number of boxes listed.
+const MAX_BOXES: usize = 65536;
+/// Nesting limit for container boxes.
+const MAX_DEPTH: usize = 16;
+/// Boxes which only contain other boxes.
+const CONTAINERS: &[&[u8]] = &[
+    b"moov", b"trak", b"mdia", b"minf", b"stbl", b"dinf", b"edts", b"udta", b"mvex", b"moof",
+    b"traf", b"mfra", b"sinf", b"schi", b"ipro", b"tref",
+];
+
+/// Whether `data` starts with a box typically found at the start of MP4 and QuickTime files.
+pub fn detect(data: &[u8]) -> bool {
+    matches!(
+        data.get(4..8),
+        Some(b"ftyp" | b"moov" | b"mdat" | b"free" | b"wide" | b"skip")
+    )
+}
+
+pub fn parse(data: &[u8]) -> Option<Structure> {
+    let mut mp4 = Structure::new("MP4");
+    parse_boxes(&mut mp4, data, 0, data.len(), 0);
+    Some(mp4)
+}
+
+/// Adds the boxes between `start` and `end`, descending into containers.
+fn parse_boxes(mp4: &mut Structure, data: &[u8], start: usize, end: usize, depth: usize) {
+    let mut position = start;
+    while position + 8 <= end && mp4.entries.len() < MAX_BOXES {
+        let prefix = format!("box[{}]", mp4.entries.len());
+        let mut payload = mp4.add_fields(
+            &prefix,
+            position,
+            true,
+            &[("size", "u32"), ("type", "char[4]")],
+        );
+        let kind = &data[position + 4..position + 8];
+        let size = match mp4.value(data, &format!("{}.size", prefix)).unwrap_or(0) {
+            0 => (end - position) as u64,
+            1 if payload + 8 <= end => {
+                payload = mp4.add_fields(&prefix, payload, true, &[("largesize", "u64")]);
+                mp4.value(data, &format!("{}.largesize", prefix))
+                    .unwrap_or(0)
+            }
+            size => size,
+        };
+        let mut label = format!(
+            "{:<20} offset 0x{:<8x} size 0x{:x}",
+            format!("{}{}", "  ".repeat(depth), String::from_utf8_lossy(kind)),
+            position,
+            size
+        );
+        let header = (payload - position) as u64;
+        let box_end = position as u64 + size;
+        if size < header {
+            label += "  invalid size";
+            mp4.entry(label, Some(payload as u64));
+            break;
+        }
+        if box_end > end as u64 {
+            label += "  exceeds parent";
+        }
+        mp4.entry(label, Some(payload as u64));
+        let box_end = box_end.min(end as u64) as usize;
+        if depth < MAX_DEPTH {
+            if CONTAINERS.contains(&kind) {
+                parse_boxes(mp4, data, payload, box_end, depth + 1);
+            } else if kind == b"meta" {
+                // A full box with version and flags before its children.
+                mp4.add_fields(&prefix, payload, true, &[("version_flags", "u32")]);
+                parse_boxes(mp4, data, payload + 4, box_end, depth + 1);
+            }
+        }
+        position = box_end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_mp4() -> Vec<u8> {
+        let mut mp4 = b"\x00\x00\x00\x10ftypisom\x00\x00\x00\x00".to_vec();
+        mp4.extend(b"\x00\x00\x00\x18moov\x00\x00\x00\x10trak\x00\x00\x00\x08mdia");
+        mp4.extend(b"\x00\x00\x00\x01mdat\x00\x00\x00\x00\x00\x00\x00\x12ab");
+        mp4
+    }
+
+    #[test]
+    fn test_parse() {
+        let data = build_mp4();
+        assert!(detect(&data));
+        let mp4 = parse(&data).unwrap();
+        let labels: Vec<_> = mp4
+            .entries
+            .iter()
+            .map(|(label, _)| label.split(" offset").next().unwrap().trim_end())
+            .collect();
+        assert_eq!(labels, vec!["ftyp", "moov", "  trak", "    mdia", "mdat"]);
+        let offsets: Vec<_> = mp4.entries.iter().map(|(_, offset)| *offset).collect();
+        assert_eq!(
+            offsets,
+            vec![Some(8), Some(24), Some(32), Some(40), Some(56)]
+        );
+        assert_eq!(mp4.value(&data, "box[4].largesize"), Some(0x12));
+    }
+
+    #[test]
+    fn test_size_validation() {
+        let mut data = build_mp4();
+        data[27] = 0x20;
+        let mp4 = parse(&data).unwrap();
+        assert!(mp4.entries[2].0.ends_with("exceeds parent"));
+        data[3] = 0x04;
+        let mp4 = parse(&data).unwrap();
+        assert_eq!(mp4.entries.len(), 1);
+        assert!(mp4.entries[0].0.ends_with("invalid size"));
+    }
+}
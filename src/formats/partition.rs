@@ -0,0 +1,316 @@
+use super::Structure;
+
+const SECTOR_SIZE: usize = 512;
+/// Offset of the partition entries in the MBR.
+const MBR_ENTRIES: usize = 446;
+/// Upper bound for the number of GPT entries listed.
+const MAX_ENTRIES: usize = 1024;
+
+/// Whether `data` starts with a sector ending in the MBR boot signature.
+pub fn detect(data: &[u8]) -> bool {
+    data.get(510..512) == Some(b"\x55\xaa")
+}
+
+pub fn parse(data: &[u8]) -> Option<Structure> {
+    let mut disk = Structure::new("MBR");
+    disk.entry("MBR".into(), Some(0));
+    let mut protective = false;
+    for index in 0..4 {
+        let prefix = format!("mbr.partition[{}]", index);
+        let offset = MBR_ENTRIES + 16 * index;
+        disk.add_fields(
+            &prefix,
+            offset,
+            false,
+            &[
+                ("status", "u8"),
+                ("chs_first", "bytes[3]"),
+                ("type", "u8"),
+                ("chs_last", "bytes[3]"),
+                ("lba_first", "u32"),
+                ("sectors", "u32"),
+            ],
+        );
+        let kind = disk.value(data, &format!("{}.type", prefix))? as u8;
+        if kind == 0 {
+            continue;
+        }
+        protective |= kind == 0xee;
+        let start = disk.value(data, &format!("{}.lba_first", prefix))?;
+        let sectors = disk.value(data, &format!("{}.sectors", prefix))?;
+        let status = match disk.value(data, &format!("{}.status", prefix))? {
+            0x80 => "  bootable",
+            0x00 => "",
+            _ => "  invalid status",
+        };
+        let label = format!(
+            "  Partition {} {:<24} start 0x{:<10x} size 0x{:x}{}",
+            index + 1,
+            mbr_type_name(kind),
+            start,
+            sectors,
+            status
+        );
+        disk.entry(label, Some(start * SECTOR_SIZE as u64));
+    }
+    if protective || data.get(SECTOR_SIZE..SECTOR_SIZE + 8) == Some(b"EFI PART") {
+        disk.format = "GPT";
+        parse_gpt(&mut disk, data);
+    }
+    Some(disk)
+}
+
+/// Adds the GPT header in the second sector and its partition entries.
+fn parse_gpt(disk: &mut Structure, data: &[u8]) {
+    let header = SECTOR_SIZE;
+    disk.add_fields(
+        "gpt",
+        header,
+        false,
+        &[
+            ("signature", "char[8]"),
+            ("revision", "u32"),
+            ("header_size", "u32"),
+            ("header_crc", "u32"),
+            ("reserved", "u32"),
+            ("current_lba", "u64"),
+            ("backup_lba", "u64"),
+            ("first_usable_lba", "u64"),
+            ("last_usable_lba", "u64"),
+            ("disk_guid", "bytes[16]"),
+            ("entries_lba", "u64"),
+            ("entry_count", "u32"),
+            ("entry_size", "u32"),
+            ("entries_crc", "u32"),
+        ],
+    );
+    let field = |name: &str| disk.value(data, &format!("gpt.{}", name)).unwrap_or(0) as usize;
+    let (header_size, entries_lba) = (field("header_size"), field("entries_lba"));
+    let (count, entry_size) = (field("entry_count"), field("entry_size"));
+    let mut label = "GPT header".to_string();
+    if data.get(header..header + 8) != Some(b"EFI PART") {
+        disk.entry(label + "  invalid signature", Some(header as u64));
+        return;
+    }
+    if header_crc(data, header, header_size) != Some(field("header_crc") as u32) {
+        label += "  header CRC mismatch";
+    }
+    let entries = entries_lba.saturating_mul(SECTOR_SIZE);
+    let table = entries.checked_add(count.saturating_mul(entry_size));
+    match table.and_then(|end| data.get(entries..end)) {
+        Some(table) if crc32fast::hash(table) != field("entries_crc") as u32 => {
+            label += "  entries CRC mismatch";
+        }
+        Some(_) => {}
+        None => label += "  entries truncated",
+    }
+    disk.entry(label, Some(header as u64));
+    if entry_size < 128 {
+        return;
+    }
+    for index in 0..count.min(MAX_ENTRIES) {
+        let Some(offset) = index
+            .checked_mul(entry_size)
+            .and_then(|start| entries.checked_add(start))
+            .filter(|offset| data.get(*offset..offset.saturating_add(128)).is_some())
+        else {
+            break;
+        };
+        let kind = &data[offset..offset + 16];
+        if kind.iter().all(|b| *b == 0) {
+            continue;
+        }
+        let prefix = format!("gpt.partition[{}]", index);
+        disk.add_fields(
+            &prefix,
+            offset,
+            false,
+            &[
+                ("type_guid", "bytes[16]"),
+                ("unique_guid", "bytes[16]"),
+                ("first_lba", "u64"),
+                ("last_lba", "u64"),
+                ("attributes", "u64"),
+                ("name", "bytes[72]"),
+            ],
+        );
+        let first = disk
+            .value(data, &format!("{}.first_lba", prefix))
+            .unwrap_or(0);
+        let last = disk
+            .value(data, &format!("{}.last_lba", prefix))
+            .unwrap_or(0);
+        let name: Vec<u16> = data[offset + 56..offset + 128]
+            .chunks(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .take_while(|c| *c != 0)
+            .collect();
+        let label = format!(
+            "  Partition {:<3} {:<24} start 0x{:<10x} size 0x{:<10x} {}",
+            index + 1,
+            gpt_type_name(kind),
+            first,
+            last.saturating_add(1).saturating_sub(first),
+            String::from_utf16_lossy(&name)
+        );
+        disk.entry(label, Some(first.saturating_mul(SECTOR_SIZE as u64)));
+    }
+}
+
+/// Computes the CRC of the GPT header at `offset` with its CRC field zeroed.
+fn header_crc(data: &[u8], offset: usize, size: usize) -> Option<u32> {
+    let mut header = data.get(offset..offset.checked_add(size)?)?.to_vec();
+    header.get_mut(16..20)?.fill(0);
+    Some(crc32fast::hash(&header))
+}
+
+fn mbr_type_name(kind: u8) -> String {
+    match kind {
+        0x01 => "FAT12".into(),
+        0x04 | 0x06 | 0x0e => "FAT16".into(),
+        0x05 | 0x0f => "Extended".into(),
+        0x07 => "NTFS/exFAT".into(),
+        0x0b | 0x0c => "FAT32".into(),
+        0x82 => "Linux swap".into(),
+        0x83 => "Linux".into(),
+        0x8e => "Linux LVM".into(),
+        0xa5 => "FreeBSD".into(),
+        0xaf => "HFS+".into(),
+        0xee => "GPT protective".into(),
+        0xef => "EFI System".into(),
+        0xfd => "Linux RAID".into(),
+        kind => format!("0x{:02x}", kind),
+    }
+}
+
+fn gpt_type_name(guid: &[u8]) -> String {
+    let guid = format_guid(guid);
+    let name = match guid.as_str() {
+        "C12A7328-F81F-11D2-BA4B-00A0C93EC93B" => "EFI System",
+        "21686148-6449-6E6F-744E-656564454649" => "BIOS boot",
+        "E3C9E316-0B5C-4DB8-817D-F92DF00215AE" => "Microsoft reserved",
+        "EBD0A0A2-B9E5-4433-87C0-68B6B72699C7" => "Basic data",
+        "DE94BBA4-06D1-4D40-A16A-BFD50179D6AC" => "Windows recovery",
+        "0FC63DAF-8483-4772-8E79-3D69D8477DE4" => "Linux filesystem",
+        "0657FD6D-A4AB-43C4-84E5-0933C84B4F4F" => "Linux swap",
+        "E6D6D379-F507-44C2-A23C-238F2A3DF928" => "Linux LVM",
+        "A19D880F-05FC-4D3B-A006-743F0F84911E" => "Linux RAID",
+        "48465300-0000-11AA-AA11-00306543ECAC" => "HFS+",
+        "7C3457EF-0000-11AA-AA11-00306543ECAC" => "APFS",
+        _ => return guid,
+    };
+    name.into()
+}
+
+/// Formats a GUID stored with its first three groups in little-endian.
+fn format_guid(guid: &[u8]) -> String {
+    let hex = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<String>()
+    };
+    let reversed = |bytes: &[u8]| hex(&bytes.iter().rev().copied().collect::<Vec<_>>());
+    format!(
+        "{}-{}-{}-{}-{}",
+        reversed(&guid[0..4]),
+        reversed(&guid[4..6]),
+        reversed(&guid[6..8]),
+        hex(&guid[8..10]),
+        hex(&guid[10..16])
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINUX_FILESYSTEM: [u8; 16] = [
+        0xaf, 0x3d, 0xc6, 0x0f, 0x83, 0x84, 0x72, 0x47, 0x8e, 0x79, 0x3d, 0x69, 0xd8, 0x47, 0x7d,
+        0xe4,
+    ];
+
+    fn build_mbr() -> Vec<u8> {
+        let mut disk = vec![0; 4 * SECTOR_SIZE];
+        disk[510..512].copy_from_slice(b"\x55\xaa");
+        let entry = &mut disk[MBR_ENTRIES + 16..MBR_ENTRIES + 32];
+        entry[0] = 0x80;
+        entry[4] = 0x83;
+        entry[8..12].copy_from_slice(&2u32.to_le_bytes());
+        entry[12..16].copy_from_slice(&2u32.to_le_bytes());
+        disk
+    }
+
+    fn build_gpt() -> Vec<u8> {
+        let mut disk = vec![0; 8 * SECTOR_SIZE];
+        disk[510..512].copy_from_slice(b"\x55\xaa");
+        disk[MBR_ENTRIES + 4] = 0xee;
+        let entries = 2 * SECTOR_SIZE;
+        disk[entries..entries + 16].copy_from_slice(&LINUX_FILESYSTEM);
+        disk[entries + 32..entries + 40].copy_from_slice(&4u64.to_le_bytes());
+        disk[entries + 40..entries + 48].copy_from_slice(&7u64.to_le_bytes());
+        disk[entries + 56..entries + 60].copy_from_slice(b"r\0x\0");
+        let entries_crc = crc32fast::hash(&disk[entries..entries + 4 * 128]);
+        let header = &mut disk[SECTOR_SIZE..SECTOR_SIZE + 92];
+        header[..8].copy_from_slice(b"EFI PART");
+        header[12..16].copy_from_slice(&92u32.to_le_bytes());
+        header[72..80].copy_from_slice(&2u64.to_le_bytes());
+        header[80..84].copy_from_slice(&4u32.to_le_bytes());
+        header[84..88].copy_from_slice(&128u32.to_le_bytes());
+        header[88..92].copy_from_slice(&entries_crc.to_le_bytes());
+        let crc = crc32fast::hash(header);
+        header[16..20].copy_from_slice(&crc.to_le_bytes());
+        disk
+    }
+
+    #[test]
+    fn test_parse_mbr() {
+        let data = build_mbr();
+        assert!(detect(&data));
+        let disk = parse(&data).unwrap();
+        assert_eq!(disk.format, "MBR");
+        assert_eq!(disk.entries.len(), 2);
+        assert!(disk.entries[1].0.contains("Partition 2 Linux"));
+        assert!(disk.entries[1].0.ends_with("bootable"));
+        assert_eq!(disk.entries[1].1, Some(1024));
+    }
+
+    #[test]
+    fn test_parse_gpt() {
+        let mut data = build_gpt();
+        let disk = parse(&data).unwrap();
+        assert_eq!(disk.format, "GPT");
+        assert_eq!(disk.entries[2].0, "GPT header");
+        assert!(disk.entries[3].0.contains("Linux filesystem"));
+        assert!(disk.entries[3].0.ends_with("size 0x4          rx"));
+        assert_eq!(disk.entries[3].1, Some(2048));
+
+        data[2 * SECTOR_SIZE + 32] = 5;
+        let disk = parse(&data).unwrap();
+        assert_eq!(disk.entries[2].0, "GPT header  entries CRC mismatch");
+    }
+
+    #[test]
+    fn test_parse_gpt_out_of_range() {
+        let mut data = build_gpt();
+        let entries = 2 * SECTOR_SIZE;
+        data[entries + 32..entries + 48].fill(0xff);
+        assert!(
+            parse(&data).unwrap().entries[3]
+                .0
+                .contains("start 0xffffffffffffffff")
+        );
+        data[SECTOR_SIZE + 72..SECTOR_SIZE + 80].fill(0xff);
+        let disk = parse(&data).unwrap();
+        assert_eq!(disk.entries.len(), 3);
+        assert!(disk.entries[2].0.ends_with("entries truncated"));
+    }
+
+    #[test]
+    fn test_format_guid() {
+        assert_eq!(
+            format_guid(&LINUX_FILESYSTEM),
+            "0FC63DAF-8483-4772-8E79-3D69D8477DE4"
+        );
+    }
+}
@@ -0,0 +1,353 @@
+use super::Structure;
+
+/// Upper bound for the number of packets listed.
+const MAX_PACKETS: usize = 65536;
+const SECTION_HEADER: u32 = 0x0a0d0d0a;
+const BYTE_ORDER_MAGIC: u32 = 0x1a2b3c4d;
+const INTERFACE_DESCRIPTION: u64 = 1;
+const SIMPLE_PACKET: u64 = 3;
+const ENHANCED_PACKET: u64 = 6;
+/// Option of interface description blocks holding the timestamp resolution.
+const IF_TSRESOL: usize = 9;
+
+/// Returns whether `data` is a pcap file and, if so, whether it is big-endian and its
+/// timestamps are in nanoseconds.
+fn pcap_byte_order(data: &[u8]) -> Option<(bool, bool)> {
+    let magic = u32::from_be_bytes(data.get(..4)?.try_into().unwrap());
+    match magic {
+        0xa1b2c3d4 => Some((true, false)),
+        0xd4c3b2a1 => Some((false, false)),
+        0xa1b23c4d => Some((true, true)),
+        0x4d3cb2a1 => Some((false, true)),
+        _ => None,
+    }
+}
+
+/// Whether `data` is a pcap or pcapng capture.
+pub fn detect(data: &[u8]) -> bool {
+    pcap_byte_order(data).is_some() || data.get(..4) == Some(&SECTION_HEADER.to_be_bytes())
+}
+
+pub fn parse(data: &[u8]) -> Option<Structure> {
+    match pcap_byte_order(data) {
+        Some((big_endian, nanoseconds)) => parse_pcap(data, big_endian, nanoseconds),
+        None => parse_pcapng(data),
+    }
+}
+
+fn parse_pcap(data: &[u8], big_endian: bool, nanoseconds: bool) -> Option<Structure> {
+    let mut pcap = Structure::new("pcap");
+    let mut position = pcap.add_fields(
+        "header",
+        0,
+        big_endian,
+        &[
+            ("magic", "u32"),
+            ("version_major", "u16"),
+            ("version_minor", "u16"),
+            ("thiszone", "i32"),
+            ("sigfigs", "u32"),
+            ("snaplen", "u32"),
+            ("network", "u32"),
+        ],
+    );
+    let snaplen = pcap.value(data, "header.snaplen")?;
+    let label = format!(
+        "Header  link type {}, snapshot length {}",
+        pcap.value(data, "header.network")?,
+        snaplen
+    );
+    pcap.entry(label, Some(0));
+    let digits = if nanoseconds { 9 } else { 6 };
+    while position + 16 <= data.len() && pcap.entries.len() <= MAX_PACKETS {
+        let prefix = format!("packet[{}]", pcap.entries.len());
+        let payload = pcap.add_fields(
+            &prefix,
+            position,
+            big_endian,
+            &[
+                ("ts_sec", "u32"),
+                ("ts_fraction", "u32"),
+                ("incl_len", "u32"),
+                ("orig_len", "u32"),
+            ],
+        );
+        let field = |name: &str| pcap.value(data, &format!("{}.{}", prefix, name)).unwrap();
+        let (captured, length) = (field("incl_len"), field("orig_len"));
+        let mut label = packet_label(
+            pcap.entries.len(),
+            position,
+            format_time(field("ts_sec"), field("ts_fraction"), digits),
+            captured,
+            length,
+        );
+        if captured > snaplen {
+            label += "  exceeds snapshot length";
+        }
+        if payload as u64 + captured > data.len() as u64 {
+            label += "  truncated";
+        }
+        pcap.entry(label, Some(position as u64));
+        position = payload + captured as usize;
+    }
+    Some(pcap)
+}
+
+fn parse_pcapng(data: &[u8]) -> Option<Structure> {
+    let mut pcapng = Structure::new("pcapng");
+    let mut big_endian = true;
+    // Timestamp resolution of each interface as (units per second, decimal digits).
+    let mut interfaces: Vec<(u64, usize)> = vec![];
+    let mut position = 0;
+    let mut packets = 0;
+    while position + 12 <= data.len() && pcapng.entries.len() < MAX_PACKETS {
+        let prefix = format!("block[{}]", pcapng.entries.len());
+        if data[position..position + 4] == SECTION_HEADER.to_be_bytes() {
+            let magic = data.get(position + 8..position + 12)?;
+            big_endian = magic == BYTE_ORDER_MAGIC.to_be_bytes();
+            interfaces.clear();
+        }
+        let body = pcapng.add_fields(
+            &prefix,
+            position,
+            big_endian,
+            &[("type", "u32"), ("total_length", "u32")],
+        );
+        let field = |pcapng: &Structure, name: &str| {
+            pcapng
+                .value(data, &format!("{}.{}", prefix, name))
+                .unwrap_or(0)
+        };
+        let (kind, total_length) = (field(&pcapng, "type"), field(&pcapng, "total_length"));
+        let end = position as u64 + total_length;
+        let mut label = match kind {
+            0x0a0d0d0a => {
+                pcapng.add_fields(
+                    &prefix,
+                    body,
+                    big_endian,
+                    &[
+                        ("byte_order_magic", "u32"),
+                        ("version_major", "u16"),
+                        ("version_minor", "u16"),
+                        ("section_length", "i64"),
+                    ],
+                );
+                format!("Section header  offset 0x{:x}", position)
+            }
+            INTERFACE_DESCRIPTION => {
+                let options = pcapng.add_fields(
+                    &prefix,
+                    body,
+                    big_endian,
+                    &[
+                        ("link_type", "u16"),
+                        ("reserved", "u16"),
+                        ("snaplen", "u32"),
+                    ],
+                );
+                let end = (end as usize).min(data.len()).saturating_sub(4);
+                interfaces.push(timestamp_resolution(data, options, end, big_endian));
+                format!(
+                    "Interface {}  link type {}",
+                    interfaces.len() - 1,
+                    field(&pcapng, "link_type")
+                )
+            }
+            ENHANCED_PACKET => {
+                pcapng.add_fields(
+                    &prefix,
+                    body,
+                    big_endian,
+                    &[
+                        ("interface_id", "u32"),
+                        ("timestamp_high", "u32"),
+                        ("timestamp_low", "u32"),
+                        ("captured_len", "u32"),
+                        ("orig_len", "u32"),
+                    ],
+                );
+                let timestamp =
+                    field(&pcapng, "timestamp_high") << 32 | field(&pcapng, "timestamp_low");
+                let interface = field(&pcapng, "interface_id") as usize;
+                let (units, digits) = interfaces.get(interface).copied().unwrap_or((1000000, 6));
+                let scale = 10u64.pow(digits as u32) as u128;
+                let fraction = (timestamp % units) as u128 * scale / units as u128;
+                packets += 1;
+                packet_label(
+                    packets - 1,
+                    position,
+                    format_time(timestamp / units, fraction as u64, digits),
+                    field(&pcapng, "captured_len"),
+                    field(&pcapng, "orig_len"),
+                )
+            }
+            SIMPLE_PACKET => {
+                pcapng.add_fields(&prefix, body, big_endian, &[("orig_len", "u32")]);
+                packets += 1;
+                let length = field(&pcapng, "orig_len");
+                let captured = total_length.saturating_sub(16).min(length);
+                packet_label(packets - 1, position, "-".into(), captured, length)
+            }
+            kind => format!("Block 0x{:x}  offset 0x{:x}", kind, position),
+        };
+        if total_length < 12 || total_length % 4 != 0 {
+            pcapng.entry(label + "  invalid length", Some(position as u64));
+            break;
+        }
+        if end > data.len() as u64 {
+            label += "  truncated";
+        } else {
+            pcapng.add_fields(&prefix, end as usize - 4, big_endian, &[("trailer", "u32")]);
+            if field(&pcapng, "trailer") != total_length {
+                label += "  length mismatch";
+            }
+        }
+        pcapng.entry(label, Some(position as u64));
+        position = end as usize;
+    }
+    Some(pcapng)
+}
+
+/// Reads the `if_tsresol` option between `start` and `end`, defaulting to microseconds.
+fn timestamp_resolution(data: &[u8], start: usize, end: usize, big_endian: bool) -> (u64, usize) {
+    let read = |offset: usize| {
+        let bytes = [data[offset], data[offset + 1]];
+        match big_endian {
+            true => u16::from_be_bytes(bytes) as usize,
+            false => u16::from_le_bytes(bytes) as usize,
+        }
+    };
+    let mut position = start;
+    while position + 4 <= end {
+        let (code, length) = (read(position), read(position + 2));
+        if code == 0 {
+            break;
+        }
+        if code == IF_TSRESOL && length == 1 && position + 4 < end {
+            let resolution = data[position + 4];
+            return match resolution & 0x80 {
+                0 => (
+                    10u64.checked_pow(resolution as u32).unwrap_or(1),
+                    resolution as usize,
+                ),
+                // Binary resolutions are shown with nanosecond digits.
+                _ => (1u64.checked_shl((resolution & 0x7f) as u32).unwrap_or(1), 9),
+            };
+        }
+        position += 4 + length.div_ceil(4) * 4;
+    }
+    (1000000, 6)
+}
+
+fn packet_label(index: usize, offset: usize, time: String, captured: u64, length: u64) -> String {
+    format!(
+        "Packet {:<6} offset 0x{:<8x} {}  {} of {} bytes",
+        index, offset, time, captured, length
+    )
+}
+
+/// Formats a Unix timestamp as a UTC date and time with `digits` fractional digits.
+fn format_time(seconds: u64, fraction: u64, digits: usize) -> String {
+    let (days, time) = ((seconds / 86400) as i64, seconds % 86400);
+    // Converts days since 1970-01-01 to a civil date, with years starting in March.
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:0>7$}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+        fraction,
+        digits
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_pcap() -> Vec<u8> {
+        let mut pcap = b"\xd4\xc3\xb2\xa1\x02\x00\x04\x00".to_vec();
+        pcap.extend([0; 8]);
+        pcap.extend(64u32.to_le_bytes());
+        pcap.extend(1u32.to_le_bytes());
+        for (seconds, length) in [(1700000000u32, 4u32), (1700000001, 80)] {
+            pcap.extend(seconds.to_le_bytes());
+            pcap.extend(250u32.to_le_bytes());
+            pcap.extend(length.to_le_bytes());
+            pcap.extend(length.to_le_bytes());
+            pcap.extend(vec![0xaa; 4]);
+        }
+        pcap
+    }
+
+    fn build_pcapng() -> Vec<u8> {
+        let mut pcapng = SECTION_HEADER.to_be_bytes().to_vec();
+        pcapng.extend(28u32.to_be_bytes());
+        pcapng.extend(BYTE_ORDER_MAGIC.to_be_bytes());
+        pcapng.extend(b"\x00\x01\x00\x00\xff\xff\xff\xff\xff\xff\xff\xff\x00\x00\x00\x1c");
+        pcapng.extend(b"\x00\x00\x00\x01\x00\x00\x00\x1c\x00\x01\x00\x00\x00\x00\x00\x00");
+        pcapng.extend(b"\x00\x09\x00\x01\x03\x00\x00\x00\x00\x00\x00\x1c");
+        pcapng.extend(b"\x00\x00\x00\x06\x00\x00\x00\x24\x00\x00\x00\x00");
+        pcapng.extend(b"\x00\x00\x00\x00\x00\x00\x13\x88\x00\x00\x00\x02\x00\x00\x00\x02");
+        pcapng.extend(b"ab\x00\x00\x00\x00\x00\x24");
+        pcapng
+    }
+
+    #[test]
+    fn test_parse_pcap() {
+        let data = build_pcap();
+        assert!(detect(&data));
+        let pcap = parse(&data).unwrap();
+        assert_eq!(pcap.entries[0].0, "Header  link type 1, snapshot length 64");
+        assert!(
+            pcap.entries[1]
+                .0
+                .ends_with("2023-11-14 22:13:20.000250  4 of 4 bytes")
+        );
+        assert_eq!(pcap.entries[2].1, Some(44));
+        assert!(
+            pcap.entries[2]
+                .0
+                .ends_with("exceeds snapshot length  truncated")
+        );
+    }
+
+    #[test]
+    fn test_parse_pcapng() {
+        let mut data = build_pcapng();
+        assert!(detect(&data));
+        let pcapng = parse(&data).unwrap();
+        assert_eq!(pcapng.format, "pcapng");
+        assert_eq!(pcapng.entries.len(), 3);
+        assert_eq!(pcapng.entries[1].0, "Interface 0  link type 1");
+        assert!(
+            pcapng.entries[2]
+                .0
+                .ends_with("1970-01-01 00:00:05.000  2 of 2 bytes")
+        );
+        assert_eq!(pcapng.entries[2].1, Some(56));
+
+        data[91] = 0x20;
+        let pcapng = parse(&data).unwrap();
+        assert!(pcapng.entries[2].0.ends_with("length mismatch"));
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0, 5, 3), "1970-01-01 00:00:00.005");
+        assert_eq!(format_time(951782400, 0, 1), "2000-02-29 00:00:00.0");
+    }
+}
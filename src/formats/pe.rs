@@ -0,0 +1,458 @@
+use super::{Structure, read_string};
+
+/// Upper bound for the number of sections, imports and resources listed.
+const MAX_ENTRIES: usize = 4096;
+const PE32_PLUS: u64 = 0x20b;
+const EXPORT_DIRECTORY: usize = 0;
+const IMPORT_DIRECTORY: usize = 1;
+const RESOURCE_DIRECTORY: usize = 2;
+const DIRECTORY_NAMES: [&str; 16] = [
+    "export",
+    "import",
+    "resource",
+    "exception",
+    "certificate",
+    "base_relocation",
+    "debug",
+    "architecture",
+    "global_ptr",
+    "tls",
+    "load_config",
+    "bound_import",
+    "iat",
+    "delay_import",
+    "clr_runtime",
+    "reserved",
+];
+
+/// A section as `(virtual address, virtual size, file offset, size in the file)`.
+type Section = (u64, u64, u64, u64);
+
+pub fn parse(data: &[u8]) -> Option<Structure> {
+    let mut pe = Structure::new("PE");
+    pe.entry("DOS header".into(), Some(0));
+    pe.add_fields("dos", 0, false, &[("e_magic", "char[2]")]);
+    pe.add_fields("dos", 0x3c, false, &[("e_lfanew", "u32")]);
+    let header = pe.value(data, "dos.e_lfanew")? as usize;
+    if data.get(header..header + 4)? != b"PE\0\0" {
+        return None;
+    }
+    pe.entry("PE header".into(), Some(header as u64));
+    let coff = [
+        ("signature", "char[4]"),
+        ("machine", "u16"),
+        ("number_of_sections", "u16"),
+        ("time_date_stamp", "u32"),
+        ("pointer_to_symbol_table", "u32"),
+        ("number_of_symbols", "u32"),
+        ("size_of_optional_header", "u16"),
+        ("characteristics", "u16"),
+    ];
+    let optional = pe.add_fields("pe", header, false, &coff);
+    let sections_offset = optional + pe.value(data, "pe.size_of_optional_header")? as usize;
+    let section_count = pe.value(data, "pe.number_of_sections")? as usize;
+
+    pe.add_fields("optional", optional, false, &[("magic", "u16")]);
+    let wide = pe.value(data, "optional.magic")? == PE32_PLUS;
+    let address = if wide { "u64" } else { "u32" };
+    let mut fields = vec![
+        ("major_linker_version", "u8"),
+        ("minor_linker_version", "u8"),
+        ("size_of_code", "u32"),
+        ("size_of_initialized_data", "u32"),
+        ("size_of_uninitialized_data", "u32"),
+        ("address_of_entry_point", "u32"),
+        ("base_of_code", "u32"),
+    ];
+    if !wide {
+        fields.push(("base_of_data", "u32"));
+    }
+    fields.extend([
+        ("image_base", address),
+        ("section_alignment", "u32"),
+        ("file_alignment", "u32"),
+        ("major_os_version", "u16"),
+        ("minor_os_version", "u16"),
+        ("major_image_version", "u16"),
+        ("minor_image_version", "u16"),
+        ("major_subsystem_version", "u16"),
+        ("minor_subsystem_version", "u16"),
+        ("win32_version_value", "u32"),
+        ("size_of_image", "u32"),
+        ("size_of_headers", "u32"),
+        ("checksum", "u32"),
+        ("subsystem", "u16"),
+        ("dll_characteristics", "u16"),
+        ("size_of_stack_reserve", address),
+        ("size_of_stack_commit", address),
+        ("size_of_heap_reserve", address),
+        ("size_of_heap_commit", address),
+        ("loader_flags", "u32"),
+        ("number_of_rva_and_sizes", "u32"),
+    ]);
+    let mut position = pe.add_fields("optional", optional + 2, false, &fields);
+    let directory_count = pe.value(data, "optional.number_of_rva_and_sizes")?.min(16) as usize;
+    let mut directories = vec![];
+    for name in &DIRECTORY_NAMES[..directory_count] {
+        let prefix = format!("directory.{}", name);
+        position = pe.add_fields(&prefix, position, false, &[("rva", "u32"), ("size", "u32")]);
+        directories.push(pe.value(data, &format!("{}.rva", prefix)).unwrap_or(0));
+    }
+
+    let section_header = [
+        ("name", "char[8]"),
+        ("virtual_size", "u32"),
+        ("virtual_address", "u32"),
+        ("size_of_raw_data", "u32"),
+        ("pointer_to_raw_data", "u32"),
+        ("pointer_to_relocations", "u32"),
+        ("pointer_to_linenumbers", "u32"),
+        ("number_of_relocations", "u16"),
+        ("number_of_linenumbers", "u16"),
+        ("characteristics", "u32"),
+    ];
+    let mut sections: Vec<Section> = vec![];
+    for index in 0..section_count.min(MAX_ENTRIES) {
+        let start = sections_offset + 40 * index;
+        if start + 40 > data.len() {
+            break;
+        }
+        let prefix = format!("section[{}]", index);
+        pe.add_fields(&prefix, start, false, &section_header);
+        let value = |name: &str| pe.value(data, &format!("{}.{}", prefix, name));
+        let section = (
+            value("virtual_address")?,
+            value("virtual_size")?,
+            value("pointer_to_raw_data")?,
+            value("size_of_raw_data")?,
+        );
+        let name = String::from_utf8_lossy(&data[start..start + 8]);
+        let label = format!(
+            "Section {:<3} {:<8}  offset 0x{:<8x} size 0x{:x}",
+            index,
+            name.trim_end_matches('\0'),
+            section.2,
+            section.3
+        );
+        pe.entry(label, Some(section.2));
+        sections.push(section);
+    }
+
+    let rva_to_offset = |rva: u64| {
+        sections
+            .iter()
+            .find(|(address, size, _, raw_size)| {
+                (*address..address + size.max(raw_size)).contains(&rva)
+            })
+            .map(|(address, _, offset, _)| rva - address + offset)
+            .or_else(|| {
+                let headers = pe.value(data, "optional.size_of_headers")?;
+                (rva < headers).then_some(rva)
+            })
+            .map(|offset| offset as usize)
+    };
+    let symbols = directories
+        .get(EXPORT_DIRECTORY)
+        .filter(|rva| **rva != 0)
+        .and_then(|rva| rva_to_offset(*rva))
+        .map(|offset| parse_exports(data, offset, &rva_to_offset))
+        .unwrap_or_default();
+    let exports: Vec<_> = symbols
+        .iter()
+        .map(|(offset, name)| (format!("Export {}", name), Some(*offset)))
+        .collect();
+    let mut imports = vec![];
+    if let Some(offset) = directories
+        .get(IMPORT_DIRECTORY)
+        .filter(|rva| **rva != 0)
+        .and_then(|rva| rva_to_offset(*rva))
+    {
+        parse_imports(data, offset, wide, &rva_to_offset, &mut imports);
+    }
+    let mut resources = vec![];
+    if let Some(offset) = directories
+        .get(RESOURCE_DIRECTORY)
+        .filter(|rva| **rva != 0)
+        .and_then(|rva| rva_to_offset(*rva))
+    {
+        parse_resources(data, offset, offset, "", &rva_to_offset, &mut resources);
+    }
+    for (label, offset) in exports.into_iter().chain(imports).chain(resources) {
+        pe.entry(label, offset.map(|offset| offset as u64));
+    }
+    pe.symbols = symbols;
+    Some(pe)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as u64)
+}
+
+/// Returns the file offsets and names of the functions exported by name.
+fn parse_exports(
+    data: &[u8],
+    offset: usize,
+    rva_to_offset: &dyn Fn(u64) -> Option<usize>,
+) -> Vec<(usize, String)> {
+    let mut exports = vec![];
+    let (Some(count), Some(functions), Some(names), Some(ordinals)) = (
+        read_u32(data, offset + 24),
+        read_u32(data, offset + 28).and_then(rva_to_offset),
+        read_u32(data, offset + 32).and_then(rva_to_offset),
+        read_u32(data, offset + 36).and_then(rva_to_offset),
+    ) else {
+        return exports;
+    };
+    for index in 0..(count as usize).min(MAX_ENTRIES) {
+        let Some(ordinal) = data.get(ordinals + 2 * index..ordinals + 2 * index + 2) else {
+            break;
+        };
+        let ordinal = u16::from_le_bytes([ordinal[0], ordinal[1]]) as usize;
+        let name = read_u32(data, names + 4 * index)
+            .and_then(rva_to_offset)
+            .and_then(|name| read_string(data, name));
+        let target = read_u32(data, functions + 4 * ordinal).and_then(rva_to_offset);
+        if let (Some(name), Some(target)) = (name, target) {
+            exports.push((target, name));
+        }
+    }
+    exports
+}
+
+/// Lists the imported libraries and the functions imported from them.
+fn parse_imports(
+    data: &[u8],
+    offset: usize,
+    wide: bool,
+    rva_to_offset: &dyn Fn(u64) -> Option<usize>,
+    entries: &mut Vec<(String, Option<usize>)>,
+) {
+    let thunk_size = if wide { 8 } else { 4 };
+    for descriptor in (offset..).step_by(20).take(MAX_ENTRIES) {
+        let (Some(lookup), Some(name), Some(thunks)) = (
+            read_u32(data, descriptor),
+            read_u32(data, descriptor + 12),
+            read_u32(data, descriptor + 16),
+        ) else {
+            return;
+        };
+        if name == 0 {
+            return;
+        }
+        let library = rva_to_offset(name)
+            .and_then(|offset| read_string(data, offset))
+            .unwrap_or_default();
+        entries.push((format!("Import {}", library), Some(descriptor)));
+        let lookup = if lookup != 0 { lookup } else { thunks };
+        let Some(table) = rva_to_offset(lookup) else {
+            continue;
+        };
+        for thunk in (table..).step_by(thunk_size).take(MAX_ENTRIES) {
+            let Some(bytes) = data.get(thunk..thunk + thunk_size) else {
+                break;
+            };
+            let value = bytes
+                .iter()
+                .rev()
+                .fold(0u64, |value, b| value << 8 | *b as u64);
+            if value == 0 {
+                break;
+            }
+            let ordinal = value >> (8 * thunk_size - 1) == 1;
+            let (function, target) = match ordinal {
+                true => (format!("#{}", value & 0xffff), None),
+                false => {
+                    let hint = rva_to_offset(value & 0x7fff_ffff);
+                    let name = hint.and_then(|hint| read_string(data, hint + 2));
+                    (name.unwrap_or_default(), hint)
+                }
+            };
+            entries.push((format!("  {}!{}", library, function), target));
+        }
+    }
+}
+
+/// Walks the resource directory tree of types, names and languages.
+fn parse_resources(
+    data: &[u8],
+    root: usize,
+    directory: usize,
+    path: &str,
+    rva_to_offset: &dyn Fn(u64) -> Option<usize>,
+    entries: &mut Vec<(String, Option<usize>)>,
+) {
+    let depth = path.matches('/').count();
+    let Some(header) = data.get(directory..directory + 16) else {
+        return;
+    };
+    let count = u16::from_le_bytes([header[12], header[13]]) as usize
+        + u16::from_le_bytes([header[14], header[15]]) as usize;
+    for entry in (directory + 16..).step_by(8).take(count.min(MAX_ENTRIES)) {
+        if entries.len() >= MAX_ENTRIES {
+            return;
+        }
+        let (Some(name), Some(target)) = (read_u32(data, entry), read_u32(data, entry + 4)) else {
+            return;
+        };
+        let name = match name >> 31 {
+            1 => {
+                let offset = root + (name & 0x7fff_ffff) as usize;
+                let length = data
+                    .get(offset..offset + 2)
+                    .map_or(0, |l| u16::from_le_bytes([l[0], l[1]]) as usize);
+                let units: Vec<u16> = data
+                    .get(offset + 2..offset + 2 + 2 * length)
+                    .unwrap_or_default()
+                    .chunks(2)
+                    .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+            _ if depth == 0 => resource_type(name),
+            _ => name.to_string(),
+        };
+        let path = format!("{}/{}", path, name);
+        let target_offset = root + (target & 0x7fff_ffff) as usize;
+        if target >> 31 == 1 {
+            // Directories only nest types, names and languages.
+            if depth < 2 {
+                parse_resources(data, root, target_offset, &path, rva_to_offset, entries);
+            }
+        } else {
+            let (Some(rva), Some(size)) = (
+                read_u32(data, target_offset),
+                read_u32(data, target_offset + 4),
+            ) else {
+                continue;
+            };
+            let label = format!("Resource {:<30} size 0x{:x}", &path[1..], size);
+            entries.push((label, rva_to_offset(rva)));
+        }
+    }
+}
+
+fn resource_type(id: u64) -> String {
+    let name = match id {
+        1 => "CURSOR",
+        2 => "BITMAP",
+        3 => "ICON",
+        4 => "MENU",
+        5 => "DIALOG",
+        6 => "STRING",
+        7 => "FONTDIR",
+        8 => "FONT",
+        9 => "ACCELERATOR",
+        10 => "RCDATA",
+        11 => "MESSAGETABLE",
+        12 => "GROUP_CURSOR",
+        14 => "GROUP_ICON",
+        16 => "VERSION",
+        24 => "MANIFEST",
+        _ => return id.to_string(),
+    };
+    name.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn put(data: &mut [u8], offset: usize, value: u32) {
+        data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Builds a PE32 file with one section at RVA 0x1000 holding an import of
+    /// `KERNEL32.dll!ExitProcess` and an icon resource.
+    fn build_pe() -> Vec<u8> {
+        let mut pe = vec![0; 0x400];
+        pe[..2].copy_from_slice(b"MZ");
+        put(&mut pe, 0x3c, 0x80);
+        pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        pe[0x86] = 1; // number_of_sections
+        pe[0x94] = 0xe0; // size_of_optional_header
+        pe[0x98] = 0x0b;
+        pe[0x99] = 0x01;
+        put(&mut pe, 0x98 + 60, 0x200); // size_of_headers
+        put(&mut pe, 0x98 + 92, 16); // number_of_rva_and_sizes
+        put(&mut pe, 0x98 + 104, 0x1000); // import directory
+        put(&mut pe, 0x98 + 112, 0x1100); // resource directory
+        let section = 0x98 + 0xe0;
+        pe[section..section + 5].copy_from_slice(b".data");
+        put(&mut pe, section + 8, 0x200);
+        put(&mut pe, section + 12, 0x1000);
+        put(&mut pe, section + 16, 0x200);
+        put(&mut pe, section + 20, 0x200);
+
+        // Import descriptor, lookup table, hint/name and library name.
+        put(&mut pe, 0x200, 0x1040);
+        put(&mut pe, 0x200 + 12, 0x1060);
+        put(&mut pe, 0x200 + 16, 0x1040);
+        put(&mut pe, 0x240, 0x1050);
+        pe[0x252..0x25d].copy_from_slice(b"ExitProcess");
+        pe[0x260..0x26c].copy_from_slice(b"KERNEL32.dll");
+
+        // Resource directories for ICON/1/1033 and the data entry.
+        pe[0x300 + 14] = 1;
+        put(&mut pe, 0x310, 3);
+        put(&mut pe, 0x314, 0x8000_0018);
+        pe[0x318 + 14] = 1;
+        put(&mut pe, 0x328, 1);
+        put(&mut pe, 0x32c, 0x8000_0030);
+        pe[0x330 + 14] = 1;
+        put(&mut pe, 0x340, 1033);
+        put(&mut pe, 0x344, 0x48);
+        put(&mut pe, 0x348, 0x1180);
+        put(&mut pe, 0x34c, 0x10);
+        pe
+    }
+
+    #[test]
+    fn test_parse() {
+        let data = build_pe();
+        let pe = parse(&data).unwrap();
+        let labels: Vec<_> = pe.entries.iter().map(|(label, _)| label.trim()).collect();
+        assert_eq!(labels[..2], ["DOS header", "PE header"]);
+        assert!(labels[2].starts_with("Section 0   .data"));
+        assert_eq!(labels[3], "Import KERNEL32.dll");
+        assert_eq!(labels[4], "KERNEL32.dll!ExitProcess");
+        assert!(labels[5].starts_with("Resource ICON/1/1033"));
+        let offsets: Vec<_> = pe.entries.iter().map(|(_, offset)| *offset).collect();
+        assert_eq!(
+            offsets,
+            vec![
+                Some(0),
+                Some(0x80),
+                Some(0x200),
+                Some(0x200),
+                Some(0x250),
+                Some(0x380)
+            ]
+        );
+        assert_eq!(pe.value(&data, "optional.size_of_headers"), Some(0x200));
+        assert_eq!(pe.value(&data, "directory.resource.rva"), Some(0x1100));
+    }
+
+    #[test]
+    fn test_parse_exports() {
+        let mut data = build_pe();
+        put(&mut data, 0x98 + 96, 0x1080); // export directory
+        put(&mut data, 0x280 + 24, 1);
+        put(&mut data, 0x280 + 28, 0x10b0);
+        put(&mut data, 0x280 + 32, 0x10b4);
+        put(&mut data, 0x280 + 36, 0x10b8);
+        put(&mut data, 0x2b0, 0x1010);
+        put(&mut data, 0x2b4, 0x10c0);
+        data[0x2c0..0x2c3].copy_from_slice(b"Run");
+        let pe = parse(&data).unwrap();
+        assert_eq!(pe.symbols, vec![(0x210, "Run".into())]);
+        assert!(pe.entries.contains(&("Export Run".into(), Some(0x210))));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let mut data = build_pe();
+        assert!(parse(&data[..0x40]).is_none());
+        data[0x80] = b'X';
+        assert!(parse(&data).is_none());
+    }
+}
@@ -0,0 +1,107 @@
+use super::Structure;
+
+pub const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// Upper bound for the number of chunks listed.
+const MAX_CHUNKS: usize = 65536;
+
+/// A chunk as `(offset, data length, type)`.
+type Chunk = (usize, usize, [u8; 4]);
+
+pub fn parse(data: &[u8]) -> Option<Structure> {
+    let mut png = Structure::new("PNG");
+    png.entry("Signature".into(), Some(0));
+    png.add_fields("", 0, false, &[("signature", "bytes[8]")]);
+    for (index, (offset, length, kind)) in chunks(data).into_iter().enumerate() {
+        let prefix = format!("chunk[{}]", index);
+        png.add_fields(
+            &prefix,
+            offset,
+            true,
+            &[("length", "u32"), ("type", "char[4]")],
+        );
+        let crc = offset + 8 + length;
+        png.add_fields(&prefix, crc, true, &[("crc", "u32")]);
+        let status = match compute_crc(data, offset, length) {
+            Some(crc) if png.value(data, &format!("{}.crc", prefix)) == Some(crc as u64) => "",
+            Some(_) => "CRC mismatch",
+            None => "truncated",
+        };
+        let label = format!(
+            "Chunk {:<4} {}  offset 0x{:<8x} length 0x{:<8x} {}",
+            index,
+            String::from_utf8_lossy(&kind),
+            offset,
+            length,
+            status
+        );
+        png.entry(label, Some(offset as u64));
+    }
+    Some(png)
+}
+
+/// Returns the chunks following the signature, stopping at the first truncated one.
+fn chunks(data: &[u8]) -> Vec<Chunk> {
+    let mut chunks = vec![];
+    let mut offset = SIGNATURE.len();
+    while chunks.len() < MAX_CHUNKS {
+        let Some(header) = data.get(offset..offset + 8) else {
+            break;
+        };
+        let length = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+        chunks.push((offset, length, header[4..].try_into().unwrap()));
+        offset += length + 12;
+    }
+    chunks
+}
+
+/// Computes the CRC over the type and data of the chunk at `offset`.
+fn compute_crc(data: &[u8], offset: usize, length: usize) -> Option<u32> {
+    data.get(offset + 8 + length..offset + 12 + length)?;
+    Some(crc32fast::hash(&data[offset + 4..offset + 8 + length]))
+}
+
+/// Returns the offset and the bytes of the correct CRC of the chunk containing `position`.
+pub fn fix_crc(data: &[u8], position: usize) -> Option<(usize, Vec<u8>)> {
+    let (offset, length, _) = chunks(data)
+        .into_iter()
+        .find(|(offset, length, _)| (*offset..offset + length + 12).contains(&position))?;
+    let crc = compute_crc(data, offset, length)?;
+    Some((offset + 8 + length, crc.to_be_bytes().to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_png() -> Vec<u8> {
+        let mut png = SIGNATURE.to_vec();
+        png.extend(b"\x00\x00\x00\x02abcd\x01\x02\x00\x00\x00\x00");
+        png.extend(b"\x00\x00\x00\x00IEND\xae\x42\x60\x82");
+        png
+    }
+
+    #[test]
+    fn test_parse() {
+        let data = build_png();
+        let png = parse(&data).unwrap();
+        let labels: Vec<_> = png.entries.iter().map(|(label, _)| label.trim()).collect();
+        assert!(labels[1].starts_with("Chunk 0    abcd  offset 0x8"));
+        assert!(labels[1].ends_with("CRC mismatch"));
+        assert!(labels[2].ends_with("length 0x0"));
+        assert_eq!(png.value(&data, "chunk[1].crc"), Some(0xae42_6082));
+    }
+
+    #[test]
+    fn test_fix_crc() {
+        let mut data = build_png();
+        let (offset, crc) = fix_crc(&data, 0x10).unwrap();
+        assert_eq!(offset, 0x12);
+        data[offset..offset + 4].copy_from_slice(&crc);
+        assert!(!parse(&data).unwrap().entries[1].0.contains("CRC"));
+        assert!(fix_crc(&data, 2).is_none());
+        assert_eq!(
+            fix_crc(&data, 0x16),
+            Some((0x1e, vec![0xae, 0x42, 0x60, 0x82]))
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use super::Structure;
+
+/// Upper bound for the number of chunks listed.
+const MAX_CHUNKS: usize = 65536;
+/// Nesting limit for `LIST` chunks.
+const MAX_DEPTH: usize = 16;
+
+pub fn parse(data: &[u8]) -> Option<Structure> {
+    let mut riff = Structure::new("RIFF");
+    let end = riff.add_fields("riff", 0, false, &[("id", "char[4]"), ("size", "u32")]);
+    riff.add_fields("riff", end, false, &[("form", "char[4]")]);
+    let size = riff.value(data, "riff.size")? as usize;
+    let form = String::from_utf8_lossy(data.get(8..12)?).to_string();
+    let label = match 8 + size {
+        length if length == data.len() => format!("RIFF {}", form),
+        length => format!(
+            "RIFF {}  size mismatch, 0x{:x} of 0x{:x} bytes",
+            form,
+            data.len(),
+            length
+        ),
+    };
+    riff.entry(label, Some(0));
+    parse_chunks(&mut riff, data, 12, (8 + size).min(data.len()), 1);
+    Some(riff)
+}
+
+/// Adds the chunks between `start` and `end`, descending into `LIST` chunks.
+fn parse_chunks(riff: &mut Structure, data: &[u8], start: usize, end: usize, depth: usize) {
+    let mut position = start;
+    while position + 8 <= end && riff.entries.len() < MAX_CHUNKS {
+        let prefix = format!("chunk[{}]", riff.entries.len());
+        let payload = riff.add_fields(
+            &prefix,
+            position,
+            false,
+            &[("id", "char[4]"), ("size", "u32")],
+        );
+        let size = riff.value(data, &format!("{}.size", prefix)).unwrap_or(0) as usize;
+        let id = String::from_utf8_lossy(&data[position..position + 4]).to_string();
+        let list = id == "LIST" && size >= 4 && payload + 4 <= end;
+        let mut label = format!("{}{}", "  ".repeat(depth), id);
+        if list {
+            riff.add_fields(&prefix, payload, false, &[("type", "char[4]")]);
+            label += &format!(" {}", String::from_utf8_lossy(&data[payload..payload + 4]));
+        }
+        label = format!("{:<24} offset 0x{:<8x} size 0x{:x}", label, position, size);
+        if payload + size > end {
+            label += "  exceeds parent";
+        }
+        riff.entry(label, Some(position as u64));
+        if list && depth < MAX_DEPTH {
+            parse_chunks(
+                riff,
+                data,
+                payload + 4,
+                (payload + size).min(end),
+                depth + 1,
+            );
+        }
+        // Chunks are padded to an even size.
+        position = payload + size + size % 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_wav() -> Vec<u8> {
+        let mut wav = b"RIFF\x00\x00\x00\x00WAVE".to_vec();
+        wav.extend(b"fmt \x02\x00\x00\x00ab");
+        wav.extend(b"LIST\x0e\x00\x00\x00INFOINAM\x01\x00\x00\x00x\x00");
+        wav.extend(b"data\x03\x00\x00\x00abc\x00");
+        let size = (wav.len() - 8) as u32;
+        wav[4..8].copy_from_slice(&size.to_le_bytes());
+        wav
+    }
+
+    #[test]
+    fn test_parse() {
+        let data = build_wav();
+        let riff = parse(&data).unwrap();
+        let labels: Vec<_> = riff
+            .entries
+            .iter()
+            .map(|(label, _)| label.split("  offset").next().unwrap().trim_end())
+            .collect();
+        assert_eq!(
+            labels,
+            vec!["RIFF WAVE", "  fmt", "  LIST INFO", "    INAM", "  data"]
+        );
+        let offsets: Vec<_> = riff.entries.iter().map(|(_, offset)| *offset).collect();
+        assert_eq!(
+            offsets,
+            vec![Some(0), Some(12), Some(22), Some(34), Some(44)]
+        );
+        assert_eq!(riff.value(&data, "chunk[4].size"), Some(3));
+    }
+
+    #[test]
+    fn test_size_validation() {
+        let mut data = build_wav();
+        data[48] = 0x10;
+        let riff = parse(&data).unwrap();
+        assert!(riff.entries[4].0.ends_with("exceeds parent"));
+        data.truncate(50);
+        let riff = parse(&data).unwrap();
+        assert!(riff.entries[0].0.contains("size mismatch"));
+        assert!(parse(&data[..6]).is_none());
+    }
+}
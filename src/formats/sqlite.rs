@@ -0,0 +1,195 @@
+use super::Structure;
+use std::collections::HashMap;
+
+pub const SIGNATURE: &[u8] = b"SQLite format 3\0";
+/// Upper bound for the number of pages listed.
+const MAX_PAGES: usize = 65536;
+
+pub fn parse(data: &[u8]) -> Option<Structure> {
+    let mut sqlite = Structure::new("SQLite");
+    sqlite.add_fields(
+        "header",
+        0,
+        true,
+        &[
+            ("magic", "char[16]"),
+            ("page_size", "u16"),
+            ("write_version", "u8"),
+            ("read_version", "u8"),
+            ("reserved_space", "u8"),
+            ("max_payload_fraction", "u8"),
+            ("min_payload_fraction", "u8"),
+            ("leaf_payload_fraction", "u8"),
+            ("change_counter", "u32"),
+            ("page_count", "u32"),
+            ("freelist_trunk", "u32"),
+            ("freelist_count", "u32"),
+            ("schema_cookie", "u32"),
+            ("schema_format", "u32"),
+            ("default_cache_size", "u32"),
+            ("largest_root_page", "u32"),
+            ("text_encoding", "u32"),
+            ("user_version", "u32"),
+            ("incremental_vacuum", "u32"),
+            ("application_id", "u32"),
+            ("reserved", "bytes[20]"),
+            ("version_valid_for", "u32"),
+            ("sqlite_version", "u32"),
+        ],
+    );
+    let field = |sqlite: &Structure, name: &str| sqlite.value(data, &format!("header.{}", name));
+    let page_size = match field(&sqlite, "page_size")? {
+        1 => 65536,
+        size => size as usize,
+    };
+    let mut label = format!("Header  page size {}", page_size);
+    if !page_size.is_power_of_two() || !(512..=65536).contains(&page_size) {
+        sqlite.entry(label + "  invalid page size", Some(0));
+        return Some(sqlite);
+    }
+    let pages = data.len().div_ceil(page_size);
+    let page_count = field(&sqlite, "page_count")? as usize;
+    if page_count != pages {
+        label += &format!("  {} pages in header, {} in file", page_count, pages);
+    }
+    sqlite.entry(label, Some(0));
+    let freelist = freelist_pages(data, page_size, field(&sqlite, "freelist_trunk")? as usize);
+    for number in 1..=pages.min(MAX_PAGES) {
+        let offset = (number - 1) * page_size;
+        let kind = match freelist.get(&number) {
+            Some(kind) => kind.to_string(),
+            None => parse_page(&mut sqlite, data, number, offset),
+        };
+        sqlite.entry(
+            format!("Page {:<8} offset 0x{:<8x} {}", number, offset, kind),
+            Some(offset as u64),
+        );
+    }
+    Some(sqlite)
+}
+
+/// Adds the b-tree header of page `number` at `offset` and describes the page.
+fn parse_page(sqlite: &mut Structure, data: &[u8], number: usize, offset: usize) -> String {
+    // The first page starts with the database header.
+    let header = offset + if number == 1 { 100 } else { 0 };
+    let kind = match data.get(header) {
+        Some(2) => "interior index",
+        Some(5) => "interior table",
+        Some(10) => "leaf index",
+        Some(13) => "leaf table",
+        _ => return "overflow or unused".into(),
+    };
+    let prefix = format!("page[{}]", number);
+    let end = sqlite.add_fields(
+        &prefix,
+        header,
+        true,
+        &[
+            ("type", "u8"),
+            ("first_freeblock", "u16"),
+            ("cell_count", "u16"),
+            ("cell_content_start", "u16"),
+            ("fragmented_bytes", "u8"),
+        ],
+    );
+    if kind.starts_with("interior") {
+        sqlite.add_fields(&prefix, end, true, &[("right_child", "u32")]);
+    }
+    match sqlite.value(data, &format!("{}.cell_count", prefix)) {
+        Some(cells) => format!("{}, {} cells", kind, cells),
+        None => format!("{}, truncated", kind),
+    }
+}
+
+/// Walks the freelist from its first trunk page and returns the kind of each free page.
+fn freelist_pages(data: &[u8], page_size: usize, trunk: usize) -> HashMap<usize, &'static str> {
+    let mut pages = HashMap::new();
+    let mut trunk = trunk;
+    let read = |offset: usize| {
+        let bytes = data.get(offset..offset + 4)?;
+        Some(u32::from_be_bytes(bytes.try_into().unwrap()) as usize)
+    };
+    // Stopping at pages seen before guards against cycles in corrupted databases.
+    while trunk != 0 && !pages.contains_key(&trunk) {
+        pages.insert(trunk, "freelist trunk");
+        let offset = (trunk - 1) * page_size;
+        let (Some(next), Some(count)) = (read(offset), read(offset + 4)) else {
+            break;
+        };
+        for index in 0..count.min(page_size / 4) {
+            if let Some(leaf) = read(offset + 8 + 4 * index) {
+                pages.entry(leaf).or_insert("freelist leaf");
+            }
+        }
+        trunk = next;
+    }
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_sqlite() -> Vec<u8> {
+        let mut sqlite = vec![0; 3 * 512];
+        sqlite[..16].copy_from_slice(SIGNATURE);
+        sqlite[16..18].copy_from_slice(&512u16.to_be_bytes());
+        sqlite[28..32].copy_from_slice(&3u32.to_be_bytes());
+        sqlite[32..36].copy_from_slice(&3u32.to_be_bytes());
+        sqlite[100] = 5;
+        sqlite[103..105].copy_from_slice(&1u16.to_be_bytes());
+        sqlite[512] = 13;
+        sqlite[515..517].copy_from_slice(&4u16.to_be_bytes());
+        sqlite
+    }
+
+    #[test]
+    fn test_parse() {
+        let data = build_sqlite();
+        let sqlite = parse(&data).unwrap();
+        let labels: Vec<_> = sqlite
+            .entries
+            .iter()
+            .map(|(label, _)| label.split("  ").last().unwrap().trim_start())
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                "page size 512",
+                "interior table, 1 cells",
+                "leaf table, 4 cells",
+                "freelist trunk"
+            ]
+        );
+        assert_eq!(sqlite.entries[3].1, Some(1024));
+        assert_eq!(sqlite.value(&data, "page[1].right_child"), Some(0));
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let mut data = build_sqlite();
+        data.truncate(1024);
+        let sqlite = parse(&data).unwrap();
+        assert!(
+            sqlite.entries[0]
+                .0
+                .ends_with("3 pages in header, 2 in file")
+        );
+
+        data[16..18].copy_from_slice(&1000u16.to_be_bytes());
+        let sqlite = parse(&data).unwrap();
+        assert_eq!(sqlite.entries.len(), 1);
+        assert!(sqlite.entries[0].0.ends_with("invalid page size"));
+    }
+
+    #[test]
+    fn test_freelist_cycle() {
+        let mut data = build_sqlite();
+        data[1024..1028].copy_from_slice(&3u32.to_be_bytes());
+        data[1028..1032].copy_from_slice(&1u32.to_be_bytes());
+        data[1032..1036].copy_from_slice(&2u32.to_be_bytes());
+        let pages = freelist_pages(&data, 512, 3);
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[&2], "freelist leaf");
+    }
+}
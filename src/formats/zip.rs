@@ -0,0 +1,165 @@
+use super::Structure;
+
+const END_OF_CENTRAL_DIRECTORY: &[u8] = b"PK\x05\x06";
+const CENTRAL_DIRECTORY_HEADER: &[u8] = b"PK\x01\x02";
+const LOCAL_FILE_HEADER: &[u8] = b"PK\x03\x04";
+/// Size of the end of central directory record without the comment.
+const END_SIZE: usize = 22;
+/// Upper bound for the number of entries listed.
+const MAX_ENTRIES: u64 = 65535;
+
+pub fn detect(data: &[u8]) -> bool {
+    data.starts_with(LOCAL_FILE_HEADER) || data.starts_with(END_OF_CENTRAL_DIRECTORY)
+}
+
+pub fn parse(data: &[u8]) -> Option<Structure> {
+    let mut zip = Structure::new("ZIP");
+    // The record is followed by a comment of up to 64 KiB.
+    let search = data.len().saturating_sub(END_SIZE + 0xffff);
+    let end = data[search..]
+        .windows(4)
+        .rposition(|window| window == END_OF_CENTRAL_DIRECTORY)?
+        + search;
+    let fields = [
+        ("signature", "u32"),
+        ("disk_number", "u16"),
+        ("central_directory_disk", "u16"),
+        ("disk_entries", "u16"),
+        ("total_entries", "u16"),
+        ("central_directory_size", "u32"),
+        ("central_directory_offset", "u32"),
+        ("comment_length", "u16"),
+    ];
+    zip.add_fields("end", end, false, &fields);
+    let count = zip.value(data, "end.total_entries")?;
+    let mut position = zip.value(data, "end.central_directory_offset")? as usize;
+    zip.entry(
+        format!("End of central directory, {} entries", count),
+        Some(end as u64),
+    );
+    zip.entry("Central directory".into(), Some(position as u64));
+
+    let central = [
+        ("signature", "u32"),
+        ("version_made_by", "u16"),
+        ("version_needed", "u16"),
+        ("flags", "u16"),
+        ("compression", "u16"),
+        ("modification_time", "u16"),
+        ("modification_date", "u16"),
+        ("crc32", "u32"),
+        ("compressed_size", "u32"),
+        ("uncompressed_size", "u32"),
+        ("name_length", "u16"),
+        ("extra_length", "u16"),
+        ("comment_length", "u16"),
+        ("disk_start", "u16"),
+        ("internal_attributes", "u16"),
+        ("external_attributes", "u32"),
+        ("local_header_offset", "u32"),
+    ];
+    let local = [
+        ("signature", "u32"),
+        ("version_needed", "u16"),
+        ("flags", "u16"),
+        ("compression", "u16"),
+        ("modification_time", "u16"),
+        ("modification_date", "u16"),
+        ("crc32", "u32"),
+        ("compressed_size", "u32"),
+        ("uncompressed_size", "u32"),
+        ("name_length", "u16"),
+        ("extra_length", "u16"),
+    ];
+    for index in 0..count.min(MAX_ENTRIES) {
+        if data.get(position..position + 4) != Some(CENTRAL_DIRECTORY_HEADER) {
+            zip.entry(
+                format!("Invalid central directory header {}", index),
+                Some(position as u64),
+            );
+            break;
+        }
+        let prefix = format!("central[{}]", index);
+        let name_start = zip.add_fields(&prefix, position, false, &central);
+        let value = |name: &str| zip.value(data, &format!("{}.{}", prefix, name));
+        let name_length = value("name_length")? as usize;
+        let name = String::from_utf8_lossy(data.get(name_start..name_start + name_length)?);
+        let header = value("local_header_offset")?;
+        let label = format!(
+            "{:<24} {:>10} bytes, {:>10} compressed",
+            name,
+            value("uncompressed_size")?,
+            value("compressed_size")?
+        );
+        let name = name.to_string();
+        position = name_start
+            + name_length
+            + value("extra_length")? as usize
+            + value("comment_length")? as usize;
+        zip.entry(label, Some(header));
+
+        let header = header as usize;
+        if data.get(header..header + 4) != Some(LOCAL_FILE_HEADER) {
+            zip.entry(format!("  Invalid local header of {}", name), None);
+            continue;
+        }
+        let prefix = format!("local[{}]", index);
+        let name_start = zip.add_fields(&prefix, header, false, &local);
+        let value = |name: &str| zip.value(data, &format!("{}.{}", prefix, name));
+        let start = name_start + value("name_length")? as usize + value("extra_length")? as usize;
+        zip.entry("  Data".into(), Some(start as u64));
+    }
+    Some(zip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds an archive holding `a.txt` stored uncompressed.
+    fn build_zip() -> Vec<u8> {
+        let mut zip = LOCAL_FILE_HEADER.to_vec();
+        zip.extend([0; 14]);
+        zip.extend([3, 0, 0, 0, 3, 0, 0, 0, 5, 0, 0, 0]);
+        zip.extend(b"a.txtabc");
+        let central = zip.len();
+        zip.extend(CENTRAL_DIRECTORY_HEADER);
+        zip.extend([0; 16]);
+        zip.extend([3, 0, 0, 0, 3, 0, 0, 0, 5, 0, 0, 0]);
+        zip.extend([0; 14]);
+        zip.extend(b"a.txt");
+        let size = zip.len() - central;
+        zip.extend(END_OF_CENTRAL_DIRECTORY);
+        zip.extend([0, 0, 0, 0, 1, 0, 1, 0]);
+        zip.extend((size as u32).to_le_bytes());
+        zip.extend((central as u32).to_le_bytes());
+        zip.extend([0, 0]);
+        zip
+    }
+
+    #[test]
+    fn test_parse() {
+        let data = build_zip();
+        assert!(detect(&data));
+        let zip = parse(&data).unwrap();
+        let labels: Vec<_> = zip.entries.iter().map(|(label, _)| label.trim()).collect();
+        assert_eq!(labels[0], "End of central directory, 1 entries");
+        assert!(labels[2].starts_with("a.txt"));
+        assert!(labels[2].ends_with("3 bytes,          3 compressed"));
+        let offsets: Vec<_> = zip.entries.iter().map(|(_, offset)| *offset).collect();
+        assert_eq!(offsets, vec![Some(89), Some(38), Some(0), Some(35)]);
+        assert_eq!(zip.value(&data, "local[0].name_length"), Some(5));
+    }
+
+    #[test]
+    fn test_parse_corrupted() {
+        let mut data = build_zip();
+        data[0] = b'X';
+        let zip = parse(&data).unwrap();
+        assert_eq!(zip.entries[3].0, "  Invalid local header of a.txt");
+        data[38] = b'X';
+        let zip = parse(&data).unwrap();
+        assert_eq!(zip.entries[2].0, "Invalid central directory header 0");
+        assert!(parse(&data[..80]).is_none());
+    }
+}
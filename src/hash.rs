@@ -0,0 +1,75 @@
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+pub enum HashAlgorithm {
+    Crc32,
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "crc32" => Ok(HashAlgorithm::Crc32),
+            "md5" => Ok(HashAlgorithm::Md5),
+            "sha1" => Ok(HashAlgorithm::Sha1),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            _ => Err(()),
+        }
+    }
+}
+
+impl HashAlgorithm {
+    /// Returns the digest of `data` as lowercase hex digits.
+    pub fn digest(&self, data: &[u8]) -> String {
+        self.digest_bytes(data)
+            .iter()
+            .map(|b| format!("{:0>2x}", b))
+            .collect()
+    }
+
+    /// Returns the digest of `data`, CRC32 in big-endian byte order.
+    pub fn digest_bytes(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Crc32 => crc32fast::hash(data).to_be_bytes().to_vec(),
+            HashAlgorithm::Md5 => Md5::digest(data).to_vec(),
+            HashAlgorithm::Sha1 => Sha1::digest(data).to_vec(),
+            HashAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert!(matches!("crc32".parse(), Ok(HashAlgorithm::Crc32)));
+        assert!(matches!("sha256".parse(), Ok(HashAlgorithm::Sha256)));
+        assert!("sha512".parse::<HashAlgorithm>().is_err());
+    }
+
+    #[test]
+    fn test_digest() {
+        let data = b"abc";
+        assert_eq!(HashAlgorithm::Crc32.digest(data), "352441c2");
+        assert_eq!(
+            HashAlgorithm::Md5.digest(data),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            HashAlgorithm::Sha1.digest(data),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            HashAlgorithm::Sha256.digest(data),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use std::path::PathBuf;
+
+/// Number of commands kept in the history.
+const MAX_ENTRIES: usize = 1000;
+
+/// The commands entered in Command Mode, searches included as `find <pattern>`, the latest last
+/// and each at most once. Loaded from and saved to a file if one is given.
+#[derive(Default)]
+pub struct History {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// Reads the history from `path`, one command per line, and saves it there from now on.
+    /// Starts an empty history if there is no such file.
+    pub fn load(path: PathBuf) -> Result<Self, std::io::Error> {
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(text) => text.lines().map(String::from).collect(),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => vec![],
+            Err(error) => return Err(error),
+        };
+        Ok(History {
+            entries,
+            path: Some(path),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Adds `command` as the latest entry, moving it if it is already in the history.
+    pub fn push(&mut self, command: &str) -> Result<(), std::io::Error> {
+        let command = command.trim();
+        if command.is_empty() {
+            return Ok(());
+        }
+        self.entries.retain(|entry| entry != command);
+        self.entries.push(command.into());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        match &self.path {
+            Some(path) => std::fs::write(path, self.entries.join("\n") + "\n"),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the latest entry before `index` starting with `prefix`, and its index.
+    pub fn previous(&self, prefix: &str, index: usize) -> Option<(usize, &str)> {
+        let index = self.entries[..index.min(self.entries.len())]
+            .iter()
+            .rposition(|entry| entry.starts_with(prefix))?;
+        Some((index, &self.entries[index]))
+    }
+
+    /// Returns the earliest entry after `index` starting with `prefix`, and its index.
+    pub fn next(&self, prefix: &str, index: usize) -> Option<(usize, &str)> {
+        let start = (index + 1).min(self.entries.len());
+        let index = start
+            + self.entries[start..]
+                .iter()
+                .position(|entry| entry.starts_with(prefix))?;
+        Some((index, &self.entries[index]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_browse() {
+        let mut history = History::default();
+        for command in ["find aa", "goto 0x10", "find bb", "find aa"] {
+            history.push(command).unwrap();
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.previous("find ", 3), Some((2, "find aa")));
+        assert_eq!(history.previous("find ", 2), Some((1, "find bb")));
+        assert_eq!(history.previous("find ", 1), None);
+        assert_eq!(history.next("find ", 1), Some((2, "find aa")));
+        assert_eq!(history.next("", 2), None);
+    }
+
+    #[test]
+    fn test_load() {
+        let path = std::env::temp_dir().join("ashe_test_history");
+        let _ = std::fs::remove_file(&path);
+        let mut history = History::load(path.clone()).unwrap();
+        history.push("calc 1 + 2").unwrap();
+        history.push("find 00ff").unwrap();
+        let history = History::load(path.clone()).unwrap();
+        assert_eq!(history.previous("", 2), Some((1, "find 00ff")));
+        std::fs::remove_file(path).unwrap();
+    }
+}
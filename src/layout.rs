@@ -0,0 +1,235 @@
+use std::ops::Range;
+
+/// A range of bytes collapsed into a single row showing `label`.
+#[derive(Clone)]
+pub struct Span {
+    pub range: Range<usize>,
+    pub label: String,
+    /// Whether the span elides a run of identical bytes rather than a folded range.
+    pub run: bool,
+}
+
+/// A row of the hex view.
+pub enum Row<'a> {
+    /// The bytes of a line starting at the given offset.
+    Bytes(usize),
+    Span(&'a Span),
+}
+
+/// Maps the rows of the hex view to offsets, with lines of `bytes_per_line` bytes and spans
+/// collapsed into single rows.
+///
+/// Lines start at a multiple of `bytes_per_line` plus a phase, so that a view scrolled by an
+/// arbitrary offset keeps its alignment. Spans are widened to whole lines, merging those which
+/// then overlap.
+pub struct Layout {
+    bytes_per_line: usize,
+    phase: usize,
+    spans: Vec<Span>,
+}
+
+impl Layout {
+    /// Creates the layout of a view whose first row starts at `offset`.
+    pub fn new(bytes_per_line: usize, offset: usize, mut spans: Vec<Span>) -> Self {
+        let mut layout = Layout {
+            bytes_per_line,
+            phase: offset % bytes_per_line,
+            spans: vec![],
+        };
+        spans.sort_by_key(|span| span.range.start);
+        for mut span in spans {
+            span.range = layout.line_start(span.range.start)..layout.line_end(span.range.end);
+            match layout.spans.last_mut() {
+                Some(last) if last.range.end >= span.range.start => {
+                    last.range.end = last.range.end.max(span.range.end);
+                }
+                _ => layout.spans.push(span),
+            }
+        }
+        layout
+    }
+
+    /// Collapses the lines within runs of a repeated byte, except the first, unless `expanded`
+    /// holds an offset within the run or fewer than two lines would be elided. Lines within
+    /// existing spans are left alone.
+    pub fn collapse_runs(mut self, data: &[u8], expanded: &[usize]) -> Self {
+        let mut runs = vec![];
+        let mut position = 0;
+        while let Some(byte) = data.get(position) {
+            let length = data[position..]
+                .iter()
+                .position(|other| other != byte)
+                .unwrap_or(data.len() - position);
+            let run = position..position + length;
+            let start = self.line_end(position) + self.bytes_per_line;
+            let end = self.line_start(run.end);
+            if end >= start + 2 * self.bytes_per_line
+                && !expanded.iter().any(|offset| run.contains(offset))
+                && self.span_at(start).is_none()
+                && self.span_at(end - 1).is_none()
+                && !self
+                    .spans
+                    .iter()
+                    .any(|span| (start..end).contains(&span.range.start))
+            {
+                runs.push(Span {
+                    range: start..end,
+                    label: format!("{:0>2x} repeated", byte),
+                    run: true,
+                });
+            }
+            position = run.end;
+        }
+        self.spans.extend(runs);
+        self.spans.sort_by_key(|span| span.range.start);
+        self
+    }
+
+    /// Returns the start of the line containing `position`, ignoring spans.
+    fn line_start(&self, position: usize) -> usize {
+        match position < self.phase {
+            true => 0,
+            false => position - (position - self.phase) % self.bytes_per_line,
+        }
+    }
+
+    /// Returns the first line start at or after `position`.
+    fn line_end(&self, position: usize) -> usize {
+        match self.line_start(position) {
+            start if start == position => start,
+            0 if self.phase > 0 => self.phase,
+            start => start + self.bytes_per_line,
+        }
+    }
+
+    /// Returns the span containing `position`, widened to whole lines.
+    pub fn span_at(&self, position: usize) -> Option<&Span> {
+        let index = self
+            .spans
+            .partition_point(|span| span.range.start <= position);
+        let span = &self.spans[index.checked_sub(1)?];
+        span.range.contains(&position).then_some(span)
+    }
+
+    /// Returns the start of the row containing `position`.
+    pub fn row_start(&self, position: usize) -> usize {
+        match self.span_at(position) {
+            Some(span) => span.range.start,
+            None => self.line_start(position),
+        }
+    }
+
+    /// Returns the start of the row following the row starting at `start`.
+    pub fn next(&self, start: usize) -> usize {
+        match self.span_at(start) {
+            Some(span) => span.range.end,
+            None => self.line_end(start + 1),
+        }
+    }
+
+    /// Returns the start of the row preceding the row starting at `start`, or 0 at the start.
+    pub fn previous(&self, start: usize) -> usize {
+        self.row_start(start.saturating_sub(1))
+    }
+
+    /// Returns `count` rows starting with the row containing `offset`.
+    pub fn rows(&self, offset: usize, count: usize) -> Vec<Row<'_>> {
+        let mut start = self.row_start(offset);
+        let mut rows = Vec::with_capacity(count);
+        for _ in 0..count {
+            rows.push(match self.span_at(start) {
+                Some(span) => Row::Span(span),
+                None => Row::Bytes(start),
+            });
+            start = self.next(start);
+        }
+        rows
+    }
+
+    /// Returns the offset of the first of `count` rows which end with the row containing
+    /// `position`.
+    pub fn scroll_to(&self, position: usize, count: usize) -> usize {
+        let mut start = self.row_start(position);
+        for _ in 1..count {
+            start = self.previous(start);
+        }
+        start
+    }
+}
+
+impl Row<'_> {
+    pub fn start(&self) -> usize {
+        match self {
+            Row::Bytes(start) => *start,
+            Row::Span(span) => span.range.start,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(range: Range<usize>) -> Span {
+        Span {
+            range,
+            label: "folded".into(),
+            run: false,
+        }
+    }
+
+    fn starts(layout: &Layout, offset: usize, count: usize) -> Vec<usize> {
+        layout.rows(offset, count).iter().map(Row::start).collect()
+    }
+
+    #[test]
+    fn test_rows() {
+        let layout = Layout::new(16, 0, vec![span(0x28..0x41), span(0x80..0x90)]);
+        assert_eq!(starts(&layout, 0, 6), vec![0, 0x10, 0x20, 0x50, 0x60, 0x70]);
+        assert!(matches!(layout.rows(0x30, 1)[0], Row::Span(span) if span.range == (0x20..0x50)));
+        assert_eq!(starts(&layout, 0x70, 3), vec![0x70, 0x80, 0x90]);
+        assert_eq!(layout.row_start(0x85), 0x80);
+        assert_eq!(layout.previous(0x50), 0x20);
+        assert_eq!(layout.previous(0x20), 0x10);
+        assert_eq!(layout.previous(0), 0);
+        assert_eq!(layout.scroll_to(0x95, 4), 0x60);
+    }
+
+    #[test]
+    fn test_phase() {
+        let layout = Layout::new(16, 0x24, vec![span(0x30..0x40)]);
+        assert_eq!(starts(&layout, 0, 4), vec![0, 4, 0x14, 0x24]);
+        assert_eq!(layout.span_at(0x30).unwrap().range, 0x24..0x44);
+        assert_eq!(starts(&layout, 0x24, 2), vec![0x24, 0x44]);
+        assert_eq!(layout.scroll_to(0x50, 3), 0x14);
+    }
+
+    #[test]
+    fn test_collapse_runs() {
+        let mut data = vec![1; 0x18];
+        data.extend([0; 0x48]);
+        data.extend([2; 0x8]);
+        data.extend([0; 0x30]);
+        let layout = Layout::new(16, 0, vec![]).collapse_runs(&data, &[]);
+        assert_eq!(layout.spans.len(), 1);
+        assert_eq!(layout.spans[0].range, 0x30..0x60);
+        assert_eq!(layout.spans[0].label, "00 repeated");
+        assert_eq!(starts(&layout, 0, 6), vec![0, 0x10, 0x20, 0x30, 0x60, 0x70]);
+
+        let layout = Layout::new(16, 0, vec![]).collapse_runs(&data, &[0x40]);
+        assert!(layout.spans.is_empty());
+        let layout = Layout::new(16, 0, vec![span(0x50..0x58)]).collapse_runs(&data, &[]);
+        assert_eq!(layout.spans.len(), 1);
+        assert!(!layout.spans[0].run);
+        let layout = Layout::new(16, 4, vec![]).collapse_runs(&data, &[]);
+        assert_eq!(layout.spans[0].range, 0x34..0x54);
+    }
+
+    #[test]
+    fn test_merged_spans() {
+        let layout = Layout::new(16, 0, vec![span(0x18..0x20), span(0x1c..0x34)]);
+        assert_eq!(layout.spans.len(), 1);
+        assert_eq!(layout.spans[0].range, 0x10..0x40);
+        assert!(layout.span_at(0x40).is_none());
+    }
+}
@@ -0,0 +1,49 @@
+//! A simple hex editor.
+//!
+//! The `ashe` binary is a thin front end to this library. Other tools can embed the parts they
+//! need: [`buffer`] holds edited data with undo and redo, [`codec`] reads and writes files in
+//! their encodings, [`search`] finds byte patterns, [`layout`] maps the rows of a hex view to
+//! offsets, [`parse`] reads the numbers and ranges that commands take, and [`editor`] is the
+//! whole interactive editor drawn on a [`terminal`].
+
+pub mod action;
+mod analysis;
+mod annotations;
+mod asm;
+mod base64;
+pub mod buffer;
+mod calc;
+mod carve;
+mod checksum;
+pub mod codec;
+mod compression;
+mod cyclic;
+mod decode;
+mod disasm;
+pub mod dump;
+pub mod editor;
+mod encoding;
+mod export;
+mod formats;
+mod hash;
+mod history;
+pub mod layout;
+pub mod logging;
+mod magic;
+mod messages;
+mod notes;
+mod overview;
+mod panel;
+pub mod parse;
+mod patch;
+mod regions;
+pub mod render;
+mod report;
+mod rom;
+pub mod search;
+mod settings;
+mod template;
+pub mod terminal;
+mod transform;
+mod tui;
+mod xref;
@@ -0,0 +1,21 @@
+use std::path::Path;
+use std::sync::Mutex;
+use tracing_subscriber::EnvFilter;
+
+/// Records events to the file at `path`, appending to it, with a verbosity given by `filter` in
+/// the syntax of `RUST_LOG`, e.g. `debug` or `info,ashe::buffer=trace`.
+pub fn init(path: &Path, filter: &str) -> Result<(), std::io::Error> {
+    let filter = EnvFilter::try_new(filter).map_err(|error| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, error.to_string())
+    })?;
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_ansi(false)
+        .with_writer(Mutex::new(file))
+        .init();
+    Ok(())
+}
@@ -0,0 +1,93 @@
+/// A file type recognized by the bytes at a fixed offset from its start.
+pub struct Signature {
+    pub name: &'static str,
+    pub extension: &'static str,
+    pub offset: usize,
+    pub magic: &'static [u8],
+}
+
+const fn signature(
+    name: &'static str,
+    extension: &'static str,
+    offset: usize,
+    magic: &'static [u8],
+) -> Signature {
+    Signature {
+        name,
+        extension,
+        offset,
+        magic,
+    }
+}
+
+pub const SIGNATURES: &[Signature] = &[
+    signature("PNG image", "png", 0, b"\x89PNG\r\n\x1a\n"),
+    signature("JPEG image", "jpg", 0, b"\xff\xd8\xff"),
+    signature("GIF image", "gif", 0, b"GIF87a"),
+    signature("GIF image", "gif", 0, b"GIF89a"),
+    signature("BMP image", "bmp", 0, b"BM"),
+    signature("PDF document", "pdf", 0, b"%PDF-"),
+    signature("ZIP archive", "zip", 0, b"PK\x03\x04"),
+    signature("gzip archive", "gz", 0, b"\x1f\x8b\x08"),
+    signature("bzip2 archive", "bz2", 0, b"BZh"),
+    signature("xz archive", "xz", 0, b"\xfd7zXZ\x00"),
+    signature("7-Zip archive", "7z", 0, b"7z\xbc\xaf\x27\x1c"),
+    signature("Zstandard archive", "zst", 0, b"\x28\xb5\x2f\xfd"),
+    signature("RAR archive", "rar", 0, b"Rar!\x1a\x07"),
+    signature("tar archive", "tar", 257, b"ustar"),
+    signature("ISO 9660 image", "iso", 0x8001, b"CD001"),
+    signature("ELF executable", "elf", 0, b"\x7fELF"),
+    signature("DOS/PE executable", "exe", 0, b"MZ"),
+    signature("Mach-O executable", "macho", 0, b"\xfe\xed\xfa\xce"),
+    signature("Mach-O executable", "macho", 0, b"\xfe\xed\xfa\xcf"),
+    signature("Mach-O executable", "macho", 0, b"\xce\xfa\xed\xfe"),
+    signature("Mach-O executable", "macho", 0, b"\xcf\xfa\xed\xfe"),
+    signature("Java class", "class", 0, b"\xca\xfe\xba\xbe"),
+    signature("WebAssembly module", "wasm", 0, b"\x00asm"),
+    signature("SQLite database", "sqlite", 0, b"SQLite format 3\x00"),
+    signature("RIFF container", "riff", 0, b"RIFF"),
+    signature("Ogg stream", "ogg", 0, b"OggS"),
+    signature("FLAC audio", "flac", 0, b"fLaC"),
+    signature("MP3 audio", "mp3", 0, b"ID3"),
+];
+
+/// Returns the signatures of the files which could start at `position`.
+pub fn matches_at(data: &[u8], position: usize) -> impl Iterator<Item = &'static Signature> {
+    SIGNATURES.iter().filter(move |signature| {
+        data.get(position + signature.offset..position + signature.offset + signature.magic.len())
+            == Some(signature.magic)
+    })
+}
+
+/// Identifies the type of the file `data`.
+pub fn identify(data: &[u8]) -> Option<&'static Signature> {
+    matches_at(data, 0).next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identify() {
+        assert_eq!(
+            identify(b"\x89PNG\r\n\x1a\n\x00").unwrap().name,
+            "PNG image"
+        );
+        assert_eq!(identify(b"\x7fELF\x02").unwrap().name, "ELF executable");
+        assert!(identify(b"\x89PNG").is_none());
+        assert!(identify(b"").is_none());
+
+        let mut tar = vec![0; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert_eq!(identify(&tar).unwrap().name, "tar archive");
+    }
+
+    #[test]
+    fn test_matches_at() {
+        let data = b"....PK\x03\x04....";
+        assert_eq!(matches_at(data, 0).count(), 0);
+        let names: Vec<_> = matches_at(data, 4).map(|s| s.name).collect();
+        assert_eq!(names, vec!["ZIP archive"]);
+    }
+}
@@ -1,8 +1,12 @@
-mod ashe;
-
+use ashe::dump::DumpFormat;
 use ashe::editor::Editor;
-use clap::Parser;
+use ashe::logging;
+use ashe::parse::parse_number;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use std::io::{BufWriter, IsTerminal, Write};
 use std::path::PathBuf;
+use std::process::ExitCode;
 
 #[derive(Parser, Debug)]
 #[command(version, about = "A Simple Hex Editor", long_about = None)]
@@ -10,15 +14,172 @@ struct Args {
     /// File to read
     file: PathBuf,
 
+    /// Further files to open, or with --diff the file to compare against
+    others: Vec<PathBuf>,
+
     /// Number of bytes to display per line
     #[arg(short, long, default_value_t = 16)]
     bytes_per_line: u32,
+
+    /// Show FILE and the single other file side by side, highlighting differing bytes
+    #[arg(long, requires = "others")]
+    diff: bool,
+
+    /// Edit the files as stored, without decoding HEX, S-record or compressed files
+    #[arg(long)]
+    raw: bool,
+
+    /// Run COMMAND without opening the editor, may be given multiple times
+    #[arg(short, long = "command", value_name = "COMMAND")]
+    commands: Vec<String>,
+
+    /// Place the cursor and the view at OFFSET, decimal or hexadecimal with `0x`
+    #[arg(long, value_name = "OFFSET", value_parser = parse_offset)]
+    offset: Option<usize>,
+
+    /// Edit only LENGTH bytes at OFFSET, written back in place on save
+    #[arg(long, value_name = "LENGTH", value_parser = parse_offset, requires = "offset")]
+    length: Option<usize>,
+
+    /// Open the editor at the first occurrence of the hex bytes PATTERN, or exit if there is none
+    #[arg(long, value_name = "PATTERN", conflicts_with = "find_str")]
+    find: Option<String>,
+
+    /// Open the editor at the first occurrence of TEXT, or exit if there is none
+    #[arg(long, value_name = "TEXT")]
+    find_str: Option<String>,
+
+    /// Print a plain hex dump of the file, or of the range given by --offset and --length,
+    /// instead of opening the editor, as done when the output is not a terminal
+    #[arg(long)]
+    dump: bool,
+
+    /// Format of the dump
+    #[arg(long, value_name = "FORMAT", default_value = "text")]
+    format: DumpFormat,
+
+    /// Record events, commands, file operations and errors to FILE
+    #[arg(long, value_name = "FILE")]
+    log: Option<PathBuf>,
+
+    /// Verbosity of the log, e.g. `debug` or `info,ashe::buffer=trace`
+    #[arg(long, value_name = "FILTER", default_value = "info", requires = "log")]
+    log_filter: String,
+}
+
+/// File in the home directory keeping the commands entered in Command Mode.
+const HISTORY_FILE: &str = ".ashe_history";
+/// Exit status after a clean exit.
+const SUCCESS: u8 = 0;
+/// Exit status after a usage or I/O error, a failed command or a missing `--find` pattern.
+const FAILURE: u8 = 1;
+/// Exit status after quitting with `Ctrl` + `C` or a batch run which left changes unsaved.
+const DISCARDED: u8 = 2;
+
+fn main() -> ExitCode {
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        Err(error) => {
+            let _ = error.print();
+            return ExitCode::from(if error.use_stderr() { FAILURE } else { SUCCESS });
+        }
+    };
+    if let Some(path) = &args.log
+        && let Err(error) = logging::init(path, &args.log_filter)
+    {
+        return fail(&format!("Failed to open log file: {}", error));
+    }
+    let editor = match (args.offset, args.length) {
+        (Some(offset), Some(length)) => {
+            Editor::init_window(&args.file, args.bytes_per_line, offset as u64, length)
+        }
+        _ => Editor::init(&args.file, args.bytes_per_line, args.raw),
+    };
+    let mut editor = match editor {
+        Ok(editor) => editor,
+        Err(error) => {
+            return fail(&format!(
+                "Failed to open {}: {}",
+                args.file.display(),
+                error
+            ));
+        }
+    };
+    if args.diff {
+        let [other] = &args.others[..] else {
+            let _ = Args::command()
+                .error(
+                    ErrorKind::WrongNumberOfValues,
+                    "--diff takes exactly two files",
+                )
+                .print();
+            return ExitCode::from(FAILURE);
+        };
+        if let Err(error) = editor.open_diff(other) {
+            return fail(&format!("Failed to open {}: {}", other.display(), error));
+        }
+    } else {
+        for other in &args.others {
+            if let Err(error) = editor.add_file(other, args.raw) {
+                return fail(&format!("Failed to open {}: {}", other.display(), error));
+            }
+        }
+    }
+    if let (Some(offset), None) = (args.offset, args.length) {
+        editor.move_to(offset);
+    }
+    let search = match (&args.find, &args.find_str) {
+        (Some(pattern), _) => Some((pattern, false)),
+        (_, Some(text)) => Some((text, true)),
+        _ => None,
+    };
+    let found = match search.map(|(pattern, text)| editor.find_first(pattern, text)) {
+        Some(Ok(position)) => Some(position),
+        Some(Err(message)) => return fail(message),
+        None => None,
+    };
+    if args.commands.is_empty() && (args.dump || !std::io::stdout().is_terminal()) {
+        let from = match (found, args.length) {
+            (Some(position), _) => position,
+            (None, Some(_)) => 0,
+            (None, None) => args.offset.unwrap_or(0),
+        };
+        let mut out = BufWriter::new(std::io::stdout().lock());
+        return match editor
+            .dump(args.format, from, &mut out)
+            .and_then(|_| out.flush())
+        {
+            Err(error) if error.kind() != std::io::ErrorKind::BrokenPipe => {
+                fail(&error.to_string())
+            }
+            _ => ExitCode::from(SUCCESS),
+        };
+    }
+    if !args.commands.is_empty() {
+        if !editor.run_commands(&args.commands) {
+            return ExitCode::from(FAILURE);
+        }
+    } else {
+        if let Some(home) = std::env::var_os("HOME") {
+            editor.load_history(PathBuf::from(home).join(HISTORY_FILE));
+        }
+        if let Err(error) = editor.run() {
+            return fail(&format!("Failed to run editor: {}", error));
+        }
+    }
+    match editor.is_modified() {
+        true => ExitCode::from(DISCARDED),
+        false => ExitCode::from(SUCCESS),
+    }
+}
+
+fn fail(message: &str) -> ExitCode {
+    eprintln!("{}", message);
+    ExitCode::from(FAILURE)
 }
 
-fn main() {
-    let args = Args::parse();
-    Editor::init(&args.file, args.bytes_per_line)
-        .expect("Failed to initialize editor")
-        .run()
-        .expect("Failed to run editor");
+/// Parses an offset as the editor commands do, in decimal or in hexadecimal prefixed by `0x`.
+fn parse_offset(value: &str) -> Result<usize, String> {
+    parse_number(value)
+        .ok_or_else(|| "expected a decimal number or a hex one prefixed by 0x".into())
 }
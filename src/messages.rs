@@ -0,0 +1,120 @@
+use crossterm::style::Color;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How serious a message is, which decides its color.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Severity::Info => Color::Reset,
+            Severity::Warning => Color::Yellow,
+            Severity::Error => Color::Red,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Message {
+    pub text: String,
+    pub severity: Severity,
+    posted: Instant,
+}
+
+/// Messages shown in the status bar until the next key press, or for a given lifetime, and the
+/// log of all messages of the session.
+#[derive(Default)]
+pub struct Messages {
+    queue: VecDeque<Message>,
+    log: Vec<Message>,
+}
+
+impl Messages {
+    pub fn push(&mut self, severity: Severity, text: impl AsRef<str>) {
+        match severity {
+            Severity::Info => tracing::info!("{}", text.as_ref()),
+            Severity::Warning => tracing::warn!("{}", text.as_ref()),
+            Severity::Error => tracing::error!("{}", text.as_ref()),
+        }
+        let message = Message {
+            text: text.as_ref().into(),
+            severity,
+            posted: Instant::now(),
+        };
+        self.log.push(message.clone());
+        self.queue.push_back(message);
+    }
+
+    /// Returns all messages of the session, oldest first, including those no longer shown.
+    pub fn log(&self) -> &[Message] {
+        &self.log
+    }
+
+    /// Returns the messages still shown, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &Message> {
+        self.queue.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Removes the messages posted at least `lifetime` ago, or all of them if `lifetime` is zero.
+    pub fn expire(&mut self, lifetime: Duration) {
+        self.queue
+            .retain(|message| !lifetime.is_zero() && message.posted.elapsed() < lifetime);
+    }
+
+    /// Returns the time until the latest message expires, if it expires without a key press.
+    pub fn remaining(&self, lifetime: Duration) -> Option<Duration> {
+        let message = self.queue.back().filter(|_| !lifetime.is_zero())?;
+        Some(lifetime.saturating_sub(message.posted.elapsed()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push() {
+        let mut messages = Messages::default();
+        messages.push(Severity::Info, "Saved");
+        messages.push(Severity::Error, "Writing failed");
+        let texts: Vec<_> = messages.iter().map(|message| &message.text).collect();
+        assert_eq!(texts, ["Saved", "Writing failed"]);
+        assert_eq!(messages.iter().last().unwrap().severity.color(), Color::Red);
+    }
+
+    #[test]
+    fn test_expire() {
+        let mut messages = Messages::default();
+        messages.push(Severity::Info, "Saved");
+        messages.expire(Duration::from_secs(60));
+        assert!(!messages.is_empty());
+        assert!(messages.remaining(Duration::from_secs(60)).unwrap() > Duration::ZERO);
+        assert!(messages.remaining(Duration::ZERO).is_none());
+        messages.expire(Duration::ZERO);
+        assert!(messages.is_empty());
+        assert_eq!(messages.log().len(), 1);
+        assert!(messages.remaining(Duration::from_secs(60)).is_none());
+    }
+}
@@ -0,0 +1,156 @@
+use super::parse::parse_number;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// A text note attached to a range of the file.
+pub struct Note {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+impl Note {
+    /// Parses a note from an offset or a range `<start>..<end>` and its text, which may be
+    /// enclosed in double quotes.
+    pub fn parse(location: &str, text: &str) -> Option<Self> {
+        let range = parse_location(location)?;
+        let text = text.trim();
+        let text = text
+            .strip_prefix('"')
+            .and_then(|text| text.strip_suffix('"'))
+            .unwrap_or(text);
+        if text.is_empty() {
+            return None;
+        }
+        Some(Note {
+            range,
+            text: text.into(),
+        })
+    }
+}
+
+/// Parses an offset, as the range of its byte, or a non-empty range `<start>..<end>`.
+pub fn parse_location(location: &str) -> Option<Range<usize>> {
+    let range = match location.split_once("..") {
+        Some((start, end)) => parse_number(start)?..parse_number(end)?,
+        None => {
+            let offset = parse_number(location)?;
+            offset..offset.checked_add(1)?
+        }
+    };
+    (range.start < range.end).then_some(range)
+}
+
+/// Returns the path of the file keeping the notes on `path`, next to it.
+pub fn sidecar(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(".notes");
+    path.with_file_name(name)
+}
+
+/// Reads the notes on `path` from its sidecar file, with one note per line as
+/// `<start>..<end> <text>`, along with the numbers of the lines skipped as invalid. Returns no
+/// notes if there is no sidecar file.
+pub fn load(path: &Path) -> Result<(Vec<Note>, Vec<usize>), std::io::Error> {
+    let text = match std::fs::read_to_string(sidecar(path)) {
+        Ok(text) => text,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok((vec![], vec![])),
+        Err(error) => return Err(error),
+    };
+    let mut notes = vec![];
+    let mut skipped = vec![];
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (location, text) = line.split_once(' ').unwrap_or((line, ""));
+        match Note::parse(location, text) {
+            Some(note) => notes.push(note),
+            None => skipped.push(index + 1),
+        }
+    }
+    Ok((notes, skipped))
+}
+
+/// Writes `notes` to the sidecar file of `path`, removing it if there are no notes.
+pub fn save(path: &Path, notes: &[Note]) -> Result<(), std::io::Error> {
+    if notes.is_empty() {
+        return match std::fs::remove_file(sidecar(path)) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        };
+    }
+    let text: String = notes
+        .iter()
+        .map(|note| {
+            format!(
+                "0x{:x}..0x{:x} {}\n",
+                note.range.start, note.range.end, note.text
+            )
+        })
+        .collect();
+    std::fs::write(sidecar(path), text)
+}
+
+/// Returns the innermost note containing `position`.
+pub fn at(notes: &[Note], position: usize) -> Option<&Note> {
+    notes
+        .iter()
+        .filter(|note| note.range.contains(&position))
+        .min_by_key(|note| note.range.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let note = Note::parse("0x40", "\"entry point\"").unwrap();
+        assert_eq!(
+            (note.range, note.text.as_str()),
+            (0x40..0x41, "entry point")
+        );
+        let note = Note::parse("16..0x20", " header ").unwrap();
+        assert_eq!((note.range, note.text.as_str()), (16..32, "header"));
+        assert!(Note::parse("0x20..0x10", "reversed").is_none());
+        assert!(Note::parse("0x10", "").is_none());
+        assert!(Note::parse("x", "text").is_none());
+        assert!(Note::parse("0xffffffffffffffff", "end").is_none());
+    }
+
+    #[test]
+    fn test_at() {
+        let notes = [
+            Note::parse("0..0x100", "outer").unwrap(),
+            Note::parse("0x10..0x20", "inner").unwrap(),
+        ];
+        assert_eq!(at(&notes, 0x18).unwrap().text, "inner");
+        assert_eq!(at(&notes, 0x20).unwrap().text, "outer");
+        assert!(at(&notes, 0x100).is_none());
+    }
+
+    #[test]
+    fn test_load_and_save() {
+        let path = std::env::temp_dir().join("ashe_test_notes.bin");
+        assert_eq!(
+            sidecar(&path).file_name().unwrap(),
+            "ashe_test_notes.bin.notes"
+        );
+        let notes = vec![Note::parse("0x40", "entry point").unwrap()];
+        save(&path, &notes).unwrap();
+        let text = std::fs::read_to_string(sidecar(&path)).unwrap();
+        assert_eq!(text, "0x40..0x41 entry point\n");
+        let (loaded, _) = load(&path).unwrap();
+        assert_eq!(
+            (loaded[0].range.clone(), loaded[0].text.as_str()),
+            (0x40..0x41, "entry point")
+        );
+
+        std::fs::write(sidecar(&path), "# notes\n0x40 ok\nbroken\n").unwrap();
+        let (loaded, skipped) = load(&path).unwrap();
+        assert_eq!((loaded.len(), skipped), (1, vec![3]));
+        save(&path, &[]).unwrap();
+        assert!(!sidecar(&path).exists());
+        assert!(load(&path).unwrap().0.is_empty());
+    }
+}
@@ -0,0 +1,178 @@
+use super::analysis;
+use super::terminal::{Terminal, draw, drawln};
+use super::tui;
+use crossterm::style::Color;
+use std::str::FromStr;
+
+/// How the cells of the overview are colored.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Coloring {
+    /// By the most common class of bytes: zeros, `0xff`, ASCII text, other control bytes or
+    /// bytes above `0x7f`.
+    Class,
+    Entropy,
+}
+
+impl FromStr for Coloring {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "class" => Ok(Coloring::Class),
+            "entropy" => Ok(Coloring::Entropy),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The whole file shown in place of the hex view with one colored cell per block of bytes,
+/// and a selected cell to zoom into.
+pub struct Overview {
+    coloring: Coloring,
+    /// Offset within the selected cell.
+    pub position: usize,
+    columns: usize,
+}
+
+impl Overview {
+    pub fn new(coloring: Coloring, position: usize, columns: usize) -> Self {
+        Overview {
+            coloring,
+            position,
+            columns,
+        }
+    }
+
+    /// Returns the number of bytes per cell for the file of `length` bytes to fit into `lines`.
+    pub fn cell_size(&self, length: usize, lines: u32) -> usize {
+        length.div_ceil(self.columns * lines.max(1) as usize).max(1)
+    }
+
+    /// Moves the selection by `delta` cells, with rows of cells wrapping around.
+    pub fn move_selection(&mut self, delta: i64, length: usize, lines: u32) {
+        let size = self.cell_size(length, lines) as i64;
+        let position = self.position as i64 - self.position as i64 % size + delta * size;
+        self.position = position.clamp(0, length.saturating_sub(1) as i64) as usize;
+    }
+
+    /// Moves the selection by `delta` rows of cells.
+    pub fn move_rows(&mut self, delta: i64, length: usize, lines: u32) {
+        self.move_selection(delta * self.columns as i64, length, lines);
+    }
+
+    /// Draws the overview of `data` framed by a box, marking the selected cell.
+    pub fn draw(&self, data: &[u8], lines: u32) -> Result<(), std::io::Error> {
+        let size = self.cell_size(data.len(), lines);
+        let title = format!(
+            " Overview: {} bytes per cell, selected {} ",
+            size,
+            tui::format_offset(self.position)
+        );
+        drawln!(
+            "\r {}{}{}{}{}{}",
+            tui::TOP_LEFT_CORNER,
+            tui::VERTICAL.repeat(11),
+            tui::TOP_T,
+            title,
+            tui::VERTICAL.repeat(self.columns.saturating_sub(title.chars().count())),
+            tui::TOP_RIGHT_CORNER
+        )?;
+        let selected = self.position / size;
+        for line in 0..lines as usize {
+            let start = line * self.columns * size;
+            draw!(
+                "\r {} {} {}",
+                tui::HORIZONTAL,
+                tui::format_offset(start),
+                tui::HORIZONTAL
+            )?;
+            for column in 0..self.columns {
+                let cell = line * self.columns + column;
+                let bytes = data.get(cell * size..((cell + 1) * size).min(data.len()));
+                match bytes {
+                    Some(bytes) if !bytes.is_empty() => {
+                        Terminal::set_background_color(cell_color(self.coloring, bytes))?;
+                        draw!("{}", if cell == selected { "\u{25c6}" } else { " " })?;
+                        Terminal::set_background_color(Color::Reset)?;
+                    }
+                    _ => draw!(" ")?,
+                }
+            }
+            drawln!("{}", tui::HORIZONTAL)?;
+        }
+        drawln!(
+            "\r {}{}{}{}{}",
+            tui::BOTTOM_LEFT_CORNER,
+            tui::VERTICAL.repeat(11),
+            tui::BOTTOM_T,
+            tui::VERTICAL.repeat(self.columns),
+            tui::BOTTOM_RIGHT_CORNER
+        )?;
+        Ok(())
+    }
+}
+
+/// Returns the color of a cell holding `bytes`.
+fn cell_color(coloring: Coloring, bytes: &[u8]) -> Color {
+    if coloring == Coloring::Entropy {
+        return analysis::entropy_color(analysis::entropy(bytes));
+    }
+    let mut counts = [0; 5];
+    for byte in bytes {
+        let class = match byte {
+            0 => 0,
+            0xff => 1,
+            b'\t' | b'\n' | b'\r' | 0x20..0x7f => 2,
+            0..0x80 => 3,
+            _ => 4,
+        };
+        counts[class] += 1;
+    }
+    let colors = [
+        Color::DarkGrey,
+        Color::Grey,
+        Color::DarkGreen,
+        Color::DarkBlue,
+        Color::DarkRed,
+    ];
+    let class = (0..counts.len()).max_by_key(|class| (counts[*class], 5 - class));
+    colors[class.unwrap_or_default()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_size() {
+        let overview = Overview::new(Coloring::Class, 0, 64);
+        assert_eq!(overview.cell_size(100, 10), 1);
+        assert_eq!(overview.cell_size(64 * 10 * 16, 10), 16);
+        assert_eq!(overview.cell_size(64 * 10 * 16 + 1, 10), 17);
+        assert_eq!(overview.cell_size(0, 0), 1);
+    }
+
+    #[test]
+    fn test_move_selection() {
+        let mut overview = Overview::new(Coloring::Entropy, 0x25, 4);
+        overview.move_selection(1, 0x100, 4);
+        assert_eq!(overview.position, 0x30);
+        overview.move_rows(-1, 0x100, 4);
+        assert_eq!(overview.position, 0);
+        overview.move_rows(100, 0x100, 4);
+        assert_eq!(overview.position, 0xff);
+    }
+
+    #[test]
+    fn test_cell_color() {
+        assert_eq!(cell_color(Coloring::Class, &[0, 0, b'a']), Color::DarkGrey);
+        assert_eq!(cell_color(Coloring::Class, b"text\n"), Color::DarkGreen);
+        assert_eq!(
+            cell_color(Coloring::Class, &[0x80, 0xff, 0x81]),
+            Color::DarkRed
+        );
+        assert_eq!(cell_color(Coloring::Class, &[0, 0xff]), Color::DarkGrey);
+        assert_eq!(cell_color(Coloring::Entropy, &[0; 16]), Color::DarkBlue);
+        assert_eq!("entropy".parse(), Ok(Coloring::Entropy));
+    }
+}
@@ -0,0 +1,208 @@
+use super::terminal::{Terminal, draw, drawln};
+use super::tui;
+use crossterm::style::Color;
+
+/// A list shown in place of the hex view, whose entries may point to an offset to jump to.
+pub struct Panel {
+    title: String,
+    entries: Vec<(String, Option<u32>)>,
+    checked: Option<Vec<bool>>,
+    /// Text typed to narrow down the entries, if the panel can be filtered.
+    filter: Option<String>,
+    /// Index of the selected entry among the entries matching the filter.
+    selected: usize,
+}
+
+impl Panel {
+    pub fn new(title: &str, entries: Vec<(String, Option<u32>)>) -> Self {
+        Panel {
+            title: title.into(),
+            entries,
+            checked: None,
+            filter: None,
+            selected: 0,
+        }
+    }
+
+    /// Lets typed text narrow down the entries to those containing it, ignoring case.
+    pub fn with_filter(mut self) -> Self {
+        self.filter = Some(String::new());
+        self
+    }
+
+    pub fn is_filtered(&self) -> bool {
+        self.filter.is_some()
+    }
+
+    pub fn push_filter(&mut self, c: char) {
+        if let Some(filter) = &mut self.filter {
+            filter.push(c);
+            self.selected = 0;
+        }
+    }
+
+    pub fn pop_filter(&mut self) {
+        if let Some(filter) = &mut self.filter {
+            filter.pop();
+            self.selected = 0;
+        }
+    }
+
+    /// Returns the indices of the entries matching the filter.
+    fn visible(&self) -> Vec<usize> {
+        let filter = self.filter.as_deref().unwrap_or_default().to_lowercase();
+        (0..self.entries.len())
+            .filter(|index| self.entries[*index].0.to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    /// Adds a checkbox to every entry, initially checked.
+    pub fn with_checkboxes(mut self) -> Self {
+        self.checked = Some(vec![true; self.entries.len()]);
+        self
+    }
+
+    pub fn toggle(&mut self) {
+        let Some(index) = self.selected() else {
+            return;
+        };
+        if let Some(checked) = self.checked.as_mut().and_then(|c| c.get_mut(index)) {
+            *checked = !*checked;
+        }
+    }
+
+    pub fn checked(&self) -> Option<&[bool]> {
+        self.checked.as_deref()
+    }
+
+    pub fn move_selection(&mut self, delta: i64) {
+        let last = self.visible().len().saturating_sub(1) as i64;
+        self.selected = (self.selected as i64 + delta).clamp(0, last) as usize;
+    }
+
+    /// Returns the index of the selected entry, if any entry matches the filter.
+    pub fn selected(&self) -> Option<usize> {
+        self.visible().get(self.selected).copied()
+    }
+
+    pub fn selected_offset(&self) -> Option<u32> {
+        self.selected().and_then(|index| self.entries[index].1)
+    }
+
+    /// Draws the panel framed by a box with an interior of `width` characters.
+    pub fn draw(&self, lines: u32, width: usize) -> Result<(), std::io::Error> {
+        let visible = self.visible();
+        let title = match &self.filter {
+            Some(filter) => format!(" {} ({}) > {}_ ", self.title, visible.len(), filter),
+            None => format!(" {} ({}) ", self.title, visible.len()),
+        };
+        drawln!(
+            "\r {}{}{}{}",
+            tui::TOP_LEFT_CORNER,
+            title,
+            tui::VERTICAL.repeat(width.saturating_sub(title.chars().count())),
+            tui::TOP_RIGHT_CORNER
+        )?;
+        let lines = lines as usize;
+        let first = (self.selected + 1).saturating_sub(lines);
+        for row in first..first + lines {
+            draw!("\r {} ", tui::HORIZONTAL)?;
+            let index = visible.get(row).copied().unwrap_or(self.entries.len());
+            let label = match (self.entries.get(index), &self.checked) {
+                (Some((label, _)), Some(checked)) => {
+                    let mark = if checked[index] { 'x' } else { ' ' };
+                    format!("[{}] {}", mark, label)
+                }
+                (Some((label, _)), None) => label.clone(),
+                (None, _) => String::new(),
+            };
+            let label: String = label.chars().take(width - 2).collect();
+            if row == self.selected && !visible.is_empty() {
+                Terminal::set_background_color(Color::DarkYellow)?;
+            }
+            draw!("{:<1$}", label, width - 2)?;
+            Terminal::set_background_color(Color::Reset)?;
+            drawln!(" {}", tui::HORIZONTAL)?;
+        }
+        drawln!(
+            "\r {}{}{}",
+            tui::BOTTOM_LEFT_CORNER,
+            tui::VERTICAL.repeat(width),
+            tui::BOTTOM_RIGHT_CORNER
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_test_panel() -> Panel {
+        Panel::new(
+            "Test",
+            vec![
+                ("first".into(), Some(0x10)),
+                ("second".into(), None),
+                ("third".into(), Some(0x30)),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_move_selection() {
+        let mut panel = setup_test_panel();
+        assert_eq!(panel.selected_offset(), Some(0x10));
+        panel.move_selection(1);
+        assert_eq!(panel.selected_offset(), None);
+        panel.move_selection(10);
+        assert_eq!(panel.selected_offset(), Some(0x30));
+        panel.move_selection(-10);
+        assert_eq!(panel.selected, 0);
+    }
+
+    #[test]
+    fn test_checkboxes() {
+        let mut panel = setup_test_panel();
+        panel.toggle();
+        assert!(panel.checked().is_none());
+
+        let mut panel = setup_test_panel().with_checkboxes();
+        panel.move_selection(1);
+        panel.toggle();
+        assert_eq!(panel.checked(), Some(&[true, false, true][..]));
+        panel.toggle();
+        assert_eq!(panel.checked(), Some(&[true, true, true][..]));
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut panel = setup_test_panel().with_checkboxes().with_filter();
+        panel.toggle();
+        panel.push_filter('I');
+        assert_eq!(panel.visible(), vec![0, 2]);
+        panel.move_selection(5);
+        assert_eq!(panel.selected(), Some(2));
+        panel.push_filter('x');
+        assert_eq!(panel.selected(), None);
+        panel.toggle();
+        panel.pop_filter();
+        panel.pop_filter();
+        panel.pop_filter();
+        assert_eq!(panel.selected_offset(), Some(0x10));
+        assert_eq!(panel.checked(), Some(&[false, true, true][..]));
+
+        let mut panel = setup_test_panel();
+        panel.push_filter('x');
+        assert!(!panel.is_filtered());
+        assert_eq!(panel.visible().len(), 3);
+    }
+
+    #[test]
+    fn test_empty_panel() {
+        let mut panel = Panel::new("Empty", vec![]);
+        panel.move_selection(1);
+        assert_eq!(panel.selected, 0);
+        assert_eq!(panel.selected_offset(), None);
+    }
+}
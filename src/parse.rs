@@ -0,0 +1,70 @@
+use std::ops::Range;
+
+/// Parses a range `<start>..<end>` of two numbers as read by `parse_number`.
+pub fn parse_range(value: &str) -> Option<Range<usize>> {
+    let (start, end) = value.split_once("..")?;
+    Some(parse_number(start)?..parse_number(end)?)
+}
+
+/// Parses a decimal number or a hexadecimal one prefixed by `0x`.
+pub fn parse_number(value: &str) -> Option<usize> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Parses hex digit pairs in either case, optionally prefixed by `0x` or `0X`.
+pub fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    if text.is_empty() || !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Returns an error about the line at index `line` of a text file, numbered from 1 in the message.
+pub fn invalid_data(line: usize, message: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("line {}: {}", line + 1, message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("42"), Some(42));
+        assert_eq!(parse_number("0x2a"), Some(42));
+        assert_eq!(parse_number("0X2A"), Some(42));
+        assert_eq!(parse_number("x"), None);
+        assert_eq!(parse_range("0x10..32"), Some(16..32));
+        assert_eq!(parse_range("16"), None);
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex("00fF10"), Some(vec![0x00, 0xff, 0x10]));
+        assert_eq!(parse_hex("0xdead"), Some(vec![0xde, 0xad]));
+        assert_eq!(parse_hex("0"), None);
+        assert_eq!(parse_hex("0x"), None);
+        assert_eq!(parse_hex("zz"), None);
+        assert_eq!(parse_hex("\u{e9}a"), None);
+        assert_eq!(
+            invalid_data(2, "unknown type").to_string(),
+            "line 3: unknown type"
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use super::{
+    invalid_data, read, read_footer, read_length, read_number, write_footer, write_number,
+};
+
+const HEADER: &[u8] = b"BPS1";
+const SOURCE_READ: u64 = 0;
+const TARGET_READ: u64 = 1;
+const SOURCE_COPY: u64 = 2;
+const TARGET_COPY: u64 = 3;
+
+/// Creates a BPS patch using only source and target reads, which keeps it simple while
+/// still being compact for in-place edits.
+pub fn create(original: &[u8], modified: &[u8]) -> Vec<u8> {
+    let unchanged = |i: usize| original.get(i) == Some(&modified[i]);
+    let mut patch = HEADER.to_vec();
+    write_number(&mut patch, original.len() as u64);
+    write_number(&mut patch, modified.len() as u64);
+    write_number(&mut patch, 0);
+    let mut i = 0;
+    while i < modified.len() {
+        let start = i;
+        let kind = unchanged(i);
+        while i < modified.len() && unchanged(i) == kind {
+            i += 1;
+        }
+        let action = if kind { SOURCE_READ } else { TARGET_READ };
+        write_number(&mut patch, ((i - start - 1) as u64) << 2 | action);
+        if !kind {
+            patch.extend(&modified[start..i]);
+        }
+    }
+    write_footer(&mut patch, original, modified);
+    patch
+}
+
+/// Applies a BPS patch after checking that `data` matches its source checksum.
+pub fn apply(patch: &[u8], data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    let (body, source_checksum, target_checksum) = read_footer(patch)?;
+    let mut body = body
+        .strip_prefix(HEADER)
+        .ok_or_else(|| invalid_data("Not a BPS patch"))?;
+    if crc32fast::hash(data) != source_checksum {
+        return Err(invalid_data("Source checksum mismatch"));
+    }
+    let source_length = read_length(&mut body)?;
+    let target_length = read_length(&mut body)?;
+    let metadata_length = read_length(&mut body)?;
+    read(&mut body, metadata_length)?;
+    if source_length != data.len() {
+        return Err(invalid_data("Source size mismatch"));
+    }
+
+    let mut output = Vec::new();
+    let mut source_offset: i64 = 0;
+    let mut target_offset: i64 = 0;
+    while !body.is_empty() {
+        let action = read_number(&mut body)?;
+        // Every action must fit in what is left of the target, which bounds the output.
+        let length = usize::try_from(action >> 2)
+            .ok()
+            .filter(|&length| length < target_length - output.len())
+            .ok_or_else(|| invalid_data("Target size mismatch"))?
+            + 1;
+        match action & 3 {
+            SOURCE_READ => {
+                let position = output.len();
+                let bytes = data
+                    .get(position..position + length)
+                    .ok_or_else(|| invalid_data("Invalid source read"))?;
+                output.extend(bytes);
+            }
+            TARGET_READ => output.extend(read(&mut body, length)?),
+            SOURCE_COPY => {
+                source_offset = source_offset
+                    .checked_add(read_offset(&mut body)?)
+                    .ok_or_else(|| invalid_data("Invalid source copy"))?;
+                let bytes = usize::try_from(source_offset)
+                    .ok()
+                    .and_then(|position| data.get(position..position.checked_add(length)?))
+                    .ok_or_else(|| invalid_data("Invalid source copy"))?;
+                output.extend(bytes);
+                source_offset += length as i64;
+            }
+            TARGET_COPY => {
+                target_offset = target_offset
+                    .checked_add(read_offset(&mut body)?)
+                    .ok_or_else(|| invalid_data("Invalid target copy"))?;
+                // Target copies may overlap the bytes they produce, so copy byte by byte.
+                for _ in 0..length {
+                    let byte = usize::try_from(target_offset)
+                        .ok()
+                        .and_then(|position| output.get(position).copied())
+                        .ok_or_else(|| invalid_data("Invalid target copy"))?;
+                    output.push(byte);
+                    target_offset += 1;
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+    if output.len() != target_length {
+        return Err(invalid_data("Target size mismatch"));
+    }
+    if crc32fast::hash(&output) != target_checksum {
+        return Err(invalid_data("Target checksum mismatch"));
+    }
+    Ok(output)
+}
+
+fn read_offset(body: &mut &[u8]) -> Result<i64, std::io::Error> {
+    let value = read_number(body)?;
+    let offset = (value >> 1) as i64;
+    Ok(if value & 1 != 0 { -offset } else { offset })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create() {
+        let patch = create(&[1, 2, 3, 4], &[1, 9, 3, 4, 5]);
+        assert_eq!(&patch[..13], b"BPS1\x84\x85\x80\x80\x81\x09\x84\x81\x05");
+        assert_eq!(patch.len(), 25);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let original: Vec<u8> = (0..=255).collect();
+        let mut modified = original.clone();
+        modified[10] = 0;
+        modified[200..210].fill(0xff);
+        let shorter = modified[..250].to_vec();
+        let mut longer = modified.clone();
+        longer.extend([1, 0, 2]);
+
+        for target in [modified, shorter, longer] {
+            let patch = create(&original, &target);
+            assert_eq!(apply(&patch, &original).unwrap(), target);
+        }
+    }
+
+    #[test]
+    fn test_apply_copies() {
+        let source = b"abcdef";
+        let target = b"defabcabcabc";
+        let mut patch = HEADER.to_vec();
+        write_number(&mut patch, 6);
+        write_number(&mut patch, 12);
+        write_number(&mut patch, 0);
+        // Copy "def" from source offset 3, then "abc" from source offset 0.
+        write_number(&mut patch, 2 << 2 | SOURCE_COPY);
+        write_number(&mut patch, 3 << 1);
+        write_number(&mut patch, 2 << 2 | SOURCE_COPY);
+        write_number(&mut patch, 6 << 1 | 1);
+        // Repeat the last three bytes twice with an overlapping target copy.
+        write_number(&mut patch, 5 << 2 | TARGET_COPY);
+        write_number(&mut patch, 3 << 1);
+        write_footer(&mut patch, source, target);
+        assert_eq!(apply(&patch, source).unwrap(), target);
+    }
+
+    #[test]
+    fn test_apply_wrong_source() {
+        let patch = create(&[1, 2, 3], &[1, 2, 4]);
+        assert!(apply(&patch, &[1, 2, 5]).is_err());
+    }
+
+    #[test]
+    fn test_apply_invalid_sizes() {
+        let header = |target_length: u64| {
+            let mut patch = HEADER.to_vec();
+            write_number(&mut patch, 1);
+            write_number(&mut patch, target_length);
+            write_number(&mut patch, 0);
+            patch
+        };
+        let mut patch = header(u64::MAX >> 1);
+        write_footer(&mut patch, &[0], &[0]);
+        assert!(apply(&patch, &[0]).is_err());
+
+        // A target copy far longer than the declared target.
+        let mut patch = header(2);
+        write_number(&mut patch, TARGET_READ);
+        patch.push(7);
+        write_number(&mut patch, (1 << 40) << 2 | TARGET_COPY);
+        write_number(&mut patch, 0);
+        write_footer(&mut patch, &[0], &[7, 7]);
+        assert!(apply(&patch, &[0]).is_err());
+
+        let mut patch = header(1);
+        write_number(&mut patch, SOURCE_COPY);
+        write_number(&mut patch, u64::MAX - 1);
+        write_footer(&mut patch, &[0], &[0]);
+        assert!(apply(&patch, &[0]).is_err());
+    }
+}
@@ -0,0 +1,140 @@
+use super::{invalid_data, read};
+
+const HEADER: &[u8] = b"PATCH";
+const FOOTER: &[u8] = b"EOF";
+const MAX_OFFSET: usize = 0xff_ffff;
+const MAX_RECORD_LENGTH: usize = 0xffff;
+/// Offset which reads as the footer and thus cannot start a record.
+const EOF_OFFSET: usize = 0x45_4f46;
+
+/// Creates an IPS patch, using the truncation extension if `modified` is shorter.
+pub fn create(original: &[u8], modified: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    let differs = |i: usize| original.get(i) != Some(&modified[i]);
+    let mut patch = HEADER.to_vec();
+    let mut i = 0;
+    while i < modified.len() {
+        if !differs(i) {
+            i += 1;
+            continue;
+        }
+        let start = if i == EOF_OFFSET { i - 1 } else { i };
+        if start > MAX_OFFSET {
+            return Err(invalid_data("Offset too large for IPS"));
+        }
+        let mut end = i;
+        while end < modified.len() && end - start < MAX_RECORD_LENGTH && differs(end) {
+            end += 1;
+        }
+        patch.extend(&(start as u32).to_be_bytes()[1..]);
+        patch.extend(((end - start) as u16).to_be_bytes());
+        patch.extend(&modified[start..end]);
+        i = end;
+    }
+    patch.extend(FOOTER);
+    if modified.len() < original.len() {
+        if modified.len() > MAX_OFFSET {
+            return Err(invalid_data("Offset too large for IPS"));
+        }
+        patch.extend(&(modified.len() as u32).to_be_bytes()[1..]);
+    }
+    Ok(patch)
+}
+
+pub fn apply(patch: &[u8], data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    let mut patch = patch
+        .strip_prefix(HEADER)
+        .ok_or_else(|| invalid_data("Not an IPS patch"))?;
+    let mut output = data.to_vec();
+    loop {
+        if let Some(rest) = patch.strip_prefix(FOOTER) {
+            match rest {
+                [] => {}
+                [a, b, c] => output.truncate(u32::from_be_bytes([0, *a, *b, *c]) as usize),
+                _ => return Err(invalid_data("Trailing data in IPS patch")),
+            }
+            return Ok(output);
+        }
+        let offset = read_u24(&mut patch)?;
+        let (length, rle) = match read(&mut patch, 2)? {
+            [0, 0] => {
+                let length = u16::from_be_bytes(read(&mut patch, 2)?.try_into().unwrap());
+                (length as usize, Some(read(&mut patch, 1)?[0]))
+            }
+            length => (
+                u16::from_be_bytes(length.try_into().unwrap()) as usize,
+                None,
+            ),
+        };
+        if output.len() < offset + length {
+            output.resize(offset + length, 0);
+        }
+        match rle {
+            Some(value) => output[offset..offset + length].fill(value),
+            None => output[offset..offset + length].copy_from_slice(read(&mut patch, length)?),
+        }
+    }
+}
+
+fn read_u24(patch: &mut &[u8]) -> Result<usize, std::io::Error> {
+    let value = read(patch, 3)?;
+    Ok(u32::from_be_bytes([0, value[0], value[1], value[2]]) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create() {
+        let patch = create(&[0, 1, 2, 3, 4], &[0, 9, 9, 3, 4, 5]).unwrap();
+        assert_eq!(
+            patch,
+            b"PATCH\x00\x00\x01\x00\x02\x09\x09\x00\x00\x05\x00\x01\x05EOF"
+        );
+        assert_eq!(create(&[1, 2], &[1, 2]).unwrap(), b"PATCHEOF");
+    }
+
+    #[test]
+    fn test_create_truncated() {
+        let patch = create(&[0, 1, 2, 3], &[0, 1]).unwrap();
+        assert_eq!(patch, b"PATCHEOF\x00\x00\x02");
+    }
+
+    #[test]
+    fn test_create_avoids_eof_offset() {
+        let original = vec![0; EOF_OFFSET + 1];
+        let mut modified = original.clone();
+        modified[EOF_OFFSET] = 1;
+        let patch = create(&original, &modified).unwrap();
+        assert_eq!(patch, b"PATCH\x45\x4f\x45\x00\x02\x00\x01EOF");
+        assert_eq!(apply(&patch, &original).unwrap(), modified);
+    }
+
+    #[test]
+    fn test_apply() {
+        let patch = b"PATCH\x00\x00\x01\x00\x02\x09\x09\x00\x00\x06\x00\x00\x00\x02\x07EOF";
+        assert_eq!(
+            apply(patch, &[0, 1, 2, 3]).unwrap(),
+            vec![0, 9, 9, 3, 0, 0, 7, 7]
+        );
+        assert_eq!(apply(b"PATCHEOF\x00\x00\x01", &[1, 2]).unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn test_apply_invalid() {
+        assert!(apply(b"PTCH", &[]).is_err());
+        assert!(apply(b"PATCH\x00\x00\x01\x00\x02\x09", &[]).is_err());
+        assert!(apply(b"PATCHEOF\x00", &[]).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let original: Vec<u8> = (0..=255).collect();
+        let mut modified = original.clone();
+        modified[10] = 0;
+        modified[200..210].fill(0xff);
+        modified.truncate(250);
+        let patch = create(&original, &modified).unwrap();
+        assert_eq!(apply(&patch, &original).unwrap(), modified);
+    }
+}
@@ -0,0 +1,181 @@
+mod bps;
+mod ips;
+mod ups;
+mod vcdiff;
+
+use std::path::Path;
+
+pub enum PatchFormat {
+    Ips,
+    Bps,
+    Ups,
+    Vcdiff,
+}
+
+impl PatchFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ips" => Some(PatchFormat::Ips),
+            "bps" => Some(PatchFormat::Bps),
+            "ups" => Some(PatchFormat::Ups),
+            "vcdiff" | "vcd" | "xd" => Some(PatchFormat::Vcdiff),
+            _ => None,
+        }
+    }
+
+    /// Creates a patch transforming `original` into `modified`.
+    pub fn create(&self, original: &[u8], modified: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        match self {
+            PatchFormat::Ips => ips::create(original, modified),
+            PatchFormat::Bps => Ok(bps::create(original, modified)),
+            PatchFormat::Ups => Ok(ups::create(original, modified)),
+            PatchFormat::Vcdiff => Ok(vcdiff::create(original, modified)),
+        }
+    }
+
+    pub fn apply(&self, patch: &[u8], data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        match self {
+            PatchFormat::Ips => ips::apply(patch, data),
+            PatchFormat::Bps => bps::apply(patch, data),
+            PatchFormat::Ups => ups::apply(patch, data),
+            PatchFormat::Vcdiff => vcdiff::apply(patch, data),
+        }
+    }
+}
+
+/// Largest source or target size a patch may declare, as files are edited in memory.
+const MAX_LENGTH: u64 = 1 << 32;
+
+fn invalid_data(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+fn read<'a>(patch: &mut &'a [u8], length: usize) -> Result<&'a [u8], std::io::Error> {
+    let (value, rest) = patch
+        .split_at_checked(length)
+        .ok_or_else(|| invalid_data("Truncated patch"))?;
+    *patch = rest;
+    Ok(value)
+}
+
+/// Writes a number in the variable length encoding used by BPS and UPS.
+fn write_number(patch: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            patch.push(byte | 0x80);
+            return;
+        }
+        patch.push(byte);
+        value -= 1;
+    }
+}
+
+fn read_number(patch: &mut &[u8]) -> Result<u64, std::io::Error> {
+    let mut value: u64 = 0;
+    let mut shift: u64 = 1;
+    loop {
+        let byte = read(patch, 1)?[0];
+        value = (byte as u64 & 0x7f)
+            .checked_mul(shift)
+            .and_then(|part| value.checked_add(part))
+            .ok_or_else(|| invalid_data("Number too large"))?;
+        if byte & 0x80 != 0 {
+            return Ok(value);
+        }
+        shift = shift
+            .checked_mul(0x80)
+            .ok_or_else(|| invalid_data("Number too large"))?;
+        value = value
+            .checked_add(shift)
+            .ok_or_else(|| invalid_data("Number too large"))?;
+    }
+}
+
+/// Reads a source or target size, rejecting sizes above `MAX_LENGTH`.
+fn read_length(patch: &mut &[u8]) -> Result<usize, std::io::Error> {
+    Some(read_number(patch)?)
+        .filter(|&length| length <= MAX_LENGTH)
+        .and_then(|length| usize::try_from(length).ok())
+        .ok_or_else(|| invalid_data("Invalid size"))
+}
+
+/// Appends the source, target and patch CRC32 footer shared by BPS and UPS.
+fn write_footer(patch: &mut Vec<u8>, source: &[u8], target: &[u8]) {
+    patch.extend(crc32fast::hash(source).to_le_bytes());
+    patch.extend(crc32fast::hash(target).to_le_bytes());
+    patch.extend(crc32fast::hash(patch).to_le_bytes());
+}
+
+/// Validates the patch checksum and splits off the footer, returning the source and target
+/// checksums.
+fn read_footer(patch: &[u8]) -> Result<(&[u8], u32, u32), std::io::Error> {
+    let (body, footer) = patch
+        .split_at_checked(patch.len().saturating_sub(12))
+        .filter(|(_, footer)| footer.len() == 12)
+        .ok_or_else(|| invalid_data("Truncated patch"))?;
+    let checksum = |i: usize| u32::from_le_bytes(footer[i..i + 4].try_into().unwrap());
+    if crc32fast::hash(&patch[..patch.len() - 4]) != checksum(8) {
+        return Err(invalid_data("Patch checksum mismatch"));
+    }
+    Ok((body, checksum(0), checksum(4)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        assert!(matches!(
+            PatchFormat::from_path(Path::new("hack.IPS")),
+            Some(PatchFormat::Ips)
+        ));
+        assert!(matches!(
+            PatchFormat::from_path(Path::new("hack.bps")),
+            Some(PatchFormat::Bps)
+        ));
+        assert!(matches!(
+            PatchFormat::from_path(Path::new("hack.ups")),
+            Some(PatchFormat::Ups)
+        ));
+        assert!(matches!(
+            PatchFormat::from_path(Path::new("hack.xd")),
+            Some(PatchFormat::Vcdiff)
+        ));
+        assert!(PatchFormat::from_path(Path::new("hack.zip")).is_none());
+        assert!(PatchFormat::from_path(Path::new("hack")).is_none());
+    }
+
+    #[test]
+    fn test_numbers() {
+        for value in [0, 1, 127, 128, 255, 16511, 16512, 1 << 40] {
+            let mut patch = vec![];
+            write_number(&mut patch, value);
+            let mut slice = patch.as_slice();
+            assert_eq!(read_number(&mut slice).unwrap(), value);
+            assert!(slice.is_empty());
+        }
+        let mut patch = vec![];
+        write_number(&mut patch, 128);
+        assert_eq!(patch, vec![0x00, 0x80]);
+        assert!(read_number(&mut &[0x00][..]).is_err());
+        assert!(read_number(&mut &[0x7f; 16][..]).is_err());
+    }
+
+    #[test]
+    fn test_footer() {
+        let mut patch = b"body".to_vec();
+        write_footer(&mut patch, b"source", b"target");
+        let (body, source, target) = read_footer(&patch).unwrap();
+        assert_eq!(body, b"body");
+        assert_eq!(source, crc32fast::hash(b"source"));
+        assert_eq!(target, crc32fast::hash(b"target"));
+
+        patch[0] = b'B';
+        assert!(read_footer(&patch).is_err());
+        assert!(read_footer(b"short").is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use super::{
+    invalid_data, read, read_footer, read_length, read_number, write_footer, write_number,
+};
+
+const HEADER: &[u8] = b"UPS1";
+
+pub fn create(original: &[u8], modified: &[u8]) -> Vec<u8> {
+    let length = original.len().max(modified.len());
+    let xor = |i: usize| original.get(i).unwrap_or(&0) ^ modified.get(i).unwrap_or(&0);
+    let mut patch = HEADER.to_vec();
+    write_number(&mut patch, original.len() as u64);
+    write_number(&mut patch, modified.len() as u64);
+    let mut last = 0;
+    let mut i = 0;
+    while i < length {
+        if xor(i) == 0 {
+            i += 1;
+            continue;
+        }
+        write_number(&mut patch, (i - last) as u64);
+        while i < length && xor(i) != 0 {
+            patch.push(xor(i));
+            i += 1;
+        }
+        // The terminating zero also covers the unchanged byte after the hunk.
+        patch.push(0);
+        i += 1;
+        last = i;
+    }
+    write_footer(&mut patch, original, modified);
+    patch
+}
+
+/// Applies a UPS patch after checking that `data` matches its source checksum. As UPS
+/// patches are symmetric, they can also be reverted by applying them to the target.
+pub fn apply(patch: &[u8], data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    let (body, source_checksum, target_checksum) = read_footer(patch)?;
+    let mut body = body
+        .strip_prefix(HEADER)
+        .ok_or_else(|| invalid_data("Not a UPS patch"))?;
+    let source_length = read_length(&mut body)?;
+    let target_length = read_length(&mut body)?;
+    let checksum = crc32fast::hash(data);
+    let (input_length, length, expected_checksum) = if checksum == source_checksum {
+        (source_length, target_length, target_checksum)
+    } else if checksum == target_checksum {
+        (target_length, source_length, source_checksum)
+    } else {
+        return Err(invalid_data("Source checksum mismatch"));
+    };
+    if input_length != data.len() {
+        return Err(invalid_data("Source size mismatch"));
+    }
+
+    let end = source_length.max(target_length);
+    let mut output = data.to_vec();
+    let mut position: usize = 0;
+    while !body.is_empty() {
+        position = usize::try_from(read_number(&mut body)?)
+            .ok()
+            .and_then(|skip| position.checked_add(skip))
+            .ok_or_else(|| invalid_data("Invalid hunk offset"))?;
+        loop {
+            let value = read(&mut body, 1)?[0];
+            if value != 0 && position >= output.len() {
+                if position >= end {
+                    return Err(invalid_data("Invalid hunk offset"));
+                }
+                output.resize(position + 1, 0);
+            }
+            if position < output.len() {
+                output[position] ^= value;
+            }
+            position += 1;
+            if value == 0 {
+                break;
+            }
+        }
+    }
+    output.truncate(length);
+
+    // Only pad the output with zeros once the header size is confirmed by the checksum.
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&output);
+    let zeros = [0; 0x1000];
+    let mut padding = length - output.len();
+    while padding > 0 {
+        let count = padding.min(zeros.len());
+        hasher.update(&zeros[..count]);
+        padding -= count;
+    }
+    if hasher.finalize() != expected_checksum {
+        return Err(invalid_data("Target checksum mismatch"));
+    }
+    output.resize(length, 0);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create() {
+        let patch = create(&[1, 2, 3, 4], &[1, 9, 3, 4, 5]);
+        assert_eq!(&patch[..12], b"UPS1\x84\x85\x81\x0b\x00\x81\x05\x00");
+        assert_eq!(patch.len(), 24);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let original: Vec<u8> = (0..=255).collect();
+        let mut modified = original.clone();
+        modified[10] = 0;
+        modified[200..210].fill(0xff);
+        let shorter = modified[..250].to_vec();
+        let mut longer = modified.clone();
+        longer.extend([1, 0, 2]);
+
+        for target in [modified, shorter, longer] {
+            let patch = create(&original, &target);
+            assert_eq!(apply(&patch, &original).unwrap(), target);
+            assert_eq!(apply(&patch, &target).unwrap(), original);
+        }
+    }
+
+    #[test]
+    fn test_apply_wrong_source() {
+        let patch = create(&[1, 2, 3], &[1, 2, 4]);
+        assert!(apply(&patch, &[1, 2, 5]).is_err());
+    }
+
+    #[test]
+    fn test_apply_invalid() {
+        let mut patch = b"UPX1\x81\x81".to_vec();
+        write_footer(&mut patch, &[0], &[0]);
+        assert!(apply(&patch, &[0]).is_err());
+
+        // A huge target size, and a hunk past the end of both files.
+        let mut patch = HEADER.to_vec();
+        write_number(&mut patch, 1);
+        write_number(&mut patch, u64::MAX >> 1);
+        write_footer(&mut patch, &[0], &[1]);
+        assert!(apply(&patch, &[0]).is_err());
+        let mut patch = HEADER.to_vec();
+        write_number(&mut patch, 1);
+        write_number(&mut patch, 1);
+        write_number(&mut patch, 1 << 40);
+        patch.extend([1, 0]);
+        write_footer(&mut patch, &[0], &[1]);
+        assert!(apply(&patch, &[0]).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip_zero_padding() {
+        let patch = create(&[1, 2], &[1, 3, 0, 0, 0]);
+        assert_eq!(apply(&patch, &[1, 2]).unwrap(), [1, 3, 0, 0, 0]);
+        assert_eq!(apply(&patch, &[1, 3, 0, 0, 0]).unwrap(), [1, 2]);
+    }
+}
@@ -0,0 +1,437 @@
+use super::{MAX_LENGTH, invalid_data, read};
+use std::collections::HashMap;
+
+const MAGIC: &[u8] = b"\xd6\xc3\xc4\x00";
+const VCD_DECOMPRESS: u8 = 0x01;
+const VCD_CODETABLE: u8 = 0x02;
+const VCD_APPHEADER: u8 = 0x04;
+const VCD_SOURCE: u8 = 0x01;
+const VCD_TARGET: u8 = 0x02;
+const VCD_ADLER32: u8 = 0x04;
+
+const NOOP: u8 = 0;
+const ADD: u8 = 1;
+const RUN: u8 = 2;
+const COPY: u8 = 3;
+/// Default code table indices of the single instructions with an explicit size.
+const RUN_INDEX: u8 = 0;
+const ADD_INDEX: u8 = 1;
+const COPY_SELF_INDEX: u8 = 19;
+
+const NEAR_CACHE_SIZE: usize = 4;
+const SAME_CACHE_SIZE: usize = 3;
+/// Windows are kept well below the target window limit of common decoders like xdelta3.
+const WINDOW_SIZE: usize = 1 << 22;
+const BLOCK_SIZE: usize = 8;
+const MIN_COPY: usize = 4;
+const MIN_RUN: usize = 8;
+
+/// Creates a VCDIFF delta with the whole of `original` as source segment for every window.
+pub fn create(original: &[u8], modified: &[u8]) -> Vec<u8> {
+    let mut index = HashMap::new();
+    for position in (0..original.len().saturating_sub(BLOCK_SIZE - 1)).step_by(BLOCK_SIZE) {
+        index
+            .entry(&original[position..position + BLOCK_SIZE])
+            .or_insert(position);
+    }
+    let mut patch = MAGIC.to_vec();
+    patch.push(0);
+    for (window, target) in modified.chunks(WINDOW_SIZE).enumerate() {
+        let mut encoder = WindowEncoder::default();
+        let mut delta: isize = (window * WINDOW_SIZE) as isize;
+        let mut i = 0;
+        while i < target.len() {
+            let match_length = |source: usize| {
+                original[source.min(original.len())..]
+                    .iter()
+                    .zip(&target[i..])
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            };
+            // Prefer continuing at the offset of the previous copy before looking up blocks.
+            let mut candidate = usize::try_from(i as isize + delta)
+                .ok()
+                .map(|source| (source, match_length(source)));
+            if candidate.is_none_or(|(_, length)| length < MIN_COPY) {
+                candidate = target
+                    .get(i..i + BLOCK_SIZE)
+                    .and_then(|block| index.get(block))
+                    .map(|source| (*source, match_length(*source)));
+            }
+            match candidate {
+                Some((source, length)) if length >= MIN_COPY => {
+                    encoder.copy(source, length);
+                    delta = source as isize - i as isize;
+                    i += length;
+                }
+                _ => {
+                    let byte = target[i];
+                    let run = target[i..].iter().take_while(|b| **b == byte).count();
+                    if run >= MIN_RUN {
+                        encoder.run(byte, run);
+                        i += run;
+                    } else {
+                        encoder.add(byte);
+                        i += 1;
+                    }
+                }
+            }
+        }
+        encoder.write(&mut patch, original.len(), target.len());
+    }
+    patch
+}
+
+#[derive(Default)]
+struct WindowEncoder {
+    data: Vec<u8>,
+    instructions: Vec<u8>,
+    addresses: Vec<u8>,
+    pending_add: usize,
+}
+
+impl WindowEncoder {
+    fn add(&mut self, byte: u8) {
+        self.data.push(byte);
+        self.pending_add += 1;
+    }
+
+    fn run(&mut self, byte: u8, length: usize) {
+        self.flush_add();
+        self.data.push(byte);
+        self.instructions.push(RUN_INDEX);
+        write_number(&mut self.instructions, length as u64);
+    }
+
+    fn copy(&mut self, address: usize, length: usize) {
+        self.flush_add();
+        self.instructions.push(COPY_SELF_INDEX);
+        write_number(&mut self.instructions, length as u64);
+        write_number(&mut self.addresses, address as u64);
+    }
+
+    fn flush_add(&mut self) {
+        if self.pending_add > 0 {
+            self.instructions.push(ADD_INDEX);
+            write_number(&mut self.instructions, self.pending_add as u64);
+            self.pending_add = 0;
+        }
+    }
+
+    fn write(mut self, patch: &mut Vec<u8>, source_length: usize, target_length: usize) {
+        self.flush_add();
+        let mut delta = vec![];
+        write_number(&mut delta, target_length as u64);
+        delta.push(0);
+        write_number(&mut delta, self.data.len() as u64);
+        write_number(&mut delta, self.instructions.len() as u64);
+        write_number(&mut delta, self.addresses.len() as u64);
+        delta.extend(self.data);
+        delta.extend(self.instructions);
+        delta.extend(self.addresses);
+
+        if source_length > 0 {
+            patch.push(VCD_SOURCE);
+            write_number(patch, source_length as u64);
+            write_number(patch, 0);
+        } else {
+            patch.push(0);
+        }
+        write_number(patch, delta.len() as u64);
+        patch.extend(delta);
+    }
+}
+
+/// Applies a VCDIFF delta using the default code table. Secondary compression, custom code
+/// tables and windows sourcing from the target are not supported.
+pub fn apply(patch: &[u8], data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    let mut patch = patch
+        .strip_prefix(MAGIC)
+        .ok_or_else(|| invalid_data("Not a VCDIFF patch"))?;
+    let header = read(&mut patch, 1)?[0];
+    if header & (VCD_DECOMPRESS | VCD_CODETABLE) != 0 {
+        return Err(invalid_data("Compressed VCDIFF is not supported"));
+    }
+    if header & VCD_APPHEADER != 0 {
+        let length = read_number(&mut patch)? as usize;
+        read(&mut patch, length)?;
+    }
+
+    let table = code_table();
+    let mut output = vec![];
+    while !patch.is_empty() {
+        let indicator = read(&mut patch, 1)?[0];
+        if indicator & VCD_TARGET != 0 {
+            return Err(invalid_data("VCDIFF target windows are not supported"));
+        }
+        let source = if indicator & VCD_SOURCE != 0 {
+            let length = read_number(&mut patch)? as usize;
+            let position = read_number(&mut patch)? as usize;
+            data.get(position..position.saturating_add(length))
+                .ok_or_else(|| invalid_data("Invalid source segment"))?
+        } else {
+            &[]
+        };
+        let delta_length = read_number(&mut patch)? as usize;
+        let mut delta = read(&mut patch, delta_length)?;
+        let target_length = Some(read_number(&mut delta)?)
+            .filter(|&length| length <= MAX_LENGTH - output.len() as u64)
+            .ok_or_else(|| invalid_data("Invalid size"))? as usize;
+        if read(&mut delta, 1)?[0] != 0 {
+            return Err(invalid_data("Compressed VCDIFF is not supported"));
+        }
+        let data_length = read_number(&mut delta)? as usize;
+        let instructions_length = read_number(&mut delta)? as usize;
+        let addresses_length = read_number(&mut delta)? as usize;
+        if indicator & VCD_ADLER32 != 0 {
+            read(&mut delta, 4)?;
+        }
+        let data = read(&mut delta, data_length)?;
+        let mut instructions = read(&mut delta, instructions_length)?;
+        let mut window = WindowDecoder {
+            source,
+            data,
+            addresses: read(&mut delta, addresses_length)?,
+            target: vec![],
+            target_length,
+            near: [0; NEAR_CACHE_SIZE],
+            next_near: 0,
+            same: [0; SAME_CACHE_SIZE * 256],
+        };
+        while !instructions.is_empty() {
+            let index = read(&mut instructions, 1)?[0] as usize;
+            for (kind, size, mode) in [table[index].0, table[index].1] {
+                if kind == NOOP {
+                    continue;
+                }
+                let size = match size {
+                    0 => read_number(&mut instructions)? as usize,
+                    size => size as usize,
+                };
+                window.execute(kind, size, mode)?;
+            }
+        }
+        if window.target.len() != target_length {
+            return Err(invalid_data("Target size mismatch"));
+        }
+        output.extend(window.target);
+    }
+    Ok(output)
+}
+
+struct WindowDecoder<'a> {
+    source: &'a [u8],
+    data: &'a [u8],
+    addresses: &'a [u8],
+    target: Vec<u8>,
+    target_length: usize,
+    near: [usize; NEAR_CACHE_SIZE],
+    next_near: usize,
+    same: [usize; SAME_CACHE_SIZE * 256],
+}
+
+impl WindowDecoder<'_> {
+    fn execute(&mut self, kind: u8, size: usize, mode: u8) -> Result<(), std::io::Error> {
+        if size > self.target_length - self.target.len() {
+            return Err(invalid_data("Target size mismatch"));
+        }
+        match kind {
+            ADD => self.target.extend(read(&mut self.data, size)?),
+            RUN => {
+                let byte = read(&mut self.data, 1)?[0];
+                self.target.extend(std::iter::repeat_n(byte, size));
+            }
+            _ => {
+                let address = self.read_address(mode)?;
+                let end = address
+                    .checked_add(size)
+                    .ok_or_else(|| invalid_data("Invalid copy address"))?;
+                for i in address..end {
+                    let byte = match i.checked_sub(self.source.len()) {
+                        None => self.source[i],
+                        Some(position) => *self
+                            .target
+                            .get(position)
+                            .ok_or_else(|| invalid_data("Invalid copy address"))?,
+                    };
+                    self.target.push(byte);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_address(&mut self, mode: u8) -> Result<usize, std::io::Error> {
+        let here = self.source.len() + self.target.len();
+        let mode = mode as usize;
+        let address = match mode {
+            0 => read_number(&mut self.addresses)? as usize,
+            1 => here
+                .checked_sub(read_number(&mut self.addresses)? as usize)
+                .ok_or_else(|| invalid_data("Invalid copy address"))?,
+            2..6 => usize::try_from(read_number(&mut self.addresses)?)
+                .ok()
+                .and_then(|offset| self.near[mode - 2].checked_add(offset))
+                .ok_or_else(|| invalid_data("Invalid copy address"))?,
+            _ => {
+                let offset = read(&mut self.addresses, 1)?[0] as usize;
+                self.same[(mode - 6) * 256 + offset]
+            }
+        };
+        if address >= here {
+            return Err(invalid_data("Invalid copy address"));
+        }
+        self.near[self.next_near] = address;
+        self.next_near = (self.next_near + 1) % NEAR_CACHE_SIZE;
+        self.same[address % (SAME_CACHE_SIZE * 256)] = address;
+        Ok(address)
+    }
+}
+
+type Instruction = (u8, u8, u8);
+
+/// Builds the default code table of RFC 3284, section 5.6.
+fn code_table() -> Vec<(Instruction, Instruction)> {
+    let none = (NOOP, 0, 0);
+    let mut table = vec![((RUN, 0, 0), none)];
+    table.extend((0..=17).map(|size| ((ADD, size, 0), none)));
+    for mode in 0..=8 {
+        table.push(((COPY, 0, mode), none));
+        table.extend((4..=18).map(|size| ((COPY, size, mode), none)));
+    }
+    for mode in 0..=5 {
+        for add_size in 1..=4 {
+            table.extend((4..=6).map(|size| ((ADD, add_size, 0), (COPY, size, mode))));
+        }
+    }
+    for mode in 6..=8 {
+        table.extend((1..=4).map(|add_size| ((ADD, add_size, 0), (COPY, 4, mode))));
+    }
+    table.extend((0..=8).map(|mode| ((COPY, 4, mode), (ADD, 1, 0))));
+    table
+}
+
+fn write_number(patch: &mut Vec<u8>, value: u64) {
+    let mut bytes = vec![(value & 0x7f) as u8];
+    let mut value = value >> 7;
+    while value > 0 {
+        bytes.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    patch.extend(bytes.iter().rev());
+}
+
+fn read_number(patch: &mut &[u8]) -> Result<u64, std::io::Error> {
+    let mut value: u64 = 0;
+    loop {
+        let byte = read(patch, 1)?[0];
+        if value.leading_zeros() < 7 {
+            return Err(invalid_data("Number too large"));
+        }
+        value = (value << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_table() {
+        let table = code_table();
+        assert_eq!(table.len(), 256);
+        assert_eq!(table[RUN_INDEX as usize].0, (RUN, 0, 0));
+        assert_eq!(table[ADD_INDEX as usize].0, (ADD, 0, 0));
+        assert_eq!(table[COPY_SELF_INDEX as usize].0, (COPY, 0, 0));
+        assert_eq!(table[163], ((ADD, 1, 0), (COPY, 4, 0)));
+        assert_eq!(table[247], ((COPY, 4, 0), (ADD, 1, 0)));
+    }
+
+    #[test]
+    fn test_numbers() {
+        let mut patch = vec![];
+        write_number(&mut patch, 123456789);
+        assert_eq!(patch, vec![0xba, 0xef, 0x9a, 0x15]);
+        assert_eq!(read_number(&mut patch.as_slice()).unwrap(), 123456789);
+        assert!(read_number(&mut &[0xff; 11][..]).is_err());
+    }
+
+    #[test]
+    fn test_create() {
+        let patch = create(b"abcdefgh", b"abcdXfgh");
+        assert_eq!(
+            patch,
+            b"\xd6\xc3\xc4\x00\x00\x01\x08\x00\x0e\x08\x00\x04\x04\x01Xfgh\x13\x04\x01\x04\x00"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let original: Vec<u8> = (0..=255).cycle().take(2000).collect();
+        let mut modified = original.clone();
+        modified[10] = 0;
+        modified.splice(500..500, [1, 2, 3]);
+        modified.drain(1500..1600);
+        modified.extend(b"appended");
+        modified.extend([0; 100]);
+        let patch = create(&original, &modified);
+        assert_eq!(apply(&patch, &original).unwrap(), modified);
+        assert!(patch.len() < 100);
+
+        assert_eq!(apply(&create(&[], b"new"), &[]).unwrap(), b"new");
+        assert_eq!(apply(&create(b"old", &[]), b"old").unwrap(), b"");
+    }
+
+    #[test]
+    fn test_apply_cached_addresses() {
+        // Target "abcabc" from source "abc": copy from address 0, then copy again using the
+        // first near cache slot with an offset of 0.
+        let mut patch = MAGIC.to_vec();
+        patch.extend([0, VCD_SOURCE, 3, 0, 11, 6, 0, 0, 4, 2]);
+        patch.extend([COPY_SELF_INDEX, 3, COPY_SELF_INDEX + 32, 3]);
+        patch.extend([0, 0]);
+        assert_eq!(apply(&patch, b"abc").unwrap(), b"abcabc");
+    }
+
+    #[test]
+    fn test_apply_invalid() {
+        assert!(apply(b"\xd6\xc3\xc4\x01", &[]).is_err());
+        assert!(apply(b"\xd6\xc3\xc4\x00\x01", &[]).is_err());
+        assert!(apply(b"\xd6\xc3\xc4\x00\x00\x01\x08\x00", b"short").is_err());
+
+        let window = |delta: &[u8]| {
+            let mut patch = MAGIC.to_vec();
+            patch.extend([0, VCD_SOURCE, 3, 0]);
+            write_number(&mut patch, delta.len() as u64);
+            patch.extend(delta);
+            patch
+        };
+        // A huge target size, and a run longer than the declared target.
+        let mut delta = vec![];
+        write_number(&mut delta, u64::MAX >> 8);
+        assert!(apply(&window(&delta), b"abc").is_err());
+        let mut delta = vec![1, 0, 1, 0, 0, b'x', RUN_INDEX];
+        write_number(&mut delta, u64::MAX >> 8);
+        delta[3] = (delta.len() - 6) as u8;
+        assert!(apply(&window(&delta), b"abc").is_err());
+        // A near cache offset far past the target.
+        let mut delta = vec![
+            6,
+            0,
+            0,
+            4,
+            0,
+            COPY_SELF_INDEX,
+            3,
+            COPY_SELF_INDEX + 32,
+            3,
+            0,
+        ];
+        write_number(&mut delta, u64::MAX >> 8);
+        delta[4] = (delta.len() - 9) as u8;
+        assert!(apply(&window(&delta), b"abc").is_err());
+    }
+}
@@ -0,0 +1,84 @@
+use crossterm::style::Color;
+use std::ops::Range;
+
+/// Colors available for regions, by name.
+const COLORS: [(&str, Color); 6] = [
+    ("red", Color::DarkRed),
+    ("green", Color::DarkGreen),
+    ("blue", Color::DarkBlue),
+    ("magenta", Color::DarkMagenta),
+    ("cyan", Color::DarkCyan),
+    ("grey", Color::DarkGrey),
+];
+
+/// A named range of the file, tinted in the hex and text panes.
+pub struct Region {
+    pub name: String,
+    pub range: Range<usize>,
+    pub color: Color,
+}
+
+impl Region {
+    /// Creates a region with the color named `color`, or else the `index`th color of the
+    /// palette, so that consecutive regions differ.
+    pub fn new(name: &str, range: Range<usize>, color: Option<&str>, index: usize) -> Option<Self> {
+        let color = match color {
+            Some(color) => parse_color(color)?,
+            None => COLORS[index % COLORS.len()].1,
+        };
+        (range.start < range.end).then(|| Region {
+            name: name.into(),
+            range,
+            color,
+        })
+    }
+}
+
+pub fn parse_color(name: &str) -> Option<Color> {
+    COLORS
+        .iter()
+        .find(|(color, _)| *color == name)
+        .map(|(_, color)| *color)
+}
+
+/// Returns the name of a region color, or `grey` for colors outside the palette.
+pub fn color_name(color: Color) -> &'static str {
+    COLORS
+        .iter()
+        .find(|(_, other)| *other == color)
+        .map_or("grey", |(name, _)| name)
+}
+
+/// Returns the innermost region containing `position`.
+pub fn at(regions: &[Region], position: usize) -> Option<&Region> {
+    regions
+        .iter()
+        .filter(|region| region.range.contains(&position))
+        .min_by_key(|region| region.range.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let region = Region::new("header", 0..0x10, Some("green"), 0).unwrap();
+        assert_eq!(region.color, Color::DarkGreen);
+        let region = Region::new("data", 0x10..0x20, None, 7).unwrap();
+        assert_eq!(region.color, Color::DarkGreen);
+        assert!(Region::new("data", 0x10..0x20, Some("pink"), 0).is_none());
+        assert!(Region::new("empty", 0x10..0x10, None, 0).is_none());
+    }
+
+    #[test]
+    fn test_at() {
+        let regions = [
+            Region::new("file", 0..0x100, None, 0).unwrap(),
+            Region::new("header", 0..0x10, None, 1).unwrap(),
+        ];
+        assert_eq!(at(&regions, 4).unwrap().name, "header");
+        assert_eq!(at(&regions, 0x10).unwrap().name, "file");
+        assert!(at(&regions, 0x100).is_none());
+    }
+}
@@ -0,0 +1,107 @@
+/// A grid of characters drawn into instead of the terminal, for tests and automation without a
+/// terminal. Colors and attributes are dropped, and text moves through the grid as it would
+/// through a terminal in raw mode: `\r` returns to the first column, `\n` moves down a row
+/// without returning, and moving down from the last row scrolls the grid up.
+pub struct Grid {
+    width: u16,
+    height: u16,
+    rows: Vec<Vec<char>>,
+    x: u16,
+    y: u16,
+}
+
+impl Grid {
+    pub fn new(width: u16, height: u16) -> Self {
+        Grid {
+            width,
+            height,
+            rows: vec![vec![' '; width as usize]; height as usize],
+            x: 0,
+            y: 0,
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Writes `text` at the cursor, dropping what falls right of the last column.
+    pub fn print(&mut self, text: &str) {
+        for c in text.chars() {
+            match c {
+                '\r' => self.x = 0,
+                '\n' if self.y + 1 >= self.height => {
+                    if !self.rows.is_empty() {
+                        self.rows.remove(0);
+                        self.rows.push(vec![' '; self.width as usize]);
+                    }
+                }
+                '\n' => self.y += 1,
+                c => {
+                    let row = self.rows.get_mut(self.y as usize);
+                    if let Some(cell) = row.and_then(|row| row.get_mut(self.x as usize)) {
+                        *cell = c;
+                    }
+                    self.x = self.x.saturating_add(1);
+                }
+            }
+        }
+    }
+
+    pub fn move_to(&mut self, x: u16, y: u16) {
+        self.x = x;
+        self.y = y.min(self.height.saturating_sub(1));
+    }
+
+    pub fn clear(&mut self) {
+        for row in &mut self.rows {
+            row.fill(' ');
+        }
+    }
+
+    /// Clears the row of the cursor from the cursor to its end.
+    pub fn clear_until_newline(&mut self) {
+        if let Some(row) = self.rows.get_mut(self.y as usize) {
+            let x = (self.x as usize).min(row.len());
+            row[x..].fill(' ');
+        }
+    }
+
+    /// Returns the rows of the grid without trailing spaces.
+    pub fn lines(&self) -> Vec<String> {
+        self.rows
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim_end().into())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_print() {
+        let mut grid = Grid::new(6, 2);
+        grid.print("\rabc\n");
+        grid.print("\rdefghij");
+        assert_eq!(grid.lines(), ["abc", "defghi"]);
+        grid.move_to(1, 0);
+        grid.clear_until_newline();
+        grid.print("x\n");
+        assert_eq!(grid.lines(), ["ax", "defghi"]);
+        grid.print("\rscroll\n");
+        assert_eq!(grid.lines(), ["scroll", ""]);
+        grid.clear();
+        assert_eq!(grid.lines(), ["", ""]);
+
+        let mut grid = Grid::new(6, 0);
+        grid.print("\rabc\n");
+        grid.clear_until_newline();
+        assert!(grid.lines().is_empty());
+    }
+}
@@ -0,0 +1,69 @@
+use super::buffer::diff;
+
+/// Formats the differences between `data` and `reference_data` as a tab separated table, one
+/// range per line, preceded by a summary in `#` comment lines.
+pub fn compare_report(file: &str, data: &[u8], reference: &str, reference_data: &[u8]) -> String {
+    let changes = diff(reference_data, data);
+    let differing: usize = changes
+        .iter()
+        .map(|change| change.old.len().max(change.new.len()))
+        .sum();
+    let mut report = format!("# file: {} ({} bytes)\n", file, data.len());
+    report += &format!(
+        "# reference: {} ({} bytes)\n",
+        reference,
+        reference_data.len()
+    );
+    report += &format!(
+        "# {} differing ranges, {} bytes\n",
+        changes.len(),
+        differing
+    );
+    report += "# offset\tlength\treference\tfile\n";
+    for change in changes {
+        report += &format!(
+            "0x{:08x}\t{}\t{}\t{}\n",
+            change.offset,
+            change.old.len().max(change.new.len()),
+            format_bytes(&change.old),
+            format_bytes(&change.new)
+        );
+    }
+    report
+}
+
+/// Formats `bytes` as contiguous hex digits, or `-` if there are none.
+fn format_bytes(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return "-".into();
+    }
+    bytes.iter().map(|b| format!("{:0>2x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_report() {
+        let report = compare_report("file.bin", &[1, 9, 3], "golden.bin", &[1, 2, 3, 4]);
+        assert_eq!(
+            report,
+            "\
+# file: file.bin (3 bytes)
+# reference: golden.bin (4 bytes)
+# 2 differing ranges, 2 bytes
+# offset\tlength\treference\tfile
+0x00000001\t1\t02\t09
+0x00000003\t1\t04\t-
+"
+        );
+    }
+
+    #[test]
+    fn test_compare_report_identical() {
+        let report = compare_report("a", &[1], "b", &[1]);
+        assert!(report.contains("# 0 differing ranges, 0 bytes\n"));
+        assert!(report.ends_with("file\n"));
+    }
+}
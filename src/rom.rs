@@ -0,0 +1,201 @@
+use std::str::FromStr;
+
+const SNES_COPIER_HEADER: usize = 0x200;
+const SNES_LOROM_HEADER: usize = 0x7fc0;
+const SNES_HIROM_HEADER: usize = 0xffc0;
+const SNES_MAP_MODE: usize = 0x15;
+const SNES_CHECKSUM: usize = 0x1c;
+const GENESIS_CONSOLE_NAME: usize = 0x100;
+const GENESIS_CHECKSUM: usize = 0x18e;
+const GENESIS_DATA: usize = 0x200;
+const GBA_HEADER: std::ops::Range<usize> = 0xa0..0xbd;
+const GBA_FIXED_VALUE: usize = 0xb2;
+const GBA_COMPLEMENT: usize = 0xbd;
+
+/// Console ROM header checksums fixed by the `:fix-checksum` command.
+#[derive(Debug, PartialEq)]
+pub enum RomChecksum {
+    Snes,
+    Genesis,
+    Gba,
+}
+
+impl FromStr for RomChecksum {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "snes" => Ok(RomChecksum::Snes),
+            "genesis" | "megadrive" => Ok(RomChecksum::Genesis),
+            "gba" => Ok(RomChecksum::Gba),
+            _ => Err(()),
+        }
+    }
+}
+
+impl RomChecksum {
+    /// Guesses the console from the header of the ROM.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.get(GBA_FIXED_VALUE) == Some(&0x96) && data.len() > GBA_COMPLEMENT {
+            Some(RomChecksum::Gba)
+        } else if [0, 1].iter().any(|i| {
+            data.get(GENESIS_CONSOLE_NAME + i..GENESIS_CONSOLE_NAME + i + 4) == Some(b"SEGA")
+        }) {
+            Some(RomChecksum::Genesis)
+        } else {
+            snes_header(data).map(|_| RomChecksum::Snes)
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RomChecksum::Snes => "SNES",
+            RomChecksum::Genesis => "Genesis",
+            RomChecksum::Gba => "GBA",
+        }
+    }
+
+    /// Returns the offset and the bytes to write there to fix the checksum, or `None` if the
+    /// ROM is too small to hold the header.
+    pub fn fix(&self, data: &[u8]) -> Option<(usize, Vec<u8>)> {
+        match self {
+            RomChecksum::Snes => {
+                let header = snes_header(data).unwrap_or(SNES_LOROM_HEADER);
+                let skip = data.len() % 0x400 == SNES_COPIER_HEADER;
+                let offset = header + SNES_CHECKSUM + if skip { SNES_COPIER_HEADER } else { 0 };
+                let mut rom = data
+                    .get(if skip { SNES_COPIER_HEADER } else { 0 }..)?
+                    .to_vec();
+                rom.get_mut(header + SNES_CHECKSUM..header + SNES_CHECKSUM + 4)?
+                    .copy_from_slice(&[0xff, 0xff, 0, 0]);
+                let checksum = snes_sum(&rom);
+                let mut bytes = (!checksum).to_le_bytes().to_vec();
+                bytes.extend(checksum.to_le_bytes());
+                Some((offset, bytes))
+            }
+            RomChecksum::Genesis => {
+                data.get(GENESIS_CHECKSUM + 1)?;
+                let checksum = data
+                    .get(GENESIS_DATA..)
+                    .unwrap_or_default()
+                    .chunks(2)
+                    .map(|word| u16::from_be_bytes([word[0], *word.get(1).unwrap_or(&0)]))
+                    .fold(0u16, u16::wrapping_add);
+                Some((GENESIS_CHECKSUM, checksum.to_be_bytes().to_vec()))
+            }
+            RomChecksum::Gba => {
+                data.get(GBA_COMPLEMENT)?;
+                let sum = data[GBA_HEADER]
+                    .iter()
+                    .fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+                Some((GBA_COMPLEMENT, vec![sum.wrapping_add(0x19).wrapping_neg()]))
+            }
+        }
+    }
+}
+
+/// Finds the internal header, preferring HiROM if its map mode byte matches.
+fn snes_header(data: &[u8]) -> Option<usize> {
+    let skip = if data.len() % 0x400 == SNES_COPIER_HEADER {
+        SNES_COPIER_HEADER
+    } else {
+        0
+    };
+    [(SNES_HIROM_HEADER, 1), (SNES_LOROM_HEADER, 0)]
+        .into_iter()
+        .find(|(header, mode)| {
+            data.get(skip + header + SNES_MAP_MODE)
+                .is_some_and(|map| map & 0xe0 == 0x20 && map & 1 == *mode)
+        })
+        .map(|(header, _)| header)
+}
+
+/// Sums all bytes, mirroring the part beyond the largest power of two to fill up to twice
+/// its size like the console does.
+fn snes_sum(rom: &[u8]) -> u16 {
+    let sum = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .fold(0u16, |sum, byte| sum.wrapping_add(*byte as u16))
+    };
+    if rom.is_empty() {
+        return 0;
+    }
+    let base = 1 << rom.len().ilog2();
+    let rest = &rom[base..];
+    if rest.is_empty() {
+        return sum(rom);
+    }
+    let repeats = (base / rest.len()) as u16;
+    sum(&rom[..base]).wrapping_add(sum(rest).wrapping_mul(repeats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let mut gba = vec![0; 0xc0];
+        gba[GBA_FIXED_VALUE] = 0x96;
+        assert_eq!(RomChecksum::detect(&gba), Some(RomChecksum::Gba));
+
+        let mut genesis = vec![0; 0x200];
+        genesis[0x100..0x104].copy_from_slice(b"SEGA");
+        assert_eq!(RomChecksum::detect(&genesis), Some(RomChecksum::Genesis));
+
+        let mut snes = vec![0; 0x8000];
+        snes[SNES_LOROM_HEADER + SNES_MAP_MODE] = 0x20;
+        assert_eq!(RomChecksum::detect(&snes), Some(RomChecksum::Snes));
+        assert_eq!(RomChecksum::detect(&[0; 16]), None);
+    }
+
+    #[test]
+    fn test_fix_snes() {
+        let mut rom = vec![1; 0x8000];
+        rom[SNES_LOROM_HEADER + SNES_MAP_MODE] = 0x20;
+        let sum = (0x8000 - 5 + 0x20 + 0x1fe) as u16;
+        let mut expected = (!sum).to_le_bytes().to_vec();
+        expected.extend(sum.to_le_bytes());
+        assert_eq!(
+            RomChecksum::Snes.fix(&rom),
+            Some((0x7fdc, expected.clone()))
+        );
+
+        let mut copier = vec![0; SNES_COPIER_HEADER];
+        copier.extend(&rom);
+        assert_eq!(
+            RomChecksum::Snes.fix(&copier),
+            Some((0x7fdc + 0x200, expected))
+        );
+        assert_eq!(RomChecksum::Snes.fix(&[0; 16]), None);
+    }
+
+    #[test]
+    fn test_snes_sum_mirrors() {
+        let mut rom = vec![1; 0x100];
+        rom.extend([2; 0x40]);
+        assert_eq!(snes_sum(&rom), 0x100 + 2 * 0x40 * 4);
+    }
+
+    #[test]
+    fn test_fix_genesis() {
+        let mut rom = vec![0; 0x205];
+        rom[0x200..].copy_from_slice(&[0x12, 0x34, 0xff, 0xff, 0x01]);
+        assert_eq!(
+            RomChecksum::Genesis.fix(&rom),
+            Some((GENESIS_CHECKSUM, vec![0x13, 0x33]))
+        );
+        assert_eq!(RomChecksum::Genesis.fix(&[0; 16]), None);
+    }
+
+    #[test]
+    fn test_fix_gba() {
+        let mut rom = vec![0; 0xc0];
+        rom[GBA_FIXED_VALUE] = 0x96;
+        assert_eq!(
+            RomChecksum::Gba.fix(&rom),
+            Some((GBA_COMPLEMENT, vec![0x51]))
+        );
+    }
+}
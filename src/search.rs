@@ -0,0 +1,147 @@
+/// A pattern searched for with `find`, whose matches are stepped through with `n` and `N`.
+pub struct Search {
+    pub pattern: Vec<u8>,
+    /// Offsets of the matches, in ascending order.
+    matches: Vec<usize>,
+}
+
+impl Search {
+    pub fn new(data: &[u8], pattern: Vec<u8>) -> Self {
+        let mut search = Search {
+            pattern,
+            matches: vec![],
+        };
+        search.update(data);
+        search
+    }
+
+    /// Finds the matches again after `data` has changed.
+    pub fn update(&mut self, data: &[u8]) {
+        self.matches = data
+            .windows(self.pattern.len())
+            .enumerate()
+            .filter(|(_, window)| *window == self.pattern)
+            .map(|(offset, _)| offset)
+            .collect();
+    }
+
+    pub fn count(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Returns the first match after `position`, or before it if not `forward`, wrapping
+    /// around at the ends of the data.
+    pub fn next(&self, position: usize, forward: bool) -> Option<usize> {
+        let index = match forward {
+            true => self.matches.partition_point(|offset| *offset <= position),
+            false => self.matches.partition_point(|offset| *offset < position),
+        };
+        match forward {
+            true => self.matches.get(index).or(self.matches.first()).copied(),
+            false => match index.checked_sub(1) {
+                Some(index) => Some(self.matches[index]),
+                None => self.matches.last().copied(),
+            },
+        }
+    }
+
+    /// Returns the index of the match starting at `position`, if `data` still holds it.
+    pub fn index_at(&self, data: &[u8], position: usize) -> Option<usize> {
+        let index = self.matches.binary_search(&position).ok()?;
+        data[position..].starts_with(&self.pattern).then_some(index)
+    }
+}
+
+/// Returns the offset of the first match of `pattern` at or after `position`.
+pub fn find_from(data: &[u8], pattern: &[u8], position: usize) -> Option<usize> {
+    let offset = data
+        .get(position..)?
+        .windows(pattern.len())
+        .position(|window| window == pattern)?;
+    Some(position + offset)
+}
+
+/// Returns `data` with every match of `pattern` replaced by `bytes`, from the start without
+/// overlapping, and the number of replaced matches.
+pub fn replace_all(data: &[u8], pattern: &[u8], bytes: &[u8]) -> (Vec<u8>, usize) {
+    let mut result = Vec::with_capacity(data.len());
+    let mut position = 0;
+    let mut count = 0;
+    while let Some(offset) = find_from(data, pattern, position) {
+        result.extend_from_slice(&data[position..offset]);
+        result.extend_from_slice(bytes);
+        position = offset + pattern.len();
+        count += 1;
+    }
+    result.extend_from_slice(&data[position..]);
+    (result, count)
+}
+
+/// Finds `pattern` encoded with an unknown constant added to every character, as used by the
+/// custom text encodings of many games. Returns the offsets of the matches and the value added
+/// to the characters of each. Patterns need at least two characters to be meaningful.
+pub fn relative_search(data: &[u8], pattern: &[u8]) -> Vec<(usize, u8)> {
+    let Some(first) = pattern.first() else {
+        return vec![];
+    };
+    let differences: Vec<u8> = pattern
+        .windows(2)
+        .map(|w| w[1].wrapping_sub(w[0]))
+        .collect();
+    data.windows(pattern.len())
+        .enumerate()
+        .filter(|(_, window)| {
+            window
+                .windows(2)
+                .map(|w| w[1].wrapping_sub(w[0]))
+                .eq(differences.iter().copied())
+        })
+        .map(|(position, window)| (position, window[0].wrapping_sub(*first)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_search() {
+        // "HERO" with 'A' encoded as 0x0a.
+        let data = b"\x00\x11\x0e\x1b\x18\x00HERO";
+        assert_eq!(
+            relative_search(data, b"HERO"),
+            vec![(1, 0x0a_u8.wrapping_sub(b'A')), (6, 0)]
+        );
+        assert!(relative_search(data, b"HEROES").is_empty());
+        assert!(relative_search(data, b"").is_empty());
+    }
+
+    #[test]
+    fn test_replace_all() {
+        assert_eq!(find_from(b"abcabc", b"bc", 2), Some(4));
+        assert_eq!(find_from(b"abcabc", b"bc", 5), None);
+        assert_eq!(replace_all(b"aaaba", b"aa", b"x"), (b"xaba".to_vec(), 1));
+        assert_eq!(
+            replace_all(b"abcab", b"ab", b"wxyz"),
+            (b"wxyzcwxyz".to_vec(), 2)
+        );
+        assert_eq!(replace_all(b"abc", b"d", b""), (b"abc".to_vec(), 0));
+    }
+
+    #[test]
+    fn test_search() {
+        let mut data = b"abcabcab".to_vec();
+        let search = Search::new(&data, b"ab".to_vec());
+        assert_eq!(search.count(), 3);
+        assert_eq!(search.next(0, true), Some(3));
+        assert_eq!(search.next(4, true), Some(6));
+        assert_eq!(search.next(6, true), Some(0));
+        assert_eq!(search.next(3, false), Some(0));
+        assert_eq!(search.next(0, false), Some(6));
+        assert_eq!(search.index_at(&data, 6), Some(2));
+        assert_eq!(search.index_at(&data, 1), None);
+        data[6] = b'x';
+        assert_eq!(search.index_at(&data, 6), None);
+        assert!(Search::new(&data, b"zz".to_vec()).next(0, true).is_none());
+    }
+}
@@ -0,0 +1,155 @@
+use super::encoding::{TextEncoding, UnprintableStyle};
+use super::parse::parse_number;
+use std::time::Duration;
+
+/// Options changed with the `:set` command.
+pub struct Settings {
+    /// Highlight bytes which differ from the file on disk.
+    pub diff_disk: bool,
+    /// Show the entropy of each block next to the hex view.
+    pub entropy: bool,
+    /// Elide the lines within runs of a repeated byte.
+    pub collapse: bool,
+    /// Encoding of the text pane and of text searched or written.
+    pub encoding: TextEncoding,
+    /// How bytes without a printable character are shown in the text pane.
+    pub unprintable: UnprintableStyle,
+    /// Boundary in bytes which the cursor and the view are snapped to.
+    pub alignment: usize,
+    /// Size in bytes of the blocks marked by guides in the hex and text panes.
+    pub guides: Option<usize>,
+    /// How long messages stay in the status bar, or until the next key press if zero.
+    pub message_time: Duration,
+    /// Number of lines kept visible above and below the cursor when scrolling.
+    pub scroll_off: usize,
+    /// Block edits within the ranges protected with `:protect`.
+    pub protect: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            diff_disk: false,
+            entropy: false,
+            collapse: false,
+            encoding: TextEncoding::default(),
+            unprintable: UnprintableStyle::default(),
+            alignment: 512,
+            guides: None,
+            message_time: Duration::ZERO,
+            scroll_off: 0,
+            protect: true,
+        }
+    }
+}
+
+impl Settings {
+    /// Applies an argument of the `:set` command, either `option`, `nooption` or `option=value`.
+    pub fn set(&mut self, argument: &str) -> Result<(), &'static str> {
+        if let Some(name) = argument.strip_prefix("encoding=") {
+            self.encoding = name.parse().map_err(|_| "Unknown encoding")?;
+            return Ok(());
+        }
+        if let Some(name) = argument.strip_prefix("unprintable=") {
+            self.unprintable = name.parse().map_err(|_| "Unknown style")?;
+            return Ok(());
+        }
+        if let Some(value) = argument.strip_prefix("align=") {
+            self.alignment = parse_number(value)
+                .filter(|alignment| *alignment > 0)
+                .ok_or("Invalid alignment")?;
+            return Ok(());
+        }
+        if let Some(value) = argument.strip_prefix("messagetime=") {
+            let milliseconds = parse_number(value).ok_or("Invalid message time")?;
+            self.message_time = Duration::from_millis(milliseconds as u64);
+            return Ok(());
+        }
+        if let Some(value) = argument.strip_prefix("scrolloff=") {
+            self.scroll_off = parse_number(value).ok_or("Invalid scroll margin")?;
+            return Ok(());
+        }
+        if let Some(value) = argument.strip_prefix("guides=") {
+            let size = parse_number(value).filter(|size| *size > 0);
+            self.guides = Some(size.ok_or("Invalid guide size")?);
+            return Ok(());
+        }
+        let (name, enabled) = match argument.strip_prefix("no") {
+            Some(name) => (name, false),
+            None => (argument, true),
+        };
+        match name {
+            "diffdisk" => self.diff_disk = enabled,
+            "entropy" => self.entropy = enabled,
+            "collapse" => self.collapse = enabled,
+            "protect" => self.protect = enabled,
+            "guides" => self.guides = enabled.then_some(self.alignment),
+            _ => return Err("Unknown option"),
+        }
+        Ok(())
+    }
+
+    /// Returns the nearest boundary after `position`, or before it if not `forward`.
+    pub fn align(&self, position: usize, forward: bool) -> usize {
+        match forward {
+            true => (position / self.alignment + 1) * self.alignment,
+            false => (position.saturating_sub(1) / self.alignment) * self.alignment,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set() {
+        let mut settings = Settings::default();
+        assert!(!settings.diff_disk);
+        assert!(settings.set("diffdisk").is_ok());
+        assert!(settings.diff_disk);
+        assert!(settings.set("nodiffdisk").is_ok());
+        assert!(!settings.diff_disk);
+        assert!(settings.set("entropy").is_ok());
+        assert!(settings.entropy);
+        assert!(settings.set("collapse").is_ok());
+        assert!(settings.collapse);
+        assert!(settings.protect);
+        assert!(settings.set("noprotect").is_ok());
+        assert!(!settings.protect);
+        assert!(settings.set("encoding=ebcdic").is_ok());
+        assert!(matches!(settings.encoding, TextEncoding::Ebcdic));
+        assert_eq!(settings.set("encoding=utf-7"), Err("Unknown encoding"));
+        assert!(settings.set("unprintable=category").is_ok());
+        assert!(matches!(settings.unprintable, UnprintableStyle::Category));
+        assert!(settings.set("unprintable=").is_err());
+        assert!(settings.set("unknown").is_err());
+        assert_eq!(settings.alignment, 512);
+        assert!(settings.set("align=0x1000").is_ok());
+        assert_eq!(settings.alignment, 0x1000);
+        assert_eq!(settings.set("align=0"), Err("Invalid alignment"));
+        assert!(settings.set("guides").is_ok());
+        assert_eq!(settings.guides, Some(0x1000));
+        assert!(settings.set("guides=24").is_ok());
+        assert_eq!(settings.guides, Some(24));
+        assert!(settings.set("noguides").is_ok());
+        assert_eq!(settings.guides, None);
+        assert_eq!(settings.set("guides=x"), Err("Invalid guide size"));
+        assert!(settings.set("messagetime=3000").is_ok());
+        assert_eq!(settings.message_time, Duration::from_secs(3));
+        assert!(settings.set("scrolloff=3").is_ok());
+        assert_eq!(settings.scroll_off, 3);
+        assert_eq!(settings.set("scrolloff=-1"), Err("Invalid scroll margin"));
+    }
+
+    #[test]
+    fn test_align() {
+        let mut settings = Settings::default();
+        assert!(settings.set("align=16").is_ok());
+        assert_eq!(settings.align(0x25, true), 0x30);
+        assert_eq!(settings.align(0x30, true), 0x40);
+        assert_eq!(settings.align(0x25, false), 0x20);
+        assert_eq!(settings.align(0x20, false), 0x10);
+        assert_eq!(settings.align(0, false), 0);
+    }
+}
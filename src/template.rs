@@ -0,0 +1,245 @@
+use super::parse::{invalid_data, parse_number};
+use std::ops::Range;
+
+/// How the bytes of a field are interpreted.
+#[derive(Debug, PartialEq)]
+enum FieldType {
+    Unsigned(usize),
+    Signed(usize),
+    Float(usize),
+    Bytes(usize),
+    Text(usize),
+}
+
+/// A named, typed range of bytes.
+#[derive(Debug, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub range: Range<usize>,
+    kind: FieldType,
+    big_endian: bool,
+    /// Whether edits to the field are blocked, as for magic numbers or reserved areas.
+    pub read_only: bool,
+}
+
+/// Fields mapped onto the buffer by a structure definition.
+///
+/// Definitions contain one field per line as `[@offset] <type> <name> [readonly]`. Fields
+/// follow each other unless an offset relative to the start of the structure is given.
+/// Supported types are `u8` to `u64`, `i8` to `i64`, `f32` and `f64`, optionally suffixed by
+/// `le` or `be`, as well as `bytes[n]` and `char[n]`. Empty lines and lines starting with `#`
+/// are ignored.
+#[derive(Default)]
+pub struct Template {
+    pub fields: Vec<Field>,
+}
+
+impl Template {
+    /// Parses a structure definition placed at `base`.
+    pub fn parse(text: &str, base: usize) -> Result<Template, std::io::Error> {
+        let mut template = Template::default();
+        let mut position = base;
+        for (number, line) in text.lines().enumerate() {
+            let mut words = line.split_whitespace().peekable();
+            if words.peek().is_none_or(|word| word.starts_with('#')) {
+                continue;
+            }
+            if let Some(offset) = words.next_if(|word| word.starts_with('@')) {
+                let offset = parse_number(&offset[1..])
+                    .ok_or_else(|| invalid_data(number, "invalid offset"))?;
+                position = base + offset;
+            }
+            let (Some(kind), Some(name)) = (words.next(), words.next()) else {
+                return Err(invalid_data(number, "expected <type> <name> [readonly]"));
+            };
+            let read_only = match (words.next(), words.next()) {
+                (None, _) => false,
+                (Some("readonly"), None) => true,
+                _ => return Err(invalid_data(number, "expected <type> <name> [readonly]")),
+            };
+            position += template
+                .add(name, position, kind)
+                .ok_or_else(|| invalid_data(number, "unknown type"))?;
+            if let Some(field) = template.fields.last_mut() {
+                field.read_only = read_only;
+            }
+        }
+        Ok(template)
+    }
+
+    /// Adds a field with a type as written in definitions at `offset` and returns its size, or
+    /// `None` if the type is unknown.
+    pub fn add(&mut self, name: &str, offset: usize, kind: &str) -> Option<usize> {
+        let field = Field::new(name, offset, kind)?;
+        let size = field.range.len();
+        self.fields.push(field);
+        Some(size)
+    }
+
+    /// Returns the index and the field containing `position`.
+    pub fn field_at(&self, position: usize) -> Option<(usize, &Field)> {
+        self.fields
+            .iter()
+            .enumerate()
+            .find(|(_, field)| field.range.contains(&position))
+    }
+}
+
+impl Field {
+    /// Creates a field with a type as written in definitions at `offset`, or returns `None` if
+    /// the type is unknown.
+    pub fn new(name: &str, offset: usize, kind: &str) -> Option<Self> {
+        let (kind, big_endian) = parse_type(kind)?;
+        let size = match kind {
+            FieldType::Unsigned(size)
+            | FieldType::Signed(size)
+            | FieldType::Float(size)
+            | FieldType::Bytes(size)
+            | FieldType::Text(size) => size,
+        };
+        Some(Field {
+            name: name.into(),
+            range: offset..offset + size,
+            kind,
+            big_endian,
+            read_only: false,
+        })
+    }
+
+    /// Reads the field as an unsigned integer in its byte order.
+    pub fn unsigned(&self, data: &[u8]) -> Option<u64> {
+        let mut bytes = data.get(self.range.clone())?.to_vec();
+        if !self.big_endian {
+            bytes.reverse();
+        }
+        Some(bytes.iter().fold(0u64, |value, b| value << 8 | *b as u64))
+    }
+
+    /// Formats the name and value of the field, or `None` if it extends beyond `data`.
+    pub fn describe(&self, data: &[u8]) -> Option<String> {
+        let unsigned = self.unsigned(data)?;
+        let value = match self.kind {
+            FieldType::Unsigned(size) => format!("{} (0x{:0>2$x})", unsigned, unsigned, 2 * size),
+            FieldType::Signed(size) => {
+                let shift = 64 - 8 * size as u32;
+                format!("{}", ((unsigned << shift) as i64) >> shift)
+            }
+            FieldType::Float(4) => format!("{}", f32::from_bits(unsigned as u32)),
+            FieldType::Float(_) => format!("{}", f64::from_bits(unsigned)),
+            FieldType::Bytes(_) => data[self.range.clone()]
+                .iter()
+                .map(|b| format!("{:0>2x}", b))
+                .collect::<Vec<_>>()
+                .join(" "),
+            FieldType::Text(_) => format!(
+                "\"{}\"",
+                data[self.range.clone()]
+                    .iter()
+                    .take_while(|b| **b != 0)
+                    .map(|b| b.escape_ascii().to_string())
+                    .collect::<String>()
+            ),
+        };
+        Some(format!("{} = {}", self.name, value))
+    }
+}
+
+fn parse_type(kind: &str) -> Option<(FieldType, bool)> {
+    if let Some(length) = kind
+        .strip_prefix("bytes[")
+        .and_then(|k| k.strip_suffix(']'))
+    {
+        return Some((FieldType::Bytes(parse_number(length)?), false));
+    }
+    if let Some(length) = kind.strip_prefix("char[").and_then(|k| k.strip_suffix(']')) {
+        return Some((FieldType::Text(parse_number(length)?), false));
+    }
+    let (kind, big_endian) = match kind.strip_suffix("be") {
+        Some(kind) => (kind, true),
+        None => (kind.strip_suffix("le").unwrap_or(kind), false),
+    };
+    let kind = match kind {
+        "u8" => FieldType::Unsigned(1),
+        "u16" => FieldType::Unsigned(2),
+        "u32" => FieldType::Unsigned(4),
+        "u64" => FieldType::Unsigned(8),
+        "i8" => FieldType::Signed(1),
+        "i16" => FieldType::Signed(2),
+        "i32" => FieldType::Signed(4),
+        "i64" => FieldType::Signed(8),
+        "f32" => FieldType::Float(4),
+        "f64" => FieldType::Float(8),
+        _ => return None,
+    };
+    Some((kind, big_endian))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+# header
+char[4] magic readonly
+u16be version
+
+@0x8 i8 delta
+f32 scale
+bytes[2] reserved
+";
+
+    #[test]
+    fn test_parse() {
+        let template = Template::parse(EXAMPLE, 0x10).unwrap();
+        let ranges: Vec<_> = template.fields.iter().map(|f| f.range.clone()).collect();
+        assert_eq!(
+            ranges,
+            vec![0x10..0x14, 0x14..0x16, 0x18..0x19, 0x19..0x1d, 0x1d..0x1f]
+        );
+        assert!(template.fields[1].big_endian);
+        assert!(template.fields[0].read_only && !template.fields[1].read_only);
+        assert_eq!(template.fields[4].kind, FieldType::Bytes(2));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = Template::parse("u8 a\nu24 b\n", 0).err().unwrap();
+        assert_eq!(error.to_string(), "line 2: unknown type");
+        assert!(Template::parse("u8", 0).is_err());
+        assert!(Template::parse("u8 a b", 0).is_err());
+        assert!(Template::parse("u8 a readonly b", 0).is_err());
+        assert!(Template::parse("@x u8 a", 0).is_err());
+    }
+
+    #[test]
+    fn test_field_at() {
+        let template = Template::parse(EXAMPLE, 0).unwrap();
+        assert_eq!(template.field_at(5).unwrap().0, 1);
+        assert_eq!(template.field_at(5).unwrap().1.name, "version");
+        assert!(template.field_at(6).is_none());
+    }
+
+    #[test]
+    fn test_describe() {
+        let template = Template::parse(EXAMPLE, 0).unwrap();
+        let mut data = b"ELF\x00\x01\x02\x00\x00\xfe".to_vec();
+        data.extend(1.5f32.to_le_bytes());
+        data.extend([0xab, 0xcd]);
+        let values: Vec<_> = template
+            .fields
+            .iter()
+            .map(|field| field.describe(&data).unwrap())
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                "magic = \"ELF\"",
+                "version = 258 (0x0102)",
+                "delta = -2",
+                "scale = 1.5",
+                "reserved = ab cd"
+            ]
+        );
+        assert!(template.fields[0].describe(&data[..2]).is_none());
+    }
+}
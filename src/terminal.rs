@@ -0,0 +1,198 @@
+use super::base64;
+use super::render::Grid;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{DisableBracketedPaste, EnableBracketedPaste};
+use crossterm::style::{Attribute, SetAttribute};
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::{queue, terminal};
+use std::cell::RefCell;
+use std::fmt::Arguments;
+use std::io::{IsTerminal, Write, stdout};
+
+thread_local! {
+    /// The grid drawn into instead of stdout while rendering headless.
+    static GRID: RefCell<Option<Grid>> = const { RefCell::new(None) };
+}
+
+/// Like `print!`, but drawing through [`Terminal::print`].
+macro_rules! draw {
+    ($($arg:tt)*) => {
+        $crate::terminal::Terminal::print(format_args!($($arg)*))
+    };
+}
+
+/// Like `println!`, but drawing through [`Terminal::print`].
+macro_rules! drawln {
+    () => {
+        $crate::terminal::draw!("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::terminal::draw!("{}\n", format_args!($($arg)*))
+    };
+}
+
+pub(crate) use {draw, drawln};
+
+#[derive(Copy, Clone)]
+pub struct Position {
+    pub x: u16,
+    pub y: u16,
+}
+
+pub struct Terminal;
+
+/// Runs `f` on the grid if rendering headless, or else returns `None`.
+fn with_grid<T>(f: impl FnOnce(&mut Grid) -> T) -> Option<T> {
+    GRID.with_borrow_mut(|grid| grid.as_mut().map(f))
+}
+
+fn is_headless() -> bool {
+    GRID.with_borrow(Option::is_some)
+}
+
+/// Puts back the grid replaced by [`Terminal::capture`] when dropped, even if drawing panics.
+struct Restore(Option<Grid>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        GRID.set(self.0.take());
+    }
+}
+
+impl Terminal {
+    /// Draws into `grid` instead of stdout while running `draw`, returning the grid drawn into.
+    pub fn capture(
+        grid: Grid,
+        draw: impl FnOnce() -> Result<(), std::io::Error>,
+    ) -> Result<Grid, std::io::Error> {
+        let _restore = Restore(GRID.replace(Some(grid)));
+        let result = draw();
+        let grid = GRID.take().expect("grid taken while drawing");
+        result.map(|_| grid)
+    }
+
+    /// Writes formatted text at the cursor position.
+    pub fn print(text: Arguments) -> Result<(), std::io::Error> {
+        match with_grid(|grid| grid.print(&text.to_string())) {
+            Some(()) => Ok(()),
+            None => stdout().write_fmt(text),
+        }
+    }
+
+    pub fn initialize() -> Result<(), std::io::Error> {
+        tracing::debug!("entering raw mode");
+        terminal::enable_raw_mode()?;
+        Self::clear_screen()?;
+        Self::move_cursor_to(Position { x: 0, y: 0 })?;
+        queue!(stdout(), Hide, EnableBracketedPaste)?;
+        Self::execute()?;
+        Ok(())
+    }
+
+    pub fn terminate() -> Result<(), std::io::Error> {
+        tracing::debug!("leaving raw mode");
+        Self::execute()?;
+        terminal::disable_raw_mode()?;
+        queue!(stdout(), Show, DisableBracketedPaste)?;
+        Self::execute()?;
+        Ok(())
+    }
+
+    pub fn height() -> Result<u16, std::io::Error> {
+        if let Some(height) = with_grid(|grid| grid.height()) {
+            return Ok(height);
+        }
+        let (_, height) = terminal::size()?;
+        Ok(height)
+    }
+
+    pub fn width() -> Result<u16, std::io::Error> {
+        if let Some(width) = with_grid(|grid| grid.width()) {
+            return Ok(width);
+        }
+        let (width, _) = terminal::size()?;
+        Ok(width)
+    }
+
+    pub fn move_cursor_to(position: Position) -> Result<(), std::io::Error> {
+        if with_grid(|grid| grid.move_to(position.x, position.y)).is_some() {
+            return Ok(());
+        }
+        queue!(stdout(), MoveTo(position.x, position.y))?;
+        Ok(())
+    }
+
+    pub fn set_foreground_color(color: crossterm::style::Color) -> Result<(), std::io::Error> {
+        if is_headless() {
+            return Ok(());
+        }
+        queue!(stdout(), crossterm::style::SetForegroundColor(color))
+    }
+
+    pub fn set_background_color(color: crossterm::style::Color) -> Result<(), std::io::Error> {
+        if is_headless() {
+            return Ok(());
+        }
+        queue!(stdout(), crossterm::style::SetBackgroundColor(color))
+    }
+
+    pub fn set_underlined(underlined: bool) -> Result<(), std::io::Error> {
+        if is_headless() {
+            return Ok(());
+        }
+        let attribute = match underlined {
+            true => Attribute::Underlined,
+            false => Attribute::NoUnderline,
+        };
+        queue!(stdout(), SetAttribute(attribute))
+    }
+
+    /// Copies `text` to the system clipboard using the OSC 52 escape sequence. Does nothing
+    /// if the output is not a terminal.
+    pub fn copy_to_clipboard(text: &str) -> Result<(), std::io::Error> {
+        if !stdout().is_terminal() || is_headless() {
+            return Ok(());
+        }
+        let sequence = format!("\x1b]52;c;{}\x07", base64::encode(text.as_bytes()));
+        queue!(stdout(), crossterm::style::Print(sequence))
+    }
+
+    pub fn clear_screen() -> Result<(), std::io::Error> {
+        if with_grid(Grid::clear).is_some() {
+            return Ok(());
+        }
+        queue!(stdout(), Clear(ClearType::All))?;
+        Ok(())
+    }
+
+    pub fn clear_until_newline() -> Result<(), std::io::Error> {
+        if with_grid(Grid::clear_until_newline).is_some() {
+            return Ok(());
+        }
+        queue!(stdout(), Clear(ClearType::UntilNewLine))?;
+        Ok(())
+    }
+
+    pub fn execute() -> Result<(), std::io::Error> {
+        stdout().flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture() {
+        let grid = Terminal::capture(Grid::new(4, 1), || draw!("ab")).unwrap();
+        assert_eq!(grid.lines(), ["ab"]);
+        assert!(!is_headless());
+
+        let result = std::panic::catch_unwind(|| {
+            Terminal::capture(Grid::new(4, 1), || panic!("drawing failed"))
+        });
+        assert!(result.is_err());
+        assert!(!is_headless());
+    }
+}
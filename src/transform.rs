@@ -0,0 +1,247 @@
+use super::parse::{parse_hex, parse_number};
+
+/// An operation applied to each byte of a range.
+#[derive(Debug, PartialEq)]
+pub enum Transform {
+    /// XOR with a key, repeated from the start of the range.
+    Xor(Vec<u8>),
+    /// AND with a key, repeated from the start of the range.
+    And(Vec<u8>),
+    /// OR with a key, repeated from the start of the range.
+    Or(Vec<u8>),
+    Not,
+    /// Wrapping addition of a constant, also used for subtraction.
+    Add(u8),
+    /// Reversal of the bytes in each group of the given size, leaving an incomplete group at the
+    /// end unchanged.
+    ByteSwap(usize),
+    /// Rotation of ASCII letters through the alphabet, as in the Caesar cipher.
+    Rotate(u8),
+    /// Replacement with a pattern, repeated from the start of the range.
+    Fill(Vec<u8>),
+    /// Replacement with random bytes, from the system's secure random number generator or from a
+    /// generator with the given seed.
+    Random(Option<u64>),
+}
+
+impl Transform {
+    /// Parses the arguments of the command `name`, returning `None` for invalid arguments.
+    pub fn parse<'a>(name: &str, mut args: impl Iterator<Item = &'a str>) -> Option<Self> {
+        let transform = match name {
+            "xor" => Transform::Xor(parse_hex(args.next()?)?),
+            "and" => Transform::And(parse_hex(args.next()?)?),
+            "or" => Transform::Or(parse_hex(args.next()?)?),
+            "not" => Transform::Not,
+            "add" => Transform::Add(parse_byte(args.next()?)?),
+            "sub" => Transform::Add(parse_byte(args.next()?)?.wrapping_neg()),
+            "rot13" => Transform::Rotate(13),
+            "rot" => Transform::Rotate((args.next()?.parse::<i64>().ok()?.rem_euclid(26)) as u8),
+            "fill" => Transform::Fill(parse_hex(args.next()?)?),
+            "zero" => Transform::Fill(vec![0]),
+            "fill-random" => match args.next() {
+                Some(seed) => Transform::Random(Some(parse_number(seed)? as u64)),
+                None => Transform::Random(None),
+            },
+            "bswap" => match args.next()? {
+                "2" => Transform::ByteSwap(2),
+                "4" => Transform::ByteSwap(4),
+                "8" => Transform::ByteSwap(8),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        args.next().is_none().then_some(transform)
+    }
+
+    pub fn usage(name: &str) -> &'static str {
+        match name {
+            "xor" => "Usage: xor <hex key>",
+            "and" => "Usage: and <hex mask>",
+            "or" => "Usage: or <hex mask>",
+            "not" => "Usage: not",
+            "add" => "Usage: add <value from 0 to 255>",
+            "sub" => "Usage: sub <value from 0 to 255>",
+            "bswap" => "Usage: bswap <2|4|8>",
+            "rot13" => "Usage: rot13",
+            "rot" => "Usage: rot <n>",
+            "fill" => "Usage: fill <hex pattern>",
+            "zero" => "Usage: zero",
+            "fill-random" => "Usage: fill-random [seed]",
+            _ => "Unknown transform",
+        }
+    }
+
+    pub fn apply(&self, data: &mut [u8]) {
+        match self {
+            Transform::Xor(key) => {
+                for (byte, key) in data.iter_mut().zip(key.iter().cycle()) {
+                    *byte ^= key;
+                }
+            }
+            Transform::And(mask) => {
+                for (byte, mask) in data.iter_mut().zip(mask.iter().cycle()) {
+                    *byte &= mask;
+                }
+            }
+            Transform::Or(mask) => {
+                for (byte, mask) in data.iter_mut().zip(mask.iter().cycle()) {
+                    *byte |= mask;
+                }
+            }
+            Transform::Not => {
+                for byte in data {
+                    *byte = !*byte;
+                }
+            }
+            Transform::Add(value) => {
+                for byte in data {
+                    *byte = byte.wrapping_add(*value);
+                }
+            }
+            Transform::ByteSwap(size) => {
+                for group in data.chunks_exact_mut(*size) {
+                    group.reverse();
+                }
+            }
+            Transform::Rotate(shift) => {
+                for byte in data {
+                    let base = match byte {
+                        b'a'..=b'z' => b'a',
+                        b'A'..=b'Z' => b'A',
+                        _ => continue,
+                    };
+                    *byte = base + (*byte - base + shift) % 26;
+                }
+            }
+            Transform::Fill(pattern) if pattern.len() == 1 => data.fill(pattern[0]),
+            Transform::Fill(pattern) => {
+                for (byte, value) in data.iter_mut().zip(pattern.iter().cycle()) {
+                    *byte = *value;
+                }
+            }
+            Transform::Random(None) => {
+                getrandom::fill(data).expect("Failed to read random data from the system");
+            }
+            Transform::Random(Some(seed)) => {
+                let mut state = *seed;
+                for chunk in data.chunks_mut(8) {
+                    let value = split_mix(&mut state).to_le_bytes();
+                    chunk.copy_from_slice(&value[..chunk.len()]);
+                }
+            }
+        }
+    }
+}
+
+/// Advances the SplitMix64 generator, a small generator with good statistical quality.
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn parse_byte(text: &str) -> Option<u8> {
+    parse_number(text)?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let parse = |command: &str| {
+            let mut args = command.split_whitespace();
+            Transform::parse(args.next().unwrap(), args)
+        };
+        assert_eq!(parse("xor 0xdead"), Some(Transform::Xor(vec![0xde, 0xad])));
+        assert_eq!(parse("xor ff"), Some(Transform::Xor(vec![0xff])));
+        assert_eq!(parse("xor 0XdEaD"), Some(Transform::Xor(vec![0xde, 0xad])));
+        assert_eq!(parse("xor f"), None);
+        assert_eq!(parse("xor"), None);
+        assert_eq!(parse("xor ff ff"), None);
+        assert_eq!(parse("and 0x0f"), Some(Transform::And(vec![0x0f])));
+        assert_eq!(parse("not"), Some(Transform::Not));
+        assert_eq!(parse("not ff"), None);
+        assert_eq!(parse("add 0x20"), Some(Transform::Add(0x20)));
+        assert_eq!(parse("sub 1"), Some(Transform::Add(0xff)));
+        assert_eq!(parse("add 256"), None);
+        assert_eq!(parse("bswap 4"), Some(Transform::ByteSwap(4)));
+        assert_eq!(parse("bswap 3"), None);
+        assert_eq!(parse("rot13"), Some(Transform::Rotate(13)));
+        assert_eq!(parse("rot -1"), Some(Transform::Rotate(25)));
+        assert_eq!(parse("rot"), None);
+        assert_eq!(parse("zero"), Some(Transform::Fill(vec![0])));
+        assert_eq!(parse("fill-random 0x10"), Some(Transform::Random(Some(16))));
+        assert_eq!(parse("fill-random"), Some(Transform::Random(None)));
+    }
+
+    #[test]
+    fn test_xor() {
+        let mut data = [0x00, 0x01, 0x02, 0xff, 0x10];
+        Transform::Xor(vec![0x0f, 0xf0]).apply(&mut data);
+        assert_eq!(data, [0x0f, 0xf1, 0x0d, 0x0f, 0x1f]);
+    }
+
+    #[test]
+    fn test_bitwise() {
+        let mut data = [0x12, 0x34, 0x56];
+        Transform::And(vec![0xf0, 0x0f]).apply(&mut data);
+        assert_eq!(data, [0x10, 0x04, 0x50]);
+        Transform::Or(vec![0x01]).apply(&mut data);
+        assert_eq!(data, [0x11, 0x05, 0x51]);
+        Transform::Not.apply(&mut data);
+        assert_eq!(data, [0xee, 0xfa, 0xae]);
+    }
+
+    #[test]
+    fn test_add() {
+        let mut data = [0x00, 0x41, 0xff];
+        Transform::Add(0x20).apply(&mut data);
+        assert_eq!(data, [0x20, 0x61, 0x1f]);
+    }
+
+    #[test]
+    fn test_rotate() {
+        let mut data = *b"Hello, Zz!";
+        Transform::Rotate(13).apply(&mut data);
+        assert_eq!(&data, b"Uryyb, Mm!");
+        Transform::Rotate(13).apply(&mut data);
+        Transform::Rotate(1).apply(&mut data);
+        assert_eq!(&data, b"Ifmmp, Aa!");
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut data = [1; 5];
+        Transform::Fill(vec![0xaa, 0xbb]).apply(&mut data);
+        assert_eq!(data, [0xaa, 0xbb, 0xaa, 0xbb, 0xaa]);
+        Transform::Fill(vec![0]).apply(&mut data);
+        assert_eq!(data, [0; 5]);
+    }
+
+    #[test]
+    fn test_random() {
+        let (mut a, mut b) = ([0; 11], [0; 11]);
+        Transform::Random(Some(1)).apply(&mut a);
+        Transform::Random(Some(1)).apply(&mut b);
+        assert_eq!(a, b);
+        assert_eq!(&a[..8], &0x910a2dec89025cc1_u64.to_le_bytes());
+        Transform::Random(Some(2)).apply(&mut b);
+        assert_ne!(a, b);
+        let mut data = [0; 64];
+        Transform::Random(None).apply(&mut data);
+        assert_ne!(data, [0; 64]);
+    }
+
+    #[test]
+    fn test_byte_swap() {
+        let mut data = [1, 2, 3, 4, 5, 6];
+        Transform::ByteSwap(4).apply(&mut data);
+        assert_eq!(data, [4, 3, 2, 1, 5, 6]);
+        Transform::ByteSwap(2).apply(&mut data);
+        assert_eq!(data, [3, 4, 1, 2, 6, 5]);
+    }
+}
@@ -0,0 +1,146 @@
+use super::terminal::{Terminal, draw, drawln};
+use crossterm::style::Color;
+use std::ops::Range;
+
+pub const VERTICAL: &str = "\u{2500}";
+pub const HORIZONTAL: &str = "\u{2502}";
+pub const TOP_T: &str = "\u{252c}";
+pub const BOTTOM_T: &str = "\u{2534}";
+pub const TOP_LEFT_CORNER: &str = "\u{256d}";
+pub const TOP_RIGHT_CORNER: &str = "\u{256e}";
+pub const BOTTOM_RIGHT_CORNER: &str = "\u{256f}";
+pub const BOTTOM_LEFT_CORNER: &str = "\u{2570}";
+
+/// Formats `offset` as shown in the address column and the status bar, e.g. `0001 00f0`.
+pub fn format_offset(offset: usize) -> String {
+    format!(
+        "{:0>4x} {:0>4x}",
+        offset / (256 * 256),
+        offset % (256 * 256)
+    )
+}
+
+pub enum BoxPart {
+    Top,
+    Bottom,
+}
+
+/// Draws the top or bottom of the box around `views` side by side, each with an address column
+/// and `panes` pairs of hex and text columns, followed by auxiliary columns of the given widths.
+pub fn draw_box_part(
+    part: BoxPart,
+    bytes_per_line: u32,
+    views: u32,
+    panes: u32,
+    columns: &[usize],
+) -> Result<(), std::io::Error> {
+    let (corner_left, tee, corner_right) = match part {
+        BoxPart::Top => (TOP_LEFT_CORNER, TOP_T, TOP_RIGHT_CORNER),
+        BoxPart::Bottom => (BOTTOM_LEFT_CORNER, BOTTOM_T, BOTTOM_RIGHT_CORNER),
+    };
+    let pane = format!(
+        "{}{}{}{}",
+        tee,
+        VERTICAL.repeat((3 * bytes_per_line + 1) as usize),
+        tee,
+        VERTICAL.repeat(bytes_per_line as usize + 2)
+    );
+    let view = format!("{}{}", VERTICAL.repeat(11), pane.repeat(panes as usize));
+    let columns: String = columns
+        .iter()
+        .map(|width| format!("{}{}", tee, VERTICAL.repeat(*width)))
+        .collect();
+    drawln!(
+        "\r {}{}{}{}",
+        corner_left,
+        vec![view; views as usize].join(tee),
+        columns,
+        corner_right
+    )
+}
+
+/// An open file shown in the tab bar.
+pub struct Tab {
+    pub name: String,
+    pub modified: bool,
+}
+
+impl Tab {
+    fn label(&self) -> String {
+        format!(" {}{} ", self.name, if self.modified { "+" } else { "" })
+    }
+}
+
+/// Draws the header line with the program name, a tab for each open file with the active one
+/// highlighted, and `detail` in grey. Tabs are scrolled to keep the active one within `width`.
+pub fn draw_header(
+    tabs: &[Tab],
+    active: usize,
+    detail: &str,
+    width: usize,
+) -> Result<(), std::io::Error> {
+    const TITLE: &str = "     Ashe     ";
+    Terminal::set_foreground_color(Color::DarkYellow)?;
+    draw!("\r{}", TITLE)?;
+    Terminal::set_foreground_color(Color::Reset)?;
+    let labels: Vec<_> = tabs.iter().map(Tab::label).collect();
+    let widths: Vec<_> = labels.iter().map(|label| label.chars().count()).collect();
+    let visible = visible_tabs(&widths, active, width.saturating_sub(TITLE.len()));
+    for index in visible {
+        if index == active {
+            Terminal::set_background_color(Color::DarkGrey)?;
+        }
+        draw!("{}", labels[index])?;
+        Terminal::set_background_color(Color::Reset)?;
+    }
+    Terminal::set_foreground_color(Color::DarkGrey)?;
+    draw!(" {}", detail)?;
+    Terminal::set_foreground_color(Color::Reset)?;
+    Terminal::clear_until_newline()?;
+    drawln!()?;
+    Ok(())
+}
+
+/// Returns the range of tabs fitting into `available` columns which includes the active tab,
+/// starting from the first tab if possible.
+fn visible_tabs(widths: &[usize], active: usize, available: usize) -> Range<usize> {
+    let mut start = 0;
+    while start < active && widths[start..=active].iter().sum::<usize>() > available {
+        start += 1;
+    }
+    let mut end = active + 1;
+    while end < widths.len() && widths[start..=end].iter().sum::<usize>() <= available {
+        end += 1;
+    }
+    start..end.min(widths.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visible_tabs() {
+        let widths = [10, 10, 10, 10];
+        assert_eq!(visible_tabs(&widths, 0, 100), 0..4);
+        assert_eq!(visible_tabs(&widths, 1, 25), 0..2);
+        assert_eq!(visible_tabs(&widths, 3, 25), 2..4);
+        assert_eq!(visible_tabs(&widths, 2, 5), 2..3);
+        assert_eq!(visible_tabs(&[], 0, 5), 0..0);
+    }
+
+    #[test]
+    fn test_format_offset() {
+        assert_eq!(format_offset(0x100f0), "0001 00f0");
+        assert_eq!(format_offset(0x1_0000_0000), "10000 0000");
+    }
+
+    #[test]
+    fn test_tab_label() {
+        let tab = Tab {
+            name: "a.bin".into(),
+            modified: true,
+        };
+        assert_eq!(tab.label(), " a.bin+ ");
+    }
+}
@@ -0,0 +1,106 @@
+use super::parse::parse_number;
+
+/// The width, byte order and base address of the pointers searched by `:xref`.
+#[derive(Debug, PartialEq)]
+pub struct PointerFormat {
+    pub size: usize,
+    pub big_endian: bool,
+    pub base: u64,
+}
+
+impl PointerFormat {
+    /// Parses the arguments of `:xref`, any of `u16`, `u32` or `u64`, `le` or `be`, and the
+    /// address the buffer is loaded at. Pointers are 32-bit little-endian relative to the start
+    /// of the buffer by default.
+    pub fn parse<'a>(args: impl Iterator<Item = &'a str>) -> Option<Self> {
+        let mut format = PointerFormat {
+            size: 4,
+            big_endian: false,
+            base: 0,
+        };
+        for arg in args {
+            match arg {
+                "u16" => format.size = 2,
+                "u32" => format.size = 4,
+                "u64" => format.size = 8,
+                "le" => format.big_endian = false,
+                "be" => format.big_endian = true,
+                base => format.base = parse_number(base)? as u64,
+            }
+        }
+        Some(format)
+    }
+
+    /// Returns the offset pointed at by the value at `position`, or `None` if the value does not
+    /// fit in `data` or lies below the base.
+    pub fn target(&self, data: &[u8], position: usize) -> Option<usize> {
+        let bytes = data.get(position..position.checked_add(self.size)?)?;
+        let mut value = [0; 8];
+        value[..self.size].copy_from_slice(bytes);
+        if self.big_endian {
+            value[..self.size].reverse();
+        }
+        u64::from_le_bytes(value)
+            .checked_sub(self.base)
+            .and_then(|offset| usize::try_from(offset).ok())
+    }
+
+    /// Returns the offsets of all values pointing at `offset`, aligned or not.
+    pub fn references(&self, data: &[u8], offset: usize) -> Vec<usize> {
+        let target = self.base.wrapping_add(offset as u64);
+        if self.size < 8 && target >> (8 * self.size) != 0 {
+            return vec![];
+        }
+        let mut pattern = target.to_le_bytes()[..self.size].to_vec();
+        if self.big_endian {
+            pattern.reverse();
+        }
+        data.windows(self.size)
+            .enumerate()
+            .filter(|(_, window)| *window == pattern)
+            .map(|(position, _)| position)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let format = PointerFormat::parse("be u16 0x8000".split_whitespace()).unwrap();
+        assert_eq!(
+            format,
+            PointerFormat {
+                size: 2,
+                big_endian: true,
+                base: 0x8000
+            }
+        );
+        assert_eq!(PointerFormat::parse("".split_whitespace()).unwrap().size, 4);
+        assert!(PointerFormat::parse("u24".split_whitespace()).is_none());
+    }
+
+    #[test]
+    fn test_references() {
+        let data = b"\x10\x00\x00\x00\x00\x80\x10\x00\x00\x00";
+        let format = PointerFormat::parse("".split_whitespace()).unwrap();
+        assert_eq!(format.references(data, 0x10), vec![0, 6]);
+        let format = PointerFormat::parse("u16 be 0x8000".split_whitespace()).unwrap();
+        assert_eq!(format.references(data, 0x10), vec![5]);
+        let format = PointerFormat::parse("u16 0x10000".split_whitespace()).unwrap();
+        assert!(format.references(data, 0).is_empty());
+    }
+
+    #[test]
+    fn test_target() {
+        let data = b"\x10\x00\x00\x00\x80\x00";
+        let format = PointerFormat::parse("".split_whitespace()).unwrap();
+        assert_eq!(format.target(data, 0), Some(0x10));
+        assert_eq!(format.target(data, 3), None);
+        let format = PointerFormat::parse("u16 be 0x8000".split_whitespace()).unwrap();
+        assert_eq!(format.target(data, 4), Some(0));
+        assert_eq!(format.target(data, 0), None);
+    }
+}